@@ -0,0 +1,35 @@
+/*
+ * mdtagger の C API (cargo の ffi フィーチャーでビルドしたライブラリ用)
+ *
+ * 文字列はすべて UTF-8 の NUL 終端。失敗したときは mdtagger_last_error で理由を読める。
+ */
+#ifndef MDTAGGER_H
+#define MDTAGGER_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Markdown ファイルにタグを付け、タグデータベースを保存する (DBにないタグは登録する)。
+ * 新しく付いたタグの数を返す。失敗したら -1。 */
+int mdtagger_add_tags(const char *db_path, const char *md_path,
+                      const char *const *tags, size_t n_tags);
+
+/* 入力を既存のタグに対応付ける (完全一致・エイリアス・略語のみ)。
+ * タグ名 (複数なら改行区切り) を返す。見つからないかエラーなら NULL。
+ * 返した文字列は mdtagger_string_free で解放すること。 */
+char *mdtagger_resolve(const char *db_path, const char *input);
+
+/* mdtagger_resolve が返した文字列を解放する (NULL なら何もしない) */
+void mdtagger_string_free(char *s);
+
+/* 同じスレッドで最後に失敗した呼び出しのメッセージ (成功していれば NULL)。解放しないこと。 */
+const char *mdtagger_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MDTAGGER_H */
@@ -0,0 +1,69 @@
+// --- 非同期のまとめて処理 (feature = "async") ---
+//
+// ノートのWebアプリのバックエンドなど、非同期のサービスに組み込むためのもの。
+// ファイルの読み書きは tokio で同時に進め、タグの解決とDBの更新は1つずつ順に行う。
+
+use crate::db::TagDatabase;
+use crate::error::Result;
+use crate::markdown::{read_head, replace_head};
+use crate::operation::{ChangeSet, TagOperation};
+use std::sync::Arc;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::task::JoinSet;
+
+/// 1つの操作を非同期に実行する
+pub async fn execute(operation: &TagOperation, db: &Mutex<TagDatabase>) -> Result<ChangeSet> {
+    let path = operation.path().to_path_buf();
+    let options = operation.front_matter_options().clone();
+    // 先頭だけを読み書きする処理はブロックするので、別のスレッドで行う
+    let before = blocking(move || read_head(&path, &options)).await?;
+    let changes = operation.plan(before, &mut *db.lock().await)?;
+    if changes.applied {
+        let path = operation.path().to_path_buf();
+        let (before, after) = (changes.before.clone(), changes.after.clone());
+        blocking(move || replace_head(&path, &before, &after)).await?;
+        changes.record_usage(&mut *db.lock().await);
+    }
+    Ok(changes)
+}
+
+/// ブロックする処理を tokio のブロック用のスレッドで実行する (パニックはそのまま伝える)
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+/// 複数の操作を、同時に最大 concurrency 件まで実行する (結果は operations と同じ順)
+///
+/// tokio のランタイムの中で呼ぶこと。1つが失敗しても残りは続ける。
+pub async fn execute_all(
+    operations: Vec<TagOperation>,
+    db: Arc<Mutex<TagDatabase>>,
+    concurrency: usize,
+) -> Vec<Result<ChangeSet>> {
+    let limit = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    let count = operations.len();
+    for (i, operation) in operations.into_iter().enumerate() {
+        let (db, limit) = (Arc::clone(&db), Arc::clone(&limit));
+        tasks.spawn(async move {
+            let _permit = limit.acquire_owned().await;
+            (i, execute(&operation, &db).await)
+        });
+    }
+
+    let mut results: Vec<Option<Result<ChangeSet>>> = (0..count).map(|_| None).collect();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((i, result)) => results[i] = Some(result),
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+    // タスクは中断しないので、すべての操作の結果がそろっている
+    results
+        .into_iter()
+        .map(|r| r.expect("every task reports its result"))
+        .collect()
+}
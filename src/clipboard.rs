@@ -0,0 +1,29 @@
+// --- クリップボードからのタグの読み取り ---
+
+use anyhow::{Context, Result};
+use mdtagger::tr;
+
+/// クリップボードの文字列をタグの並びとして読む
+pub fn read_tags() -> Result<Vec<String>> {
+    let text = arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .with_context(|| {
+            tr!(
+                "Failed to read the clipboard",
+                "クリップボードを読み込めません"
+            )
+        })?;
+    Ok(parse_tags(&text))
+}
+
+/// カンマ・空白・改行で区切る。ブラウザやチャットからコピーした #rust のような先頭の # は外す
+fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(|c: char| c == ',' || c == '、' || c.is_whitespace()) {
+        let tag = tag.trim().trim_start_matches('#');
+        if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
@@ -0,0 +1,1321 @@
+// --- サブコマンド: add (タグの追加) ---
+
+use anyhow::{Context, Result, bail};
+use clap::Args;
+use log::{info, warn};
+use mdtagger::db::{TagConfig, TagDatabase, TagEntry, record_usage, save_config};
+use mdtagger::markdown::{
+    FrontMatterFormat, FrontMatterOptions, add_tags, collect_markdown_files, content_tags,
+    line_ending, raw_front_matter, raw_toml_front_matter, read_head, remove_tags, replace_tag,
+};
+use mdtagger::normalize::Normalizer;
+use mdtagger::prompt::{confirm, edit_text, input_with_completion, multi_select};
+use mdtagger::resolver::{
+    FuzzyResolver, ResolveOptions, TagResolver, UnknownPolicy, load_synonyms, near_duplicates,
+};
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use super::{GlobalArgs, OutputFormat};
+use crate::decisions::DecisionLog;
+use crate::editor::{EditorFile, EditorReport};
+use crate::exit::{ErrorCode, Exit};
+use crate::git;
+use crate::hooks::{self, PreResolveReply};
+use crate::index::FileIndex;
+use crate::preview::print_diff;
+use crate::undo::Recorder;
+use crate::usage::ResolverStats;
+
+#[derive(Args)]
+pub struct AddArgs {
+    /// 対象のMarkdownファイル (ディレクトリを指定すると配下の .md をすべて対象にする)
+    #[arg(value_name = "FILE")]
+    path: PathBuf,
+
+    /// 付けるタグ (省略すると補完つきの入力画面を開く)。+rust は付ける、-draft は外す
+    /// (FILE より後の -y なども外すタグになるので、短いオプションは FILE より前か --yes のように書く)
+    #[arg(value_name = "TAGS")]
+    tags: Vec<String>,
+
+    /// git で変更したファイル (git status に出るもの) だけを対象にする。--changed=<REF> なら REF から変わったもの
+    #[arg(long, value_name = "REF", num_args = 0..=1, require_equals = true)]
+    changed: Option<Option<String>>,
+
+    /// クリップボードにあるタグ (カンマ・空白区切り) も付ける
+    #[cfg(feature = "clipboard")]
+    #[arg(long)]
+    from_clipboard: bool,
+
+    /// 外すタグ (タグの並びに -draft と書いても同じ)
+    #[arg(long, value_name = "TAG")]
+    remove: Vec<String>,
+
+    /// 毎回かならず付けるタグ (カンマ区切り、設定ファイルの default_tags より優先)
+    #[arg(
+        long = "default-tags",
+        value_name = "TAGS",
+        env = "MDTAGGER_DEFAULT_TAGS",
+        value_delimiter = ','
+    )]
+    default_tags: Vec<String>,
+
+    /// 既定のタグ (--default-tags / default_tags) を付けない
+    #[arg(long)]
+    no_default_tags: bool,
+
+    /// 対話プロンプトを一切出さずに実行する (スクリプトやCI向け)
+    #[arg(long)]
+    non_interactive: bool,
+
+    /// 非対話モードで未知のタグを見つけたときの扱い (デフォルト: fail)
+    #[arg(long, value_enum, value_name = "POLICY")]
+    on_unknown: Option<UnknownPolicy>,
+
+    /// 編集距離で見つからない場合も、発音 (Soundex) が同じタグを候補に出す
+    #[arg(long)]
+    phonetic: bool,
+
+    /// 指定した距離以内 (省略時は1) の候補が1つだけなら確認せずに採用する。距離は --auto-accept=2 のように書く
+    #[arg(
+        long,
+        value_name = "DIST",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "1"
+    )]
+    auto_accept: Option<usize>,
+
+    /// 書き込む前に Front Matter の差分を色付きで表示し、確認する
+    #[arg(long)]
+    diff: bool,
+
+    /// ファイルごとに、付けるタグの一覧を見せて書き込むか確認する
+    #[arg(long)]
+    confirm: bool,
+
+    /// ファイルは書き換えずに、書き換えた後の内容を標準出力に出す (ファイルは1つだけ)
+    #[arg(long, conflicts_with_all = ["diff", "confirm", "edit"])]
+    print: bool,
+
+    /// ファイルは書き換えずに、エディタの拡張機能が当てる編集 (バイトの位置) とタグのキーの位置を JSON で表示する
+    #[arg(long, conflicts_with_all = ["diff", "confirm", "edit", "print", "verify"])]
+    editor_protocol: bool,
+
+    /// 大文字・小文字だけがDBと違う既存のタグ (Rust など) を、確認せずに正式名 (rust) に直す
+    #[arg(long)]
+    normalize_existing: bool,
+
+    /// タグを付けたあと、書き込む前に Front Matter を $EDITOR で開いて手直しする
+    #[arg(long)]
+    edit: bool,
+
+    /// 書き込んだファイルを読み直し、本文が1バイトも変わらず、Front Matter もタグ以外は
+    /// 変わっていないことを確かめる。確かめられなければ元に戻してエラーにする
+    #[arg(long, conflicts_with = "edit")]
+    verify: bool,
+
+    /// タグの決定 (入力・候補・選んだタグ・編集距離) を1行ずつJSONで追記するファイル
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// 同義語ファイル (1行に同義語をカンマ区切りで並べたCSV)
+    #[arg(long, value_name = "FILE", env = "SMART_TAGS_SYNONYMS")]
+    synonyms: Option<PathBuf>,
+
+    /// 意味的に近いタグを候補に出すための埋め込みAPI (OpenAI互換, 例: http://localhost:11434/v1)
+    #[cfg(feature = "embeddings")]
+    #[arg(long, value_name = "URL", env = "SMART_TAGS_EMBEDDINGS_URL")]
+    embeddings_url: Option<String>,
+
+    /// 埋め込みに使うモデル名
+    #[cfg(feature = "embeddings")]
+    #[arg(
+        long,
+        value_name = "MODEL",
+        env = "SMART_TAGS_EMBEDDINGS_MODEL",
+        default_value = "text-embedding-3-small"
+    )]
+    embeddings_model: String,
+
+    /// 意味的な候補とみなすコサイン類似度の下限
+    #[cfg(feature = "embeddings")]
+    #[arg(long, value_name = "SCORE", default_value_t = 0.8)]
+    semantic_threshold: f32,
+}
+
+// --- JSON出力 ---
+/// --output json で出力する内容の JSON Schema
+pub fn output_schema() -> schemars::Schema {
+    crate::schema::for_output::<Report>()
+}
+
+/// --editor-protocol で出力する内容の JSON Schema
+pub fn editor_schema() -> schemars::Schema {
+    crate::schema::for_output::<EditorReport>()
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Report {
+    /// タグデータベースを更新したか
+    db_updated: bool,
+    files: Vec<FileReport>,
+    warnings: Vec<String>,
+    summary: Summary,
+}
+
+/// 実行の最後に表示するまとめ
+#[derive(Serialize, JsonSchema)]
+struct Summary {
+    /// 書き換えたファイルの数
+    modified: usize,
+    /// 変更がなかったか、確認で断ったファイルの数
+    skipped: usize,
+    /// 読み書きに失敗したファイルの数
+    failed: usize,
+    /// DBに登録した新しいタグ
+    new_tags: Vec<String>,
+    /// 登録したエイリアス
+    aliases_added: Vec<CreatedAlias>,
+    /// DBにもエイリアスにもなかった入力
+    unknown_tags: Vec<String>,
+}
+
+impl Summary {
+    fn new(reports: &[FileReport], unknown_tags: BTreeSet<String>) -> Self {
+        let modified = reports.iter().filter(|r| r.modified).count();
+        let failed = reports.iter().filter(|r| r.error.is_some()).count();
+        let mut new_tags = Vec::new();
+        let mut aliases_added = Vec::new();
+        for report in reports {
+            for tag in &report.new_tags {
+                if !new_tags.contains(tag) {
+                    new_tags.push(tag.clone());
+                }
+            }
+            aliases_added.extend(report.aliases_created.iter().cloned());
+        }
+        Self {
+            modified,
+            skipped: reports.len() - modified - failed,
+            failed,
+            new_tags,
+            aliases_added,
+            unknown_tags: unknown_tags.into_iter().collect(),
+        }
+    }
+
+    fn print(&self) {
+        info!("{}", tr!("Summary:", "まとめ:"));
+        info!(
+            "{}",
+            tr!(
+                "   files: {} modified, {} skipped, {} failed",
+                "   ファイル: 変更 {} / スキップ {} / 失敗 {}",
+                self.modified,
+                self.skipped,
+                self.failed
+            )
+        );
+        if !self.new_tags.is_empty() {
+            info!(
+                "{}",
+                tr!(
+                    "   new tags: {}",
+                    "   新しいタグ: {}",
+                    self.new_tags.join(", ")
+                )
+            );
+        }
+        if !self.aliases_added.is_empty() {
+            let aliases: Vec<String> = self
+                .aliases_added
+                .iter()
+                .map(|a| format!("{} -> {}", a.alias, a.tag))
+                .collect();
+            info!(
+                "{}",
+                tr!(
+                    "   aliases added: {}",
+                    "   追加したエイリアス: {}",
+                    aliases.join(", ")
+                )
+            );
+        }
+        if !self.unknown_tags.is_empty() {
+            info!(
+                "{}",
+                tr!(
+                    "   unknown tags: {}",
+                    "   未登録だったタグ: {}",
+                    self.unknown_tags.join(", ")
+                )
+            );
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+struct FileReport {
+    path: PathBuf,
+    /// 解決後に付与したタグ
+    tags: Vec<String>,
+    /// 外したタグ
+    removed: Vec<String>,
+    /// 正式な表記に直したタグ ("Rust -> rust" の形)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    casing_fixed: Vec<String>,
+    /// このファイルの処理中に登録したエイリアス
+    aliases_created: Vec<CreatedAlias>,
+    /// このファイルの処理中にDBへ登録した新しいタグ
+    new_tags: Vec<String>,
+    /// ファイルを書き換えたか (すでに付いていれば false)
+    modified: bool,
+    /// 読み書きに失敗したときのエラー
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
+struct CreatedAlias {
+    alias: String,
+    tag: String,
+}
+
+/// タグが指定されなかったときに、DBのタグから選ばせて、続けて自由に入力させる
+fn ask_tags(config: &TagConfig) -> Result<Vec<String>> {
+    let mut known: Vec<&TagEntry> = config.tags.iter().collect();
+    known.sort_by(|a, b| b.uses.cmp(&a.uses).then_with(|| a.name.cmp(&b.name)));
+    let names: Vec<String> = known.iter().map(|e| e.name.clone()).collect();
+
+    let mut tags = Vec::new();
+    if !names.is_empty() {
+        for i in multi_select(
+            &tr!(
+                "Tags to add (space to toggle, enter to confirm)",
+                "追加するタグ (スペースで選択、Enterで確定)"
+            ),
+            &names,
+            &[],
+        )? {
+            tags.push(names[i].clone());
+        }
+    }
+
+    // 補完にはエイリアスも使う (解決時に正式名へ直る)
+    let mut completions = names.clone();
+    completions.extend(known.iter().flat_map(|e| e.aliases.iter().cloned()));
+    loop {
+        let input = input_with_completion(
+            &tr!(
+                "Another tag (Tab to complete, empty to finish)",
+                "他のタグ (Tabで補完、空欄で終了)"
+            ),
+            &completions,
+        )?;
+        let input = input.trim();
+        if input.is_empty() {
+            break;
+        }
+        tags.push(input.to_string());
+    }
+    Ok(tags)
+}
+
+/// 一緒に付けることの多いタグとして勧める数
+const MAX_RELATED: usize = 5;
+
+/// 勧めるのに必要な、一緒に付いていたファイルの数
+const MIN_RELATED_FILES: usize = 2;
+
+/// DBのあるディレクトリのノートのタグ (一緒に付けることの多いタグを数える)
+fn related_files(global: &GlobalArgs, config: &TagConfig) -> Result<FileIndex> {
+    let root = match global.db_path().parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    FileIndex::build(
+        root,
+        &global.settings,
+        &TagDatabase::new(config.clone()),
+        global.db_path(),
+    )
+}
+
+/// 「よく一緒に付けるタグ」を見せ、1回の確認でまとめて付ける
+fn add_related_tags(
+    report: &mut FileReport,
+    index: &FileIndex,
+    config: &TagConfig,
+    removals: &[String],
+) -> Result<()> {
+    if report.tags.is_empty() {
+        return Ok(());
+    }
+    // すでにファイルに付いているタグは勧めない
+    let path = fs::canonicalize(&report.path).ok();
+    let current: Vec<String> = index
+        .files
+        .iter()
+        .find(|file| fs::canonicalize(&file.path).ok() == path)
+        .map(|file| file.tags.clone())
+        .unwrap_or_default();
+    let related: Vec<String> = index
+        .related(&report.tags)
+        .into_iter()
+        .filter(|(tag, count)| {
+            *count >= MIN_RELATED_FILES
+                && config.tags.iter().any(|e| &e.name == tag)
+                && !current.contains(tag)
+                && !removals.contains(tag)
+        })
+        .take(MAX_RELATED)
+        .map(|(tag, _)| tag)
+        .collect();
+    if related.is_empty() {
+        return Ok(());
+    }
+    let prompt = tr!(
+        "Often used with {}: {}. Add them too?",
+        "{} とよく一緒に付けるタグ: {}。これらも付けますか?",
+        report.tags.join(", "),
+        related.join(", ")
+    );
+    if confirm(&prompt, false, false)? {
+        report.tags.extend(related);
+    }
+    Ok(())
+}
+
+/// タグ名・エイリアス・略語のどれかとしてDBにあるか
+fn is_known(config: &TagConfig, input: &str) -> bool {
+    config.abbreviations.contains_key(input)
+        || config
+            .tags
+            .iter()
+            .any(|e| e.name == input || e.aliases.iter().any(|a| a == input))
+}
+
+/// 書き換える前と後のファイルの先頭 (read_head で読む部分) を返す (外したタグは report.removed に入れる)
+fn rewrite(
+    report: &mut FileReport,
+    removals: &[String],
+    front_matter: &FrontMatterOptions,
+) -> Result<(String, String)> {
+    let content = read_head(&report.path, front_matter)?;
+    let invalid = || {
+        tr!(
+            "Invalid front matter in {:?}",
+            "{:?} の Front Matter が不正です",
+            report.path
+        )
+    };
+    let mut new_content = content.clone();
+    if !report.tags.is_empty() {
+        new_content = add_tags(&content, &report.tags, front_matter)
+            .map_err(|err| err.in_file(&report.path))?;
+    }
+    if !removals.is_empty() {
+        report.removed = content_tags(&content, front_matter)
+            .with_context(invalid)?
+            .into_iter()
+            .filter(|tag| removals.contains(tag))
+            .collect();
+        new_content = remove_tags(&new_content, removals, front_matter)
+            .map_err(|err| err.in_file(&report.path))?;
+    }
+    Ok((content, new_content))
+}
+
+/// 大文字・小文字だけがDBの正式名と違う既存のタグ (Rust と rust など) を正式名に直す
+///
+/// --normalize-existing なら確認せずに直し、対話モードなら確認する。どちらでもなければ警告だけ出す。
+fn fix_casing(
+    report: &mut FileReport,
+    content: String,
+    config: &TagConfig,
+    front_matter: &FrontMatterOptions,
+    normalize_existing: bool,
+    interactive: bool,
+    assume_yes: bool,
+) -> Result<String> {
+    let fixes: Vec<(String, String)> = content_tags(&content, front_matter)?
+        .into_iter()
+        .filter_map(|tag| {
+            config
+                .tags
+                .iter()
+                .find(|e| e.name != tag && e.name.to_lowercase() == tag.to_lowercase())
+                .map(|e| (tag, e.name.clone()))
+        })
+        .collect();
+    if fixes.is_empty() {
+        return Ok(content);
+    }
+
+    let listed: Vec<String> = fixes
+        .iter()
+        .map(|(from, to)| format!("{} -> {}", from, to))
+        .collect();
+    let apply = if normalize_existing {
+        true
+    } else if interactive {
+        confirm(
+            &tr!(
+                "Rewrite tags in {:?} to their canonical casing ({})?",
+                "{:?} のタグを正式な表記に直しますか ({})?",
+                report.path,
+                listed.join(", ")
+            ),
+            true,
+            assume_yes,
+        )?
+    } else {
+        warn!(
+            "{}",
+            tr!(
+                "⚠️  {:?} has tags that differ from the database only in casing ({}); pass --normalize-existing to fix them",
+                "⚠️  {:?} に大文字・小文字だけがDBと違うタグがあります ({})。直すには --normalize-existing を付けてください",
+                report.path,
+                listed.join(", ")
+            )
+        );
+        false
+    };
+    if !apply {
+        return Ok(content);
+    }
+
+    let mut fixed = content;
+    for (from, to) in &fixes {
+        fixed = replace_tag(&fixed, from, to, front_matter)?;
+    }
+    report.casing_fixed = listed;
+    Ok(fixed)
+}
+
+/// 書き換えた内容の Front Matter をエディタで開き、YAML (TOML の Front Matter なら TOML) として読めるまで直してもらう
+///
+/// 途中で直すのをやめた場合は None (そのファイルは書き込まない)。
+fn edit_front_matter(path: &Path, content: &str) -> Result<Option<String>> {
+    let (format, raw, body) = match raw_toml_front_matter(content) {
+        Some((toml, body)) => (FrontMatterFormat::Toml, toml, body),
+        None => {
+            let (yaml, body) = raw_front_matter(content).unwrap_or(("", content));
+            (FrontMatterFormat::Yaml, yaml, body)
+        }
+    };
+    let (suffix, language) = match format {
+        FrontMatterFormat::Yaml => (".yaml", "YAML"),
+        FrontMatterFormat::Toml => (".toml", "TOML"),
+    };
+    let mut text = format!("{}\n", raw);
+    loop {
+        // 保存せずに閉じたら、タグを付けただけの内容をそのまま使う
+        let Some(edited) = edit_text(&text, suffix)? else {
+            return Ok(Some(content.to_string()));
+        };
+        let parsed = match format {
+            FrontMatterFormat::Yaml => serde_yaml::from_str::<Option<serde_yaml::Mapping>>(&edited)
+                .map(drop)
+                .map_err(|err| err.to_string()),
+            FrontMatterFormat::Toml => toml::from_str::<toml::Table>(&edited)
+                .map(drop)
+                .map_err(|err| err.to_string()),
+        };
+        match parsed {
+            Ok(()) => {
+                let raw = edited.trim_end_matches(['\r', '\n']);
+                let (fence, eol) = (format.fence(), line_ending(content));
+                return Ok(Some(format!("{fence}{eol}{raw}{eol}{fence}{eol}{body}")));
+            }
+            Err(err) => {
+                warn!(
+                    "❌ {}",
+                    tr!(
+                        "Invalid {} in the front matter of {:?}: {}",
+                        "{1:?} の Front Matter の {0} が不正です: {2}",
+                        language,
+                        path,
+                        err
+                    )
+                );
+                if !confirm(&tr!("Edit again?", "もう一度編集しますか?"), true, false)? {
+                    return Ok(None);
+                }
+                text = edited;
+            }
+        }
+    }
+}
+
+/// 失敗したファイルを記録して、残りのファイルの処理を続ける
+fn record_failure(report: &mut FileReport, err: anyhow::Error) -> ErrorCode {
+    warn!("❌ {:?}", err);
+    report.error = Some(format!("{:#}", err));
+    ErrorCode::from_error(&err)
+}
+
+fn tag_names(config: &TagConfig) -> HashSet<String> {
+    config.tags.iter().map(|t| t.name.clone()).collect()
+}
+
+fn alias_pairs(config: &TagConfig) -> HashSet<(String, String)> {
+    config
+        .tags
+        .iter()
+        .flat_map(|t| t.aliases.iter().map(|a| (a.clone(), t.name.clone())))
+        .collect()
+}
+
+/// タグの並びにある -draft を --remove=draft に書き換える (clap がオプションと誤解しないように)
+///
+/// FILE より後の -word は、-y のように短いオプションと同じ綴りでもすべて外すタグとして扱う。
+/// FILE より前の短いオプション (-y や -vq のようなまとまりも含む) と、--yes のような長いオプションはそのまま残す。
+pub fn expand_removals(command: &clap::Command, args: Vec<OsString>) -> Vec<OsString> {
+    let Some(add) = command.find_subcommand("add") else {
+        return args;
+    };
+    let is_short = |c: char| add.get_arguments().any(|a| a.get_short() == Some(c)) || c == 'h';
+    let takes_value = |arg: &str| {
+        add.get_arguments().any(|a| {
+            let named = match arg.strip_prefix("--") {
+                Some(long) => a.get_long() == Some(long),
+                None => arg.chars().nth(1).is_some_and(|c| a.get_short() == Some(c)),
+            };
+            named && a.get_action().takes_values()
+        })
+    };
+
+    let mut expanded = Vec::with_capacity(args.len());
+    let mut in_add = false;
+    let mut seen_file = false;
+    let mut skip_value = false;
+    for arg in args {
+        let Some(text) = arg.to_str() else {
+            expanded.push(arg);
+            continue;
+        };
+        if skip_value {
+            skip_value = false;
+        } else if text == "--" {
+            // これより後はすべてタグとして扱われる
+            in_add = false;
+        } else if text.starts_with("--") {
+            skip_value = !text.contains('=') && takes_value(text);
+        } else if let Some(tag) = text.strip_prefix('-').filter(|t| !t.is_empty()) {
+            if in_add && (seen_file || !tag.chars().all(is_short)) {
+                expanded.push(OsString::from(format!("--remove={}", tag)));
+                continue;
+            }
+            skip_value = tag.len() == 1 && takes_value(text);
+        } else if !in_add && text == "add" {
+            in_add = true;
+        } else if in_add {
+            seen_file = true;
+        }
+        expanded.push(arg);
+    }
+    expanded
+}
+
+impl AddArgs {
+    /// 標準出力を結果の出力に使うか (メッセージは標準エラー出力へ回す)
+    pub fn prints_document(&self) -> bool {
+        self.print || self.editor_protocol
+    }
+}
+
+pub fn run(global: &GlobalArgs, cli: &AddArgs) -> Result<Exit> {
+    let db_path = global.db_path();
+    let settings = &global.settings;
+    // 1. 指定されたパスからロード
+    let mut config = global.load_db()?;
+    let mut md_paths = collect_markdown_files(&cli.path)?;
+    if let Some(reference) = &cli.changed {
+        git::retain_changed(&mut md_paths, reference.as_deref())?;
+        if md_paths.is_empty() {
+            info!(
+                "{}",
+                tr!(
+                    "No changed Markdown files in {:?}",
+                    "{:?} に変更した Markdown ファイルはありません",
+                    cli.path
+                )
+            );
+            return Ok(Exit::Unchanged);
+        }
+    }
+    let front_matter = settings.front_matter_for_files(&md_paths)?;
+    // --print と --editor-protocol はファイルを書き換えないので、DBも保存せず、undo と履歴にも残さない
+    // (読み込んだDBの写しで解決するだけ)
+    let prints = cli.prints_document();
+    let mut recorder = (!prints).then(|| Recorder::start("add", db_path, &front_matter));
+    if cli.print && md_paths.len() != 1 {
+        bail!(tr!(
+            "--print works on a single file",
+            "--print は1つのファイルにだけ使えます"
+        ));
+    }
+    if cli.print && global.output == OutputFormat::Json {
+        bail!(tr!(
+            "--print cannot be combined with --output json",
+            "--print と --output json は同時に使えません"
+        ));
+    }
+    if cli.editor_protocol && global.output == OutputFormat::Json {
+        bail!(tr!(
+            "--editor-protocol cannot be combined with --output json",
+            "--editor-protocol と --output json は同時に使えません"
+        ));
+    }
+
+    if cli.edit && !global.interactive {
+        bail!(tr!(
+            "--edit needs a terminal",
+            "--edit は端末でのみ使えます"
+        ));
+    }
+    if (cli.diff || cli.confirm) && !global.interactive && !global.yes {
+        bail!(tr!(
+            "--diff and --confirm need a terminal (add --yes to write without asking)",
+            "--diff と --confirm は端末でのみ使えます (確認せずに書き込むなら --yes を付けてください)"
+        ));
+    }
+
+    let mut config_updated = false;
+
+    info!("{}", tr!("Using DB: {:?}", "使用するDB: {:?}", db_path)); // 現在どのDBを使っているか表示
+
+    // 端末でなければ確認を出せないので、非対話モードと同じに扱う (--yes なら確認自体をしない)
+    let non_interactive = cli.non_interactive
+        || settings.non_interactive == Some(true)
+        || (!global.interactive && !global.yes);
+    let default_tags: Vec<String> = if cli.no_default_tags {
+        Vec::new()
+    } else if !cli.default_tags.is_empty() {
+        cli.default_tags.clone()
+    } else {
+        settings.default_tags.clone().unwrap_or_default()
+    }
+    .into_iter()
+    .map(|tag| tag.trim().to_string())
+    .filter(|tag| !tag.is_empty())
+    .collect();
+    // +rust は付ける、-draft は外す (-- の後に書かれた場合もここで分ける)
+    let mut removals = cli.remove.clone();
+    let mut requested = Vec::new();
+    #[cfg(feature = "clipboard")]
+    if cli.from_clipboard {
+        let pasted = crate::clipboard::read_tags()?;
+        if pasted.is_empty() {
+            bail!(tr!(
+                "The clipboard has no tags",
+                "クリップボードにタグがありません"
+            ));
+        }
+        info!(
+            "{}",
+            tr!(
+                "Tags from the clipboard: {:?}",
+                "クリップボードのタグ: {:?}",
+                pasted
+            )
+        );
+        requested.extend(pasted);
+    }
+    for tag in &cli.tags {
+        match tag.strip_prefix('-') {
+            Some(tag) => removals.push(tag.to_string()),
+            None => requested.push(tag.strip_prefix('+').unwrap_or(tag).to_string()),
+        }
+    }
+    let mut tags = if !requested.is_empty() || !removals.is_empty() {
+        requested
+    } else if (non_interactive || global.yes) && default_tags.is_empty() {
+        bail!(tr!(
+            "No tags given (pass them as arguments in non-interactive mode)",
+            "タグが指定されていません (非対話モードでは引数で指定してください)"
+        ));
+    } else if non_interactive || global.yes {
+        Vec::new()
+    } else {
+        ask_tags(&config)?
+    };
+    for tag in default_tags {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    if tags.is_empty() && removals.is_empty() {
+        bail!(tr!("No tags given", "タグが指定されていません"));
+    }
+
+    let normalizer = Normalizer::new(&config.normalize)?;
+    // 外すタグは入力どおりの名前に加え、エイリアスなら正式名も外す
+    let removals: Vec<String> = removals
+        .iter()
+        .map(|tag| normalizer.apply(tag))
+        .filter(|tag| !tag.is_empty())
+        .flat_map(|tag| {
+            let canonical = config
+                .tags
+                .iter()
+                .find(|e| e.aliases.contains(&tag))
+                .map(|e| e.name.clone());
+            std::iter::once(tag).chain(canonical)
+        })
+        .collect();
+    let mut resolver = FuzzyResolver::new(ResolveOptions {
+        policy: non_interactive.then_some(
+            cli.on_unknown
+                .or(settings.on_unknown)
+                .unwrap_or(UnknownPolicy::Fail),
+        ),
+        phonetic: cli.phonetic,
+        auto_accept: cli.auto_accept.or(config.defaults.auto_accept_distance),
+        synonyms: match &cli.synonyms {
+            Some(path) => load_synonyms(path)?,
+            None => Vec::new(),
+        },
+        max_distance: settings.max_distance,
+        assume_yes: global.yes,
+        #[cfg(feature = "embeddings")]
+        semantic: match &cli.embeddings_url {
+            Some(url) => Some(mdtagger::embeddings::SemanticIndex::new(
+                url,
+                &cli.embeddings_model,
+                std::env::var("SMART_TAGS_EMBEDDINGS_KEY").ok(),
+                cli.semantic_threshold,
+                db_path,
+            )?),
+            None => None,
+        },
+    });
+
+    // 「残りすべてに適用」を選んだ入力タグの決定
+    let mut batch_choices: HashMap<String, Vec<String>> = HashMap::new();
+    let mut reports = Vec::new();
+    let mut warnings = Vec::new();
+    let mut unknown_tags = BTreeSet::new();
+    let mut decisions = DecisionLog::open(cli.log_file.as_deref())?;
+    // 一緒に付けることの多いタグを勧めるための、ノートのタグ (最初に勧めるときに読む)
+    let mut related_index: Option<FileIndex> = None;
+    let mut usage = match settings.usage_stats {
+        Some(true) if !prints => Some(ResolverStats::load(db_path)?),
+        _ => None,
+    };
+
+    for (i, md_path) in md_paths.iter().enumerate() {
+        let remaining = md_paths.len() - i - 1;
+        if md_paths.len() > 1 {
+            info!(
+                "{}",
+                tr!(
+                    "Checking tags for {:?}...",
+                    "{:?} のタグを確認中...",
+                    md_path
+                )
+            );
+        } else {
+            info!("{}", tr!("Checking tags...", "タグを確認中..."));
+        }
+
+        let mut report = FileReport {
+            path: md_path.clone(),
+            tags: Vec::new(),
+            removed: Vec::new(),
+            casing_fixed: Vec::new(),
+            aliases_created: Vec::new(),
+            new_tags: Vec::new(),
+            modified: false,
+            error: None,
+        };
+        for raw_tag in &tags {
+            if let Some(choice) = batch_choices.get(raw_tag) {
+                decisions.record_batch(md_path, raw_tag, choice)?;
+                report.tags.extend(choice.clone());
+                continue;
+            }
+
+            let input = normalizer.apply(raw_tag);
+            if input.is_empty() {
+                continue;
+            }
+            // pre_resolve フックは入力を置き換えるか、タグを決めてしまえる
+            let reply = hooks::pre_resolve(db_path, md_path, &input, &config)?;
+            let input = reply.input.unwrap_or(input);
+            if !is_known(&config, &input) {
+                unknown_tags.insert(input.clone());
+            }
+            let names_before = tag_names(&config);
+            let aliases_before = alias_pairs(&config);
+            let resolution = match reply.tags {
+                Some(tags) => PreResolveReply::resolution(tags, &mut config),
+                None => resolver.resolve(&input, &mut config)?,
+            };
+            decisions.record(md_path, &input, &resolution)?;
+            if let Some(usage) = &mut usage {
+                usage.record(&resolution);
+            }
+            report.new_tags.extend(
+                config
+                    .tags
+                    .iter()
+                    .filter(|t| !names_before.contains(&t.name))
+                    .map(|t| t.name.clone()),
+            );
+            report
+                .aliases_created
+                .extend(
+                    alias_pairs(&config)
+                        .difference(&aliases_before)
+                        .map(|(alias, tag)| CreatedAlias {
+                            alias: alias.clone(),
+                            tag: tag.clone(),
+                        }),
+                );
+            if resolution.updated {
+                config_updated = true;
+            }
+            // 確認なしで作られた場合も、既存タグとの表記ゆれはここで目立たせる
+            if resolution.created {
+                let new_tags = resolution
+                    .tags
+                    .iter()
+                    .filter(|t| !names_before.contains(*t));
+                for tag in new_tags {
+                    for near in near_duplicates(&config, tag) {
+                        let warning = tr!(
+                            "New tag '{}' is very close to existing tag '{}'",
+                            "新しいタグ '{}' は既存のタグ '{}' とよく似ています",
+                            tag,
+                            near
+                        );
+                        warn!("⚠️  {}", warning);
+                        warnings.push(warning);
+                    }
+                }
+            }
+            if resolution.prompted
+                && remaining > 0
+                && confirm(
+                    &tr!(
+                        "Apply this choice for '{}' to all {} remaining files?",
+                        "'{}' への選択を残りの {} ファイルすべてに適用しますか?",
+                        raw_tag,
+                        remaining
+                    ),
+                    false,
+                    global.yes,
+                )?
+            {
+                batch_choices.insert(raw_tag.clone(), resolution.tags.clone());
+            }
+            report.tags.extend(resolution.tags);
+        }
+        // 付けると同時に外すよう指定されたタグは外す
+        report.tags.retain(|tag| !removals.contains(tag));
+        if !non_interactive && !global.yes && settings.related_tags != Some(false) {
+            let index = match &related_index {
+                Some(index) => index,
+                None => related_index.insert(related_files(global, &config)?),
+            };
+            add_related_tags(&mut report, index, &config, &removals)?;
+        }
+        reports.push(report);
+    }
+    if let Some(usage) = &usage {
+        usage.save(db_path)?;
+    }
+
+    // 2. 指定されたパスへ保存 (使用回数はファイルを書き換えたあとに数える)
+    let config_updated = config_updated && !prints;
+    if config_updated {
+        save_config(db_path, &config)?;
+        info!(
+            "{}",
+            tr!(
+                "✨ Tag database updated at {:?}",
+                "✨ タグデータベースを更新しました: {:?}",
+                db_path
+            )
+        );
+    }
+    #[cfg(feature = "embeddings")]
+    if let Some(semantic) = &resolver.options.semantic {
+        semantic.save()?;
+    }
+
+    // 3. Markdownファイルを更新 (--print なら標準出力へ)
+    let mut would_change = false;
+    // 書き換えたファイルに足したタグ (ファイルごと。使用回数に数える)
+    let mut used = Vec::new();
+    // --editor-protocol で返すファイルごとの編集
+    let mut editor_files = Vec::new();
+    // 最初に失敗したファイルのエラーの種類 (終了コードに使う)
+    let mut failure = None;
+    // Hugo や Zola のサイトで公開しているページの語 (まだない語を付けると、新しい一覧のページができる)
+    let key = &front_matter.tag_key;
+    let mut site_terms = match (&settings.zola_site, &settings.hugo_site) {
+        (Some(zola), _) => zola
+            .taxonomy(key)
+            .is_some_and(|k| &k == key)
+            .then(|| zola.terms(key)),
+        (None, Some(hugo)) => hugo.taxonomy(key).is_some().then(|| hugo.terms(key)),
+        (None, None) => None,
+    };
+    for report in &mut reports {
+        let (content, new_content) = match rewrite(report, &removals, &front_matter) {
+            Ok(contents) => contents,
+            Err(err) => {
+                failure = failure.or(Some(record_failure(report, err)));
+                continue;
+            }
+        };
+        let new_content = match fix_casing(
+            report,
+            new_content,
+            &config,
+            &front_matter,
+            cli.normalize_existing,
+            !non_interactive,
+            global.yes,
+        ) {
+            Ok(fixed) => fixed,
+            Err(err) => {
+                failure = failure.or(Some(record_failure(report, err)));
+                continue;
+            }
+        };
+        let new_content = if cli.edit {
+            match edit_front_matter(&report.path, &new_content) {
+                Ok(Some(edited)) => edited,
+                Ok(None) => {
+                    info!(
+                        "{}",
+                        tr!("   Skipped {:?}", "   {:?} をスキップしました", report.path)
+                    );
+                    continue;
+                }
+                Err(err) => {
+                    failure = failure.or(Some(record_failure(report, err)));
+                    continue;
+                }
+            }
+        } else {
+            new_content
+        };
+        // 変える内容があれば、pre_write フックに確かめさせる (--print でも同じ内容を出す)
+        let new_content = if new_content != content {
+            let checked = match &recorder {
+                Some(recorder) => recorder.pre_write(&report.path, &content, new_content),
+                None => hooks::pre_write(
+                    db_path,
+                    "add",
+                    &report.path,
+                    &content,
+                    new_content,
+                    &front_matter,
+                ),
+            };
+            match checked {
+                Ok(checked) => checked,
+                Err(err) => {
+                    failure = failure.or(Some(record_failure(report, err)));
+                    continue;
+                }
+            }
+        } else {
+            new_content
+        };
+        if let Some(terms) = &mut site_terms {
+            for tag in &report.tags {
+                if terms.insert(tag.to_lowercase()) {
+                    let warning = tr!(
+                        "'{}' is a new term of the taxonomy '{}' and gets its own page on the site",
+                        "'{}' はタクソノミー '{}' の新しい語で、サイトに一覧のページが作られます",
+                        tag,
+                        front_matter.tag_key
+                    );
+                    warn!("⚠️  {}", warning);
+                    warnings.push(warning);
+                }
+            }
+        }
+        if cli.print {
+            // 本文は読み込まずに、ファイルからそのまま出す
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(new_content.as_bytes())?;
+            let mut file = File::open(&report.path)?;
+            file.seek(SeekFrom::Start(content.len() as u64))?;
+            io::copy(&mut file, &mut stdout)?;
+            would_change |= new_content != content;
+            continue;
+        }
+        if cli.editor_protocol {
+            would_change |= new_content != content;
+            editor_files.push(EditorFile::new(
+                report.path.clone(),
+                &content,
+                &new_content,
+                &front_matter,
+                report.tags.clone(),
+                report.removed.clone(),
+            ));
+            continue;
+        }
+        if new_content == content {
+            info!(
+                "{}",
+                tr!(
+                    "   {:?} already has these tags",
+                    "   {:?} にはすでにこれらのタグが付いています",
+                    report.path
+                )
+            );
+            continue;
+        }
+        if cli.diff {
+            print_diff(&report.path, &content, &new_content);
+        }
+        // --diff なら差分、--confirm だけならタグの一覧を見せて確認する
+        if cli.diff || cli.confirm {
+            let prompt = if cli.diff {
+                tr!(
+                    "Write changes to {:?}?",
+                    "{:?} に書き込みますか?",
+                    report.path
+                )
+            } else if !report.removed.is_empty() {
+                tr!(
+                    "Add tags {:?} to {:?} and remove {:?}?",
+                    "{1:?} にタグ {0:?} を追加し、{2:?} を外しますか?",
+                    report.tags,
+                    report.path,
+                    report.removed
+                )
+            } else {
+                tr!(
+                    "Add tags {:?} to {:?}?",
+                    "{1:?} にタグ {0:?} を追加しますか?",
+                    report.tags,
+                    report.path
+                )
+            };
+            if !confirm(&prompt, true, global.yes)? {
+                info!(
+                    "{}",
+                    tr!("   Skipped {:?}", "   {:?} をスキップしました", report.path)
+                );
+                continue;
+            }
+        }
+        // 実際にファイルへ足したタグ (すでに付いていたタグは数えない)
+        let added: Vec<String> = match (
+            content_tags(&content, &front_matter),
+            content_tags(&new_content, &front_matter),
+        ) {
+            (Ok(before), Ok(after)) => after.into_iter().filter(|t| !before.contains(t)).collect(),
+            _ => Vec::new(),
+        };
+        let recorder = recorder
+            .as_mut()
+            .expect("--print and --editor-protocol do not write");
+        let written = if cli.verify {
+            recorder.write_file_verified(&report.path, &content, new_content, &front_matter)
+        } else {
+            recorder.write_file(&report.path, &content, new_content)
+        };
+        if let Err(err) = written {
+            failure = failure.or(Some(record_failure(report, err)));
+            continue;
+        }
+        report.modified = true;
+        used.extend(added);
+
+        if !report.tags.is_empty() {
+            info!(
+                "{}",
+                tr!(
+                    "✅ Successfully added tags to {:?}: {:?}",
+                    "✅ {:?} にタグを追加しました: {:?}",
+                    report.path,
+                    report.tags
+                )
+            );
+        }
+        if !report.removed.is_empty() {
+            info!(
+                "{}",
+                tr!(
+                    "✅ Removed tags from {:?}: {:?}",
+                    "✅ {:?} からタグを外しました: {:?}",
+                    report.path,
+                    report.removed
+                )
+            );
+        }
+        if !report.casing_fixed.is_empty() {
+            info!(
+                "{}",
+                tr!(
+                    "✅ Fixed tag casing in {:?}: {}",
+                    "✅ {:?} のタグの表記を直しました: {}",
+                    report.path,
+                    report.casing_fixed.join(", ")
+                )
+            );
+        }
+    }
+
+    if !warnings.is_empty() {
+        warn!("{}", tr!("⚠️  Warnings:", "⚠️  警告:"));
+        for warning in &warnings {
+            warn!("   - {}", warning);
+        }
+    }
+
+    // 書き換えたファイルに足したタグだけ使用回数を数える (何も変わらなければDBは書き換えない)
+    if let Some(recorder) = recorder {
+        if record_usage(&mut config, &used) {
+            save_config(db_path, &config)?;
+        }
+        recorder.finish()?;
+    }
+
+    let modified_any = reports.iter().any(|report| report.modified);
+    let summary = Summary::new(&reports, unknown_tags);
+    if global.output == OutputFormat::Json {
+        let report = Report {
+            db_updated: config_updated,
+            files: reports,
+            warnings,
+            summary,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if cli.editor_protocol {
+        let report = EditorReport::new(config_updated, editor_files);
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if !cli.print {
+        summary.print();
+    }
+    if let Some(code) = failure {
+        return Ok(code.exit());
+    }
+    Ok(if modified_any || would_change {
+        Exit::Success
+    } else {
+        Exit::Unchanged
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cli, Command};
+    use clap::{CommandFactory, Parser};
+
+    fn expand(args: &[&str]) -> Vec<String> {
+        let mut command = Cli::command();
+        command.build();
+        let args = std::iter::once("mdtagger").chain(args.iter().copied());
+        expand_removals(&command, args.map(OsString::from).collect())
+            .into_iter()
+            .skip(1)
+            .map(|arg| arg.into_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn dashed_tags_after_file_are_removals() {
+        assert_eq!(
+            expand(&["add", "a.md", "rust", "-draft"]),
+            ["add", "a.md", "rust", "--remove=draft"]
+        );
+        // 短いオプションと同じ綴りでも、FILE より後なら外すタグ
+        assert_eq!(
+            expand(&["add", "a.md", "-y", "-vq"]),
+            ["add", "a.md", "--remove=y", "--remove=vq"]
+        );
+    }
+
+    #[test]
+    fn short_flags_before_file_stay_flags() {
+        assert_eq!(
+            expand(&["-v", "add", "-y", "a.md", "-cli"]),
+            ["-v", "add", "-y", "a.md", "--remove=cli"]
+        );
+        assert_eq!(expand(&["add", "-vy", "a.md"]), ["add", "-vy", "a.md"]);
+        // 短いオプションにない文字を含めば、FILE より前でも外すタグ
+        assert_eq!(
+            expand(&["add", "-draft", "a.md"]),
+            ["add", "--remove=draft", "a.md"]
+        );
+    }
+
+    #[test]
+    fn long_options_and_their_values_are_kept() {
+        assert_eq!(
+            expand(&["add", "--db", "-tags.json", "a.md", "--yes"]),
+            ["add", "--db", "-tags.json", "a.md", "--yes"]
+        );
+        assert_eq!(
+            expand(&["add", "a.md", "--remove", "-x", "--remove=y"]),
+            ["add", "a.md", "--remove", "-x", "--remove=y"]
+        );
+    }
+
+    #[test]
+    fn arguments_after_double_dash_or_outside_add_are_kept() {
+        assert_eq!(
+            expand(&["add", "a.md", "--", "-draft"]),
+            ["add", "a.md", "--", "-draft"]
+        );
+        assert_eq!(expand(&["find", "-draft"]), ["find", "-draft"]);
+        assert_eq!(expand(&["-y", "list"]), ["-y", "list"]);
+    }
+
+    #[test]
+    fn expanded_arguments_parse() {
+        let args = expand(&["-q", "add", "-y", "a.md", "rust", "-y", "-draft"]);
+        let cli = Cli::try_parse_from(std::iter::once("mdtagger".to_string()).chain(args)).unwrap();
+        assert!(cli.global.yes);
+        let Some(Command::Add(add)) = cli.command else {
+            panic!("not the add command");
+        };
+        assert_eq!(add.path, PathBuf::from("a.md"));
+        assert_eq!(add.tags, ["rust"]);
+        assert_eq!(add.remove, ["y", "draft"]);
+    }
+
+    fn parse_add(args: &[&str]) -> AddArgs {
+        let args = std::iter::once("mdtagger").chain(args.iter().copied());
+        match Cli::try_parse_from(args).unwrap().command {
+            Some(Command::Add(add)) => add,
+            _ => panic!("not the add command"),
+        }
+    }
+
+    #[test]
+    fn auto_accept_does_not_take_the_next_argument() {
+        let add = parse_add(&["add", "a.md", "--auto-accept", "rust"]);
+        assert_eq!(add.auto_accept, Some(1));
+        assert_eq!(add.tags, ["rust"]);
+
+        let add = parse_add(&["add", "--auto-accept", "a.md", "rust"]);
+        assert_eq!(add.auto_accept, Some(1));
+        assert_eq!(add.path, PathBuf::from("a.md"));
+        assert_eq!(add.tags, ["rust"]);
+
+        let add = parse_add(&["add", "a.md", "rust", "--auto-accept=2"]);
+        assert_eq!(add.auto_accept, Some(2));
+        assert!(parse_add(&["add", "a.md", "rust"]).auto_accept.is_none());
+    }
+}
@@ -0,0 +1,266 @@
+// --- サブコマンド: duplicates (重複したノートのタグを揃える) ---
+//
+// ディレクトリの Markdown ファイルのうち、タイトルが同じか本文がよく似ている (MinHash で見積もった Jaccard 係数が
+// --threshold 以上の) 組を探し、片方にしか付いていないタグを表示する。二重に取り込んでメタデータだけが違うノートを見つける。
+// --copy-tags なら確認してから、組の両方に相手のタグを足す。
+
+use anyhow::{Result, bail};
+use clap::Args;
+use log::info;
+use mdtagger::db::TagDatabase;
+use mdtagger::markdown::{FrontMatter, add_tags, read_head};
+use mdtagger::prompt::confirm;
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::{GlobalArgs, OutputFormat};
+use crate::exit::Exit;
+use crate::index::FileIndex;
+use crate::minhash;
+use crate::undo::Recorder;
+
+#[derive(Args)]
+pub struct DuplicatesArgs {
+    /// 探すディレクトリ
+    #[arg(short = 'C', long = "dir", value_name = "DIR", default_value = ".")]
+    dir: PathBuf,
+
+    /// 本文をよく似ているとみなす類似度 (0.0 から 1.0)
+    #[arg(long, value_name = "SCORE", default_value_t = 0.8)]
+    threshold: f64,
+
+    /// 組ごとに確認してから、両方のファイルに相手のタグを足す (--yes なら確認しない)
+    #[arg(long)]
+    copy_tags: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DuplicatesReport {
+    /// 似ているファイルの組 (類似度の高い順)
+    pairs: Vec<DuplicatePair>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DuplicatePair {
+    /// DIR からの相対パス (区切りは /)
+    files: [String; 2],
+    /// 本文の類似度 (見積もった Jaccard 係数)
+    similarity: f64,
+    /// タイトル (Front Matter の title か、最初の見出し) が同じか
+    same_title: bool,
+    /// それぞれのファイルに足すと相手と揃うタグ
+    missing: [Vec<String>; 2],
+}
+
+/// --output json で出力する内容の JSON Schema
+pub fn output_schema() -> schemars::Schema {
+    crate::schema::for_output::<DuplicatesReport>()
+}
+
+/// 比べるためのタイトル (小文字にし、文字と数字だけを残す)
+///
+/// Front Matter の title か、本文の最初の見出し (# ...)。index.md や README.md のように
+/// 別のノートでもよく重なるので、ファイル名は使わない。
+fn title_key(front_matter: &FrontMatter, body: &str) -> String {
+    let title = match front_matter.get("title") {
+        Some(Value::String(title)) => title.as_str(),
+        _ => body
+            .lines()
+            .find_map(|line| line.strip_prefix("# "))
+            .unwrap_or_default(),
+    };
+    title
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+pub fn run(global: &GlobalArgs, args: &DuplicatesArgs) -> Result<Exit> {
+    if !(0.0..=1.0).contains(&args.threshold) {
+        bail!(tr!(
+            "--threshold must be between 0.0 and 1.0",
+            "--threshold は 0.0 から 1.0 の間で指定してください"
+        ));
+    }
+    if args.copy_tags && !global.interactive && !global.yes {
+        bail!(tr!(
+            "--copy-tags needs a terminal (add --yes to copy without asking)",
+            "--copy-tags は端末でのみ使えます (確認せずに書き換えるなら --yes を付けてください)"
+        ));
+    }
+    let db = TagDatabase::new(global.load_db()?);
+    let index = FileIndex::build(&args.dir, &global.settings, &db, global.db_path())?;
+
+    let mut titles = Vec::new();
+    let mut signatures = Vec::new();
+    for file in &index.files {
+        let content = fs::read_to_string(&file.path).unwrap_or_default();
+        let (front_matter, body) = FrontMatter::parse(&content).unwrap_or_default();
+        titles.push(title_key(&front_matter, body));
+        signatures.push(minhash::signature(body));
+    }
+
+    // 本文の似ている候補と、タイトルの同じ組
+    let mut candidates = minhash::candidates(&signatures);
+    for i in 0..titles.len() {
+        for j in i + 1..titles.len() {
+            if !titles[i].is_empty() && titles[i] == titles[j] {
+                candidates.insert((i, j));
+            }
+        }
+    }
+    let mut pairs: Vec<(usize, usize, f64, bool)> = candidates
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let similarity = match (&signatures[i], &signatures[j]) {
+                (Some(a), Some(b)) => minhash::similarity(a, b),
+                _ => 0.0,
+            };
+            let same_title = !titles[i].is_empty() && titles[i] == titles[j];
+            (similarity >= args.threshold || same_title).then_some((i, j, similarity, same_title))
+        })
+        .collect();
+    pairs.sort_by(|a, b| {
+        b.2.total_cmp(&a.2)
+            .then_with(|| index.files[a.0].path.cmp(&index.files[b.0].path))
+            .then_with(|| index.files[a.1].path.cmp(&index.files[b.1].path))
+    });
+
+    let missing = |from: usize, to: usize| -> Vec<String> {
+        index.files[from]
+            .tags
+            .iter()
+            .filter(|tag| !index.files[to].tags.contains(tag))
+            .cloned()
+            .collect()
+    };
+    let report = DuplicatesReport {
+        pairs: pairs
+            .iter()
+            .map(|&(i, j, similarity, same_title)| DuplicatePair {
+                files: [
+                    index.relative(&index.files[i].path),
+                    index.relative(&index.files[j].path),
+                ],
+                similarity: (similarity * 100.0).round() / 100.0,
+                same_title,
+                missing: [missing(j, i), missing(i, j)],
+            })
+            .collect(),
+    };
+
+    if global.output != OutputFormat::Json {
+        if report.pairs.is_empty() {
+            info!(
+                "{}",
+                tr!(
+                    "No duplicate notes in {} files",
+                    "{} ファイルに重複したノートはありません",
+                    index.files.len()
+                )
+            );
+        }
+        for pair in &report.pairs {
+            let reason = if pair.same_title {
+                tr!(
+                    "same title, {}% similar",
+                    "タイトルが同じ、類似度 {}%",
+                    (pair.similarity * 100.0).round()
+                )
+            } else {
+                tr!(
+                    "{}% similar",
+                    "類似度 {}%",
+                    (pair.similarity * 100.0).round()
+                )
+            };
+            println!("{} <-> {} ({})", pair.files[0], pair.files[1], reason);
+            for (file, missing) in pair.files.iter().zip(&pair.missing) {
+                if !missing.is_empty() {
+                    println!(
+                        "   {}",
+                        tr!(
+                            "add to {}: {}",
+                            "{} に足すタグ: {}",
+                            file,
+                            missing.join(", ")
+                        )
+                    );
+                }
+            }
+        }
+    }
+
+    let mut modified = 0;
+    if args.copy_tags {
+        // 同じファイルが複数の組に入ることがあるので、足すタグをファイルごとにまとめてから1回で書く
+        let mut additions: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for (&(i, j, _, _), pair) in pairs.iter().zip(&report.pairs) {
+            if pair.missing.iter().all(Vec::is_empty) {
+                continue;
+            }
+            let prompt = tr!(
+                "Copy tags between {} and {}?",
+                "{} と {} のタグを揃えますか?",
+                pair.files[0],
+                pair.files[1]
+            );
+            if !confirm(&prompt, false, global.yes)? {
+                continue;
+            }
+            for (n, missing) in [i, j].into_iter().zip(&pair.missing) {
+                let tags = additions.entry(n).or_default();
+                for tag in missing {
+                    if !tags.contains(tag) {
+                        tags.push(tag.clone());
+                    }
+                }
+            }
+        }
+        let mut recorder = Recorder::start(
+            "duplicates",
+            global.db_path(),
+            &global.settings.front_matter(),
+        );
+        for (n, tags) in &additions {
+            if tags.is_empty() {
+                continue;
+            }
+            let path = &index.files[*n].path;
+            let options = global.settings.front_matter_for(path);
+            let content = read_head(path, &options)?;
+            let new_content =
+                add_tags(&content, tags, &options).map_err(|err| err.in_file(path))?;
+            if new_content == content {
+                continue;
+            }
+            let new_content = recorder.pre_write(path, &content, new_content)?;
+            recorder.write_file(path, &content, new_content)?;
+            modified += 1;
+        }
+        recorder.finish()?;
+        info!(
+            "{}",
+            tr!(
+                "✅ Copied tags into {} files",
+                "✅ {} ファイルにタグを足しました",
+                modified
+            )
+        );
+    }
+
+    if global.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(if args.copy_tags && modified == 0 {
+        Exit::Unchanged
+    } else {
+        Exit::Success
+    })
+}
@@ -0,0 +1,168 @@
+// --- サブコマンド: export (静的サイト向けのタグの索引) ---
+//
+// ディレクトリの Markdown ファイルを読み、タグごとにページ (パス・タイトル・日付) を並べた JSON を書き出す。
+// 静的サイトのタグのページや、ブラウザの中で動く検索の索引にそのまま使える形にする。
+// Front Matter だけを読み、本文は読まない。
+
+use anyhow::{Context, Result};
+use clap::Args;
+use log::{info, warn};
+use mdtagger::markdown::{collect_markdown_files, read_front_matter};
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::GlobalArgs;
+use crate::exit::Exit;
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// 索引にするディレクトリ (ファイルも可)
+    #[arg(value_name = "DIR")]
+    path: PathBuf,
+
+    /// 書き出すファイル (- なら標準出力)
+    #[arg(short, long = "out", value_name = "FILE", default_value = "tags.json")]
+    out: PathBuf,
+
+    /// タイトルを読む Front Matter のキー (なければファイル名を使う)
+    #[arg(long, value_name = "KEY", default_value = "title")]
+    title_key: String,
+
+    /// 日付を読む Front Matter のキー
+    #[arg(long, value_name = "KEY", default_value = "date")]
+    date_key: String,
+
+    /// 下書き (draft: true) のページも入れる
+    #[arg(long)]
+    drafts: bool,
+}
+
+impl ExportArgs {
+    /// 標準出力を結果の出力に使うか (メッセージは標準エラー出力へ回す)
+    pub fn prints_index(&self) -> bool {
+        self.out.as_os_str() == "-"
+    }
+}
+
+/// タグの付いたページ
+#[derive(Serialize, JsonSchema, Clone)]
+struct Page {
+    /// DIR からの相対パス (区切りは /)
+    path: String,
+    title: String,
+    /// Front Matter に書かれたままの日付
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date: Option<String>,
+}
+
+/// タグ名 -> 日付の新しい順のページ
+type TagIndex = BTreeMap<String, Vec<Page>>;
+
+/// export が書き出す内容の JSON Schema
+pub fn output_schema() -> schemars::Schema {
+    crate::schema::for_output::<TagIndex>()
+}
+
+/// 文字列・数値・真偽値を文字列にする (日付は YAML でも TOML でも文字列として読める)
+fn scalar(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    parts.join("/")
+}
+
+pub fn run(global: &GlobalArgs, args: &ExportArgs) -> Result<Exit> {
+    let files = collect_markdown_files(&args.path)?;
+    let root = if args.path.is_dir() {
+        args.path.as_path()
+    } else {
+        args.path.parent().unwrap_or(Path::new(""))
+    };
+
+    let mut index = TagIndex::new();
+    let mut pages = 0;
+    for path in &files {
+        let front_matter = match read_front_matter(path) {
+            Ok((front_matter, _)) => front_matter,
+            Err(err) => {
+                warn!(
+                    "⚠️  {}",
+                    tr!("Skipped {:?}: {}", "{:?} をスキップしました: {}", path, err)
+                );
+                continue;
+            }
+        };
+        if !args.drafts && front_matter.get("draft") == Some(&Value::Bool(true)) {
+            continue;
+        }
+        // Jekyll のコレクションごとのキーと defaults を使う
+        let options = global.settings.front_matter_for(path);
+        let mut tags = front_matter.list(&options.tag_key);
+        if front_matter.get(&options.tag_key).is_none()
+            && let Some(site) = &global.settings.jekyll_site
+        {
+            tags = site
+                .default_list(path, &options.tag_key)
+                .unwrap_or_default();
+        }
+        if tags.is_empty() {
+            continue;
+        }
+        let page = Page {
+            path: relative(root, path),
+            title: scalar(front_matter.get(&args.title_key)).unwrap_or_else(|| {
+                path.file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            }),
+            date: scalar(front_matter.get(&args.date_key)),
+        };
+        pages += 1;
+        for tag in tags {
+            let entries = index.entry(tag).or_default();
+            if !entries.iter().any(|p| p.path == page.path) {
+                entries.push(page.clone());
+            }
+        }
+    }
+    // 日付の新しい順 (日付のないページは後ろ)、同じならパスの順
+    for entries in index.values_mut() {
+        entries.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| a.path.cmp(&b.path)));
+    }
+
+    let json = serde_json::to_string_pretty(&index)? + "\n";
+    if args.prints_index() {
+        print!("{}", json);
+    } else {
+        fs::write(&args.out, json)
+            .with_context(|| tr!("Failed to write {:?}", "{:?} に書き込めません", args.out))?;
+        info!(
+            "{}",
+            tr!(
+                "✅ Wrote {} tags on {} pages to {:?}",
+                "✅ {1} ページの {0} 個のタグを {2:?} に書き出しました",
+                index.len(),
+                pages,
+                args.out
+            )
+        );
+    }
+    Ok(Exit::Success)
+}
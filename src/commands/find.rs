@@ -0,0 +1,341 @@
+// --- サブコマンド: find (タグでファイルを探す) ---
+//
+// ディレクトリの Markdown ファイルのうち、検索式 (rust AND (cli OR tui) AND NOT draft。書式は query.rs) に合うものを表示する。
+// タグはエイリアスでも書け、入れ子のタグ (lang/rust) は親のタグ (lang) でも見つかる。
+// タグを指定しなければ、付いているタグとファイルの数の一覧を表示する。
+// --untagged ならタグのない (キーがないか空の) ファイルを表示し、--pick でそれを順に pick で付けていく。
+// --output script-filter なら Alfred / Raycast の Script Filter の JSON を出し、ランチャーからファイルを開ける。
+// 一度読んだディレクトリは索引 (index.rs) のタグ -> ファイルだけで答え、ファイルは読まない。
+
+use anyhow::{Result, bail};
+use clap::Args;
+use log::info;
+use mdtagger::db::TagDatabase;
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+use super::pick::{self, PickArgs};
+use super::{GlobalArgs, OutputFormat};
+use crate::exit::Exit;
+use crate::index::{FileIndex, IndexedFile};
+use crate::query::Query;
+use crate::table::Table;
+
+#[derive(Args)]
+pub struct FindArgs {
+    /// 検索式 (例: "rust AND (cli OR tui) AND NOT draft"。並べたタグは AND。なければタグの一覧を表示する)
+    #[arg(value_name = "QUERY")]
+    query: Vec<String>,
+
+    /// 探すディレクトリ (ファイルも可)
+    #[arg(short = 'C', long = "dir", value_name = "DIR", default_value = ".")]
+    dir: PathBuf,
+
+    /// DIR (省略すれば --dir) の、タグのキーがないか空のファイルを表示する
+    #[arg(long, value_name = "DIR", num_args = 0..=1, conflicts_with = "query")]
+    untagged: Option<Option<PathBuf>>,
+
+    /// --untagged で見つけたファイルを、1つずつ pick でタグを付けていく
+    #[arg(long, requires = "untagged")]
+    pick: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct FindReport {
+    /// タグを指定しなかったときの、タグごとのファイルの数 (多い順)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<TagCount>,
+    /// 検索式に合うファイル (--untagged ならタグのないファイル)
+    files: Vec<FoundFile>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct TagCount {
+    tag: String,
+    files: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct FoundFile {
+    /// DIR からの相対パス (区切りは /)
+    path: String,
+    tags: Vec<String>,
+}
+
+/// --output json で出力する内容の JSON Schema
+pub fn output_schema() -> schemars::Schema {
+    crate::schema::for_output::<FindReport>()
+}
+
+// --- Script Filter (Alfred の形式。Raycast の Script Filter も同じ形を読む) ---
+#[derive(Serialize)]
+struct ScriptFilter {
+    items: Vec<ScriptFilterItem>,
+}
+
+#[derive(Serialize)]
+struct ScriptFilterItem {
+    uid: String,
+    /// file なら Alfred がファイルとして扱う (⌘ で Finder に表示など)
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    kind: Option<&'static str>,
+    title: String,
+    subtitle: String,
+    /// 選んだときに次の動作へ渡す値 (ファイルなら絶対パス、タグならタグ名)
+    arg: String,
+    /// Tab で入力欄に入れる文字列
+    autocomplete: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon: Option<ScriptFilterIcon>,
+    /// false なら選べない項目 (検索式の誤りの表示)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    valid: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct ScriptFilterIcon {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    path: String,
+}
+
+/// tag が term (DBの正式名に揃えたもの) そのものか、その下の入れ子のタグか (大文字・小文字は区別しない)
+fn tag_matches(tag: &str, term: &str) -> bool {
+    let tag = tag.to_lowercase();
+    let term = term.to_lowercase();
+    tag == term || tag.starts_with(&format!("{}/", term))
+}
+
+/// 入力のタグを DB の正式名に揃える (DBになければそのまま)
+fn canonical(db: &TagDatabase, input: &str) -> String {
+    let input = input.trim().trim_start_matches('#');
+    db.lookup(input)
+        .map_or_else(|| input.to_string(), |entry| entry.name.clone())
+}
+
+/// 検索式に合うファイル (索引のタグ -> ファイルから引く)
+fn matches<'a>(index: &'a FileIndex, query: &Query) -> Vec<&'a IndexedFile> {
+    let all = (0..index.files.len()).collect();
+    let tagged = |term: &str| {
+        index
+            .tags
+            .iter()
+            .filter(|(tag, _)| tag_matches(tag, term))
+            .flat_map(|(_, files)| files.iter().copied())
+            .collect()
+    };
+    query
+        .select(&all, &tagged)
+        .into_iter()
+        .map(|i| &index.files[i])
+        .collect()
+}
+
+fn file_item(index: &FileIndex, file: &IndexedFile) -> ScriptFilterItem {
+    let path = fs::canonicalize(&file.path).unwrap_or_else(|_| file.path.clone());
+    let path = path.to_string_lossy().into_owned();
+    let title = file
+        .path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    ScriptFilterItem {
+        uid: path.clone(),
+        kind: Some("file"),
+        subtitle: if file.tags.is_empty() {
+            index.relative(&file.path)
+        } else {
+            format!("{}  #{}", index.relative(&file.path), file.tags.join(" #"))
+        },
+        autocomplete: title.clone(),
+        title,
+        icon: Some(ScriptFilterIcon {
+            kind: "fileicon",
+            path: path.clone(),
+        }),
+        arg: path,
+        valid: None,
+    }
+}
+
+fn tag_item(tag: &str, files: usize) -> ScriptFilterItem {
+    ScriptFilterItem {
+        uid: format!("tag:{}", tag),
+        kind: None,
+        title: tag.to_string(),
+        subtitle: tr!("{} files", "{} ファイル", files),
+        arg: tag.to_string(),
+        // 続けて打てば、そのタグのファイルに絞り込める
+        autocomplete: format!("{} ", tag),
+        icon: None,
+        valid: None,
+    }
+}
+
+/// タグのないファイルを表示する (--pick なら順にタグを付ける)
+fn untagged(global: &GlobalArgs, args: &FindArgs, index: &FileIndex) -> Result<Exit> {
+    let found: Vec<&IndexedFile> = index.files.iter().filter(|f| f.tags.is_empty()).collect();
+    match global.output {
+        OutputFormat::Text if args.pick => {
+            info!(
+                "{}",
+                tr!(
+                    "{} files have no tags (Ctrl+C to stop)",
+                    "タグのないファイルが {} 個あります (Ctrl+C で止めます)",
+                    found.len()
+                )
+            );
+            for (i, file) in found.iter().enumerate() {
+                info!("[{}/{}] {}", i + 1, found.len(), file.path.display());
+                pick::run(global, &PickArgs::for_file(file.path.clone()))?;
+            }
+        }
+        OutputFormat::Text => {
+            for file in &found {
+                println!("{}", file.path.display());
+            }
+        }
+        OutputFormat::Json => {
+            let report = FindReport {
+                tags: Vec::new(),
+                files: found
+                    .iter()
+                    .map(|file| FoundFile {
+                        path: index.relative(&file.path),
+                        tags: Vec::new(),
+                    })
+                    .collect(),
+            };
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        OutputFormat::ScriptFilter => {
+            let items = found.iter().map(|file| file_item(index, file)).collect();
+            println!("{}", serde_json::to_string(&ScriptFilter { items })?);
+        }
+        format @ (OutputFormat::Table | OutputFormat::Csv | OutputFormat::Markdown) => {
+            let mut table = Table::new(&["path"]);
+            for file in &found {
+                table.row(vec![index.relative(&file.path)]);
+            }
+            table.print(format);
+        }
+    }
+    Ok(Exit::Success)
+}
+
+pub fn run(global: &GlobalArgs, args: &FindArgs) -> Result<Exit> {
+    if args.pick && !global.interactive {
+        bail!(tr!(
+            "--pick needs a terminal",
+            "--pick は端末でのみ使えます"
+        ));
+    }
+    if args.pick && global.output != OutputFormat::Text {
+        bail!(tr!(
+            "--pick cannot be combined with --output",
+            "--pick と --output は同時に使えません"
+        ));
+    }
+    let db = TagDatabase::new(global.load_db()?);
+    let dir = args
+        .untagged
+        .as_ref()
+        .and_then(Option::as_ref)
+        .unwrap_or(&args.dir);
+    let index = FileIndex::load(dir, &global.settings, &db, global.db_path())?;
+    if args.untagged.is_some() {
+        return untagged(global, args, &index);
+    }
+    let input = args.query.join(" ");
+    let query = match Query::parse(&input) {
+        Ok(query) => query.map(|q| q.map_tags(&|tag| canonical(&db, &tag))),
+        // ランチャーでは打っている途中の式も届くので、誤りは選べない項目として見せる
+        Err(err) if global.output == OutputFormat::ScriptFilter => {
+            let item = ScriptFilterItem {
+                uid: "error".to_string(),
+                kind: None,
+                title: err.to_string(),
+                subtitle: input.clone(),
+                arg: String::new(),
+                autocomplete: input,
+                icon: None,
+                valid: Some(false),
+            };
+            let items = vec![item];
+            println!("{}", serde_json::to_string(&ScriptFilter { items })?);
+            return Ok(Exit::Success);
+        }
+        Err(err) => return Err(err),
+    };
+
+    // 検索式がなければタグの一覧 (ファイルの多い順)
+    let mut tags: Vec<(&str, usize)> = Vec::new();
+    let mut found: Vec<&IndexedFile> = Vec::new();
+    match &query {
+        None => {
+            tags = index.tag_counts().into_iter().collect();
+            tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        }
+        Some(query) => found = matches(&index, query),
+    }
+
+    match global.output {
+        OutputFormat::Text => {
+            for (tag, files) in &tags {
+                println!("{}\t{}", tag, files);
+            }
+            for file in &found {
+                println!("{}", file.path.display());
+            }
+        }
+        OutputFormat::Json => {
+            let report = FindReport {
+                tags: tags
+                    .iter()
+                    .map(|(tag, files)| TagCount {
+                        tag: tag.to_string(),
+                        files: *files,
+                    })
+                    .collect(),
+                files: found
+                    .iter()
+                    .map(|file| FoundFile {
+                        path: index.relative(&file.path),
+                        tags: file.tags.clone(),
+                    })
+                    .collect(),
+            };
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        OutputFormat::ScriptFilter => {
+            let items = tags
+                .iter()
+                .map(|(tag, files)| tag_item(tag, *files))
+                .chain(found.iter().map(|file| file_item(&index, file)))
+                .collect();
+            println!("{}", serde_json::to_string(&ScriptFilter { items })?);
+        }
+        // 検索式がなければタグの一覧、あればファイルの一覧の表
+        format @ (OutputFormat::Table | OutputFormat::Csv | OutputFormat::Markdown) => {
+            let table = if query.is_none() {
+                let mut table = Table::new(&["tag", "files"]);
+                for (tag, files) in &tags {
+                    table.row(vec![tag.to_string(), files.to_string()]);
+                }
+                table
+            } else {
+                let mut table = Table::new(&["path", "tags"]);
+                for file in &found {
+                    table.row(vec![index.relative(&file.path), file.tags.join(", ")]);
+                }
+                table
+            };
+            table.print(format);
+        }
+    }
+    Ok(Exit::Success)
+}
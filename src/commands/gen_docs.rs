@@ -0,0 +1,233 @@
+// --- サブコマンド: gen-docs (man ページと Markdown のリファレンスと JSON Schema を作る) ---
+//
+// clap の引数定義や出力の型からそのまま作るので、ヘルプや実際の出力と食い違うことはない。
+// Homebrew や AUR などのパッケージに同梱するためのもの。
+
+use anyhow::{Context, Result};
+use clap::{Arg, Args, Command, ValueEnum};
+use log::info;
+use mdtagger::tr;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::exit::Exit;
+use crate::schema::SchemaKind;
+
+#[derive(Args)]
+pub struct GenDocsArgs {
+    /// 書き出すディレクトリ (man/ と markdown/ と schemas/ を作る)
+    #[arg(value_name = "DIR")]
+    out_dir: PathBuf,
+
+    /// man ページを作る (--man / --markdown / --schemas のどれも付けなければすべて作る)
+    #[arg(long)]
+    man: bool,
+
+    /// Markdown のリファレンスを作る
+    #[arg(long)]
+    markdown: bool,
+
+    /// タグデータベースと --output json の出力の JSON Schema を作る
+    #[arg(long)]
+    schemas: bool,
+}
+
+/// 自分自身と、隠していない子孫のサブコマンド (help は除く)
+fn all_commands(command: &Command) -> Vec<&Command> {
+    let mut commands = vec![command];
+    for sub in command
+        .get_subcommands()
+        .filter(|s| !s.is_hide_set() && s.get_name() != "help")
+    {
+        commands.extend(all_commands(sub));
+    }
+    commands
+}
+
+/// ファイル名などに使う名前 (mdtagger-add など)
+fn full_name(command: &Command) -> &str {
+    command
+        .get_display_name()
+        .unwrap_or_else(|| command.get_name())
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<()> {
+    fs::write(path, content)
+        .with_context(|| tr!("Failed to write {:?}", "{:?} に書き込めません", path))?;
+    info!("{}", tr!("Wrote {:?}", "{:?} を書き出しました", path));
+    Ok(())
+}
+
+fn write_man_pages(command: &Command, dir: &Path) -> Result<()> {
+    for cmd in all_commands(command) {
+        let man = clap_mangen::Man::new(cmd.clone());
+        let mut page = Vec::new();
+        man.render(&mut page)?;
+        write_file(&dir.join(man.get_filename()), &page)?;
+    }
+    Ok(())
+}
+
+/// 引数1つ分の説明 (* `--db <DB_PATH>`: 説明 (環境変数: ...))
+fn argument_line(arg: &Arg) -> String {
+    let value = arg
+        .get_value_names()
+        .map(|names| {
+            names
+                .iter()
+                .map(|n| format!("<{}>", n))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|_| arg.get_action().takes_values());
+    let mut flags = Vec::new();
+    if let Some(short) = arg.get_short() {
+        flags.push(format!("-{}", short));
+    }
+    if let Some(long) = arg.get_long() {
+        flags.push(format!("--{}", long));
+    }
+    let mut name = flags.join(", ");
+    if let Some(value) = value {
+        if !name.is_empty() {
+            name.push(' ');
+        }
+        name.push_str(&value);
+    }
+
+    let mut line = format!("* `{}`", name);
+    if let Some(help) = arg.get_long_help().or(arg.get_help()) {
+        let _ = write!(line, ": {}", help.to_string().replace('\n', " "));
+    }
+    if let Some(env) = arg.get_env() {
+        let _ = write!(
+            line,
+            "{}",
+            tr!(" (env: `{}`)", " (環境変数: `{}`)", env.to_string_lossy())
+        );
+    }
+    let defaults: Vec<_> = arg
+        .get_default_values()
+        .iter()
+        .map(|v| v.to_string_lossy())
+        .collect();
+    if !defaults.is_empty() && arg.get_action().takes_values() {
+        let _ = write!(
+            line,
+            "{}",
+            tr!(
+                " [default: `{}`]",
+                " [デフォルト: `{}`]",
+                defaults.join(",")
+            )
+        );
+    }
+    line
+}
+
+fn markdown_page(cmd: &Command) -> String {
+    let mut page = format!("# {}\n\n", full_name(cmd).replace('-', " "));
+    if let Some(about) = cmd.get_long_about().or(cmd.get_about()) {
+        let _ = writeln!(page, "{}\n", about);
+    }
+    let _ = writeln!(
+        page,
+        "```text\n{}\n```\n",
+        cmd.clone().render_usage().to_string().trim()
+    );
+
+    let args: Vec<&Arg> = cmd.get_arguments().filter(|a| !a.is_hide_set()).collect();
+    let positionals: Vec<String> = args
+        .iter()
+        .filter(|a| a.is_positional())
+        .map(|a| argument_line(a))
+        .collect();
+    let options: Vec<String> = args
+        .iter()
+        .filter(|a| !a.is_positional())
+        .map(|a| argument_line(a))
+        .collect();
+    if !positionals.is_empty() {
+        let _ = writeln!(
+            page,
+            "## {}\n\n{}\n",
+            tr!("Arguments", "引数"),
+            positionals.join("\n")
+        );
+    }
+    if !options.is_empty() {
+        let _ = writeln!(
+            page,
+            "## {}\n\n{}\n",
+            tr!("Options", "オプション"),
+            options.join("\n")
+        );
+    }
+
+    let subcommands: Vec<String> = cmd
+        .get_subcommands()
+        .filter(|s| !s.is_hide_set() && s.get_name() != "help")
+        .map(|s| {
+            format!(
+                "* [`{}`]({}.md): {}",
+                s.get_name(),
+                full_name(s),
+                s.get_about().map(|a| a.to_string()).unwrap_or_default()
+            )
+        })
+        .collect();
+    if !subcommands.is_empty() {
+        let _ = writeln!(
+            page,
+            "## {}\n\n{}\n",
+            tr!("Subcommands", "サブコマンド"),
+            subcommands.join("\n")
+        );
+    }
+    page.trim_end().to_string() + "\n"
+}
+
+fn write_markdown(command: &Command, dir: &Path) -> Result<()> {
+    for cmd in all_commands(command) {
+        let path = dir.join(format!("{}.md", full_name(cmd)));
+        write_file(&path, markdown_page(cmd).as_bytes())?;
+    }
+    Ok(())
+}
+
+/// --schema で表示するものと同じ JSON Schema を <名前>.schema.json に書き出す
+fn write_schemas(_: &Command, dir: &Path) -> Result<()> {
+    for kind in SchemaKind::value_variants() {
+        let schema = serde_json::to_string_pretty(&kind.schema())? + "\n";
+        write_file(
+            &dir.join(format!("{}.schema.json", kind.name())),
+            schema.as_bytes(),
+        )?;
+    }
+    Ok(())
+}
+
+/// command は build() 済みのルートのコマンド
+pub fn run(args: &GenDocsArgs, command: &Command) -> Result<Exit> {
+    let all = !(args.man || args.markdown || args.schemas);
+    let targets = [
+        (
+            "man",
+            args.man || all,
+            write_man_pages as fn(&Command, &Path) -> Result<()>,
+        ),
+        ("markdown", args.markdown || all, write_markdown),
+        ("schemas", args.schemas || all, write_schemas),
+    ];
+    for (name, enabled, write) in targets {
+        if !enabled {
+            continue;
+        }
+        let dir = args.out_dir.join(name);
+        fs::create_dir_all(&dir)
+            .with_context(|| tr!("Failed to create {:?}", "{:?} を作成できません", dir))?;
+        write(command, &dir)?;
+    }
+    Ok(Exit::Success)
+}
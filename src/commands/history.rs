@@ -0,0 +1,80 @@
+// --- サブコマンド: history (変更履歴を見る) ---
+
+use anyhow::Result;
+use clap::Args;
+use mdtagger::tr;
+use std::path::PathBuf;
+
+use super::{GlobalArgs, OutputFormat};
+use crate::exit::Exit;
+use crate::history::{self, HistoryEntry};
+
+#[derive(Args)]
+pub struct HistoryArgs {
+    /// このタグを追加・削除した変更だけを表示する
+    #[arg(long, value_name = "TAG")]
+    tag: Option<String>,
+
+    /// このファイルを書き換えた変更だけを表示する
+    #[arg(long, value_name = "FILE")]
+    file: Option<PathBuf>,
+
+    /// 表示する最大件数 (新しいものから)
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    limit: usize,
+}
+
+/// --output json で出力する内容の JSON Schema
+pub fn output_schema() -> schemars::Schema {
+    crate::schema::for_output::<Vec<HistoryEntry>>()
+}
+
+pub fn run(global: &GlobalArgs, args: &HistoryArgs) -> Result<Exit> {
+    // 履歴には絶対パスで記録している
+    let file = args
+        .file
+        .as_ref()
+        .map(|f| std::fs::canonicalize(f).unwrap_or_else(|_| f.clone()));
+
+    let entries: Vec<HistoryEntry> = history::read(global.db_path())?
+        .into_iter()
+        .rev()
+        .filter(|entry| args.tag.as_ref().is_none_or(|tag| entry.mentions_tag(tag)))
+        .filter(|entry| {
+            file.as_ref()
+                .is_none_or(|file| entry.files.iter().any(|f| &f.path == file))
+        })
+        .take(args.limit)
+        .collect();
+
+    if global.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(Exit::Success);
+    }
+
+    if entries.is_empty() {
+        println!("{}", tr!("No history", "履歴はありません"));
+    }
+    for entry in &entries {
+        println!("{}  {}", entry.timestamp, entry.operation);
+        for f in &entry.files {
+            let mut changes: Vec<String> = f.added.iter().map(|t| format!("+{}", t)).collect();
+            changes.extend(f.removed.iter().map(|t| format!("-{}", t)));
+            println!("   {}  {}", f.path.display(), changes.join(" "));
+        }
+        let db = &entry.db;
+        for tag in &db.tags_added {
+            println!("   db  +tag {}", tag);
+        }
+        for tag in &db.tags_removed {
+            println!("   db  -tag {}", tag);
+        }
+        for alias in &db.aliases_added {
+            println!("   db  +alias {}", alias);
+        }
+        for alias in &db.aliases_removed {
+            println!("   db  -alias {}", alias);
+        }
+    }
+    Ok(Exit::Success)
+}
@@ -0,0 +1,167 @@
+// --- サブコマンド: hook (git の pre-commit フック) ---
+//
+// hook pre-commit はステージした Markdown ファイルだけを、作業ツリーではなくインデックスの内容で検査する。
+// DBにないタグ・エイリアスで書いたタグ (非推奨の書き方)・大文字と小文字の違うタグ・使わなくなったタグがあればコミットを止める
+// (設定ファイルの [lint.rules] で warning にした規則は表示するだけ)。
+// hook install は .git/hooks/pre-commit にこのコマンドを呼ぶスクリプトを置く。
+
+use anyhow::{Context, Result, bail};
+use clap::{Args, Subcommand};
+use log::{info, warn};
+use mdtagger::db::TagDatabase;
+use mdtagger::markdown::FrontMatter;
+use mdtagger::tr;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use super::GlobalArgs;
+use super::lint::{Violation, error_count, inline_violations, print_violations, tag_violations};
+use crate::exit::Exit;
+use crate::git;
+use crate::rules::RuleSet;
+
+/// install が書くスクリプトの印 (これがなければ、ほかのツールのフックとみなして上書きしない)
+const MARKER: &str = "# mdtagger hook pre-commit";
+
+#[derive(Args)]
+pub struct HookArgs {
+    #[command(subcommand)]
+    action: HookAction,
+}
+
+#[derive(Subcommand)]
+enum HookAction {
+    /// ステージした Markdown ファイルのタグを検査し、問題があれば失敗する (pre-commit フックから呼ぶ)
+    PreCommit,
+    /// .git/hooks/pre-commit に、hook pre-commit を呼ぶスクリプトを置く
+    Install(InstallArgs),
+}
+
+#[derive(Args)]
+struct InstallArgs {
+    /// すでにほかの pre-commit フックがあっても上書きする
+    #[arg(long)]
+    force: bool,
+}
+
+/// ステージした (追加・変更・名前の変更をした) Markdown ファイル。パスはリポジトリの一番上から
+fn staged_markdown_files() -> Result<Vec<String>> {
+    let output = git::run(&[
+        "diff",
+        "--cached",
+        "--name-only",
+        "--diff-filter=ACMR",
+        "-z",
+    ])?;
+    Ok(git::split_paths(&output)
+        .filter(|name| name.ends_with(".md"))
+        .map(str::to_string)
+        .collect())
+}
+
+fn pre_commit(global: &GlobalArgs) -> Result<Exit> {
+    let files = staged_markdown_files()?;
+    if files.is_empty() {
+        return Ok(Exit::Success);
+    }
+    let db = TagDatabase::new(global.load_db()?);
+    let top = git::path(&["rev-parse", "--show-toplevel"])?;
+
+    let mut violations: Vec<Violation> = Vec::new();
+    for name in &files {
+        // 一部だけステージしたファイルもあるので、作業ツリーではなくインデックスの内容を見る
+        let staged = git::run(&["show", &format!(":{}", name)])?;
+        let content = String::from_utf8_lossy(&staged);
+        let path = top.join(name);
+        let options = global.settings.front_matter_for(&path);
+        let rules = RuleSet::for_path(&global.settings.lint, &path);
+        match FrontMatter::parse(&content) {
+            Ok((front_matter, _)) => violations.extend(
+                inline_violations(Path::new(name), &content, &db, &options, &rules).unwrap_or_else(
+                    || tag_violations(Path::new(name), &front_matter, &db, &options, &rules),
+                ),
+            ),
+            Err(err) => warn!(
+                "⚠️  {}",
+                tr!(
+                    "Skipped {}: invalid front matter ({})",
+                    "{} をスキップしました: Front Matter が不正です ({})",
+                    name,
+                    err
+                )
+            ),
+        }
+    }
+
+    print_violations(global, &violations)?;
+    // 警告だけならコミットは止めない
+    if error_count(&violations) == 0 {
+        return Ok(Exit::Success);
+    }
+    warn!(
+        "❌ {}",
+        tr!(
+            "Commit stopped: {} tag problems in {} staged files. Fix the tags (mdtagger add --normalize-existing fixes casing) and stage them again, or skip this check with git commit --no-verify",
+            "コミットを止めました: ステージした {1} ファイルにタグの問題が {0} 件あります。タグを直して (大文字・小文字は mdtagger add --normalize-existing で直せます) もう一度ステージするか、git commit --no-verify で検査を飛ばしてください",
+            error_count(&violations),
+            files.len()
+        )
+    );
+    Ok(Exit::Lint)
+}
+
+fn install(global: &GlobalArgs, args: &InstallArgs) -> Result<Exit> {
+    // worktree や core.hooksPath でも正しい場所を git に聞く
+    let hooks = git::path(&["rev-parse", "--git-path", "hooks"])?;
+    let path = hooks.join("pre-commit");
+    if let Ok(existing) = fs::read_to_string(&path)
+        && !existing.contains(MARKER)
+        && !args.force
+    {
+        bail!(tr!(
+            "{:?} already exists and was not written by mdtagger (pass --force to replace it)",
+            "{:?} はすでにあり、mdtagger が書いたものではありません (置き換えるなら --force を付けてください)",
+            path
+        ));
+    }
+
+    // インストールした実行ファイルを使い、--db を指定していればフックにも渡す
+    let exe = env::current_exe()?;
+    let mut command = format!("exec {}", shell_quote(&exe.to_string_lossy()));
+    if let Some(db) = &global.db {
+        let db = fs::canonicalize(db).unwrap_or_else(|_| db.clone());
+        command.push_str(&format!(" --db {}", shell_quote(&db.to_string_lossy())));
+    }
+    let script = format!("#!/bin/sh\n{}\n{} hook pre-commit\n", MARKER, command);
+    fs::create_dir_all(&hooks)
+        .with_context(|| tr!("Failed to create {:?}", "{:?} を作成できません", hooks))?;
+    fs::write(&path, script)
+        .with_context(|| tr!("Failed to write {:?}", "{:?} に書き込めません", path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    }
+    info!(
+        "{}",
+        tr!(
+            "✅ Installed the pre-commit hook at {:?}",
+            "✅ pre-commit フックを {:?} に置きました",
+            path
+        )
+    );
+    Ok(Exit::Success)
+}
+
+/// sh のための単一引用符での囲み
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+pub fn run(global: &GlobalArgs, args: &HookArgs) -> Result<Exit> {
+    match &args.action {
+        HookAction::PreCommit => pre_commit(global),
+        HookAction::Install(install_args) => install(global, install_args),
+    }
+}
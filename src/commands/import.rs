@@ -0,0 +1,291 @@
+// --- サブコマンド: import (ほかのツールから移すときのタグの取り込み) ---
+//
+// import notion は Notion の Markdown エクスポートのマルチセレクトのプロパティ (Tags など) を読み、
+// 値をタグのキーに書き込む。DBにない値は新しいタグとして登録する (整形ルールと入力の書き換え規則は add と同じ)。
+// import dendron は Dendron の階層をドットで書いたファイル名 (lang.rust.async.md) を入れ子のタグ (lang/rust/async) として
+// DBに登録し、--mirror ならそのファイルの Front Matter にも書き込む。
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use log::info;
+use mdtagger::db::{SlugConfig, TagDatabase, TagEntry, record_usage};
+use mdtagger::markdown::{FrontMatter, add_tags, collect_markdown_files, read_head};
+use mdtagger::normalize::Normalizer;
+use mdtagger::tr;
+use std::path::{Path, PathBuf};
+
+use super::GlobalArgs;
+use crate::exit::Exit;
+use crate::notion;
+use crate::undo::Recorder;
+
+#[derive(Args)]
+pub struct ImportArgs {
+    #[command(subcommand)]
+    source: ImportSource,
+}
+
+#[derive(Subcommand)]
+enum ImportSource {
+    /// Notion の Markdown エクスポートのマルチセレクトのプロパティをタグにする
+    Notion(NotionArgs),
+    /// Dendron のファイル名の階層 (lang.rust.async.md) を入れ子のタグ (lang/rust/async) として登録する
+    Dendron(DendronArgs),
+}
+
+#[derive(Args)]
+struct NotionArgs {
+    /// エクスポートを展開したディレクトリ (ファイルも可)
+    #[arg(value_name = "DIR")]
+    path: PathBuf,
+
+    /// タグにするプロパティの名前 (複数指定可。大文字・小文字は区別しない)
+    #[arg(long = "property", value_name = "NAME", default_values_t = ["Tags".to_string()])]
+    properties: Vec<String>,
+
+    /// 取り込んだプロパティの行 (と Front Matter のキー) を消す
+    #[arg(long)]
+    strip: bool,
+
+    /// ファイルとDBは書き換えずに、取り込むタグだけを表示する
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args)]
+struct DendronArgs {
+    /// Dendron の保管庫 (vault) のディレクトリ (ファイルも可)
+    #[arg(value_name = "DIR")]
+    path: PathBuf,
+
+    /// 登録したタグを、それぞれのファイルの Front Matter にも書き込む
+    #[arg(long)]
+    mirror: bool,
+
+    /// ファイルとDBは書き換えずに、登録するタグだけを表示する
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// Dendron のファイル名の階層を入れ子のタグにする (root.md と、階層のない名前は None)
+fn dendron_tag(path: &Path, slug: &SlugConfig) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    if stem == "root" || !stem.contains('.') {
+        return None;
+    }
+    let tag = slug.apply(&stem.split('.').collect::<Vec<_>>().join("/"));
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// DBのタグの名前にする (なければ整形ルール slug を通して登録する)
+fn map_value(
+    db: &mut TagDatabase,
+    slug: &SlugConfig,
+    value: &str,
+    new_tags: &mut Vec<String>,
+) -> String {
+    if let Some(entry) = db.lookup(value) {
+        return entry.name.clone();
+    }
+    let lower = value.to_lowercase();
+    if let Some(entry) = db.tags().iter().find(|e| e.name.to_lowercase() == lower) {
+        return entry.name.clone();
+    }
+    let name = slug.apply(value);
+    if let Some(entry) = db.lookup(&name) {
+        return entry.name.clone();
+    }
+    db.edit(|config| config.tags.push(TagEntry::new(&name)));
+    new_tags.push(name.clone());
+    name
+}
+
+/// プロパティの値と、プロパティを消した (strip でなければ元の) 内容
+fn take_properties(
+    content: &str,
+    properties: &[String],
+    tag_key: &str,
+    strip: bool,
+) -> Result<(Vec<String>, String)> {
+    let (mut front_matter, body) = FrontMatter::parse(content)?;
+    let mut values = Vec::new();
+    for property in properties {
+        if let Some((key, found)) = notion::front_matter_values(&front_matter, property) {
+            values.extend(found);
+            // タグのキーそのものに文字列で入っていたら、リストに書き直すために消す
+            if strip || key == tag_key {
+                front_matter.remove(&key);
+            }
+        }
+    }
+    let mut body = body.to_string();
+    let lines = notion::property_lines(&body);
+    let mut removed = Vec::new();
+    for line in &lines {
+        if properties
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(&line.name))
+        {
+            values.extend(notion::split_values(&line.value));
+            removed.push(line.range.clone());
+        }
+    }
+    if strip {
+        for range in removed.into_iter().rev() {
+            body.replace_range(range, "");
+        }
+    }
+    Ok((values, front_matter.with_body(&body)))
+}
+
+fn import_notion(global: &GlobalArgs, args: &NotionArgs) -> Result<Exit> {
+    let db_path = global.db_path();
+    let files = collect_markdown_files(&args.path)?;
+    let options = global.settings.front_matter_for_files(&files)?;
+    let mut recorder = Recorder::start("import", db_path, &options);
+    let mut db = TagDatabase::new(global.load_db()?);
+    let normalizer = Normalizer::new(&db.config().normalize)?;
+    let slug = db.config().slug.clone();
+
+    let mut new_tags = Vec::new();
+    let mut changes = Vec::new();
+    for path in &files {
+        let content = read_head(path, &options)?;
+        let (values, stripped) =
+            take_properties(&content, &args.properties, &options.tag_key, args.strip)
+                .with_context(|| {
+                    tr!(
+                        "Invalid front matter in {:?}",
+                        "{:?} の Front Matter が不正です",
+                        path
+                    )
+                })?;
+        if values.is_empty() {
+            continue;
+        }
+        let mut tags: Vec<String> = Vec::new();
+        for value in &values {
+            let input = normalizer.apply(value);
+            if input.is_empty() {
+                continue;
+            }
+            let tag = map_value(&mut db, &slug, &input, &mut new_tags);
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        let new_content = add_tags(&stripped, &tags, &options).map_err(|err| err.in_file(path))?;
+        info!("   {:?}: {}", path, tags.join(", "));
+        changes.push((path, content, new_content, tags));
+    }
+
+    if !new_tags.is_empty() {
+        info!(
+            "{}",
+            tr!("✨ New tags: {}", "✨ 新しいタグ: {}", new_tags.join(", "))
+        );
+    }
+    if args.dry_run {
+        return Ok(Exit::Success);
+    }
+
+    let mut modified = 0;
+    for (path, content, new_content, tags) in changes {
+        db.edit(|config| record_usage(config, &tags));
+        if new_content == content {
+            continue;
+        }
+        let new_content = recorder.pre_write(path, &content, new_content)?;
+        recorder.write_file(path, &content, new_content)?;
+        modified += 1;
+    }
+    db.save(db_path)?;
+    recorder.finish()?;
+    info!(
+        "{}",
+        tr!(
+            "✅ Imported tags into {} files ({} new tags)",
+            "✅ {} ファイルにタグを取り込みました (新しいタグ {} 個)",
+            modified,
+            new_tags.len()
+        )
+    );
+    Ok(if modified > 0 {
+        Exit::Success
+    } else {
+        Exit::Unchanged
+    })
+}
+
+fn import_dendron(global: &GlobalArgs, args: &DendronArgs) -> Result<Exit> {
+    let db_path = global.db_path();
+    let files = collect_markdown_files(&args.path)?;
+    let options = global.settings.front_matter_for_files(&files)?;
+    let mut recorder = Recorder::start("import", db_path, &options);
+    let mut db = TagDatabase::new(global.load_db()?);
+    // 区切りの / は整形ルールに関わらず残す
+    let slug = SlugConfig {
+        nested: true,
+        ..db.config().slug.clone()
+    };
+
+    let mut new_tags = Vec::new();
+    let mut changes = Vec::new();
+    for path in &files {
+        let Some(tag) = dendron_tag(path, &slug) else {
+            continue;
+        };
+        let tag = map_value(&mut db, &slug, &tag, &mut new_tags);
+        info!("   {:?}: {}", path, tag);
+        if args.mirror {
+            changes.push((path, tag));
+        }
+    }
+
+    if !new_tags.is_empty() {
+        info!(
+            "{}",
+            tr!("✨ New tags: {}", "✨ 新しいタグ: {}", new_tags.join(", "))
+        );
+    }
+    if args.dry_run {
+        return Ok(Exit::Success);
+    }
+
+    let mut modified = 0;
+    for (path, tag) in changes {
+        let content = read_head(path, &options)?;
+        let tags = vec![tag];
+        let new_content = add_tags(&content, &tags, &options).map_err(|err| err.in_file(path))?;
+        db.edit(|config| record_usage(config, &tags));
+        if new_content == content {
+            continue;
+        }
+        let new_content = recorder.pre_write(path, &content, new_content)?;
+        recorder.write_file(path, &content, new_content)?;
+        modified += 1;
+    }
+    db.save(db_path)?;
+    recorder.finish()?;
+    info!(
+        "{}",
+        tr!(
+            "✅ Registered {} new hierarchy tags ({} files updated)",
+            "✅ 階層のタグを {} 個登録しました ({} ファイルを書き換えました)",
+            new_tags.len(),
+            modified
+        )
+    );
+    Ok(if modified > 0 || !new_tags.is_empty() {
+        Exit::Success
+    } else {
+        Exit::Unchanged
+    })
+}
+
+pub fn run(global: &GlobalArgs, args: &ImportArgs) -> Result<Exit> {
+    match &args.source {
+        ImportSource::Notion(notion_args) => import_notion(global, notion_args),
+        ImportSource::Dendron(dendron_args) => import_dendron(global, dendron_args),
+    }
+}
@@ -0,0 +1,389 @@
+// --- サブコマンド: lint (規則違反の検査・書き込みなし) ---
+
+use anyhow::Result;
+use clap::Args;
+use log::info;
+use mdtagger::MdtaggerError;
+use mdtagger::db::TagDatabase;
+use mdtagger::markdown::{
+    FrontMatter, FrontMatterOptions, collect_markdown_files, find_inline_field, read_front_matter,
+};
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{GlobalArgs, OutputFormat};
+use crate::exit::Exit;
+use crate::git;
+use crate::rules::{
+    Finding, RULES, RuleSet, Severity, check_each_tag, check_file_tags, description,
+};
+use crate::table::Table;
+use crate::taxonomy::Taxonomy;
+
+#[derive(Args)]
+pub struct LintArgs {
+    /// 検査するディレクトリ (ファイルも可)
+    #[arg(value_name = "DIR", required_unless_present = "list_rules")]
+    path: Option<PathBuf>,
+
+    /// 規則の一覧と、DIR (省略時はカレントディレクトリ) に当てはめる重大度を表示する
+    #[arg(long)]
+    list_rules: bool,
+
+    /// すべてのファイルに付いていなければならないタグ (設定ファイルの lint.required_tags に追加する)
+    #[arg(long = "require", value_name = "TAG")]
+    required_tags: Vec<String>,
+
+    /// 1つのファイルに付けてよいタグの数の上限 (設定ファイルの lint.max_tags より優先)
+    #[arg(long, value_name = "N")]
+    max_tags: Option<usize>,
+
+    /// 使ってよいタグの木と制約を書いたファイル (taxonomy.yaml) で、すべてのファイルを検査する
+    #[arg(long, value_name = "FILE")]
+    schema: Option<PathBuf>,
+
+    /// git で変更したファイル (git status に出るもの) だけを対象にする。--changed=<REF> なら REF から変わったもの
+    #[arg(long, value_name = "REF", num_args = 0..=1, require_equals = true)]
+    changed: Option<Option<String>>,
+}
+
+/// 見つかった規則違反
+#[derive(Serialize, JsonSchema)]
+pub struct Violation {
+    path: PathBuf,
+    /// 1 から数えた行番号
+    line: usize,
+    /// unknown-tag / alias / casing / deprecated-tag / required-tag / required-group / too-many-tags / front-matter / read-error /
+    /// not-in-taxonomy / taxonomy-parent / too-few-tags / exclusive-tags (最後の4つは --schema のときだけ)
+    rule: &'static str,
+    /// error か warning (設定ファイルの [lint.rules.<ID>] の severity)
+    severity: Severity,
+    message: String,
+}
+
+/// --output json で出力する内容の JSON Schema
+pub fn output_schema() -> schemars::Schema {
+    crate::schema::for_output::<Vec<Violation>>()
+}
+
+/// Front Matter の中で tag が書かれている行 (見つからなければタグのキーの行、それもなければ1行目)
+///
+/// taxonomies.tags のような入れ子のキーは、最後の名前 (tags) の行を探す。TOML の tags = [...] も探す
+fn tag_line(content: &str, tag: Option<&str>, options: &FrontMatterOptions) -> usize {
+    let name = options.tag_key.rsplit('.').next().unwrap_or_default();
+    let is_key = |line: &str| {
+        line.strip_prefix(name)
+            .is_some_and(|rest| rest.starts_with(':') || rest.trim_start().starts_with('='))
+    };
+    let mut key_line = None;
+    for (i, line) in content.lines().enumerate().skip(1) {
+        let trimmed = line.trim();
+        if trimmed == "---" || trimmed == "+++" {
+            break;
+        }
+        if is_key(trimmed) {
+            key_line = Some(i + 1);
+        }
+        if let (Some(tag), Some(_)) = (tag, key_line) {
+            let item = trimmed
+                .trim_start_matches('-')
+                .trim()
+                .trim_matches(['"', '\'']);
+            let in_flow = trimmed.contains('[')
+                && trimmed
+                    .split(['[', ']', ','])
+                    .any(|t| t.trim().trim_matches(['"', '\'']) == tag);
+            if item == tag || in_flow {
+                return i + 1;
+            }
+        }
+    }
+    key_line.unwrap_or(1)
+}
+
+/// タグの規則違反を、タグを書いた行の違反にする (line はタグ、ファイル全体の問題なら None を受け取る)
+fn to_violations(
+    path: &Path,
+    tags: &[String],
+    findings: Vec<Finding>,
+    line: impl Fn(Option<&str>) -> usize,
+) -> Vec<Violation> {
+    findings
+        .into_iter()
+        .map(|finding| Violation {
+            path: path.to_path_buf(),
+            line: line(finding.tag.map(|i| tags[i].as_str())),
+            rule: finding.rule,
+            severity: finding.severity,
+            message: finding.message,
+        })
+        .collect()
+}
+
+/// Front Matter のタグのうち、DBの書き方と違うもの (hook pre-commit でも使う)
+pub fn tag_violations(
+    path: &Path,
+    front_matter: &FrontMatter,
+    db: &TagDatabase,
+    options: &FrontMatterOptions,
+    rules: &RuleSet,
+) -> Vec<Violation> {
+    let content = front_matter.to_string();
+    let tags = front_matter.list(&options.tag_key);
+    let findings = check_each_tag(rules, db, &tags);
+    to_violations(path, &tags, findings, |tag| {
+        tag_line(&content, tag, options)
+    })
+}
+
+/// 本文のフィールド (Dataview か Logseq) の行 (1 から) とタグ (options.inline_field がないか、フィールドがなければ None)
+fn inline_tags(content: &str, options: &FrontMatterOptions) -> Option<(usize, Vec<String>)> {
+    let style = options.inline_field?;
+    let (_, body) = FrontMatter::parse(content).ok()?;
+    let field = find_inline_field(body, &options.tag_key, style)?;
+    let front_matter_lines = content[..content.len() - body.len()].matches('\n').count();
+    Some((front_matter_lines + field.line + 1, field.values))
+}
+
+/// インラインフィールドのタグのうち、DBの書き方と違うもの (フィールドがなければ None)
+pub fn inline_violations(
+    path: &Path,
+    content: &str,
+    db: &TagDatabase,
+    options: &FrontMatterOptions,
+    rules: &RuleSet,
+) -> Option<Vec<Violation>> {
+    let (line, tags) = inline_tags(content, options)?;
+    let findings = check_each_tag(rules, db, &tags);
+    Some(to_violations(path, &tags, findings, |_| line))
+}
+
+/// 違反を --output に合わせて標準出力に出す
+pub fn print_violations(global: &GlobalArgs, violations: &[Violation]) -> Result<()> {
+    if global.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(violations)?);
+    } else if global.output.is_table() {
+        let mut table = Table::new(&["path", "line", "rule", "severity", "message"]);
+        for v in violations {
+            table.row(vec![
+                v.path.display().to_string(),
+                v.line.to_string(),
+                v.rule.to_string(),
+                v.severity.name().to_string(),
+                v.message.clone(),
+            ]);
+        }
+        table.print(global.output);
+    } else {
+        // エディタや CI がたどれるように path:line: の形で出す (警告は規則のあとに warning: を付ける)
+        for v in violations {
+            let severity = match v.severity {
+                Severity::Warning => "warning: ",
+                _ => "",
+            };
+            println!(
+                "{}:{}: [{}] {}{}",
+                v.path.display(),
+                v.line,
+                v.rule,
+                severity,
+                v.message
+            );
+        }
+    }
+    Ok(())
+}
+
+/// 失敗させる (重大度が error の) 違反の数
+pub fn error_count(violations: &[Violation]) -> usize {
+    violations
+        .iter()
+        .filter(|v| v.severity == Severity::Error)
+        .count()
+}
+
+fn check_file(
+    path: &Path,
+    db: &TagDatabase,
+    options: &FrontMatterOptions,
+    inherited: Option<Vec<String>>,
+    rules: &RuleSet,
+    taxonomy: Option<&Taxonomy>,
+    violations: &mut Vec<Violation>,
+) {
+    let mut report = |rule: &'static str, message: String| {
+        let severity = rules.severity(rule);
+        if severity != Severity::Off {
+            violations.push(Violation {
+                path: path.to_path_buf(),
+                line: 1,
+                rule,
+                severity,
+                message,
+            })
+        }
+    };
+
+    // 本文は検査しないので、Front Matter だけを読む
+    let front_matter = match read_front_matter(path) {
+        Ok((front_matter, _)) => front_matter,
+        Err(MdtaggerError::ParseError { source, .. }) => {
+            report(
+                "front-matter",
+                tr!(
+                    "Invalid front matter: {}",
+                    "Front Matter が不正です: {}",
+                    source
+                ),
+            );
+            return;
+        }
+        Err(err) => {
+            let message = match err {
+                MdtaggerError::IoError { source, .. } => source.to_string(),
+                err => err.to_string(),
+            };
+            report("read-error", message);
+            return;
+        }
+    };
+    // インラインフィールドは本文にあるので、そのときだけファイル全体を読む
+    let inline = if options.inline_field.is_some() {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| inline_tags(&content, options))
+    } else {
+        None
+    };
+    let content = front_matter.to_string();
+    let inline_field = inline.is_some();
+    let (own_tags, key_line) = match inline {
+        Some((line, tags)) => (Some(tags), line),
+        None => {
+            let tags = front_matter
+                .get(&options.tag_key)
+                .map(|_| front_matter.list(&options.tag_key));
+            (tags, tag_line(&content, None, options))
+        }
+    };
+    if let Some(tags) = &own_tags {
+        let mut findings = check_each_tag(rules, db, tags);
+        if let Some(taxonomy) = taxonomy {
+            // タクソノミーにあるタグは、DBになくても unknown-tag にしない (タクソノミーを語彙として使う)
+            findings.retain(|f| {
+                f.rule != "unknown-tag" || f.tag.is_none_or(|i| !taxonomy.allows(&tags[i]))
+            });
+            findings.extend(taxonomy.check_each_tag(rules, db, tags));
+        }
+        violations.extend(to_violations(path, tags, findings, |tag| {
+            if inline_field {
+                key_line
+            } else {
+                tag_line(&content, tag, options)
+            }
+        }));
+    }
+
+    // ファイルにタグのキーがなければ、Jekyll の defaults で付くタグが付いているものとみなす
+    let tags = match (own_tags, inherited) {
+        (Some(tags), _) => tags,
+        (None, inherited) => inherited.unwrap_or_default(),
+    };
+    let mut findings = check_file_tags(rules, &tags);
+    if let Some(taxonomy) = taxonomy {
+        findings.extend(taxonomy.check_file_tags(rules, db, &tags));
+    }
+    violations.extend(to_violations(path, &tags, findings, |_| key_line));
+}
+
+/// 規則の一覧と、DIR に当てはめる重大度 (--list-rules)
+fn list_rules(global: &GlobalArgs, path: &Path) {
+    let rules = RuleSet::for_path(&global.settings.lint, path);
+    if global.output.is_table() {
+        let mut table = Table::new(&["rule", "severity", "description"]);
+        for (id, _) in RULES {
+            table.row(vec![
+                id.to_string(),
+                rules.severity(id).name().to_string(),
+                description(id),
+            ]);
+        }
+        table.print(global.output);
+        return;
+    }
+    for (id, _) in RULES {
+        println!(
+            "{:<15} {:<8} {}",
+            id,
+            rules.severity(id).name(),
+            description(id)
+        );
+    }
+}
+
+pub fn run(global: &GlobalArgs, args: &LintArgs) -> Result<Exit> {
+    let path = args.path.as_deref().unwrap_or(Path::new("."));
+    if args.list_rules {
+        list_rules(global, path);
+        return Ok(Exit::Success);
+    }
+    let db = TagDatabase::new(global.load_db()?);
+    let taxonomy = args.schema.as_deref().map(Taxonomy::load).transpose()?;
+
+    let mut files = collect_markdown_files(path)?;
+    if let Some(reference) = &args.changed {
+        git::retain_changed(&mut files, reference.as_deref())?;
+    }
+    let mut violations = Vec::new();
+    for path in &files {
+        // Jekyll のコレクションごとのキーと defaults を使う
+        let options = global.settings.front_matter_for(path);
+        let inherited = global
+            .settings
+            .jekyll_site
+            .as_ref()
+            .and_then(|site| site.default_list(path, &options.tag_key));
+        // ディレクトリごとの上書きを当てはめ、コマンドラインの指定を重ねる
+        let mut rules = RuleSet::for_path(&global.settings.lint, path);
+        rules.require(&args.required_tags);
+        if let Some(max) = args.max_tags {
+            rules.limit(max);
+        }
+        check_file(
+            path,
+            &db,
+            &options,
+            inherited,
+            &rules,
+            taxonomy.as_ref(),
+            &mut violations,
+        );
+    }
+
+    print_violations(global, &violations)?;
+    if global.output != OutputFormat::Json {
+        let warnings = violations
+            .iter()
+            .filter(|v| v.severity == Severity::Warning)
+            .count();
+        info!(
+            "{}",
+            tr!(
+                "{} files checked, {} problems ({} warnings)",
+                "{} ファイルを検査し、{} 件の問題が見つかりました (うち警告 {} 件)",
+                files.len(),
+                violations.len(),
+                warnings
+            )
+        );
+    }
+
+    Ok(if error_count(&violations) > 0 {
+        Exit::Lint
+    } else {
+        Exit::Success
+    })
+}
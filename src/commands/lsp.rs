@@ -0,0 +1,800 @@
+// --- サブコマンド: lsp (Front Matter のタグのための Language Server) ---
+//
+// エディタから標準入出力で起動し、Markdown の Front Matter の中だけで次のことを行う。
+// - タグの補完 (DBのタグとエイリアス。よく使う順)
+// - 診断 (lint と同じ規則。エイリアスは非推奨の書き方として取り消し線で示す)
+// - コードアクション (正式名・似たタグへの置き換え、エイリアスや新しいタグとしての登録)
+// - 名前の変更 (DBのタグの名前を変え、ワークスペースのノートを書き換える編集を返す)
+
+use anyhow::{Context, Result, anyhow};
+use clap::Args;
+use log::{debug, info, warn};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    CodeActionRequest, Completion, ExecuteCommand, PrepareRenameRequest, Rename, Request as _,
+};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, Command, CompletionItem, CompletionItemKind, CompletionOptions,
+    CompletionParams, CompletionResponse, CompletionTextEdit, Diagnostic, DiagnosticSeverity,
+    DiagnosticTag, ExecuteCommandOptions, ExecuteCommandParams, InitializeParams, NumberOrString,
+    OneOf, Position, PrepareRenameResponse, PublishDiagnosticsParams, Range, RenameOptions,
+    RenameParams, ServerCapabilities, TextDocumentPositionParams, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, Url, WorkspaceEdit,
+};
+use mdtagger::markdown::{FrontMatter, collect_markdown_files, read_head, replace_tag};
+use mdtagger::tr;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::GlobalArgs;
+use crate::exit::Exit;
+use crate::live_db::LiveDb;
+use crate::rules::{RuleSet, Severity, check_each_tag, check_file_tags};
+use crate::undo::Recorder;
+
+#[derive(Args)]
+pub struct LspArgs {
+    /// 標準入出力で Language Server Protocol を話す (エディタの設定との互換のため。省略しても同じ)
+    #[arg(long)]
+    stdio: bool,
+}
+
+const ADD_ALIAS: &str = "mdtagger.addAlias";
+const ADD_TAG: &str = "mdtagger.addTag";
+
+// --- Front Matter のタグの位置 ---
+
+/// 文書の中のタグ1つ (start..end は行の中のバイト位置)
+struct TagSpan {
+    tag: String,
+    line: usize,
+    start: usize,
+    end: usize,
+}
+
+/// Front Matter のタグのキーの範囲
+struct TagBlock {
+    /// `tags:` の行 (なければ None)
+    key_line: Option<usize>,
+    /// key_line の次の行から、この行の手前までがタグのリスト
+    end_line: usize,
+    /// 閉じの `---` の行
+    close_line: usize,
+    spans: Vec<TagSpan>,
+}
+
+/// 引用符と空白を除いたタグの位置
+fn item_span(line: &str, index: usize, start: usize, end: usize) -> Option<TagSpan> {
+    let raw = &line[start..end];
+    let start = start + (raw.len() - raw.trim_start().len());
+    let end = end - (raw.len() - raw.trim_end().len());
+    let mut raw = &line[start..end];
+    let (mut start, mut end) = (start, end);
+    for quote in ['"', '\''] {
+        if raw.len() >= 2 && raw.starts_with(quote) && raw.ends_with(quote) {
+            start += 1;
+            end -= 1;
+            raw = &line[start..end];
+        }
+    }
+    (!raw.is_empty()).then(|| TagSpan {
+        tag: raw.to_string(),
+        line: index,
+        start,
+        end,
+    })
+}
+
+/// 値の部分 (`rust`、`[rust, cli]`、`- rust`) のタグを取り出す
+fn value_spans(line: &str, index: usize, start: usize, spans: &mut Vec<TagSpan>) {
+    // 行末のコメントは除く
+    let end = line[start..]
+        .find(" #")
+        .map(|i| start + i)
+        .unwrap_or(line.len());
+    let value = &line[start..end];
+    let trimmed = value.trim_start();
+    let offset = start + (value.len() - trimmed.len());
+    if let Some(inner) = trimmed.strip_prefix('[') {
+        let inner_start = offset + 1;
+        let inner_end = inner_start + inner.find(']').unwrap_or(inner.len());
+        let mut item_start = inner_start;
+        for (i, c) in line[inner_start..inner_end].char_indices() {
+            if c == ',' {
+                spans.extend(item_span(line, index, item_start, inner_start + i));
+                item_start = inner_start + i + 1;
+            }
+        }
+        spans.extend(item_span(line, index, item_start, inner_end));
+    } else {
+        spans.extend(item_span(line, index, offset, end));
+    }
+}
+
+/// Front Matter のタグのキーを探す (Front Matter がなければ None)
+fn scan(text: &str, key: &str) -> Option<TagBlock> {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.first().map(|l| l.trim_end()) != Some("---") {
+        return None;
+    }
+    let close_line = (1..lines.len()).find(|&i| lines[i].trim_end() == "---")?;
+    let prefix = format!("{}:", key);
+    let Some(key_line) = (1..close_line).find(|&i| lines[i].starts_with(&prefix)) else {
+        return Some(TagBlock {
+            key_line: None,
+            end_line: close_line,
+            close_line,
+            spans: Vec::new(),
+        });
+    };
+    let mut spans = Vec::new();
+    value_spans(lines[key_line], key_line, prefix.len(), &mut spans);
+    let mut end_line = key_line + 1;
+    while end_line < close_line {
+        let line = lines[end_line];
+        let trimmed = line.trim_start();
+        if let Some(item) = trimmed.strip_prefix('-') {
+            let start = line.len() - item.len();
+            value_spans(line, end_line, start, &mut spans);
+        } else if !trimmed.is_empty() && !line.starts_with([' ', '\t']) {
+            // 次のキー
+            break;
+        }
+        end_line += 1;
+    }
+    Some(TagBlock {
+        key_line: Some(key_line),
+        end_line,
+        close_line,
+        spans,
+    })
+}
+
+// --- 位置の変換 (LSP の列は UTF-16 で数える) ---
+
+fn line_of(text: &str, line: usize) -> &str {
+    text.lines().nth(line).unwrap_or("")
+}
+
+fn utf16_col(line: &str, byte: usize) -> u32 {
+    line[..byte].encode_utf16().count() as u32
+}
+
+fn byte_col(line: &str, col: u32) -> usize {
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= col {
+            return i;
+        }
+        units += c.len_utf16() as u32;
+    }
+    line.len()
+}
+
+fn span_range(text: &str, span: &TagSpan) -> Range {
+    let line = line_of(text, span.line);
+    Range::new(
+        Position::new(span.line as u32, utf16_col(line, span.start)),
+        Position::new(span.line as u32, utf16_col(line, span.end)),
+    )
+}
+
+fn line_range(text: &str, line: usize) -> Range {
+    let len = utf16_col(line_of(text, line), line_of(text, line).len());
+    Range::new(
+        Position::new(line as u32, 0),
+        Position::new(line as u32, len),
+    )
+}
+
+/// 文書全体の範囲 (ファイルをまるごと置き換える編集に使う)
+fn full_range(text: &str) -> Range {
+    let last = text.split('\n').count() - 1;
+    let tail = text.rsplit('\n').next().unwrap_or("");
+    Range::new(
+        Position::new(0, 0),
+        Position::new(last as u32, utf16_col(tail, tail.len())),
+    )
+}
+
+fn span_at<'a>(text: &str, block: &'a TagBlock, position: Position) -> Option<&'a TagSpan> {
+    let line = line_of(text, position.line as usize);
+    let byte = byte_col(line, position.character);
+    block
+        .spans
+        .iter()
+        .find(|s| s.line == position.line as usize && s.start <= byte && byte <= s.end)
+}
+
+// --- サーバー ---
+
+struct Server<'a> {
+    global: &'a GlobalArgs,
+    db: LiveDb<'a>,
+    connection: &'a Connection,
+    /// 開いている文書の内容
+    documents: HashMap<Url, String>,
+    /// 名前の変更で書き換えるノートを探すディレクトリ
+    roots: Vec<PathBuf>,
+}
+
+impl Server<'_> {
+    fn tag_key(&self) -> String {
+        self.global.settings.front_matter().tag_key
+    }
+
+    fn notify<N: lsp_types::notification::Notification>(&self, params: N::Params) -> Result<()> {
+        self.connection
+            .sender
+            .send(Message::Notification(Notification::new(
+                N::METHOD.to_string(),
+                params,
+            )))?;
+        Ok(())
+    }
+
+    fn publish(&mut self, uri: &Url) -> Result<()> {
+        let diagnostics = match self.documents.get(uri).cloned() {
+            Some(text) => self.diagnostics(uri, &text)?,
+            None => Vec::new(),
+        };
+        self.notify::<PublishDiagnostics>(PublishDiagnosticsParams {
+            uri: uri.clone(),
+            diagnostics,
+            version: None,
+        })
+    }
+
+    /// DBが変わったので、開いている文書の診断を出し直す
+    fn publish_all(&mut self) -> Result<()> {
+        let uris: Vec<Url> = self.documents.keys().cloned().collect();
+        for uri in &uris {
+            self.publish(uri)?;
+        }
+        Ok(())
+    }
+
+    fn diagnostics(&mut self, uri: &Url, text: &str) -> Result<Vec<Diagnostic>> {
+        let key = self.tag_key();
+        let Some(block) = scan(text, &key) else {
+            return Ok(Vec::new());
+        };
+        let diagnostic = |range, severity, code: &str, message: String| Diagnostic {
+            range,
+            severity: Some(severity),
+            code: Some(NumberOrString::String(code.to_string())),
+            source: Some("mdtagger".to_string()),
+            message,
+            ..Diagnostic::default()
+        };
+        if let Err(err) = FrontMatter::parse(text) {
+            return Ok(vec![diagnostic(
+                line_range(text, 0),
+                DiagnosticSeverity::ERROR,
+                "front-matter",
+                tr!(
+                    "Invalid front matter: {}",
+                    "Front Matter が不正です: {}",
+                    err
+                ),
+            )]);
+        }
+
+        // lint と同じ規則を、文書のパスに当てはめる設定で調べる。
+        // lint を失敗させる規則は警告に、警告にした規則は情報にする
+        let rules = match uri.to_file_path() {
+            Ok(path) => RuleSet::for_path(&self.global.settings.lint, &path),
+            Err(()) => RuleSet::for_path(&self.global.settings.lint, Path::new("")),
+        };
+        let severity = |severity| match severity {
+            Severity::Warning => DiagnosticSeverity::INFORMATION,
+            _ => DiagnosticSeverity::WARNING,
+        };
+        let db = self.db.get()?;
+        let tags: Vec<String> = block.spans.iter().map(|s| s.tag.clone()).collect();
+        let mut diagnostics = Vec::new();
+        for finding in check_each_tag(&rules, db, &tags) {
+            let Some(span) = finding.tag.map(|i| &block.spans[i]) else {
+                continue;
+            };
+            let mut d = diagnostic(
+                span_range(text, span),
+                severity(finding.severity),
+                finding.rule,
+                finding.message,
+            );
+            if finding.rule == "alias" || finding.rule == "deprecated-tag" {
+                d.tags = Some(vec![DiagnosticTag::DEPRECATED]);
+            }
+            d.data = Some(json!({ "tag": span.tag, "canonical": finding.replacement }));
+            diagnostics.push(d);
+        }
+
+        // 必須タグや上限などファイル全体の規則はキーの行に出す
+        let key_range = line_range(text, block.key_line.unwrap_or(0));
+        for finding in check_file_tags(&rules, &tags) {
+            diagnostics.push(diagnostic(
+                key_range,
+                severity(finding.severity),
+                finding.rule,
+                finding.message,
+            ));
+        }
+        Ok(diagnostics)
+    }
+
+    fn completion(&mut self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let position = params.text_document_position.position;
+        let uri = &params.text_document_position.text_document.uri;
+        let Some(text) = self.documents.get(uri).cloned() else {
+            return Ok(None);
+        };
+        let key = self.tag_key();
+        let Some(block) = scan(&text, &key) else {
+            return Ok(None);
+        };
+        let Some(key_line) = block.key_line else {
+            return Ok(None);
+        };
+        let index = position.line as usize;
+        let line = line_of(&text, index);
+        let byte = byte_col(line, position.character);
+        let in_tags = (index == key_line && byte > key.len())
+            || (index > key_line && index < block.end_line.max(key_line + 1))
+            || (index == block.end_line
+                && index < block.close_line
+                && line.trim_start().starts_with('-'));
+        if !in_tags {
+            return Ok(None);
+        }
+        // 入力中の語の先頭 (区切りは空白・括弧・カンマ・引用符)
+        let start = line[..byte]
+            .rfind(|c: char| c.is_whitespace() || "[,\"'".contains(c))
+            .map(|i| i + line[i..].chars().next().map_or(1, char::len_utf8))
+            .unwrap_or(0);
+        let range = Range::new(
+            Position::new(position.line, utf16_col(line, start)),
+            position,
+        );
+        let present: Vec<&str> = block
+            .spans
+            .iter()
+            .filter(|s| !(s.line == index && s.start == start))
+            .map(|s| s.tag.as_str())
+            .collect();
+
+        let db = self.db.get()?;
+        let mut entries: Vec<_> = db
+            .tags()
+            .iter()
+            .filter(|e| !present.contains(&e.name.as_str()))
+            .collect();
+        entries.sort_by(|a, b| b.uses.cmp(&a.uses).then_with(|| a.name.cmp(&b.name)));
+        let mut items = Vec::new();
+        for (rank, entry) in entries.iter().enumerate() {
+            let edit = CompletionTextEdit::Edit(TextEdit::new(range, entry.name.clone()));
+            items.push(CompletionItem {
+                label: entry.name.clone(),
+                kind: Some(CompletionItemKind::VALUE),
+                detail: Some(tr!("{} uses", "{} 回使用", entry.uses)),
+                sort_text: Some(format!("{:06}", rank)),
+                text_edit: Some(edit.clone()),
+                ..CompletionItem::default()
+            });
+            // エイリアスで打っても正式名を入れる
+            for alias in &entry.aliases {
+                items.push(CompletionItem {
+                    label: alias.clone(),
+                    kind: Some(CompletionItemKind::REFERENCE),
+                    detail: Some(format!("→ {}", entry.name)),
+                    sort_text: Some(format!("{:06}", rank)),
+                    filter_text: Some(alias.clone()),
+                    text_edit: Some(edit.clone()),
+                    ..CompletionItem::default()
+                });
+            }
+        }
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    fn code_actions(&mut self, params: CodeActionParams) -> Result<Vec<CodeActionOrCommand>> {
+        let uri = params.text_document.uri;
+        let max_distance = self.global.settings.max_distance.unwrap_or(2);
+        let db = self.db.get()?;
+        let mut actions = Vec::new();
+        let replace = |title: String, diagnostic: &Diagnostic, to: &str, preferred: bool| {
+            let edit = TextEdit::new(diagnostic.range, to.to_string());
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                    ..WorkspaceEdit::default()
+                }),
+                is_preferred: Some(preferred),
+                ..CodeAction::default()
+            })
+        };
+        let command = |title: String, diagnostic: &Diagnostic, name: &str, args: Vec<Value>| {
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: title.clone(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                command: Some(Command::new(title, name.to_string(), Some(args))),
+                ..CodeAction::default()
+            })
+        };
+
+        for diagnostic in &params.context.diagnostics {
+            if diagnostic.source.as_deref() != Some("mdtagger") {
+                continue;
+            }
+            let data = diagnostic.data.as_ref();
+            let tag = data.and_then(|d| d["tag"].as_str());
+            let canonical = data.and_then(|d| d["canonical"].as_str());
+            match (tag, canonical) {
+                (Some(_), Some(canonical)) => actions.push(replace(
+                    tr!("Replace with '{}'", "'{}' に置き換える", canonical),
+                    diagnostic,
+                    canonical,
+                    true,
+                )),
+                // 代わりのない deprecated-tag は外すだけなので、DBへの登録は勧めない
+                (Some(tag), None)
+                    if diagnostic.code
+                        == Some(NumberOrString::String("unknown-tag".to_string())) =>
+                {
+                    for (entry, _) in db.find_similar(tag, max_distance).into_iter().take(3) {
+                        actions.push(replace(
+                            tr!("Replace with '{}'", "'{}' に置き換える", entry.name),
+                            diagnostic,
+                            &entry.name,
+                            false,
+                        ));
+                        actions.push(command(
+                            tr!(
+                                "Register '{}' as an alias of '{}'",
+                                "'{}' を '{}' のエイリアスとして登録する",
+                                tag,
+                                entry.name
+                            ),
+                            diagnostic,
+                            ADD_ALIAS,
+                            vec![json!(entry.name), json!(tag)],
+                        ));
+                    }
+                    actions.push(command(
+                        tr!(
+                            "Register '{}' as a new tag",
+                            "'{}' を新しいタグとして登録する",
+                            tag
+                        ),
+                        diagnostic,
+                        ADD_TAG,
+                        vec![json!(tag)],
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(actions)
+    }
+
+    /// コードアクションのコマンド (DBを書き換える)
+    fn execute_command(&mut self, params: ExecuteCommandParams) -> Result<Option<Value>> {
+        let arg = |i: usize| {
+            params
+                .arguments
+                .get(i)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("missing argument {} for {}", i, params.command))
+        };
+        let db_path = self.global.db_path();
+        let front_matter = self.global.settings.front_matter();
+        let recorder = match params.command.as_str() {
+            ADD_ALIAS => {
+                let (tag, alias) = (arg(0)?, arg(1)?);
+                let recorder = Recorder::start("alias", db_path, &front_matter);
+                self.db.get()?.add_alias(&tag, &alias)?;
+                info!(
+                    "{}",
+                    tr!(
+                        "Registered '{}' as alias for '{}'",
+                        "'{}' を '{}' のエイリアスとして登録しました",
+                        alias,
+                        tag
+                    )
+                );
+                recorder
+            }
+            ADD_TAG => {
+                let tag = arg(0)?;
+                let recorder = Recorder::start("add-tag", db_path, &front_matter);
+                self.db.get()?.add_tag(&tag)?;
+                recorder
+            }
+            command => return Err(anyhow!("unknown command {:?}", command)),
+        };
+        self.db.save()?;
+        recorder.finish()?;
+        self.publish_all()?;
+        Ok(None)
+    }
+
+    fn prepare_rename(
+        &mut self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        let Some(text) = self.documents.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+        let Some(block) = scan(text, &self.tag_key()) else {
+            return Ok(None);
+        };
+        Ok(span_at(text, &block, params.position)
+            .map(|span| PrepareRenameResponse::Range(span_range(text, span))))
+    }
+
+    /// タグの名前を変える。DBのタグならDBも書き換え (変更先がすでにあれば統合する)、
+    /// ワークスペースのノートを書き換える編集を返す
+    fn rename(&mut self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let position = params.text_document_position;
+        let Some(text) = self.documents.get(&position.text_document.uri) else {
+            return Ok(None);
+        };
+        let Some(block) = scan(text, &self.tag_key()) else {
+            return Ok(None);
+        };
+        let Some(span) = span_at(text, &block, position.position) else {
+            return Ok(None);
+        };
+        let from = span.tag.clone();
+        let to = params.new_name.trim().to_string();
+        if to.is_empty() || to == from {
+            return Ok(None);
+        }
+
+        let db_path = self.global.db_path();
+        let front_matter = self.global.settings.front_matter();
+        let db = self.db.get()?;
+        if db.contains(&from) {
+            let merge = db.contains(&to);
+            let operation = if merge { "merge" } else { "rename" };
+            let recorder = Recorder::start(operation, db_path, &front_matter);
+            if merge {
+                db.merge(&from, &to)?;
+            } else {
+                db.rename(&from, &to)?;
+            }
+            self.db.save()?;
+            recorder.finish()?;
+        }
+
+        let mut roots = self.roots.clone();
+        if roots.is_empty()
+            && let Ok(path) = position.text_document.uri.to_file_path()
+        {
+            roots.extend(path.parent().map(|p| p.to_path_buf()));
+        }
+        let mut changes = HashMap::new();
+        for root in &roots {
+            for path in collect_markdown_files(root)? {
+                let Ok(uri) = Url::from_file_path(fs::canonicalize(&path).unwrap_or(path.clone()))
+                else {
+                    continue;
+                };
+                // 開いていないノートは先頭だけを読み、その範囲を置き換える (本文は読まない)
+                let content = match self.documents.get(&uri) {
+                    Some(text) => text.clone(),
+                    None => match read_head(&path, &front_matter) {
+                        Ok(content) => content,
+                        Err(_) => continue,
+                    },
+                };
+                // Front Matter が壊れているノートは飛ばす
+                let Ok(new_content) = replace_tag(&content, &from, &to, &front_matter) else {
+                    continue;
+                };
+                if new_content != content {
+                    changes.insert(uri, vec![TextEdit::new(full_range(&content), new_content)]);
+                }
+            }
+        }
+        info!(
+            "{}",
+            tr!(
+                "Renamed '{}' to '{}' ({} files)",
+                "'{}' を '{}' に変更しました ({} ファイル)",
+                from,
+                to,
+                changes.len()
+            )
+        );
+        self.publish_all()?;
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..WorkspaceEdit::default()
+        }))
+    }
+
+    fn handle_request(&mut self, request: Request) -> Response {
+        let id = request.id.clone();
+        match self.dispatch(request) {
+            Ok(result) => Response::new_ok(id, result),
+            Err((code, err)) => {
+                warn!("{:#}", err);
+                Response::new_err(id, code as i32, format!("{:#}", err))
+            }
+        }
+    }
+
+    fn dispatch(&mut self, request: Request) -> Result<Value, (ErrorCode, anyhow::Error)> {
+        fn params<R: lsp_types::request::Request>(
+            request: Request,
+        ) -> Result<R::Params, (ErrorCode, anyhow::Error)> {
+            serde_json::from_value(request.params)
+                .map_err(|err| (ErrorCode::InvalidParams, err.into()))
+        }
+        let failed = |err: anyhow::Error| (ErrorCode::RequestFailed, err);
+        let result = match request.method.as_str() {
+            Completion::METHOD => serde_json::to_value(
+                self.completion(params::<Completion>(request)?)
+                    .map_err(failed)?,
+            ),
+            CodeActionRequest::METHOD => serde_json::to_value(
+                self.code_actions(params::<CodeActionRequest>(request)?)
+                    .map_err(failed)?,
+            ),
+            ExecuteCommand::METHOD => serde_json::to_value(
+                self.execute_command(params::<ExecuteCommand>(request)?)
+                    .map_err(failed)?,
+            ),
+            PrepareRenameRequest::METHOD => serde_json::to_value(
+                self.prepare_rename(params::<PrepareRenameRequest>(request)?)
+                    .map_err(failed)?,
+            ),
+            Rename::METHOD => {
+                serde_json::to_value(self.rename(params::<Rename>(request)?).map_err(failed)?)
+            }
+            method => {
+                return Err((
+                    ErrorCode::MethodNotFound,
+                    anyhow!("unknown method {:?}", method),
+                ));
+            }
+        };
+        result.map_err(|err| failed(err.into()))
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                self.publish(&uri)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: lsp_types::DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                // 同期は文書全体なので、最後の変更が新しい内容
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    let uri = params.text_document.uri;
+                    self.documents.insert(uri.clone(), change.text);
+                    self.publish(&uri)?;
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                self.publish(&params.text_document.uri)?;
+            }
+            method => debug!("ignoring notification {}", method),
+        }
+        Ok(())
+    }
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![" ".to_string(), "[".to_string(), ",".to_string()]),
+            ..CompletionOptions::default()
+        }),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: vec![ADD_ALIAS.to_string(), ADD_TAG.to_string()],
+            ..ExecuteCommandOptions::default()
+        }),
+        rename_provider: Some(OneOf::Right(RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: Default::default(),
+        })),
+        ..ServerCapabilities::default()
+    }
+}
+
+/// initialize で渡されたワークスペースのディレクトリ
+fn workspace_roots(params: &InitializeParams) -> Vec<PathBuf> {
+    let mut uris: Vec<&Url> = params
+        .workspace_folders
+        .iter()
+        .flatten()
+        .map(|f| &f.uri)
+        .collect();
+    #[allow(deprecated)]
+    if uris.is_empty()
+        && let Some(root) = &params.root_uri
+    {
+        uris.push(root);
+    }
+    uris.into_iter()
+        .filter_map(|uri| uri.to_file_path().ok())
+        .collect()
+}
+
+pub fn run(global: &GlobalArgs, _args: &LspArgs) -> Result<Exit> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = serde_json::to_value(capabilities())?;
+    let params = connection
+        .initialize(capabilities)
+        .map_err(|err| anyhow!("{}", err))
+        .context(tr!(
+            "The editor did not initialize the language server",
+            "エディタが Language Server を初期化しませんでした"
+        ))?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    info!(
+        "{}",
+        tr!(
+            "Language server started (DB: {:?})",
+            "Language Server を起動しました (DB: {:?})",
+            global.db_path()
+        )
+    );
+
+    let mut server = Server {
+        global,
+        db: LiveDb::new(global),
+        connection: &connection,
+        documents: HashMap::new(),
+        roots: workspace_roots(&params),
+    };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection
+                    .handle_shutdown(&request)
+                    .map_err(|err| anyhow!("{}", err))?
+                {
+                    break;
+                }
+                let response = server.handle_request(request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Err(err) = server.handle_notification(notification) {
+                    warn!("{:#}", err);
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    drop(server);
+    drop(connection);
+    io_threads.join()?;
+    Ok(Exit::Success)
+}
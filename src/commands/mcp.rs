@@ -0,0 +1,210 @@
+// --- サブコマンド: mcp (AI アシスタント向けの Model Context Protocol サーバー) ---
+//
+// ノートを扱う AI アシスタントが自分でタグを作らず、DBの正式な語彙を引いて mdtagger を通してタグを付けられるよう、
+// タグデータベースをリソースとして、タグの操作をツールとして公開する。
+// 通信は serve と同じく、標準入出力の1行に1つの JSON-RPC 2.0 のメッセージ。
+
+use anyhow::Result;
+use clap::Args;
+use log::info;
+use mdtagger::markdown::read_tags;
+use mdtagger::tr;
+use schemars::{JsonSchema, schema_for};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::path::PathBuf;
+
+use super::GlobalArgs;
+use super::serve::{
+    AddParams, INVALID_PARAMS, METHOD_NOT_FOUND, Methods, QueryParams, ResolveParams, RpcError,
+    Server, parse_params, serve_lines,
+};
+use crate::exit::Exit;
+
+#[derive(Args)]
+pub struct McpArgs {}
+
+/// 対応するプロトコルのバージョン (新しい順)
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const VOCABULARY_URI: &str = "mdtagger://vocabulary";
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct ReadTagsParams {
+    /// Markdown ファイルのパス
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct ToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Deserialize)]
+struct ReadResource {
+    uri: String,
+}
+
+/// ツールの名前・説明・引数の型
+fn tools() -> Vec<Value> {
+    let tool = |name: &str, description: &str, schema: schemars::Schema| json!({ "name": name, "description": description, "inputSchema": schema });
+    vec![
+        tool(
+            "list_tags",
+            "List the canonical tags in the tag database, most used first. \
+             Use these names instead of inventing new tags.",
+            schema_for!(QueryParams),
+        ),
+        tool(
+            "resolve_tag",
+            "Map a free-form tag (typo, alias, abbreviation) to canonical tags. \
+             Does not change the database.",
+            schema_for!(ResolveParams),
+        ),
+        tool(
+            "add_tags",
+            "Add and remove tags in the front matter of a Markdown note. \
+             Unknown tags fail unless on_unknown is given; try dry_run first.",
+            schema_for!(AddParams),
+        ),
+        tool(
+            "read_tags",
+            "Read the current tags of a Markdown note.",
+            schema_for!(ReadTagsParams),
+        ),
+    ]
+}
+
+struct Mcp<'a> {
+    server: Server<'a>,
+    global: &'a GlobalArgs,
+}
+
+impl Mcp<'_> {
+    fn call_tool(&mut self, name: &str, arguments: Value) -> Result<Value, RpcError> {
+        // 引数のないツール呼び出しは {} と同じ
+        let arguments = if arguments.is_null() {
+            json!({})
+        } else {
+            arguments
+        };
+        let outcome = match name {
+            "list_tags" => parse_params(arguments).map(|p| self.server.query(p)),
+            "resolve_tag" => parse_params(arguments).map(|p| self.server.resolve_tag(p)),
+            "add_tags" => parse_params(arguments).map(|p| self.server.add_tags(p)),
+            "read_tags" => parse_params(arguments).map(|p: ReadTagsParams| {
+                let options = self.global.settings.front_matter_for(&p.path);
+                Ok(json!({ "path": p.path, "tags": read_tags(&p.path, &options)? }))
+            }),
+            _ => {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    tr!("Unknown tool {:?}", "{:?} というツールはありません", name),
+                ));
+            }
+        };
+        // ツールの失敗は、アシスタントが読めるように結果の中で返す
+        let (text, is_error) = match outcome {
+            Ok(Ok(result)) => (
+                serde_json::to_string_pretty(&result).unwrap_or_default(),
+                false,
+            ),
+            Ok(Err(err)) => (format!("{:#}", err), true),
+            Err(err) => (err.message, true),
+        };
+        Ok(json!({
+            "content": [{ "type": "text", "text": text }],
+            "isError": is_error,
+        }))
+    }
+
+    fn read_resource(&mut self, uri: &str) -> Result<Value, RpcError> {
+        if uri != VOCABULARY_URI {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                tr!(
+                    "Unknown resource {:?}",
+                    "{:?} というリソースはありません",
+                    uri
+                ),
+            ));
+        }
+        let tags = self
+            .server
+            .query(QueryParams::default())
+            .map_err(|err| RpcError::from_error(&err))?;
+        let text = serde_json::to_string_pretty(&tags).unwrap_or_default();
+        Ok(json!({
+            "contents": [{ "uri": uri, "mimeType": "application/json", "text": text }],
+        }))
+    }
+}
+
+impl Methods for Mcp<'_> {
+    fn call(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => {
+                let requested = params["protocolVersion"].as_str().unwrap_or_default();
+                let version = PROTOCOL_VERSIONS
+                    .into_iter()
+                    .find(|v| *v == requested)
+                    .unwrap_or(PROTOCOL_VERSIONS[0]);
+                Ok(json!({
+                    "protocolVersion": version,
+                    "capabilities": { "tools": {}, "resources": {} },
+                    "serverInfo": { "name": "mdtagger", "version": env!("CARGO_PKG_VERSION") },
+                    "instructions": "Tag notes with the canonical vocabulary of the tag database: \
+                        look tags up with list_tags or resolve_tag and apply them with add_tags.",
+                }))
+            }
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools() })),
+            "tools/call" => {
+                let call: ToolCall = parse_params(params)?;
+                self.call_tool(&call.name, call.arguments)
+            }
+            "resources/list" => Ok(json!({
+                "resources": [{
+                    "uri": VOCABULARY_URI,
+                    "name": "vocabulary",
+                    "description": "Canonical tags with their aliases and use counts",
+                    "mimeType": "application/json",
+                }],
+            })),
+            "resources/read" => {
+                let request: ReadResource = parse_params(params)?;
+                self.read_resource(&request.uri)
+            }
+            // 通知 (答えは返さない)
+            method if method.starts_with("notifications/") => Ok(Value::Null),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                tr!(
+                    "Unknown method {:?}",
+                    "{:?} というメソッドはありません",
+                    method
+                ),
+            )),
+        }
+    }
+}
+
+pub fn run(global: &GlobalArgs, _args: &McpArgs) -> Result<Exit> {
+    info!(
+        "{}",
+        tr!(
+            "Serving MCP on stdin/stdout (DB: {:?})",
+            "標準入出力で MCP を受け付けます (DB: {:?})",
+            global.db_path()
+        )
+    );
+    let mut mcp = Mcp {
+        server: Server::new(global),
+        global,
+    };
+    serve_lines(&mut mcp, None)?;
+    Ok(Exit::Success)
+}
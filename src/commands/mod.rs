@@ -0,0 +1,136 @@
+// --- サブコマンドの実装 ---
+
+use crate::settings::Settings;
+use anyhow::Result;
+use clap::{ArgAction, Args, ValueEnum};
+use mdtagger::MdtaggerError;
+use mdtagger::db::{TagConfig, load_config};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub mod add;
+pub mod duplicates;
+pub mod export;
+pub mod find;
+pub mod gen_docs;
+pub mod history;
+pub mod hook;
+pub mod import;
+pub mod lint;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod mcp;
+pub mod orphans;
+pub mod pick;
+pub mod reindex;
+pub mod rename;
+pub mod serve;
+pub mod similar;
+pub mod stats;
+pub mod suggest;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod undo;
+
+/// 結果の出力形式
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// 人が読むためのメッセージ
+    Text,
+    /// エディタのプラグインやスクリプト向けの JSON (メッセージは標準エラー出力へ)
+    Json,
+    /// Alfred / Raycast の Script Filter の JSON (find だけ)
+    ScriptFilter,
+    /// 列を揃えた表 (stats / lint / find / similar だけ)
+    Table,
+    /// CSV (stats / lint / find / similar だけ。表計算ソフトやほかのツールに渡す)
+    Csv,
+    /// Markdown の表 (stats / lint / find / similar だけ。ドキュメントに貼る)
+    Markdown,
+}
+
+impl OutputFormat {
+    /// 表で出す形式 (table / csv / markdown) か
+    pub fn is_table(self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Markdown
+        )
+    }
+}
+
+// すべてのサブコマンドに共通するオプション
+#[derive(Args)]
+pub struct GlobalArgs {
+    /// タグデータベースのパスを指定 (環境変数 SMART_TAGS_DB でも設定可)
+    #[arg(
+        long,
+        global = true,
+        value_name = "DB_PATH", 
+        env = "SMART_TAGS_DB",      // 環境変数を読みに行く
+    )]
+    pub db: Option<PathBuf>,
+
+    /// タグデータベースがなければ空のデータベースとして始める (なければエラーにする)
+    #[arg(long, global = true)]
+    pub create_db: bool,
+
+    /// すべての確認に「はい」と答える (TTYのないスクリプトやMakefile向け)
+    #[arg(short = 'y', long, visible_alias = "assume-yes", global = true)]
+    pub yes: bool,
+
+    /// 警告とエラー以外のメッセージを出さない
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// 解決の過程を詳しく表示する (-vv でさらに詳しく)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// 確認の答えを待つ時間 (例: 30s, 5m)。過ぎたら既定の答えで進める
+    #[arg(long, global = true, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub prompt_timeout: Option<Duration>,
+
+    /// 結果の出力形式 (--format とも書ける)
+    #[arg(long, visible_alias = "format", global = true, value_enum, value_name = "FORMAT", default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
+    /// Hugo のサイトで、タグを書き込むタクソノミー (例: categories)。設定ファイルの tag_key より優先
+    #[arg(long, global = true, value_name = "NAME")]
+    pub taxonomy: Option<String>,
+
+    /// 設定ファイルから読み込んだ値
+    #[arg(skip)]
+    pub settings: Settings,
+
+    /// 端末から実行されているか (false なら確認を出さず、非対話モードで動く)
+    #[arg(skip)]
+    pub interactive: bool,
+}
+
+impl GlobalArgs {
+    /// 引数・環境変数、設定ファイルの順に探し、なければカレントディレクトリの tags_db.json
+    pub fn db_path(&self) -> &Path {
+        self.db
+            .as_deref()
+            .or(self.settings.db.as_deref())
+            .unwrap_or(Path::new("tags_db.json"))
+    }
+
+    /// タグデータベースを読む。--create-db なしでファイルがなければ、パスの打ち間違いとみなしてエラーにする
+    ///
+    /// Obsidian の保管庫の中で、DBに新しいタグ名の整形ルールがなければ、保管庫の書き方から推定したルールを使う
+    pub fn load_db(&self) -> Result<TagConfig> {
+        let path = self.db_path();
+        if !path.exists() && !self.create_db {
+            return Err(MdtaggerError::DbNotFound(path.to_path_buf()).into());
+        }
+        let mut config = load_config(path)?;
+        if let Some(vault) = &self.settings.vault
+            && !config.slug.is_enabled()
+        {
+            config.slug = vault.slug(&self.settings.front_matter());
+        }
+        Ok(config)
+    }
+}
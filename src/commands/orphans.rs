@@ -0,0 +1,162 @@
+// --- サブコマンド: orphans (どのファイルにも付いていないタグ) ---
+//
+// ディレクトリの Markdown ファイルを読み、DBのタグのうち1つのファイルにも付いていないものを表示する。
+// エイリアスで付いていれば使われているとみなし、入れ子のタグ (lang/rust) が付いていれば親 (lang) も使われているとみなす。
+// --prune なら確認してからDBから消す (undo で戻せる)。
+
+use anyhow::{Result, bail};
+use clap::Args;
+use log::info;
+use mdtagger::db::TagDatabase;
+use mdtagger::prompt::confirm;
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use super::{GlobalArgs, OutputFormat};
+use crate::exit::Exit;
+use crate::index::FileIndex;
+use crate::undo::Recorder;
+
+#[derive(Args)]
+pub struct OrphansArgs {
+    /// 読むディレクトリ (ノートをすべて含むディレクトリを指定する)
+    #[arg(short = 'C', long = "dir", value_name = "DIR", default_value = ".")]
+    dir: PathBuf,
+
+    /// 見つけたタグを確認してからDBから消す (--yes なら確認しない)
+    #[arg(long)]
+    prune: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct OrphansReport {
+    /// 読んだファイルの数
+    files: usize,
+    /// どのファイルにも付いていないタグ (名前の順)
+    orphans: Vec<Orphan>,
+    /// DBから消したか
+    pruned: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Orphan {
+    tag: String,
+    aliases: Vec<String>,
+    /// DBに記録された、これまでに付けた回数
+    uses: u64,
+}
+
+/// --output json で出力する内容の JSON Schema
+pub fn output_schema() -> schemars::Schema {
+    crate::schema::for_output::<OrphansReport>()
+}
+
+pub fn run(global: &GlobalArgs, args: &OrphansArgs) -> Result<Exit> {
+    if args.prune && !global.interactive && !global.yes {
+        bail!(tr!(
+            "--prune needs a terminal (add --yes to remove without asking)",
+            "--prune は端末でのみ使えます (確認せずに消すなら --yes を付けてください)"
+        ));
+    }
+    let db_path = global.db_path();
+    let mut db = TagDatabase::new(global.load_db()?);
+    let index = FileIndex::build(&args.dir, &global.settings, &db, global.db_path())?;
+
+    // 付いているタグと、その親のタグ
+    let mut used: HashSet<String> = HashSet::new();
+    for file in &index.files {
+        for tag in &file.tags {
+            let tag = tag.to_lowercase();
+            let mut end = 0;
+            for part in tag.split('/') {
+                end += part.len();
+                used.insert(tag[..end].to_string());
+                end += 1;
+            }
+        }
+    }
+    let mut orphans: Vec<Orphan> = db
+        .tags()
+        .iter()
+        .filter(|entry| !used.contains(&entry.name.to_lowercase()))
+        .map(|entry| Orphan {
+            tag: entry.name.clone(),
+            aliases: entry.aliases.clone(),
+            uses: entry.uses,
+        })
+        .collect();
+    orphans.sort_by(|a, b| a.tag.cmp(&b.tag));
+
+    if global.output != OutputFormat::Json {
+        if orphans.is_empty() {
+            info!(
+                "{}",
+                tr!(
+                    "✅ Every tag in the DB is used in {} files",
+                    "✅ DBのタグはすべて {} ファイルのどれかに付いています",
+                    index.files.len()
+                )
+            );
+        } else {
+            info!(
+                "{}",
+                tr!(
+                    "{} tags are not used in any of {} files:",
+                    "{1} ファイルのどれにも付いていないタグが {0} 個あります:",
+                    orphans.len(),
+                    index.files.len()
+                )
+            );
+            for orphan in &orphans {
+                if orphan.aliases.is_empty() {
+                    println!("   {}", orphan.tag);
+                } else {
+                    println!("   {} ({})", orphan.tag, orphan.aliases.join(", "));
+                }
+            }
+        }
+    }
+
+    let mut pruned = false;
+    if args.prune && !orphans.is_empty() {
+        let prompt = tr!(
+            "Remove {} unused tags (and their aliases) from the DB?",
+            "使われていないタグ {} 個 (とそのエイリアス) をDBから消しますか?",
+            orphans.len()
+        );
+        if confirm(&prompt, false, global.yes)? {
+            let recorder = Recorder::start("prune", db_path, &global.settings.front_matter());
+            let names: HashSet<&str> = orphans.iter().map(|o| o.tag.as_str()).collect();
+            db.edit(|config| config.tags.retain(|e| !names.contains(e.name.as_str())));
+            db.save(db_path)?;
+            recorder.finish()?;
+            pruned = true;
+            info!(
+                "{}",
+                tr!(
+                    "✅ Removed {} tags from the DB (undo restores them)",
+                    "✅ DBから {} 個のタグを消しました (undo で戻せます)",
+                    orphans.len()
+                )
+            );
+        }
+    }
+
+    if global.output == OutputFormat::Json {
+        let report = OrphansReport {
+            files: index.files.len(),
+            orphans,
+            pruned,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    // --prune で何も消さなかったら、書き換えなかったことを終了コードで示す
+    Ok(if args.prune && !pruned {
+        Exit::Unchanged
+    } else {
+        Exit::Success
+    })
+}
@@ -0,0 +1,147 @@
+// --- サブコマンド: pick (DBのタグから選んで付け直す) ---
+
+use anyhow::{Context, Result, bail};
+use clap::Args;
+use log::{info, warn};
+use mdtagger::db::{TagEntry, record_usage, save_config};
+use mdtagger::markdown::{content_tags, read_head, set_tags};
+use mdtagger::prompt::{input_with_completion, multi_select};
+use mdtagger::tr;
+use std::path::PathBuf;
+
+use super::GlobalArgs;
+use crate::exit::Exit;
+use crate::undo::Recorder;
+
+#[derive(Args)]
+pub struct PickArgs {
+    /// 対象のMarkdownファイル
+    #[arg(value_name = "FILE")]
+    path: PathBuf,
+}
+
+impl PickArgs {
+    pub fn for_file(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+/// filter の文字が順に (飛び飛びでもよい) 含まれているか
+fn fuzzy_match(name: &str, filter: &str) -> bool {
+    let mut chars = name.chars().flat_map(char::to_lowercase);
+    filter
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|f| chars.any(|c| c == f))
+}
+
+pub fn run(global: &GlobalArgs, args: &PickArgs) -> Result<Exit> {
+    if !global.interactive {
+        bail!(tr!("pick needs a terminal", "pick は端末でのみ使えます"));
+    }
+    let db_path = global.db_path();
+    let front_matter = global.settings.front_matter();
+    let mut recorder = Recorder::start("pick", db_path, &front_matter);
+    let mut config = global.load_db()?;
+
+    let content = read_head(&args.path, &front_matter)?;
+    let current = content_tags(&content, &front_matter).with_context(|| {
+        tr!(
+            "Invalid front matter in {:?}",
+            "{:?} の Front Matter が不正です",
+            args.path
+        )
+    })?;
+
+    // よく使うタグを先に並べ、DBにないファイルのタグは最後に足す
+    let mut known: Vec<&TagEntry> = config.tags.iter().collect();
+    known.sort_by(|a, b| b.uses.cmp(&a.uses).then_with(|| a.name.cmp(&b.name)));
+    let mut names: Vec<String> = known.iter().map(|e| e.name.clone()).collect();
+    for tag in &current {
+        if !names.contains(tag) {
+            names.push(tag.clone());
+        }
+    }
+    if names.is_empty() {
+        bail!(tr!(
+            "The tag database has no tags to pick from",
+            "タグデータベースに選べるタグがありません"
+        ));
+    }
+
+    let filter = input_with_completion(
+        &tr!(
+            "Filter tags (empty for all)",
+            "タグの絞り込み (空欄ならすべて)"
+        ),
+        &names,
+    )?;
+    let shown: Vec<String> = names
+        .into_iter()
+        .filter(|name| fuzzy_match(name, filter.trim()))
+        .collect();
+    if shown.is_empty() {
+        warn!(
+            "{}",
+            tr!(
+                "No tags match {:?}",
+                "{:?} に一致するタグはありません",
+                filter.trim()
+            )
+        );
+        return Ok(Exit::Unchanged);
+    }
+
+    let checked: Vec<bool> = shown.iter().map(|name| current.contains(name)).collect();
+    let picked: Vec<String> = multi_select(
+        &tr!(
+            "Tags for {:?} (space to toggle, enter to confirm)",
+            "{:?} のタグ (スペースで選択、Enterで確定)",
+            args.path
+        ),
+        &shown,
+        &checked,
+    )?
+    .into_iter()
+    .map(|i| shown[i].clone())
+    .collect();
+
+    // 絞り込みで表示しなかったタグはそのまま残す
+    let mut tags: Vec<String> = current
+        .iter()
+        .filter(|tag| !shown.contains(tag) || picked.contains(tag))
+        .cloned()
+        .collect();
+    let added: Vec<String> = picked
+        .iter()
+        .filter(|tag| !current.contains(tag))
+        .cloned()
+        .collect();
+    tags.extend(added.iter().cloned());
+
+    let new_content = set_tags(&content, &tags, &front_matter)?;
+    let new_content = if new_content != content {
+        recorder.pre_write(&args.path, &content, new_content)?
+    } else {
+        new_content
+    };
+    if new_content == content {
+        info!("{}", tr!("No changes", "変更はありません"));
+        return Ok(Exit::Unchanged);
+    }
+    if record_usage(&mut config, &added) {
+        save_config(db_path, &config)?;
+    }
+    recorder.write_file(&args.path, &content, new_content)?;
+    recorder.finish()?;
+    info!(
+        "{}",
+        tr!(
+            "✅ Updated tags of {:?}: {:?}",
+            "✅ {:?} のタグを更新しました: {:?}",
+            args.path,
+            tags
+        )
+    );
+    Ok(Exit::Success)
+}
@@ -0,0 +1,38 @@
+// --- サブコマンド: reindex (ファイルのタグの索引を作り直す) ---
+//
+// find や stats の使う索引 (.mdtagger/file_index.json) を、すべてのファイルを読み直して作り直す。
+// find は読み終えたディレクトリを索引だけで答えるので、ほかのツールでファイルを書き換えたり足したり消したりしたあとや、
+// 更新日時を保ったままファイルを書き換えるツール (同期ツールなど) を使ったあと、索引を疑うときに実行する。
+
+use anyhow::Result;
+use clap::Args;
+use log::info;
+use mdtagger::db::TagDatabase;
+use mdtagger::tr;
+use std::path::PathBuf;
+
+use super::GlobalArgs;
+use crate::exit::Exit;
+use crate::index::FileIndex;
+
+#[derive(Args)]
+pub struct ReindexArgs {
+    /// 読み直すディレクトリ
+    #[arg(short = 'C', long = "dir", value_name = "DIR", default_value = ".")]
+    dir: PathBuf,
+}
+
+pub fn run(global: &GlobalArgs, args: &ReindexArgs) -> Result<Exit> {
+    let db = TagDatabase::new(global.load_db()?);
+    let index = FileIndex::rebuild(&args.dir, &global.settings, &db, global.db_path())?;
+    info!(
+        "{}",
+        tr!(
+            "✅ Indexed {} files ({} tags)",
+            "✅ {} ファイルを索引に入れました (タグ {} 個)",
+            index.files.len(),
+            index.tag_counts().len()
+        )
+    );
+    Ok(Exit::Success)
+}
@@ -0,0 +1,257 @@
+// --- サブコマンド: rename / merge (タグの名前の変更と統合) ---
+//
+// DBのタグの名前を変える (元の名前はエイリアスに残す) か、別のタグに統合し、
+// ディレクトリのファイルに付いているタグも書き換える。書き換える前に、書き換えるファイルと
+// 置き換えるタグの数を表示して確認する。--dry-run なら表示だけで書き換えない
+// (--output json と合わせて、PR で変更の影響を見てもらうのに使う)。
+
+use anyhow::{Result, bail};
+use clap::Args;
+use log::{info, warn};
+use mdtagger::db::TagDatabase;
+use mdtagger::markdown::{collect_markdown_files, content_tags, read_head, read_tags, replace_tag};
+use mdtagger::prompt::confirm;
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use super::{GlobalArgs, OutputFormat};
+use crate::exit::Exit;
+use crate::index::relative;
+use crate::preview::print_diff;
+use crate::undo::Recorder;
+
+#[derive(Args)]
+pub struct RenameArgs {
+    /// 変える (統合する) タグ
+    #[arg(value_name = "FROM")]
+    from: String,
+
+    /// 新しい名前 (merge なら統合先のタグ)
+    #[arg(value_name = "TO")]
+    to: String,
+
+    /// 書き換えるファイルを探すディレクトリ
+    #[arg(short = 'C', long = "dir", value_name = "DIR", default_value = ".")]
+    dir: PathBuf,
+
+    /// 書き換えるファイルと置き換えるタグの数を表示するだけで、書き換えない
+    #[arg(long)]
+    dry_run: bool,
+
+    /// ファイルごとの変更を差分で表示する
+    #[arg(long)]
+    diff: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct RenameReport {
+    /// rename か merge
+    operation: String,
+    from: String,
+    to: String,
+    /// FROM がDBにあり、DBも書き換えるか
+    db: bool,
+    /// 書き換えるファイル (パスの順)
+    files: Vec<FileImpact>,
+    /// 置き換えるタグの数 (ファイルごとの合計)
+    occurrences: usize,
+    /// 書き換えたか (--dry-run や、確認で断ったときは false)
+    applied: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct FileImpact {
+    /// DIR からの相対パス (区切りは /)
+    path: String,
+    /// ファイルの FROM の数
+    occurrences: usize,
+    /// 書き換える前のタグ
+    before: Vec<String>,
+    /// 書き換えたあとのタグ (TO がすでに付いていれば FROM は消えるだけ)
+    after: Vec<String>,
+}
+
+/// --output json で出力する内容の JSON Schema
+pub fn output_schema() -> schemars::Schema {
+    crate::schema::for_output::<RenameReport>()
+}
+
+/// 書き換えるファイル (パス、元の先頭、新しい先頭)
+struct Rewrite {
+    path: PathBuf,
+    content: String,
+    new_content: String,
+}
+
+pub fn run(global: &GlobalArgs, args: &RenameArgs, merge: bool) -> Result<Exit> {
+    if !args.dry_run && !global.interactive && !global.yes {
+        bail!(tr!(
+            "Rewriting files needs a terminal (add --yes to apply without asking, or --dry-run to only show the changes)",
+            "ファイルの書き換えは端末でのみ確認できます (確認せずに書き換えるなら --yes、変更を表示するだけなら --dry-run を付けてください)"
+        ));
+    }
+    let operation = if merge { "merge" } else { "rename" };
+    let db_path = global.db_path();
+    let mut db = TagDatabase::new(global.load_db()?);
+    let in_db = db.contains(&args.from);
+    // DBを書き換えられるかを先に確かめる (ファイルを調べてから失敗しないように)
+    if in_db {
+        if merge {
+            db.merge(&args.from, &args.to)?;
+        } else {
+            db.rename(&args.from, &args.to)?;
+        }
+    } else if !merge && let Some(owner) = db.lookup(&args.to) {
+        if owner.name == args.to {
+            bail!(tr!(
+                "Tag '{}' already exists (merge '{}' into it instead)",
+                "タグ '{}' はすでにあります ('{}' を統合してください)",
+                args.to,
+                args.from
+            ));
+        }
+        bail!(tr!(
+            "'{}' is already used by tag '{}'",
+            "'{}' はすでにタグ '{}' で使われています",
+            args.to,
+            owner.name
+        ));
+    }
+
+    let root = if args.dir.is_dir() {
+        args.dir.as_path()
+    } else {
+        args.dir.parent().unwrap_or(Path::new(""))
+    };
+    let mut rewrites = Vec::new();
+    let mut files = Vec::new();
+    for path in collect_markdown_files(&args.dir)? {
+        let options = global.settings.front_matter_for(&path);
+        let tags = match read_tags(&path, &options) {
+            Ok(tags) => tags,
+            Err(err) => {
+                warn!(
+                    "⚠️  {}",
+                    tr!("Skipped {:?}: {}", "{:?} をスキップしました: {}", path, err)
+                );
+                continue;
+            }
+        };
+        let occurrences = tags.iter().filter(|tag| **tag == args.from).count();
+        if occurrences == 0 {
+            continue;
+        }
+        let content = read_head(&path, &options)?;
+        let new_content = replace_tag(&content, &args.from, &args.to, &options)
+            .map_err(|err| err.in_file(&path))?;
+        if new_content == content {
+            continue;
+        }
+        files.push(FileImpact {
+            path: relative(root, &path),
+            occurrences,
+            after: content_tags(&new_content, &options).map_err(|err| err.in_file(&path))?,
+            before: tags,
+        });
+        rewrites.push(Rewrite {
+            path,
+            content,
+            new_content,
+        });
+    }
+    let mut report = RenameReport {
+        operation: operation.to_string(),
+        from: args.from.clone(),
+        to: args.to.clone(),
+        db: in_db,
+        occurrences: files.iter().map(|f| f.occurrences).sum(),
+        files,
+        applied: false,
+    };
+
+    if global.output != OutputFormat::Json {
+        info!(
+            "{}",
+            tr!(
+                "'{}' -> '{}': {} occurrences in {} files{}",
+                "'{0}' -> '{1}': {3} ファイルの {2} 箇所{4}",
+                report.from,
+                report.to,
+                report.occurrences,
+                report.files.len(),
+                if in_db {
+                    tr!(" (and the DB)", " (とDB)")
+                } else {
+                    String::new()
+                }
+            )
+        );
+        for (file, rewrite) in report.files.iter().zip(&rewrites) {
+            println!(
+                "   {}  ({}: {})",
+                file.path,
+                file.occurrences,
+                file.after.join(", ")
+            );
+            if args.diff {
+                print_diff(&rewrite.path, &rewrite.content, &rewrite.new_content);
+            }
+        }
+    }
+
+    if rewrites.is_empty() && !in_db {
+        if global.output == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        return Ok(Exit::Unchanged);
+    }
+    if !args.dry_run {
+        let prompt = if merge {
+            tr!(
+                "Merge '{}' into '{}'?",
+                "'{}' を '{}' に統合しますか?",
+                args.from,
+                args.to
+            )
+        } else {
+            tr!(
+                "Rename '{}' to '{}'?",
+                "'{}' の名前を '{}' に変えますか?",
+                args.from,
+                args.to
+            )
+        };
+        if confirm(&prompt, false, global.yes)? {
+            let mut recorder = Recorder::start(operation, db_path, &global.settings.front_matter());
+            for rewrite in rewrites {
+                let new_content =
+                    recorder.pre_write(&rewrite.path, &rewrite.content, rewrite.new_content)?;
+                recorder.write_file(&rewrite.path, &rewrite.content, new_content)?;
+            }
+            if in_db {
+                db.save(db_path)?;
+            }
+            recorder.finish()?;
+            report.applied = true;
+            info!(
+                "{}",
+                tr!(
+                    "✅ Rewrote {} files (undo restores them)",
+                    "✅ {} ファイルを書き換えました (undo で戻せます)",
+                    report.files.len()
+                )
+            );
+        }
+    }
+
+    if global.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(if report.applied || args.dry_run {
+        Exit::Success
+    } else {
+        Exit::Unchanged
+    })
+}
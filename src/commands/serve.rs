@@ -0,0 +1,405 @@
+// --- サブコマンド: serve (エディタのプラグイン向けの JSON-RPC サーバー) ---
+//
+// 標準入力から1行に1つの JSON-RPC 2.0 のメッセージを受け取り、答えを1行ずつ標準出力に返す。
+// キー入力のたびに CLI を起動しなくて済むよう、DBを読み込んだまま待ち続ける。
+// DBのファイルがほかから書き換えられたら、次の要求の前に読み直す。
+// 1行ずつの読み書きとメソッドの実装は、mcp (Model Context Protocol のサーバー) でも使う。
+
+use anyhow::Result;
+use clap::Args;
+use log::info;
+use mdtagger::db::{TagDatabase, TagEntry};
+use mdtagger::markdown::read_head;
+use mdtagger::normalize::Normalizer;
+use mdtagger::operation::{ChangeSet, TagOperation};
+use mdtagger::prompt::NeverPrompt;
+use mdtagger::resolver::{FuzzyResolver, ResolveOptions, TagResolver, UnknownPolicy};
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use super::GlobalArgs;
+use crate::exit::{ErrorCode, Exit};
+use crate::live_db::LiveDb;
+use crate::undo::Recorder;
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// 標準入出力で JSON-RPC を話す (今はこの方法だけ)
+    #[arg(long, required = true)]
+    stdio: bool,
+}
+
+// JSON-RPC 2.0 のエラーコード
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// 要求の処理に失敗した (data.code に CLI と同じエラーコードを入れる)
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// なければ通知 (答えを返さない)
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Serialize)]
+pub struct RpcError {
+    code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        Self {
+            code: SERVER_ERROR,
+            message: format!("{:#}", err),
+            data: Some(json!({ "code": ErrorCode::from_error(err) })),
+        }
+    }
+}
+
+// --- メソッドの引数と結果 ---
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ResolveParams {
+    /// 解決するタグの入力 (打ち間違い・エイリアス・略語でもよい)
+    input: String,
+    /// DBにないタグの扱い (デフォルト: skip)。DBは保存しない
+    #[serde(default)]
+    on_unknown: Option<UnknownPolicy>,
+}
+
+#[derive(Serialize)]
+struct ResolveResult {
+    tags: Vec<String>,
+    /// exact, alias, typo-correction など
+    method: &'static str,
+    created: bool,
+    candidates: Vec<Candidate>,
+}
+
+#[derive(Serialize)]
+struct Candidate {
+    tag: String,
+    distance: usize,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AddParams {
+    /// Markdown ファイルのパス
+    path: PathBuf,
+    /// 付けるタグ (エイリアスと略語は正式名にする)
+    #[serde(default)]
+    tags: Vec<String>,
+    /// 外すタグ
+    #[serde(default)]
+    remove: Vec<String>,
+    /// true ならファイルもDBも書き換えず、変わる内容だけを返す
+    #[serde(default)]
+    dry_run: bool,
+    /// DBにないタグの扱い (デフォルト: fail)
+    #[serde(default)]
+    on_unknown: Option<UnknownPolicy>,
+}
+
+#[derive(Deserialize, Default, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueryParams {
+    /// タグ名かエイリアスの先頭 (大文字・小文字は区別しない)。空ならすべて
+    prefix: String,
+    /// 返すタグの数の上限
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct QueryItem<'a> {
+    name: &'a str,
+    aliases: &'a [String],
+    uses: u64,
+}
+
+/// JSON-RPC のメソッドを処理するもの (serve と mcp)
+pub trait Methods {
+    fn call(&mut self, method: &str, params: Value) -> Result<Value, RpcError>;
+}
+
+/// serve のメソッドの実装 (mcp のツールからも呼ぶ)
+pub struct Server<'a> {
+    global: &'a GlobalArgs,
+    db: LiveDb<'a>,
+}
+
+impl Methods for Server<'_> {
+    fn call(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "resolve_tag" => {
+                let params = parse_params(params)?;
+                let result = self.resolve_tag(params);
+                result.map_err(|err| RpcError::from_error(&err))
+            }
+            "add_tags" => {
+                let params = parse_params(params)?;
+                let result = self.add_tags(params);
+                result.map_err(|err| RpcError::from_error(&err))
+            }
+            "query" => {
+                let params = if params.is_null() {
+                    QueryParams::default()
+                } else {
+                    parse_params(params)?
+                };
+                self.query(params).map_err(|err| RpcError::from_error(&err))
+            }
+            "shutdown" => Ok(Value::Null),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                tr!(
+                    "Unknown method {:?}",
+                    "{:?} というメソッドはありません",
+                    method
+                ),
+            )),
+        }
+    }
+}
+
+impl<'a> Server<'a> {
+    pub fn new(global: &'a GlobalArgs) -> Self {
+        Self {
+            global,
+            db: LiveDb::new(global),
+        }
+    }
+
+    /// 入力を1つ解決する。DBの写しに対して解決するので、DBは変わらない
+    pub fn resolve_tag(&mut self, params: ResolveParams) -> Result<Value> {
+        let max_distance = self.global.settings.max_distance;
+        let mut config = self.db.get()?.config().clone();
+        let input = Normalizer::new(&config.normalize)?.apply(&params.input);
+        let mut resolver = FuzzyResolver::with_prompter(
+            ResolveOptions {
+                policy: Some(params.on_unknown.unwrap_or(UnknownPolicy::Skip)),
+                max_distance,
+                auto_accept: config.defaults.auto_accept_distance,
+                ..ResolveOptions::default()
+            },
+            NeverPrompt,
+        );
+        let resolution = resolver.resolve(&input, &mut config)?;
+        Ok(serde_json::to_value(ResolveResult {
+            tags: resolution.tags,
+            method: resolution.method,
+            created: resolution.created,
+            candidates: resolution
+                .candidates
+                .into_iter()
+                .map(|(tag, distance)| Candidate { tag, distance })
+                .collect(),
+        })?)
+    }
+
+    /// ファイルにタグを付け外しする (CLI の add と同じく undo・履歴・フックに残す)
+    pub fn add_tags(&mut self, params: AddParams) -> Result<Value> {
+        let global = self.global;
+        let front_matter = global.settings.front_matter_for(&params.path);
+        let operation = TagOperation::new(&params.path)
+            .add(params.tags)
+            .remove(params.remove)
+            .dry_run(params.dry_run)
+            .on_unknown(params.on_unknown.unwrap_or(UnknownPolicy::Fail))
+            .front_matter(front_matter.clone());
+        let before = read_head(&params.path, &front_matter)?;
+
+        let mut recorder = Recorder::start("add", global.db_path(), &front_matter);
+        let db = self.db.get()?;
+        let original = db.clone();
+        let changes = match plan_checked(&operation, before, db, &recorder) {
+            Ok(changes) => changes,
+            Err(err) => {
+                // 書き込まないので、解決で足したタグも取り消す
+                *db = original;
+                return Err(err);
+            }
+        };
+        if changes.applied {
+            if let Err(err) =
+                recorder.write_file(&params.path, &changes.before, changes.after.clone())
+            {
+                *db = original;
+                return Err(err);
+            }
+            changes.record_usage(db);
+            self.db.save()?;
+            recorder.finish()?;
+        } else {
+            // 書き込まなかったので、解決で足したタグも取り消す (保存しないままのDBに残さない)
+            *db = original;
+        }
+        let mut result = serde_json::to_value(&changes)?;
+        result["content"] = Value::String(changes.after);
+        Ok(result)
+    }
+
+    /// 補完向けに、先頭が一致するタグを使われた回数の多い順に返す
+    pub fn query(&mut self, params: QueryParams) -> Result<Value> {
+        let prefix = params.prefix.to_lowercase();
+        let db = self.db.get()?;
+        let mut matches: Vec<&TagEntry> = db
+            .tags()
+            .iter()
+            .filter(|e| {
+                std::iter::once(&e.name)
+                    .chain(&e.aliases)
+                    .any(|name| name.to_lowercase().starts_with(&prefix))
+            })
+            .collect();
+        matches.sort_by(|a, b| b.uses.cmp(&a.uses).then_with(|| a.name.cmp(&b.name)));
+        let items: Vec<QueryItem> = matches
+            .into_iter()
+            .take(params.limit.unwrap_or(usize::MAX))
+            .map(|e| QueryItem {
+                name: &e.name,
+                aliases: &e.aliases,
+                uses: e.uses,
+            })
+            .collect();
+        Ok(serde_json::to_value(items)?)
+    }
+}
+
+/// 操作を当てはめ、書き込むなら pre_write フックに確かめさせる
+fn plan_checked(
+    operation: &TagOperation,
+    before: String,
+    db: &mut TagDatabase,
+    recorder: &Recorder,
+) -> Result<ChangeSet> {
+    let mut changes = operation.plan(before, db)?;
+    if changes.applied {
+        changes.after = recorder.pre_write(operation.path(), &changes.before, changes.after)?;
+        changes.applied = changes.after != changes.before;
+    }
+    Ok(changes)
+}
+
+pub fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|err| RpcError::new(INVALID_PARAMS, err.to_string()))
+}
+
+/// 1行を処理して、返す答えを作る (通知なら None)。2つめは exit_method を受け取ったか
+fn handle(
+    methods: &mut impl Methods,
+    line: &str,
+    exit_method: Option<&str>,
+) -> (Option<Response>, bool) {
+    let reply = |id: Value, outcome: Result<Value, RpcError>| {
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Some(Response {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        })
+    };
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(err) => {
+            return (
+                reply(
+                    Value::Null,
+                    Err(RpcError::new(PARSE_ERROR, err.to_string())),
+                ),
+                false,
+            );
+        }
+    };
+    let id = message.get("id").cloned().unwrap_or(Value::Null);
+    let request = match serde_json::from_value::<Request>(message) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(_) => {
+            let error = RpcError::new(INVALID_REQUEST, r#"jsonrpc must be "2.0""#);
+            return (reply(id, Err(error)), false);
+        }
+        Err(err) => {
+            return (
+                reply(id, Err(RpcError::new(INVALID_REQUEST, err.to_string()))),
+                false,
+            );
+        }
+    };
+    let shutdown = Some(request.method.as_str()) == exit_method;
+    let outcome = methods.call(&request.method, request.params);
+    match request.id {
+        Some(id) => (reply(id, outcome), shutdown),
+        None => (None, shutdown),
+    }
+}
+
+pub fn run(global: &GlobalArgs, _args: &ServeArgs) -> Result<Exit> {
+    info!(
+        "{}",
+        tr!(
+            "Serving JSON-RPC on stdin/stdout (DB: {:?})",
+            "標準入出力で JSON-RPC を受け付けます (DB: {:?})",
+            global.db_path()
+        )
+    );
+    serve_lines(&mut Server::new(global), Some("shutdown"))?;
+    Ok(Exit::Success)
+}
+
+/// 標準入力が閉じるか exit_method を受け取るまで、1行ずつ要求を処理して答えを返す
+pub fn serve_lines(methods: &mut impl Methods, exit_method: Option<&str>) -> Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (response, shutdown) = handle(methods, &line, exit_method);
+        if let Some(response) = response {
+            writeln!(stdout, "{}", serde_json::to_string(&response)?)?;
+            stdout.flush()?;
+        }
+        if shutdown {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+// --- サブコマンド: similar (タグの似ているノート) ---
+//
+// FILE のタグと、ディレクトリのほかのノートのタグの集合の Jaccard 係数 (共通のタグ / どちらかのタグ) を求め、
+// 高い順に表示する。どのファイルにも「関連するノート」の一覧をすぐに作れる。
+// --weighted なら、タグに付いているファイルの少なさ (IDF) で重みを付ける。
+// どこにでも付いているタグ (memo など) が同じだけのノートより、珍しいタグを共有するノートを上にする。
+
+use anyhow::{Result, bail};
+use clap::Args;
+use log::info;
+use mdtagger::db::TagDatabase;
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::{GlobalArgs, OutputFormat};
+use crate::exit::Exit;
+use crate::index::FileIndex;
+use crate::table::Table;
+
+#[derive(Args)]
+pub struct SimilarArgs {
+    /// 似ているノートを探すファイル
+    #[arg(value_name = "FILE")]
+    file: PathBuf,
+
+    /// 探すディレクトリ
+    #[arg(short = 'C', long = "dir", value_name = "DIR", default_value = ".")]
+    dir: PathBuf,
+
+    /// タグに付いているファイルの少なさで重みを付ける (珍しいタグを共有するノートを上にする)
+    #[arg(long)]
+    weighted: bool,
+
+    /// 何件表示するか
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    top: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SimilarReport {
+    /// FILE のタグ (DBの正式名)
+    tags: Vec<String>,
+    /// タグの似ているノート (似ている順。共通のタグのないノートは含めない)
+    notes: Vec<SimilarNote>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SimilarNote {
+    /// DIR からの相対パス (区切りは /)
+    path: String,
+    /// タグの集合の Jaccard 係数 (--weighted なら重み付き。0.0 から 1.0)
+    score: f64,
+    /// FILE と共通のタグ
+    shared: Vec<String>,
+}
+
+/// --output json で出力する内容の JSON Schema
+pub fn output_schema() -> schemars::Schema {
+    crate::schema::for_output::<SimilarReport>()
+}
+
+pub fn run(global: &GlobalArgs, args: &SimilarArgs) -> Result<Exit> {
+    if !args.file.is_file() {
+        bail!(tr!(
+            "{:?} is not a file",
+            "{:?} はファイルではありません",
+            args.file
+        ));
+    }
+    let db = TagDatabase::new(global.load_db()?);
+    let target = FileIndex::build(&args.file, &global.settings, &db, global.db_path())?;
+    let tags = target
+        .files
+        .into_iter()
+        .next()
+        .map(|file| file.tags)
+        .unwrap_or_default();
+    let index = FileIndex::build(&args.dir, &global.settings, &db, global.db_path())?;
+
+    // タグの重み (--weighted なら ln(1 + ファイルの数 / タグの付いたファイルの数)、でなければ 1)
+    let counts = index.tag_counts();
+    let total = index.files.len() as f64;
+    let weight = |tag: &str| -> f64 {
+        if args.weighted {
+            let files = counts.get(tag).copied().unwrap_or(0).max(1) as f64;
+            (1.0 + total / files).ln()
+        } else {
+            1.0
+        }
+    };
+    let own: f64 = tags.iter().map(|tag| weight(tag)).sum();
+
+    let canonical = |path: &PathBuf| fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+    let file = canonical(&args.file);
+    let mut scores: BTreeMap<String, (f64, Vec<String>)> = BTreeMap::new();
+    for other in &index.files {
+        if canonical(&other.path) == file {
+            continue;
+        }
+        let shared: Vec<String> = tags
+            .iter()
+            .filter(|tag| other.tags.contains(tag))
+            .cloned()
+            .collect();
+        if shared.is_empty() {
+            continue;
+        }
+        let common: f64 = shared.iter().map(|tag| weight(tag)).sum();
+        let theirs: f64 = other.tags.iter().map(|tag| weight(tag)).sum();
+        let score = common / (own + theirs - common);
+        scores.insert(index.relative(&other.path), (score, shared));
+    }
+    let mut notes: Vec<SimilarNote> = scores
+        .into_iter()
+        .map(|(path, (score, shared))| SimilarNote {
+            path,
+            score: (score * 100.0).round() / 100.0,
+            shared,
+        })
+        .collect();
+    // 同じ点数ならパスの順 (BTreeMap の順のまま)
+    notes.sort_by(|a, b| b.score.total_cmp(&a.score));
+    notes.truncate(args.top);
+    let report = SimilarReport { tags, notes };
+
+    if global.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if global.output.is_table() {
+        let mut table = Table::new(&["path", "score", "shared"]);
+        for note in &report.notes {
+            table.row(vec![
+                note.path.clone(),
+                note.score.to_string(),
+                note.shared.join(", "),
+            ]);
+        }
+        table.print(global.output);
+    } else if report.tags.is_empty() {
+        info!(
+            "{}",
+            tr!(
+                "{:?} has no tags",
+                "{:?} にはタグが付いていません",
+                args.file
+            )
+        );
+    } else if report.notes.is_empty() {
+        info!(
+            "{}",
+            tr!(
+                "No notes in {} files share a tag with {:?}",
+                "{} ファイルに、{:?} とタグの共通するノートはありません",
+                index.files.len(),
+                args.file
+            )
+        );
+    } else {
+        for note in &report.notes {
+            println!(
+                "{:.2}  {}  ({})",
+                note.score,
+                note.path,
+                note.shared.join(", ")
+            );
+        }
+    }
+    Ok(Exit::Success)
+}
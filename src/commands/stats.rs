@@ -0,0 +1,802 @@
+// --- サブコマンド: stats (タグデータベースと、タグの決まり方の集計) ---
+
+use anyhow::Result;
+use clap::Args;
+use log::debug;
+use mdtagger::db::TagDatabase;
+use mdtagger::markdown::read_front_matter;
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_yaml::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{GlobalArgs, OutputFormat};
+use crate::exit::Exit;
+use crate::git;
+use crate::index::FileIndex;
+use crate::table::Table;
+use crate::usage::ResolverStats;
+
+#[derive(Args)]
+pub struct StatsArgs {
+    /// タグの決まり方 (確認した回数、候補を採用した割合、編集距離) の集計を表示する。
+    /// 設定ファイルで usage_stats = true にしておくと add のたびに集計する
+    #[arg(long)]
+    resolver: bool,
+
+    /// DIR のファイルで一緒に付いていることの多いタグの組を表示する (統合できそうなタグを探すのに使う)
+    #[arg(long, conflicts_with = "resolver")]
+    cooccurrence: bool,
+
+    /// ノートの日付ごとに、月ごとの新しいタグと最近増えているタグを表示する (長く続けているノートの整理に使う)。
+    /// 日付は Front Matter の --date-key、ファイル名の先頭の YYYY-MM-DD、git で最初にコミットした日の順に探す
+    #[arg(long, conflicts_with_all = ["resolver", "cooccurrence"])]
+    timeline: bool,
+
+    /// DIR の一番上のフォルダーごとに、よく付いているタグを表示する (フォルダー分けとタグが重複していないかを見るのに使う)
+    #[arg(long, conflicts_with_all = ["resolver", "cooccurrence", "timeline"])]
+    by_dir: bool,
+
+    /// --timeline で日付を読む Front Matter のキー
+    #[arg(long, value_name = "KEY", default_value = "date")]
+    date_key: String,
+
+    /// --timeline で最近とみなす月数 (データの最後の月から数える)
+    #[arg(long, value_name = "MONTHS", default_value_t = 3)]
+    recent: u32,
+
+    /// よく使うタグのファイルの数、--cooccurrence、--timeline、--by-dir で読むディレクトリ
+    #[arg(short = 'C', long = "dir", value_name = "DIR", default_value = ".")]
+    dir: PathBuf,
+
+    /// よく使うタグ (--cooccurrence ならタグの組、--timeline なら増えているタグ、--by-dir ならフォルダーごとのタグ) を何件表示するか
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    top: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DbStats {
+    tags: usize,
+    aliases: usize,
+    top: Vec<TagUses>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct TagUses {
+    tag: String,
+    /// DIR の、タグの付いたファイルの数
+    files: usize,
+    /// add で付けた回数 (タグデータベースに記録した分)
+    uses: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Cooccurrence {
+    /// 読んだファイルの数
+    files: usize,
+    /// 一緒に付いていたファイルの多い順
+    pairs: Vec<TagPair>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct TagPair {
+    /// 2つのタグ (名前の順)
+    tags: [String; 2],
+    /// 両方が付いていたファイルの数
+    files: usize,
+    /// 少ない方のタグの付いたファイルのうち、もう一方も付いていた割合 (%)。100 なら片方はいつももう一方と一緒
+    overlap: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Timeline {
+    /// 読んだファイルの数
+    files: usize,
+    /// 日付の分からなかったファイルの数 (months と trending には入らない)
+    undated: usize,
+    /// 月ごとの集計 (古い順。ファイルのない月は含めない)
+    months: Vec<MonthStats>,
+    /// 最近の月のファイルで、それより前より付いている割合の増えたタグ (増えた順)
+    trending: Vec<TrendingTag>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct MonthStats {
+    /// YYYY-MM
+    month: String,
+    /// その月の日付のファイルの数
+    files: usize,
+    /// その月に初めて付いたタグ (名前の順)
+    new_tags: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct TrendingTag {
+    tag: String,
+    /// 最近の月の、タグの付いたファイルの数
+    recent: usize,
+    /// それより前の、タグの付いたファイルの数
+    earlier: usize,
+    /// ファイルに付いている割合の増えた分 (% ポイント)
+    change: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ByDir {
+    /// 読んだファイルの数
+    files: usize,
+    /// 一番上のフォルダーごとの集計 (名前の順。DIR の直下のファイルは "." にまとめる)
+    dirs: Vec<DirStats>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DirStats {
+    dir: String,
+    files: usize,
+    /// よく付いているタグ (多い順)
+    top: Vec<DirTag>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DirTag {
+    tag: String,
+    /// このフォルダーの、タグの付いたファイルの数
+    files: usize,
+    /// このフォルダーのファイルのうち、タグの付いている割合 (%)
+    share: usize,
+    /// タグの付いたファイルのうち、このフォルダーにある割合 (%)
+    here: usize,
+    /// このフォルダーの2つ以上のファイルにだけ付いていて、フォルダーの半分以上に付いている (フォルダー分けと重複しているかもしれない)
+    redundant: bool,
+}
+
+/// --output json で出力する内容の JSON Schema
+pub fn output_schema() -> schemars::Schema {
+    crate::schema::for_output::<DbStats>()
+}
+
+/// --cooccurrence --output json で出力する内容の JSON Schema
+pub fn cooccurrence_output_schema() -> schemars::Schema {
+    crate::schema::for_output::<Cooccurrence>()
+}
+
+/// --by-dir --output json で出力する内容の JSON Schema
+pub fn by_dir_output_schema() -> schemars::Schema {
+    crate::schema::for_output::<ByDir>()
+}
+
+/// --timeline --output json で出力する内容の JSON Schema
+pub fn timeline_output_schema() -> schemars::Schema {
+    crate::schema::for_output::<Timeline>()
+}
+
+/// --resolver --output json で出力する内容の JSON Schema
+pub fn resolver_output_schema() -> schemars::Schema {
+    crate::schema::for_output::<ResolverStats>()
+}
+
+/// ヒストグラムの棒の最大の長さ
+const MAX_BAR_WIDTH: usize = 40;
+
+/// 全体に対する割合 (%)
+fn percent(count: usize, total: usize) -> usize {
+    (count * 100).checked_div(total).unwrap_or(0)
+}
+
+pub fn run(global: &GlobalArgs, args: &StatsArgs) -> Result<Exit> {
+    if args.resolver {
+        return resolver_stats(global);
+    }
+    if args.cooccurrence {
+        return cooccurrence_stats(global, args);
+    }
+    if args.timeline {
+        return timeline_stats(global, args);
+    }
+    if args.by_dir {
+        return by_dir_stats(global, args);
+    }
+
+    let db = TagDatabase::new(global.load_db()?);
+    let index = FileIndex::build(&args.dir, &global.settings, &db, global.db_path())?;
+    // 手で付けたタグも数えるように、棒はファイルの数にする (DBにないタグも含める)
+    let counts = index.tag_counts();
+    let mut top: Vec<TagUses> = db
+        .tags()
+        .iter()
+        .map(|e| TagUses {
+            files: counts.get(e.name.as_str()).copied().unwrap_or(0),
+            tag: e.name.clone(),
+            uses: e.uses,
+        })
+        .collect();
+    top.extend(
+        counts
+            .iter()
+            .filter(|(tag, _)| !db.contains(tag))
+            .map(|(tag, files)| TagUses {
+                tag: tag.to_string(),
+                files: *files,
+                uses: 0,
+            }),
+    );
+    top.sort_by(|a, b| {
+        b.files
+            .cmp(&a.files)
+            .then_with(|| b.uses.cmp(&a.uses))
+            .then_with(|| a.tag.cmp(&b.tag))
+    });
+    top.truncate(args.top);
+    let stats = DbStats {
+        tags: db.tags().len(),
+        aliases: db.tags().iter().map(|e| e.aliases.len()).sum(),
+        top,
+    };
+
+    if global.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(Exit::Success);
+    }
+    if global.output.is_table() {
+        let mut table = Table::new(&["tag", "files", "uses"]);
+        for t in &stats.top {
+            table.row(vec![t.tag.clone(), t.files.to_string(), t.uses.to_string()]);
+        }
+        table.print(global.output);
+        return Ok(Exit::Success);
+    }
+    println!("{}", tr!("Tags: {}", "タグ: {}", stats.tags));
+    println!("{}", tr!("Aliases: {}", "エイリアス: {}", stats.aliases));
+    if !stats.top.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "Most used (files, times added):",
+                "よく使うタグ (ファイルの数、付けた回数):"
+            )
+        );
+        print_histogram(global, &stats.top);
+    }
+    Ok(Exit::Success)
+}
+
+/// タグの名前・ファイルの数・付けた回数・棒のヒストグラム (棒の長さは一番多いタグを端末の幅に合わせる)
+fn print_histogram(global: &GlobalArgs, top: &[TagUses]) {
+    let name_width = top
+        .iter()
+        .map(|t| console::measure_text_width(&t.tag))
+        .max()
+        .unwrap_or(0);
+    let count_width = top
+        .iter()
+        .map(|t| t.files.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let uses_width = top
+        .iter()
+        .map(|t| t.uses.to_string().len())
+        .max()
+        .unwrap_or(1);
+    // 端末でなければ幅が分からないので 80 桁とする
+    let columns = console::Term::stdout()
+        .size_checked()
+        .map_or(80, |(_, columns)| usize::from(columns));
+    let bar_width = columns
+        .saturating_sub(3 + name_width + 2 + count_width + 2 + uses_width + 2)
+        .clamp(10, MAX_BAR_WIDTH);
+    // パイプや絵文字を使わない設定では ASCII の # で描く
+    let bar = if global.interactive && global.settings.appearance.emoji() {
+        '█'
+    } else {
+        '#'
+    };
+    let max = top.iter().map(|t| t.files).max().unwrap_or(0);
+    for t in top {
+        let len = if max == 0 {
+            0
+        } else {
+            // 1つのファイルにでも付いていれば最低1つは描く
+            ((t.files as f64 / max as f64 * bar_width as f64).round() as usize)
+                .max(usize::from(t.files > 0))
+        };
+        let padding = name_width - console::measure_text_width(&t.tag);
+        println!(
+            "   {}{}  {:>count_width$}  {:>uses_width$}  {}",
+            t.tag,
+            " ".repeat(padding),
+            t.files,
+            t.uses,
+            bar.to_string().repeat(len)
+        );
+    }
+}
+
+fn cooccurrence_stats(global: &GlobalArgs, args: &StatsArgs) -> Result<Exit> {
+    let db = TagDatabase::new(global.load_db()?);
+    let index = FileIndex::build(&args.dir, &global.settings, &db, global.db_path())?;
+    let counts = index.tag_counts();
+    let mut together: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for file in &index.files {
+        let mut tags: Vec<&str> = file.tags.iter().map(String::as_str).collect();
+        tags.sort();
+        for (i, a) in tags.iter().enumerate() {
+            for b in &tags[i + 1..] {
+                *together.entry((a, b)).or_insert(0) += 1;
+            }
+        }
+    }
+    let mut pairs: Vec<TagPair> = together
+        .into_iter()
+        .map(|((a, b), files)| TagPair {
+            overlap: percent(files, counts[a].min(counts[b])),
+            tags: [a.to_string(), b.to_string()],
+            files,
+        })
+        .collect();
+    pairs.sort_by(|a, b| {
+        b.files
+            .cmp(&a.files)
+            .then_with(|| b.overlap.cmp(&a.overlap))
+            .then_with(|| a.tags.cmp(&b.tags))
+    });
+    pairs.truncate(args.top);
+    let stats = Cooccurrence {
+        files: index.files.len(),
+        pairs,
+    };
+
+    if global.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(Exit::Success);
+    }
+    if global.output.is_table() {
+        let mut table = Table::new(&["tag", "other", "files", "overlap"]);
+        for pair in &stats.pairs {
+            table.row(vec![
+                pair.tags[0].clone(),
+                pair.tags[1].clone(),
+                pair.files.to_string(),
+                pair.overlap.to_string(),
+            ]);
+        }
+        table.print(global.output);
+        return Ok(Exit::Success);
+    }
+    if stats.pairs.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "No tags are used together in {} files",
+                "{} ファイルに、一緒に付いているタグはありません",
+                stats.files
+            )
+        );
+        return Ok(Exit::Success);
+    }
+    println!(
+        "{}",
+        tr!(
+            "Tags used together ({} files; overlap = share of the rarer tag's files that also have the other):",
+            "一緒に付いているタグ ({} ファイル。重なり = 少ない方のタグのファイルのうち、もう一方も付いている割合):",
+            stats.files
+        )
+    );
+    for pair in &stats.pairs {
+        println!(
+            "   {:>5}  {:>3}%  {} + {}",
+            pair.files, pair.overlap, pair.tags[0], pair.tags[1]
+        );
+    }
+    Ok(Exit::Success)
+}
+
+fn by_dir_stats(global: &GlobalArgs, args: &StatsArgs) -> Result<Exit> {
+    let db = TagDatabase::new(global.load_db()?);
+    let index = FileIndex::build(&args.dir, &global.settings, &db, global.db_path())?;
+    let counts = index.tag_counts();
+    let mut by_dir: BTreeMap<String, Vec<&[String]>> = BTreeMap::new();
+    for file in &index.files {
+        let relative = index.relative(&file.path);
+        let dir = match relative.split_once('/') {
+            Some((dir, _)) => dir.to_string(),
+            None => ".".to_string(),
+        };
+        by_dir.entry(dir).or_default().push(&file.tags);
+    }
+    let dirs: Vec<DirStats> = by_dir
+        .into_iter()
+        .map(|(dir, files)| {
+            let mut here: BTreeMap<&str, usize> = BTreeMap::new();
+            for tag in files.iter().flat_map(|tags| tags.iter()) {
+                *here.entry(tag).or_insert(0) += 1;
+            }
+            let mut top: Vec<DirTag> = here
+                .into_iter()
+                .map(|(tag, count)| {
+                    let share = percent(count, files.len());
+                    DirTag {
+                        tag: tag.to_string(),
+                        files: count,
+                        share,
+                        here: percent(count, counts[tag]),
+                        redundant: dir != "." && count >= 2 && count == counts[tag] && share >= 50,
+                    }
+                })
+                .collect();
+            top.sort_by(|a, b| b.files.cmp(&a.files).then_with(|| a.tag.cmp(&b.tag)));
+            top.truncate(args.top);
+            DirStats {
+                dir,
+                files: files.len(),
+                top,
+            }
+        })
+        .collect();
+    let stats = ByDir {
+        files: index.files.len(),
+        dirs,
+    };
+
+    if global.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(Exit::Success);
+    }
+    if global.output.is_table() {
+        // フォルダーとタグの組ごとに1行
+        let mut table = Table::new(&["dir", "tag", "files", "share", "here", "redundant"]);
+        for dir in &stats.dirs {
+            for tag in &dir.top {
+                table.row(vec![
+                    dir.dir.clone(),
+                    tag.tag.clone(),
+                    tag.files.to_string(),
+                    tag.share.to_string(),
+                    tag.here.to_string(),
+                    tag.redundant.to_string(),
+                ]);
+            }
+        }
+        table.print(global.output);
+        return Ok(Exit::Success);
+    }
+    println!(
+        "{}",
+        tr!(
+            "Tags by folder ({} files; share = of the folder's files, here = of the tag's files):",
+            "フォルダーごとのタグ ({} ファイル。割合 = フォルダーのファイルのうち、ここ = タグの付いたファイルのうち):",
+            stats.files
+        )
+    );
+    for dir in &stats.dirs {
+        println!(
+            "{}",
+            tr!("{} ({} files)", "{} ({} ファイル)", dir.dir, dir.files)
+        );
+        for tag in &dir.top {
+            let mark = if tag.redundant {
+                tr!("  (same as the folder?)", "  (フォルダーと重複?)")
+            } else {
+                String::new()
+            };
+            println!(
+                "   {:>5}  {:>3}%  {:>3}%  {}{}",
+                tag.files, tag.share, tag.here, tag.tag, mark
+            );
+        }
+    }
+    Ok(Exit::Success)
+}
+
+/// YYYY-MM-DD などで始まる文字列の年月 (YYYY-MM)
+fn month_of(date: &str) -> Option<&str> {
+    let month = date.get(..7)?;
+    let bytes = month.as_bytes();
+    (bytes[..4].iter().all(u8::is_ascii_digit)
+        && bytes[4] == b'-'
+        && bytes[5..].iter().all(u8::is_ascii_digit))
+    .then_some(month)
+}
+
+/// 年月を月の通し番号にする (YYYY-MM の形であること)
+fn month_number(month: &str) -> u32 {
+    let year: u32 = month[..4].parse().unwrap_or(0);
+    let month: u32 = month[5..].parse().unwrap_or(1);
+    year * 12 + month.saturating_sub(1)
+}
+
+/// ファイルの年月 (Front Matter の日付、ファイル名の先頭の日付、git で最初にコミットした日の順)
+fn file_month(
+    path: &Path,
+    date_key: &str,
+    git_dates: &mut Option<HashMap<PathBuf, String>>,
+) -> Option<String> {
+    if let Ok((front_matter, _)) = read_front_matter(path) {
+        let date = match front_matter.get(date_key) {
+            Some(Value::String(date)) => Some(date),
+            _ => None,
+        };
+        if let Some(month) = date.and_then(|date| month_of(date)) {
+            return Some(month.to_string());
+        }
+    }
+    let name = path.file_name()?.to_string_lossy();
+    if let Some(month) = month_of(&name) {
+        return Some(month.to_string());
+    }
+    // git は日付のないファイルがあったときだけ1回呼ぶ (リポジトリでなければ使わない)
+    let dates = git_dates.get_or_insert_with(|| {
+        git::added_dates().unwrap_or_else(|err| {
+            debug!("git: {}", err);
+            HashMap::new()
+        })
+    });
+    let date = dates.get(&fs::canonicalize(path).ok()?)?;
+    month_of(date).map(str::to_string)
+}
+
+fn timeline_stats(global: &GlobalArgs, args: &StatsArgs) -> Result<Exit> {
+    let db = TagDatabase::new(global.load_db()?);
+    let index = FileIndex::build(&args.dir, &global.settings, &db, global.db_path())?;
+    let mut git_dates = None;
+    let mut by_month: BTreeMap<String, Vec<&[String]>> = BTreeMap::new();
+    let mut undated = 0;
+    for file in &index.files {
+        match file_month(&file.path, &args.date_key, &mut git_dates) {
+            Some(month) => by_month.entry(month).or_default().push(&file.tags),
+            None => undated += 1,
+        }
+    }
+
+    let mut seen: HashSet<&str> = HashSet::new();
+    let months: Vec<MonthStats> = by_month
+        .iter()
+        .map(|(month, files)| {
+            let mut new_tags: Vec<String> = Vec::new();
+            for tag in files.iter().flat_map(|tags| tags.iter()) {
+                if seen.insert(tag) {
+                    new_tags.push(tag.clone());
+                }
+            }
+            new_tags.sort();
+            MonthStats {
+                month: month.clone(),
+                files: files.len(),
+                new_tags,
+            }
+        })
+        .collect();
+
+    // 最後の月から --recent か月を最近とし、それより前と付いている割合を比べる
+    let mut trending = Vec::new();
+    if let Some(last) = by_month.keys().next_back() {
+        let since = (month_number(last) + 1).saturating_sub(args.recent.max(1));
+        let mut recent_files = 0;
+        let mut earlier_files = 0;
+        let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for (month, files) in &by_month {
+            let recent = month_number(month) >= since;
+            if recent {
+                recent_files += files.len();
+            } else {
+                earlier_files += files.len();
+            }
+            for tag in files.iter().flat_map(|tags| tags.iter()) {
+                let count = counts.entry(tag).or_default();
+                if recent {
+                    count.0 += 1;
+                } else {
+                    count.1 += 1;
+                }
+            }
+        }
+        for (tag, (recent, earlier)) in counts {
+            let now = percent(recent, recent_files);
+            let before = percent(earlier, earlier_files);
+            // 1つのファイルだけでは傾向とみなさない
+            if recent >= 2 && now > before {
+                trending.push(TrendingTag {
+                    tag: tag.to_string(),
+                    recent,
+                    earlier,
+                    change: now - before,
+                });
+            }
+        }
+        trending.sort_by(|a, b| {
+            b.change
+                .cmp(&a.change)
+                .then_with(|| b.recent.cmp(&a.recent))
+                .then_with(|| a.tag.cmp(&b.tag))
+        });
+        trending.truncate(args.top);
+    }
+    let stats = Timeline {
+        files: index.files.len(),
+        undated,
+        months,
+        trending,
+    };
+
+    if global.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(Exit::Success);
+    }
+    if global.output.is_table() {
+        let mut months = Table::new(&["month", "files", "new_tags"]);
+        for month in &stats.months {
+            months.row(vec![
+                month.month.clone(),
+                month.files.to_string(),
+                month.new_tags.join(", "),
+            ]);
+        }
+        months.print(global.output);
+        // CSV は1つの表にしたいので、増えているタグは table と markdown のときだけ続けて出す
+        if global.output != OutputFormat::Csv && !stats.trending.is_empty() {
+            let mut trending = Table::new(&["tag", "recent", "earlier", "change"]);
+            for tag in &stats.trending {
+                trending.row(vec![
+                    tag.tag.clone(),
+                    tag.recent.to_string(),
+                    tag.earlier.to_string(),
+                    tag.change.to_string(),
+                ]);
+            }
+            println!();
+            trending.print(global.output);
+        }
+        return Ok(Exit::Success);
+    }
+    if stats.months.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "None of {} files has a date (front matter {}, file name or git history)",
+                "{} ファイルのどれにも日付がありません (Front Matter の {}、ファイル名、git の履歴)",
+                stats.files,
+                args.date_key
+            )
+        );
+        return Ok(Exit::Success);
+    }
+    println!(
+        "{}",
+        tr!(
+            "Tags by month ({} files, {} without a date):",
+            "月ごとのタグ ({} ファイル、日付のないもの {}):",
+            stats.files,
+            stats.undated
+        )
+    );
+    for month in &stats.months {
+        let files = tr!("{} files", "{} ファイル", month.files);
+        if month.new_tags.is_empty() {
+            println!("   {}  {:>10}", month.month, files);
+        } else {
+            println!(
+                "   {}  {:>10}  {}",
+                month.month,
+                files,
+                tr!("new: {}", "新しいタグ: {}", month.new_tags.join(", "))
+            );
+        }
+    }
+    if !stats.trending.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "Trending in the last {} months (change in the share of files):",
+                "最近 {} か月で増えているタグ (ファイルに付いている割合の変化):",
+                args.recent.max(1)
+            )
+        );
+        for tag in &stats.trending {
+            println!(
+                "   +{:>3}pt  {} ({})",
+                tag.change,
+                tag.tag,
+                tr!(
+                    "{} recent, {} earlier",
+                    "最近 {}、それより前 {}",
+                    tag.recent,
+                    tag.earlier
+                )
+            );
+        }
+    }
+    Ok(Exit::Success)
+}
+
+fn resolver_stats(global: &GlobalArgs) -> Result<Exit> {
+    let stats = ResolverStats::load(global.db_path())?;
+
+    if global.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(Exit::Success);
+    }
+    if global.output.is_table() {
+        // 項目と値の2列 (決め方は method:<名前>、編集距離は distance:<距離>)
+        let mut table = Table::new(&["metric", "value"]);
+        for (metric, value) in [
+            ("resolutions", stats.resolutions),
+            ("prompts", stats.prompts),
+            ("suggested", stats.suggested),
+            ("accepted", stats.accepted),
+            ("declined", stats.declined),
+        ] {
+            table.row(vec![metric.to_string(), value.to_string()]);
+        }
+        for (method, count) in &stats.methods {
+            table.row(vec![format!("method:{}", method), count.to_string()]);
+        }
+        for (distance, count) in &stats.accepted_distances {
+            table.row(vec![format!("distance:{}", distance), count.to_string()]);
+        }
+        table.print(global.output);
+        return Ok(Exit::Success);
+    }
+    if stats.resolutions == 0 {
+        println!(
+            "{}",
+            tr!(
+                "No usage statistics yet (set usage_stats = true in the config file to collect them)",
+                "利用状況の記録はまだありません (設定ファイルに usage_stats = true と書くと集計します)"
+            )
+        );
+        return Ok(Exit::Success);
+    }
+
+    println!(
+        "{}",
+        tr!("Tags resolved: {}", "解決したタグ: {}", stats.resolutions)
+    );
+    println!(
+        "{}",
+        tr!(
+            "Prompts shown: {} ({}%)",
+            "確認した回数: {} ({}%)",
+            stats.prompts,
+            percent(stats.prompts, stats.resolutions)
+        )
+    );
+    println!(
+        "{}",
+        tr!(
+            "With suggestions: {}, accepted: {} ({}%), declined: {}",
+            "候補があった回数: {}、採用: {} ({}%)、不採用: {}",
+            stats.suggested,
+            stats.accepted,
+            percent(stats.accepted, stats.suggested),
+            stats.declined
+        )
+    );
+    if !stats.accepted_distances.is_empty() {
+        println!("{}", tr!("Accepted distances:", "採用した候補の編集距離:"));
+        for (distance, count) in &stats.accepted_distances {
+            println!(
+                "   {:>2}  {:>5} ({}%)",
+                distance,
+                count,
+                percent(*count, stats.accepted)
+            );
+        }
+    }
+    if let Some(median) = stats.median_distance() {
+        println!(
+            "{}",
+            tr!(
+                "Median accepted distance: {} (a starting point for auto_accept_distance)",
+                "採用した候補の編集距離の中央値: {} (auto_accept_distance の目安)",
+                median
+            )
+        );
+    }
+    println!("{}", tr!("By method:", "決め方ごとの回数:"));
+    for (method, count) in &stats.methods {
+        println!("   {:>5}  {}", count, method);
+    }
+    Ok(Exit::Success)
+}
@@ -0,0 +1,330 @@
+// --- サブコマンド: suggest (本文のキーワードからタグを提案) ---
+//
+// 対象ファイルの本文を単語に分け、ノート群 (vault) 全体に対する TF-IDF で
+// 特徴的な単語を求め、それに一致するDBのタグを提案する。
+// あわせて、タグやタイトルが似ているノートに付いている他のタグも提案する。
+
+use anyhow::{Context, Result};
+use clap::Args;
+use mdtagger::markdown::{
+    FrontMatterOptions, collect_markdown_files, content_tags, split_front_matter,
+};
+use mdtagger::normalize::stem;
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{GlobalArgs, OutputFormat};
+use crate::exit::Exit;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Args)]
+pub struct SuggestArgs {
+    /// タグを提案するMarkdownファイル
+    #[arg(value_name = "FILE")]
+    path: PathBuf,
+
+    /// IDF を計算するノート群のディレクトリ (デフォルトはファイルと同じディレクトリ)
+    #[arg(long, value_name = "DIR")]
+    vault: Option<PathBuf>,
+
+    /// 提案する最大件数
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    limit: usize,
+
+    /// タグを参考にする似たノートの数 (0 なら似たノートからは提案しない)
+    #[arg(long, value_name = "N", default_value_t = 5)]
+    similar_notes: usize,
+}
+
+// --- JSON出力 ---
+#[derive(Serialize, JsonSchema)]
+struct Report<'a> {
+    path: &'a Path,
+    /// 本文のキーワードから見つけたタグ (スコアの高い順)
+    suggestions: Vec<Suggestion<'a>>,
+    /// 似たノートに付いているタグ (スコアの高い順)
+    from_similar_notes: Vec<Suggestion<'a>>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Suggestion<'a> {
+    tag: &'a str,
+    score: f64,
+    /// そのタグが付いている似たノート (from_similar_notes のみ)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    example: Option<&'a Path>,
+}
+
+/// --output json で出力する内容の JSON Schema
+pub fn output_schema() -> schemars::Schema {
+    crate::schema::for_output::<Report<'static>>()
+}
+
+/// vault 内の1ノート分の情報
+struct Note {
+    path: PathBuf,
+    title_terms: HashSet<String>,
+    tags: HashSet<String>,
+    terms: HashSet<String>,
+}
+
+pub fn run(global: &GlobalArgs, args: &SuggestArgs) -> Result<Exit> {
+    let config = global.load_db()?;
+    let front_matter = global.settings.front_matter();
+    let content = fs::read_to_string(&args.path)
+        .with_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", args.path))?;
+    let (yaml, body) = split_front_matter(&content).with_context(|| {
+        tr!(
+            "Invalid front matter in {:?}",
+            "{:?} の Front Matter が不正です",
+            args.path
+        )
+    })?;
+    let existing: HashSet<String> = content_tags(&content, &front_matter)?.into_iter().collect();
+    let title_terms = title_terms(&yaml, &args.path);
+
+    let vault = match &args.vault {
+        Some(dir) => dir.clone(),
+        None => match args.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        },
+    };
+    let notes = scan_vault(&vault, &front_matter)?;
+    let scores = tf_idf(&body, &notes);
+
+    let mut suggestions: Vec<(&str, f64)> = config
+        .tags
+        .iter()
+        .filter(|entry| !existing.contains(&entry.name))
+        .filter_map(|entry| {
+            std::iter::once(&entry.name)
+                .chain(&entry.aliases)
+                .filter_map(|form| tag_score(form, &scores))
+                .max_by(f64::total_cmp)
+                .map(|score| (entry.name.as_str(), score))
+        })
+        .collect();
+    suggestions.sort_by(|a, b| b.1.total_cmp(&a.1));
+    suggestions.truncate(args.limit);
+
+    let mut related = Vec::new();
+    if args.similar_notes > 0 {
+        let target = fs::canonicalize(&args.path).ok();
+        let others: Vec<&Note> = notes
+            .iter()
+            .filter(|note| fs::canonicalize(&note.path).ok() != target)
+            .collect();
+        related = tags_from_similar_notes(&existing, &title_terms, &others, args);
+    }
+
+    if global.output == OutputFormat::Json {
+        let report = Report {
+            path: &args.path,
+            suggestions: suggestions
+                .iter()
+                .map(|&(tag, score)| Suggestion {
+                    tag,
+                    score,
+                    example: None,
+                })
+                .collect(),
+            from_similar_notes: related
+                .iter()
+                .map(|(tag, score, from)| Suggestion {
+                    tag,
+                    score: *score,
+                    example: Some(from),
+                })
+                .collect(),
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(Exit::Success);
+    }
+
+    // パイプの先では見出しを付けず、1行に1つずつタブ区切りで出す
+    if !global.interactive {
+        for (name, score) in suggestions {
+            println!("{}\t{:.3}", name, score);
+        }
+        for (name, score, from) in related {
+            println!("{}\t{:.3}\t{}", name, score, from.display());
+        }
+        return Ok(Exit::Success);
+    }
+
+    if suggestions.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "No tag suggestions for {:?}",
+                "{:?} に提案できるタグはありません",
+                args.path
+            )
+        );
+    } else {
+        println!(
+            "{}",
+            tr!(
+                "Suggested tags for {:?}:",
+                "{:?} に提案するタグ:",
+                args.path
+            )
+        );
+        for (name, score) in suggestions {
+            println!("   {:<24} {:.3}", name, score);
+        }
+    }
+
+    if !related.is_empty() {
+        println!(
+            "{}",
+            tr!("Tags from similar notes:", "似ているノートのタグ:")
+        );
+        for (name, score, from) in related {
+            println!(
+                "{}",
+                tr!(
+                    "   {:<24} {:.3}  (e.g. {:?})",
+                    "   {:<24} {:.3}  (例: {:?})",
+                    name,
+                    score,
+                    from
+                )
+            );
+        }
+    }
+    Ok(Exit::Success)
+}
+
+/// タグ集合とタイトルの単語が似ているノートを選び、そのノートにあって自分にないタグを集める
+fn tags_from_similar_notes<'a>(
+    tags: &HashSet<String>,
+    title_terms: &HashSet<String>,
+    others: &[&'a Note],
+    args: &SuggestArgs,
+) -> Vec<(String, f64, &'a Path)> {
+    let mut similar: Vec<(&Note, f64)> = others
+        .iter()
+        .map(|note| {
+            let score = jaccard(tags, &note.tags) + jaccard(title_terms, &note.title_terms);
+            (*note, score)
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    similar.sort_by(|a, b| b.1.total_cmp(&a.1));
+    similar.truncate(args.similar_notes);
+
+    let mut scored: HashMap<&str, (f64, &Path)> = HashMap::new();
+    for (note, score) in &similar {
+        for tag in note.tags.iter().filter(|t| !tags.contains(*t)) {
+            let entry = scored.entry(tag.as_str()).or_insert((0.0, &note.path));
+            entry.0 += score;
+        }
+    }
+    let mut related: Vec<(String, f64, &Path)> = scored
+        .into_iter()
+        .map(|(tag, (score, from))| (tag.to_string(), score, from))
+        .collect();
+    related.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    related.truncate(args.limit);
+    related
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Front Matter の title (なければファイル名) の単語
+fn title_terms(yaml: &serde_yaml::Value, path: &Path) -> HashSet<String> {
+    let title = yaml
+        .get("title")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .or_else(|| path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+    tokenize(&title).into_iter().collect()
+}
+
+/// vault 内のノートを読み込む (読めないファイルは統計から外すだけにする)
+fn scan_vault(vault: &Path, front_matter: &FrontMatterOptions) -> Result<Vec<Note>> {
+    let mut notes = Vec::new();
+    for path in collect_markdown_files(vault)? {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok((yaml, body)) = split_front_matter(&content) else {
+            continue;
+        };
+        notes.push(Note {
+            title_terms: title_terms(&yaml, &path),
+            tags: content_tags(&content, front_matter)
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            terms: tokenize(&body).into_iter().collect(),
+            path,
+        });
+    }
+    Ok(notes)
+}
+
+/// 本文を単語 (語幹) に分ける
+fn tokenize(text: &str) -> Vec<String> {
+    let text: String = text.nfkc().collect();
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 2)
+        .map(|w| stem(&w.to_lowercase()))
+        .collect()
+}
+
+/// 本文中の各単語の TF-IDF を vault 内の全ノートに対して計算する
+fn tf_idf(body: &str, notes: &[Note]) -> HashMap<String, f64> {
+    let terms = tokenize(body);
+    if terms.is_empty() {
+        return HashMap::new();
+    }
+    let mut tf: HashMap<String, usize> = HashMap::new();
+    for term in &terms {
+        *tf.entry(term.clone()).or_default() += 1;
+    }
+
+    let mut df: HashMap<&str, usize> = HashMap::new();
+    for note in notes {
+        for term in tf.keys() {
+            if note.terms.contains(term) {
+                *df.entry(term.as_str()).or_default() += 1;
+            }
+        }
+    }
+
+    let n = notes.len() as f64;
+    let total = terms.len() as f64;
+    tf.iter()
+        .map(|(term, count)| {
+            let doc_freq = df.get(term.as_str()).copied().unwrap_or(0) as f64;
+            let idf = ((1.0 + n) / (1.0 + doc_freq)).ln() + 1.0;
+            (term.clone(), *count as f64 / total * idf)
+        })
+        .collect()
+}
+
+/// `machine-learning` のような複合タグは、すべての語が本文にあるときだけ一致とみなす
+fn tag_score(form: &str, scores: &HashMap<String, f64>) -> Option<f64> {
+    let parts = tokenize(form);
+    if parts.is_empty() {
+        return None;
+    }
+    parts
+        .iter()
+        .map(|part| scores.get(part).copied())
+        .try_fold(f64::INFINITY, |acc, score| score.map(|s| acc.min(s)))
+}
@@ -0,0 +1,424 @@
+// --- サブコマンド: tui (全画面のタグ管理) ---
+//
+// 左にタグの木 (a/b のように / で階層化)、右に選んだタグが付いたファイルを表示し、
+// その場で名前の変更・統合・エイリアスの追加ができる。
+
+use anyhow::{Result, bail};
+use clap::Args;
+use mdtagger::markdown::{
+    FrontMatterOptions, collect_markdown_files, read_head, read_tags, replace_tag,
+};
+use mdtagger::tr;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::GlobalArgs;
+use crate::exit::Exit;
+use crate::live_db::LiveDb;
+use crate::undo::Recorder;
+
+#[derive(Args)]
+pub struct TuiArgs {
+    /// タグを集めるノートのディレクトリ
+    #[arg(value_name = "DIR", default_value = ".")]
+    dir: PathBuf,
+}
+
+pub fn run(global: &GlobalArgs, args: &TuiArgs) -> Result<Exit> {
+    if !global.interactive {
+        bail!(tr!("tui needs a terminal", "tui は端末でのみ使えます"));
+    }
+    let mut db = LiveDb::new(global);
+    db.get()?;
+    let mut app = App {
+        db_path: global.db_path().to_path_buf(),
+        dir: args.dir.clone(),
+        front_matter: global.settings.front_matter(),
+        db,
+        files: BTreeMap::new(),
+        skipped: 0,
+        nodes: Vec::new(),
+        collapsed: HashSet::new(),
+        list: ListState::default().with_selected(Some(0)),
+        mode: Mode::Browse,
+        status: String::new(),
+    };
+    app.reload()?;
+
+    let mut terminal = ratatui::try_init()?;
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result?;
+    Ok(Exit::Success)
+}
+
+/// タグの木の1行
+struct Node {
+    /// a/b/c のような完全な名前
+    path: String,
+    depth: usize,
+    /// DBに登録されているか
+    in_db: bool,
+    /// このタグ (子孫を含む) が付いたファイルの数
+    files: usize,
+    has_children: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+    Rename,
+    Merge,
+    Alias,
+}
+
+enum Mode {
+    Browse,
+    Input { action: Action, buffer: String },
+}
+
+struct App<'a> {
+    db_path: PathBuf,
+    dir: PathBuf,
+    front_matter: FrontMatterOptions,
+    /// ほかから書き換えられたら、次のキーを押したときに読み直す
+    db: LiveDb<'a>,
+    /// ファイル -> 付いているタグ
+    files: BTreeMap<PathBuf, Vec<String>>,
+    /// Front Matter を読めずに飛ばしたファイルの数
+    skipped: usize,
+    nodes: Vec<Node>,
+    collapsed: HashSet<String>,
+    list: ListState,
+    mode: Mode,
+    status: String,
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let event = event::read()?;
+            if self.db.changed() {
+                self.db.get()?;
+                self.rebuild();
+            }
+            let Event::Key(key) = event else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match &mut self.mode {
+                Mode::Browse => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+                    KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+                    KeyCode::Enter | KeyCode::Char(' ') => self.toggle(),
+                    KeyCode::Char('r') => self.start(Action::Rename),
+                    KeyCode::Char('m') => self.start(Action::Merge),
+                    KeyCode::Char('a') => self.start(Action::Alias),
+                    _ => {}
+                },
+                Mode::Input { action, buffer } => match key.code {
+                    KeyCode::Esc => self.mode = Mode::Browse,
+                    KeyCode::Backspace => {
+                        buffer.pop();
+                    }
+                    KeyCode::Char(c) => buffer.push(c),
+                    KeyCode::Enter => {
+                        let (action, value) = (*action, buffer.trim().to_string());
+                        self.mode = Mode::Browse;
+                        if !value.is_empty() {
+                            self.status = match self.apply(action, &value) {
+                                Ok(message) => message,
+                                Err(err) => tr!("Error: {:#}", "エラー: {:#}", err),
+                            };
+                        }
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    /// DBとファイルを読み直して木を作り直す
+    fn reload(&mut self) -> Result<()> {
+        self.files.clear();
+        self.skipped = 0;
+        for path in collect_markdown_files(&self.dir)? {
+            match read_tags(&path, &self.front_matter) {
+                Ok(tags) => {
+                    self.files.insert(path, tags);
+                }
+                Err(_) => self.skipped += 1,
+            }
+        }
+        self.rebuild();
+        Ok(())
+    }
+
+    fn rebuild(&mut self) {
+        let db_tags: HashSet<&str> = self
+            .db
+            .peek()
+            .map(|db| db.tags())
+            .unwrap_or_default()
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        let mut paths: BTreeSet<String> = BTreeSet::new();
+        for tag in db_tags
+            .iter()
+            .copied()
+            .chain(self.files.values().flatten().map(|t| t.as_str()))
+        {
+            // 親の階層も木の節として並べる
+            let mut prefix = String::new();
+            for (i, part) in tag.split('/').enumerate() {
+                if i > 0 {
+                    prefix.push('/');
+                }
+                prefix.push_str(part);
+                paths.insert(prefix.clone());
+            }
+        }
+
+        self.nodes = paths
+            .iter()
+            .filter(|path| {
+                !self
+                    .collapsed
+                    .iter()
+                    .any(|c| path.starts_with(&format!("{}/", c)))
+            })
+            .map(|path| Node {
+                path: path.clone(),
+                depth: path.matches('/').count(),
+                in_db: db_tags.contains(path.as_str()),
+                files: self.files_for(path).len(),
+                has_children: paths
+                    .range(format!("{}/", path)..)
+                    .next()
+                    .is_some_and(|p| p.starts_with(&format!("{}/", path))),
+            })
+            .collect();
+
+        let selected = self.list.selected().unwrap_or(0);
+        self.list
+            .select(Some(selected.min(self.nodes.len().saturating_sub(1))));
+    }
+
+    /// タグ (子孫を含む) が付いているファイル
+    fn files_for(&self, tag: &str) -> Vec<&Path> {
+        let child_prefix = format!("{}/", tag);
+        self.files
+            .iter()
+            .filter(|(_, tags)| {
+                tags.iter()
+                    .any(|t| t == tag || t.starts_with(&child_prefix))
+            })
+            .map(|(path, _)| path.as_path())
+            .collect()
+    }
+
+    fn selected(&self) -> Option<&Node> {
+        self.list.selected().and_then(|i| self.nodes.get(i))
+    }
+
+    fn toggle(&mut self) {
+        let Some(node) = self.selected() else {
+            return;
+        };
+        if !node.has_children {
+            return;
+        }
+        let path = node.path.clone();
+        if !self.collapsed.remove(&path) {
+            self.collapsed.insert(path);
+        }
+        self.rebuild();
+    }
+
+    fn start(&mut self, action: Action) {
+        if self.selected().is_some() {
+            self.mode = Mode::Input {
+                action,
+                buffer: String::new(),
+            };
+        }
+    }
+
+    /// 選択中のタグに操作を行い、DBとファイルに書き込む
+    fn apply(&mut self, action: Action, value: &str) -> Result<String> {
+        let Some(node) = self.selected() else {
+            return Ok(String::new());
+        };
+        let tag = node.path.clone();
+        let in_db = node.in_db;
+
+        let operation = match action {
+            Action::Rename => "rename",
+            Action::Merge => "merge",
+            Action::Alias => "alias",
+        };
+        let mut recorder = Recorder::start(operation, &self.db_path, &self.front_matter);
+        let message = match action {
+            Action::Alias => {
+                self.db.get()?.add_alias(&tag, value)?;
+                self.db.save()?;
+                tr!(
+                    "Registered '{}' as alias for '{}'",
+                    "'{}' を '{}' のエイリアスとして登録しました",
+                    value,
+                    tag
+                )
+            }
+            Action::Rename | Action::Merge => {
+                let db = self.db.get()?;
+                let target_in_db = db.contains(value);
+                if in_db {
+                    match action {
+                        Action::Merge => db.merge(&tag, value)?,
+                        _ => db.rename(&tag, value)?,
+                    }
+                } else if matches!(action, Action::Rename) && target_in_db {
+                    anyhow::bail!(tr!(
+                        "Tag '{}' already exists (merge into it instead)",
+                        "タグ '{}' はすでにあります (統合を使ってください)",
+                        value
+                    ));
+                }
+                let rewritten = self.rewrite_files(&tag, value, &mut recorder)?;
+                if in_db {
+                    self.db.save()?;
+                }
+                let verb = match action {
+                    Action::Merge => tr!("Merged", "統合"),
+                    _ => tr!("Renamed", "名前を変更"),
+                };
+                tr!(
+                    "{} '{}' into '{}' ({} files)",
+                    "{} '{}' -> '{}' ({} ファイル)",
+                    verb,
+                    tag,
+                    value,
+                    rewritten
+                )
+            }
+        };
+        recorder.finish()?;
+        self.reload()?;
+        Ok(message)
+    }
+
+    /// from が付いたファイルのタグを to に書き換え、書き換えたファイル数を返す
+    fn rewrite_files(&self, from: &str, to: &str, recorder: &mut Recorder) -> Result<usize> {
+        let mut rewritten = 0;
+        for (path, tags) in &self.files {
+            if !tags.iter().any(|t| t == from) {
+                continue;
+            }
+            let content = read_head(path, &self.front_matter)?;
+            let new_content = replace_tag(&content, from, to, &self.front_matter)?;
+            if new_content != content {
+                let new_content = recorder.pre_write(path, &content, new_content)?;
+                recorder.write_file(path, &content, new_content)?;
+                rewritten += 1;
+            }
+        }
+        Ok(rewritten)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status, help] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main);
+
+        let items: Vec<ListItem> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let marker = match (node.has_children, self.collapsed.contains(&node.path)) {
+                    (false, _) => "  ",
+                    (true, true) => "▸ ",
+                    (true, false) => "▾ ",
+                };
+                let label = node.path.rsplit('/').next().unwrap_or(&node.path);
+                let mut line = Line::from(format!(
+                    "{}{}{} ({})",
+                    "  ".repeat(node.depth),
+                    marker,
+                    label,
+                    node.files
+                ));
+                // DBにないタグ (ファイルにだけある) は暗く表示する
+                if !node.in_db {
+                    line = line.dim();
+                }
+                ListItem::new(line)
+            })
+            .collect();
+        let tags = List::new(items)
+            .block(Block::bordered().title(format!(
+                " Tags ({}) ",
+                self.db.peek().map_or(0, |db| db.tags().len())
+            )))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(tags, left, &mut self.list);
+
+        let (title, files): (String, Vec<ListItem>) = match self.selected() {
+            Some(node) => (
+                format!(" Files tagged '{}' ", node.path),
+                self.files_for(&node.path)
+                    .into_iter()
+                    .map(|p| ListItem::new(p.display().to_string()))
+                    .collect(),
+            ),
+            None => (" Files ".to_string(), Vec::new()),
+        };
+        frame.render_widget(
+            List::new(files).block(Block::bordered().title(title)),
+            right,
+        );
+
+        let status_line = match &self.mode {
+            Mode::Input { action, buffer } => {
+                let prompt = match action {
+                    Action::Rename => tr!("Rename to", "新しい名前"),
+                    Action::Merge => tr!("Merge into", "統合先"),
+                    Action::Alias => tr!("New alias", "追加するエイリアス"),
+                };
+                format!("{}: {}", prompt, buffer)
+            }
+            Mode::Browse if self.skipped > 0 && self.status.is_empty() => {
+                tr!(
+                    "Skipped {} files with invalid front matter",
+                    "Front Matter が不正な {} ファイルを飛ばしました",
+                    self.skipped
+                )
+            }
+            Mode::Browse => self.status.clone(),
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+        frame.render_widget(
+            Paragraph::new(tr!(
+                "↑/↓ move  Enter fold  r rename  m merge  a alias  q quit",
+                "↑/↓ 移動  Enter 開閉  r 名前の変更  m 統合  a エイリアス  q 終了"
+            ))
+            .dim(),
+            help,
+        );
+    }
+}
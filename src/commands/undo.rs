@@ -0,0 +1,137 @@
+// --- サブコマンド: undo (直前の操作を取り消す) ---
+
+use anyhow::{Result, bail};
+use clap::Args;
+use log::{info, warn};
+use mdtagger::markdown::{FrontMatterOptions, read_head, replace_head, starts_with};
+use mdtagger::tr;
+use std::fs;
+
+use super::GlobalArgs;
+use crate::exit::Exit;
+use crate::history::{self, HistoryEntry};
+use crate::hooks;
+use crate::index;
+use crate::undo::{self, FileChange};
+
+#[derive(Args)]
+pub struct UndoArgs {
+    /// 直前の操作のあとに変更されたファイルがあっても元に戻す
+    #[arg(long)]
+    force: bool,
+}
+
+/// ファイルが直前の操作で書き込んだままか (先頭だけの記録なら先頭だけを比べる)
+fn written_as_recorded(change: &FileChange) -> bool {
+    if change.head_only {
+        starts_with(&change.path, &change.after)
+    } else {
+        fs::read_to_string(&change.path).ok().as_deref() == Some(change.after.as_str())
+    }
+}
+
+/// ファイルを直前の操作の前に戻す (先頭だけの記録なら、本文はいまのまま残す)
+fn restore(change: &FileChange) -> Result<()> {
+    if !change.head_only {
+        fs::write(&change.path, &change.before)?;
+    } else if starts_with(&change.path, &change.after) {
+        replace_head(&change.path, &change.after, &change.before)?;
+    } else {
+        // --force で、先頭が書き込んだあとに変えられている
+        let current = read_head(&change.path, &FrontMatterOptions::default())?;
+        replace_head(&change.path, &current, &change.before)?;
+    }
+    Ok(())
+}
+
+pub fn run(global: &GlobalArgs, args: &UndoArgs) -> Result<Exit> {
+    let db_path = global.db_path();
+    let Some(log) = undo::last(db_path)? else {
+        info!("{}", tr!("Nothing to undo", "取り消せる操作はありません"));
+        return Ok(Exit::Unchanged);
+    };
+
+    // 直前の操作のあとに手で編集された内容を黙って消さない
+    let mut conflicts = Vec::new();
+    for change in &log.files {
+        if !written_as_recorded(change) {
+            conflicts.push(change.path.clone());
+        }
+    }
+    if fs::read_to_string(&log.db_path).ok() != log.db_after {
+        conflicts.push(log.db_path.clone());
+    }
+    if !conflicts.is_empty() && !args.force {
+        for path in &conflicts {
+            warn!(
+                "{}",
+                tr!(
+                    "⚠️  {:?} has changed since the last run",
+                    "⚠️  {:?} は直前の操作のあとに変更されています",
+                    path
+                )
+            );
+        }
+        bail!(tr!(
+            "Refusing to undo over newer changes (pass --force to undo anyway)",
+            "新しい変更を上書きしないよう取り消しを中止しました (構わず戻すには --force を付けてください)"
+        ));
+    }
+
+    for change in &log.files {
+        restore(change)?;
+        info!(
+            "{}",
+            tr!(
+                "↩️  Restored {:?}",
+                "↩️  {:?} を元に戻しました",
+                change.path
+            )
+        );
+    }
+    index::update(
+        &log.db_path,
+        &global.settings.front_matter(),
+        log.files
+            .iter()
+            .map(|change| (change.path.as_path(), change.before.as_str())),
+    );
+    match &log.db_before {
+        Some(content) => fs::write(&log.db_path, content)?,
+        None if log.db_path.exists() => fs::remove_file(&log.db_path)?,
+        None => {}
+    }
+    if log.db_after != log.db_before {
+        info!(
+            "{}",
+            tr!(
+                "↩️  Restored tag database {:?}",
+                "↩️  タグデータベース {:?} を元に戻しました",
+                log.db_path
+            )
+        );
+    }
+    undo::clear(db_path)?;
+
+    // 取り消しも履歴に残す (変更前後を入れ替えて記録する)
+    let reverted: Vec<FileChange> = log
+        .files
+        .into_iter()
+        .map(|change| FileChange {
+            path: change.path,
+            before: change.after,
+            after: change.before,
+            head_only: change.head_only,
+        })
+        .collect();
+    let entry = HistoryEntry::new(
+        "undo",
+        &global.settings.front_matter(),
+        log.db_after.as_deref(),
+        log.db_before.as_deref(),
+        &reverted,
+    );
+    history::append(db_path, &entry)?;
+    hooks::post_write(db_path, &entry);
+    Ok(Exit::Success)
+}
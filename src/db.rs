@@ -0,0 +1,797 @@
+// --- タグデータベース (JSON) ---
+
+use crate::error::{IoContext, MdtaggerError, Result};
+use crate::normalize::fold_diacritics;
+use crate::tr;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use strsim::levenshtein;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct TagEntry {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// このタグへの対応付けを断られた入力 (候補の並びで後回しにする)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rejected: Vec<String>,
+    /// これまでにファイルへ付与された回数 (候補の並び替えに使う)
+    #[serde(default)]
+    pub uses: u64,
+    /// 入力 -> 「Typo correction」でこのタグに直した回数
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub corrections: BTreeMap<String, u32>,
+}
+
+impl TagEntry {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            aliases: Vec::new(),
+            rejected: Vec::new(),
+            uses: 0,
+            corrections: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct TagConfig {
+    pub tags: Vec<TagEntry>,
+    #[serde(default, skip_serializing_if = "PromptDefaults::is_default")]
+    pub defaults: PromptDefaults,
+    #[serde(default, skip_serializing_if = "NormalizeConfig::is_default")]
+    pub normalize: NormalizeConfig,
+    /// 略語 -> 正式名 (エイリアスと違い、候補探索より前に確認なしで展開する)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub abbreviations: BTreeMap<String, String>,
+    /// 新しいタグ名の整形ルール
+    #[serde(default, skip_serializing_if = "SlugConfig::is_unsaved")]
+    pub slug: SlugConfig,
+}
+
+/// 新規タグ名の整形ルール ("Machine Learning" -> "machine-learning")
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct SlugConfig {
+    /// 小文字に揃える
+    #[serde(default)]
+    pub lowercase: bool,
+    /// 空白をこの文字列に置き換える (例: "-")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub separator: Option<String>,
+    /// 記号を取り除く (- と _ は残す)
+    #[serde(default)]
+    pub strip_punctuation: bool,
+    /// / を入れ子のタグの区切りとして残し、前後の空白を詰める ("Lang / Rust" -> "Lang/Rust")
+    #[serde(default)]
+    pub nested: bool,
+    /// DBの外 (Obsidian の保管庫など) から推定した規則か (true ならDBには保存しない)
+    #[serde(skip)]
+    pub inferred: bool,
+}
+
+impl SlugConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.lowercase || self.separator.is_some() || self.strip_punctuation || self.nested
+    }
+
+    /// DBに保存しない規則か (推定したものか、何も整形しないもの)
+    pub fn is_unsaved(&self) -> bool {
+        self.inferred || !self.is_enabled()
+    }
+
+    pub fn apply(&self, name: &str) -> String {
+        if self.nested {
+            return name
+                .split('/')
+                .map(|part| self.apply_part(part.trim()))
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("/");
+        }
+        self.apply_part(name)
+    }
+
+    fn apply_part(&self, name: &str) -> String {
+        let mut slug: String = name
+            .chars()
+            .filter(|c| !(self.strip_punctuation && c.is_ascii_punctuation() && !"-_".contains(*c)))
+            .collect();
+        if self.lowercase {
+            slug = slug.to_lowercase();
+        }
+        if let Some(sep) = &self.separator {
+            slug = slug.split_whitespace().collect::<Vec<_>>().join(sep);
+        }
+        slug
+    }
+}
+
+/// 入力タグを解決前に書き換える規則
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct NormalizeConfig {
+    /// 小文字に揃える
+    #[serde(default)]
+    pub lowercase: bool,
+    /// 上から順に適用する置換規則
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<RewriteRule>,
+}
+
+impl NormalizeConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct RewriteRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub replacement: String,
+}
+
+/// 対話プロンプトへの既定の答え (未設定なら毎回確認する)
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct PromptDefaults {
+    /// 新規タグをDBに登録するか (true: 常に登録 / false: 登録しない)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub register_new_tags: Option<bool>,
+    /// 未知のタグを最も近いタグのエイリアスにするか (true: 常に登録 / false: 選択肢に出さない)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub register_aliases: Option<bool>,
+    /// この距離以内の候補が1つだけなら確認せずに採用する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_accept_distance: Option<usize>,
+    /// 同じ入力をこの回数だけ手で直したら、エイリアス登録を勧める (デフォルト: 3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggest_alias_after: Option<u32>,
+    /// これより短い入力ではあいまい検索をせず、完全一致かエイリアスだけを使う (デフォルト: 3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_fuzzy_length: Option<usize>,
+}
+
+impl PromptDefaults {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 整形ルールを通した名前で新しいタグを登録し、その名前を返す
+pub fn register_new_tag(input: &str, config: &mut TagConfig) -> String {
+    let name = config.slug.apply(input);
+    config.tags.push(TagEntry::new(&name));
+    name
+}
+
+/// 付与したタグの使用回数を数える (DBに登録済みのタグのみ)
+pub fn record_usage(config: &mut TagConfig, tags: &[String]) -> bool {
+    let mut updated = false;
+    for tag in tags {
+        if let Some(entry) = config.tags.iter_mut().find(|e| &e.name == tag) {
+            entry.uses += 1;
+            updated = true;
+        }
+    }
+    updated
+}
+
+// --- 語彙の編集 ---
+/// タグの名前を変える (元の名前はエイリアスとして残す)
+pub fn rename_tag(config: &mut TagConfig, from: &str, to: &str) -> Result<()> {
+    if config.tags.iter().any(|e| e.name == to) {
+        return Err(MdtaggerError::InvalidEdit(tr!(
+            "Tag '{}' already exists (merge '{}' into it instead)",
+            "タグ '{}' はすでにあります ('{}' を統合してください)",
+            to,
+            from
+        )));
+    }
+    // 別のタグのエイリアスにもできない (自分のエイリアスなら、それを名前にする)
+    if let Some(owner) = config
+        .tags
+        .iter()
+        .find(|e| e.name != from && e.aliases.iter().any(|a| a == to))
+    {
+        return Err(MdtaggerError::InvalidEdit(tr!(
+            "'{}' is already used by tag '{}'",
+            "'{}' はすでにタグ '{}' で使われています",
+            to,
+            owner.name
+        )));
+    }
+    let entry = config
+        .tags
+        .iter_mut()
+        .find(|e| e.name == from)
+        .ok_or_else(|| MdtaggerError::TagNotFound(from.to_string()))?;
+    entry.name = to.to_string();
+    entry.aliases.retain(|a| a != to);
+    entry.aliases.push(from.to_string());
+    retarget_abbreviations(config, from, to);
+    Ok(())
+}
+
+/// from を into に統合する (from の名前とエイリアスは into のエイリアスになる)
+pub fn merge_tags(config: &mut TagConfig, from: &str, into: &str) -> Result<()> {
+    if from == into {
+        return Err(MdtaggerError::InvalidEdit(tr!(
+            "Cannot merge tag '{}' into itself",
+            "タグ '{}' を自分自身に統合することはできません",
+            from
+        )));
+    }
+    if !config.tags.iter().any(|e| e.name == into) {
+        return Err(MdtaggerError::TagNotFound(into.to_string()));
+    }
+    let pos = config
+        .tags
+        .iter()
+        .position(|e| e.name == from)
+        .ok_or_else(|| MdtaggerError::TagNotFound(from.to_string()))?;
+    let source = config.tags.remove(pos);
+    let target = config.tags.iter_mut().find(|e| e.name == into).unwrap();
+
+    for alias in std::iter::once(source.name).chain(source.aliases) {
+        if alias != target.name && !target.aliases.contains(&alias) {
+            target.aliases.push(alias);
+        }
+    }
+    for input in source.rejected {
+        if !target.rejected.contains(&input) {
+            target.rejected.push(input);
+        }
+    }
+    for (input, count) in source.corrections {
+        *target.corrections.entry(input).or_default() += count;
+    }
+    target.uses += source.uses;
+    retarget_abbreviations(config, from, into);
+    Ok(())
+}
+
+/// 既存のタグにエイリアスを足す
+pub fn add_alias(config: &mut TagConfig, tag: &str, alias: &str) -> Result<()> {
+    if let Some(owner) = config
+        .tags
+        .iter()
+        .find(|e| e.name == alias || e.aliases.iter().any(|a| a == alias))
+    {
+        return Err(MdtaggerError::InvalidEdit(tr!(
+            "'{}' is already used by tag '{}'",
+            "'{}' はすでにタグ '{}' で使われています",
+            alias,
+            owner.name
+        )));
+    }
+    let entry = config
+        .tags
+        .iter_mut()
+        .find(|e| e.name == tag)
+        .ok_or_else(|| MdtaggerError::TagNotFound(tag.to_string()))?;
+    entry.aliases.push(alias.to_string());
+    Ok(())
+}
+
+fn retarget_abbreviations(config: &mut TagConfig, from: &str, to: &str) {
+    for expanded in config.abbreviations.values_mut() {
+        if expanded == from {
+            *expanded = to.to_string();
+        }
+    }
+}
+
+// --- 3方向のマージ ---
+// serve・lsp・tui のようにDBを読み込んだまま動いているあいだに、ほかのプロセスや手でファイルが書き換えられたとき、
+// 読み込んだときの内容 (base) からの両方の変更を合わせて、保存するときにほかの変更を消さないようにする。
+
+/// base からの ours と theirs の変更を合わせる (theirs をもとに、ours での変更を当てる)
+///
+/// タグはタグ名で対応付ける。使われた回数と訂正の回数は両方で増えた分を足し、エイリアスと断られた入力は
+/// 両方で足したものを残してどちらかで消したものを消す。どちらかで消したタグは消す
+/// (名前の変更と統合は、元の名前を消して新しい名前を足したものとして合わさる)。
+/// 略語と設定 (defaults・normalize・slug) は、ours で変えたものは ours、そうでなければ theirs にする。
+pub fn merge_configs(base: &TagConfig, ours: &TagConfig, theirs: &TagConfig) -> TagConfig {
+    let base_tags = by_name(base);
+    let our_tags = by_name(ours);
+    let their_tags = by_name(theirs);
+
+    let mut tags = Vec::new();
+    for entry in &theirs.tags {
+        let name = entry.name.as_str();
+        match (base_tags.get(name), our_tags.get(name)) {
+            // ours で消した
+            (Some(_), None) => {}
+            (Some(original), Some(our)) => tags.push(merge_entry(original, our, entry)),
+            // 両方で足した
+            (None, Some(our)) => tags.push(merge_entry(&TagEntry::new(name), our, entry)),
+            (None, None) => tags.push(entry.clone()),
+        }
+    }
+    // ours で足したタグ (theirs で消したタグは足し直さない)
+    for entry in &ours.tags {
+        let name = entry.name.as_str();
+        if !base_tags.contains_key(name) && !their_tags.contains_key(name) {
+            tags.push(entry.clone());
+        }
+    }
+
+    let mut abbreviations = theirs.abbreviations.clone();
+    for key in base.abbreviations.keys().chain(ours.abbreviations.keys()) {
+        match (base.abbreviations.get(key), ours.abbreviations.get(key)) {
+            (original, our) if original == our => {}
+            (_, Some(expanded)) => {
+                abbreviations.insert(key.clone(), expanded.clone());
+            }
+            (_, None) => {
+                abbreviations.remove(key);
+            }
+        }
+    }
+
+    TagConfig {
+        tags,
+        defaults: pick(&base.defaults, &ours.defaults, &theirs.defaults),
+        normalize: pick(&base.normalize, &ours.normalize, &theirs.normalize),
+        abbreviations,
+        slug: pick(&base.slug, &ours.slug, &theirs.slug),
+    }
+}
+
+fn by_name(config: &TagConfig) -> HashMap<&str, &TagEntry> {
+    config.tags.iter().map(|e| (e.name.as_str(), e)).collect()
+}
+
+/// ours で変えていれば ours、そうでなければ theirs
+fn pick<T: PartialEq + Clone>(base: &T, ours: &T, theirs: &T) -> T {
+    if ours != base {
+        ours.clone()
+    } else {
+        theirs.clone()
+    }
+}
+
+fn merge_entry(base: &TagEntry, ours: &TagEntry, theirs: &TagEntry) -> TagEntry {
+    let mut corrections = BTreeMap::new();
+    for input in theirs.corrections.keys().chain(ours.corrections.keys()) {
+        let count =
+            |entry: &TagEntry| u64::from(entry.corrections.get(input).copied().unwrap_or(0));
+        let merged = merge_count(count(base), count(ours), count(theirs));
+        if merged > 0 {
+            corrections.insert(input.clone(), u32::try_from(merged).unwrap_or(u32::MAX));
+        }
+    }
+    TagEntry {
+        name: theirs.name.clone(),
+        aliases: merge_list(&base.aliases, &ours.aliases, &theirs.aliases),
+        rejected: merge_list(&base.rejected, &ours.rejected, &theirs.rejected),
+        uses: merge_count(base.uses, ours.uses, theirs.uses),
+        corrections,
+    }
+}
+
+/// 回数は両方の増えた分 (減った分) を足す
+fn merge_count(base: u64, ours: u64, theirs: u64) -> u64 {
+    (theirs + ours).saturating_sub(base)
+}
+
+/// theirs の順のまま、ours で消したものを除き、ours で足したものを後ろに足す
+fn merge_list(base: &[String], ours: &[String], theirs: &[String]) -> Vec<String> {
+    let mut merged: Vec<String> = theirs
+        .iter()
+        .filter(|item| !base.contains(item) || ours.contains(item))
+        .cloned()
+        .collect();
+    for item in ours {
+        if !base.contains(item) && !merged.contains(item) {
+            merged.push(item.clone());
+        }
+    }
+    merged
+}
+
+// --- TagDatabase ---
+/// タグデータベースと、名前・エイリアスからタグを引く索引
+///
+/// 語彙の編集 (タグ・エイリアスの追加、名前の変更、統合) はこの型を通して行い、
+/// そのたびに索引を作り直す。TagConfig を直接書き換えた場合は edit を使う。
+#[derive(Debug, Default, Clone)]
+pub struct TagDatabase {
+    config: TagConfig,
+    /// タグ名・エイリアス -> config.tags の位置
+    index: HashMap<String, usize>,
+}
+
+impl TagDatabase {
+    pub fn new(config: TagConfig) -> Self {
+        let mut db = Self {
+            config,
+            index: HashMap::new(),
+        };
+        db.reindex();
+        db
+    }
+
+    /// ファイルから読む (ファイルがなければ空のデータベース)
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self::new(load_config(path)?))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        save_config(path, &self.config)
+    }
+
+    pub fn config(&self) -> &TagConfig {
+        &self.config
+    }
+
+    pub fn into_config(self) -> TagConfig {
+        self.config
+    }
+
+    pub fn tags(&self) -> &[TagEntry] {
+        &self.config.tags
+    }
+
+    /// TagConfig を直接書き換え、索引を作り直す (TagResolver に渡すときなど)
+    pub fn edit<R>(&mut self, edit: impl FnOnce(&mut TagConfig) -> R) -> R {
+        let result = edit(&mut self.config);
+        self.reindex();
+        result
+    }
+
+    fn reindex(&mut self) {
+        self.index.clear();
+        for (i, entry) in self.config.tags.iter().enumerate() {
+            for alias in &entry.aliases {
+                self.index.entry(alias.clone()).or_insert(i);
+            }
+        }
+        // タグ名はエイリアスより優先する
+        for (i, entry) in self.config.tags.iter().enumerate() {
+            self.index.insert(entry.name.clone(), i);
+        }
+    }
+
+    /// タグ名かエイリアスが name のタグ
+    pub fn lookup(&self, name: &str) -> Option<&TagEntry> {
+        self.index.get(name).map(|&i| &self.config.tags[i])
+    }
+
+    /// name がタグ名 (エイリアスではなく) として登録されているか
+    pub fn contains(&self, name: &str) -> bool {
+        self.lookup(name).is_some_and(|e| e.name == name)
+    }
+
+    /// 編集距離 (アクセント記号は無視) が max_distance 以内のタグを、近い順・よく使う順に返す
+    pub fn find_similar(&self, input: &str, max_distance: usize) -> Vec<(&TagEntry, usize)> {
+        let folded = fold_diacritics(input);
+        let mut similar: Vec<(&TagEntry, usize)> = self
+            .config
+            .tags
+            .iter()
+            .map(|e| (e, levenshtein(&fold_diacritics(&e.name), &folded)))
+            .filter(|(_, dist)| *dist <= max_distance)
+            .collect();
+        similar.sort_by(|(a, da), (b, db)| da.cmp(db).then_with(|| b.uses.cmp(&a.uses)));
+        similar
+    }
+
+    /// 新しいタグを登録する (名前がタグ名かエイリアスとして使われていればエラー)
+    pub fn add_tag(&mut self, name: &str) -> Result<&TagEntry> {
+        if let Some(owner) = self.lookup(name) {
+            return Err(MdtaggerError::InvalidEdit(tr!(
+                "'{}' is already used by tag '{}'",
+                "'{}' はすでにタグ '{}' で使われています",
+                name,
+                owner.name
+            )));
+        }
+        self.config.tags.push(TagEntry::new(name));
+        self.reindex();
+        Ok(self.config.tags.last().unwrap())
+    }
+
+    /// 既存のタグにエイリアスを足す
+    pub fn add_alias(&mut self, tag: &str, alias: &str) -> Result<()> {
+        add_alias(&mut self.config, tag, alias)?;
+        self.reindex();
+        Ok(())
+    }
+
+    /// タグの名前を変える (元の名前はエイリアスとして残す)
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<()> {
+        rename_tag(&mut self.config, from, to)?;
+        self.reindex();
+        Ok(())
+    }
+
+    /// from を into に統合する
+    pub fn merge(&mut self, from: &str, into: &str) -> Result<()> {
+        merge_tags(&mut self.config, from, into)?;
+        self.reindex();
+        Ok(())
+    }
+}
+
+pub fn load_config(path: &Path) -> Result<TagConfig> {
+    if !path.exists() {
+        return Ok(TagConfig::default());
+    }
+    let content = fs::read_to_string(path).io_context(|| {
+        tr!(
+            "Failed to read DB file: {:?}",
+            "DBファイル {:?} を読み込めません",
+            path
+        )
+    })?;
+    serde_json::from_str(&content).map_err(|source| MdtaggerError::DbError {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+pub fn save_config(path: &Path, config: &TagConfig) -> Result<()> {
+    // 親ディレクトリが存在しない場合は作成する（親切設計）
+    if let Some(parent) = path.parent()
+        && !parent.exists()
+    {
+        fs::create_dir_all(parent)
+            .io_context(|| tr!("Failed to create {:?}", "{:?} を作成できません", parent))?;
+    }
+
+    let content =
+        serde_json::to_string_pretty(config).map_err(|source| MdtaggerError::DbError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    fs::write(path, content).io_context(|| {
+        tr!(
+            "Failed to write DB file: {:?}",
+            "DBファイル {:?} に書き込めません",
+            path
+        )
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db(names: &[&str]) -> TagDatabase {
+        TagDatabase::new(TagConfig {
+            tags: names.iter().map(|name| TagEntry::new(name)).collect(),
+            ..Default::default()
+        })
+    }
+
+    fn names(config: &TagConfig) -> Vec<&str> {
+        config.tags.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn lookup_finds_tags_by_name_and_alias() {
+        let mut db = db(&["rust", "python"]);
+        db.add_alias("rust", "rs").unwrap();
+        assert_eq!(db.lookup("rs").unwrap().name, "rust");
+        assert!(db.contains("rust"));
+        assert!(!db.contains("rs"));
+        assert!(db.lookup("go").is_none());
+
+        // 使われている名前はエイリアスにもタグにもできない
+        assert!(matches!(
+            db.add_alias("python", "rs"),
+            Err(MdtaggerError::InvalidEdit(_))
+        ));
+        assert!(matches!(
+            db.add_tag("rs"),
+            Err(MdtaggerError::InvalidEdit(_))
+        ));
+        assert!(matches!(
+            db.add_alias("go", "golang"),
+            Err(MdtaggerError::TagNotFound(_))
+        ));
+        db.add_tag("go").unwrap();
+        assert!(db.contains("go"));
+    }
+
+    #[test]
+    fn tag_name_wins_over_another_tags_alias() {
+        // 手で編集したDBでは、タグ名と別のタグのエイリアスが重なることがある
+        let mut config = db(&["js", "javascript"]).into_config();
+        config.tags[1].aliases.push("js".to_string());
+        let db = TagDatabase::new(config);
+        assert_eq!(db.lookup("js").unwrap().name, "js");
+    }
+
+    #[test]
+    fn rename_keeps_the_old_name_as_alias() {
+        let mut db = db(&["ml", "rust"]);
+        db.edit(|config| {
+            config
+                .abbreviations
+                .insert("m".to_string(), "ml".to_string())
+        });
+        db.rename("ml", "machine-learning").unwrap();
+        assert_eq!(db.lookup("ml").unwrap().name, "machine-learning");
+        assert!(db.contains("machine-learning"));
+        assert_eq!(db.config().abbreviations["m"], "machine-learning");
+
+        assert!(matches!(
+            db.rename("machine-learning", "rust"),
+            Err(MdtaggerError::InvalidEdit(_))
+        ));
+        assert!(matches!(
+            db.rename("go", "golang"),
+            Err(MdtaggerError::TagNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn rename_rejects_an_alias_of_another_tag() {
+        let mut db = db(&["rust", "python"]);
+        db.add_alias("rust", "rs").unwrap();
+        db.add_alias("python", "py").unwrap();
+        assert!(matches!(
+            db.rename("python", "rs"),
+            Err(MdtaggerError::InvalidEdit(_))
+        ));
+        assert!(db.contains("python"));
+        assert_eq!(db.lookup("rs").unwrap().name, "rust");
+
+        // 自分のエイリアスは名前にできる (元の名前がエイリアスになる)
+        db.rename("python", "py").unwrap();
+        assert!(db.contains("py"));
+        assert_eq!(db.lookup("py").unwrap().aliases, ["python"]);
+    }
+
+    #[test]
+    fn merge_moves_names_aliases_and_counts() {
+        let mut db = db(&["js", "javascript"]);
+        db.edit(|config| {
+            config.tags[0].aliases.push("ecmascript".to_string());
+            config.tags[0].uses = 3;
+            config.tags[0].corrections.insert("jss".to_string(), 2);
+            config.tags[1].uses = 5;
+            config.tags[1].corrections.insert("jss".to_string(), 1);
+        });
+        db.merge("js", "javascript").unwrap();
+        assert_eq!(names(db.config()), ["javascript"]);
+        let merged = db.lookup("js").unwrap();
+        assert_eq!(merged.name, "javascript");
+        assert_eq!(merged.aliases, ["js", "ecmascript"]);
+        assert_eq!(merged.uses, 8);
+        assert_eq!(merged.corrections["jss"], 3);
+        assert_eq!(db.lookup("ecmascript").unwrap().name, "javascript");
+
+        assert!(matches!(
+            db.merge("javascript", "javascript"),
+            Err(MdtaggerError::InvalidEdit(_))
+        ));
+        assert!(matches!(
+            db.merge("js", "javascript"),
+            Err(MdtaggerError::TagNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn find_similar_orders_by_distance_then_uses() {
+        let mut db = db(&["rest", "rust", "ruby"]);
+        db.edit(|config| config.tags[0].uses = 10);
+        let similar: Vec<(&str, usize)> = db
+            .find_similar("rast", 2)
+            .into_iter()
+            .map(|(e, dist)| (e.name.as_str(), dist))
+            .collect();
+        assert_eq!(similar, [("rest", 1), ("rust", 1)]);
+        assert_eq!(db.find_similar("café", 0).len(), 0);
+        assert_eq!(db.find_similar("rüst", 0)[0].0.name, "rust");
+    }
+
+    // --- 3方向のマージ ---
+
+    fn entry(name: &str, aliases: &[&str], uses: u64) -> TagEntry {
+        TagEntry {
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+            uses,
+            ..TagEntry::new(name)
+        }
+    }
+
+    fn config(tags: Vec<TagEntry>) -> TagConfig {
+        TagConfig {
+            tags,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merge_configs_keeps_tags_added_on_both_sides() {
+        let base = config(vec![entry("rust", &[], 0)]);
+        let ours = config(vec![entry("rust", &[], 0), entry("cli", &[], 0)]);
+        let theirs = config(vec![entry("rust", &[], 0), entry("tui", &[], 0)]);
+        assert_eq!(
+            names(&merge_configs(&base, &ours, &theirs)),
+            ["rust", "tui", "cli"]
+        );
+    }
+
+    #[test]
+    fn merge_configs_drops_tags_removed_on_either_side() {
+        let base = config(vec![entry("rust", &[], 0), entry("draft", &[], 0)]);
+        let ours = config(vec![entry("rust", &[], 0)]);
+        let theirs = base.clone();
+        assert_eq!(names(&merge_configs(&base, &ours, &theirs)), ["rust"]);
+        // theirs で消したタグは、ours で手を付けていても足し直さない
+        let ours = config(vec![entry("rust", &[], 0), entry("draft", &[], 4)]);
+        let theirs = config(vec![entry("rust", &[], 0)]);
+        assert_eq!(names(&merge_configs(&base, &ours, &theirs)), ["rust"]);
+    }
+
+    #[test]
+    fn merge_configs_adds_up_counts_and_lists() {
+        let base = config(vec![entry("rust", &["rs", "rustlang"], 2)]);
+        // ours: 3回使い、rustlang を消して ru を足した
+        let ours = config(vec![entry("rust", &["rs", "ru"], 5)]);
+        // theirs: 1回使い、oxide を足した
+        let theirs = config(vec![entry("rust", &["rs", "rustlang", "oxide"], 3)]);
+        let merged = merge_configs(&base, &ours, &theirs);
+        assert_eq!(merged.tags[0].uses, 6);
+        assert_eq!(merged.tags[0].aliases, ["rs", "oxide", "ru"]);
+    }
+
+    #[test]
+    fn merge_configs_treats_a_rename_as_remove_and_add() {
+        let base = config(vec![entry("ml", &[], 0)]);
+        let ours = config(vec![entry("machine-learning", &["ml"], 0)]);
+        let theirs = config(vec![entry("ml", &[], 1)]);
+        let merged = merge_configs(&base, &ours, &theirs);
+        assert_eq!(names(&merged), ["machine-learning"]);
+    }
+
+    #[test]
+    fn merge_configs_prefers_our_settings_only_when_we_changed_them() {
+        let mut base = config(Vec::new());
+        base.abbreviations
+            .insert("k8s".to_string(), "kubernetes".to_string());
+        base.abbreviations
+            .insert("js".to_string(), "javascript".to_string());
+
+        let mut ours = base.clone();
+        ours.abbreviations
+            .insert("k8s".to_string(), "k8".to_string());
+        ours.abbreviations.remove("js");
+        ours.defaults.register_new_tags = Some(true);
+
+        let mut theirs = base.clone();
+        theirs
+            .abbreviations
+            .insert("k8s".to_string(), "kube".to_string());
+        theirs
+            .abbreviations
+            .insert("py".to_string(), "python".to_string());
+        theirs.normalize.lowercase = true;
+
+        let merged = merge_configs(&base, &ours, &theirs);
+        assert_eq!(
+            merged.abbreviations,
+            BTreeMap::from([
+                ("k8s".to_string(), "k8".to_string()),
+                ("py".to_string(), "python".to_string()),
+            ])
+        );
+        assert_eq!(merged.defaults, ours.defaults);
+        assert_eq!(merged.normalize, theirs.normalize);
+    }
+}
@@ -0,0 +1,104 @@
+// --- タグの決定の記録 (--log-file) ---
+//
+// 入力したタグがどのタグに決まったかを、候補や編集距離とともに1行のJSONで追記する。
+// 自動採用 (--auto-accept) などが意図どおりに働いているかを後から確かめるためのもの。
+
+use anyhow::{Context, Result};
+use mdtagger::resolver::Resolution;
+use mdtagger::tr;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+#[derive(Serialize)]
+struct Decision<'a> {
+    /// RFC 3339 形式 (UTC)
+    timestamp: String,
+    file: &'a Path,
+    /// 整形ルールを通したあとの入力
+    input: &'a str,
+    candidates: Vec<Candidate<'a>>,
+    /// 決まったタグ (空ならタグを付けなかった)
+    choice: &'a [String],
+    /// exact, alias, auto-accept, typo-correction など
+    method: &'a str,
+    /// 決まったタグの編集距離 (候補になかったときは null)
+    distance: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct Candidate<'a> {
+    tag: &'a str,
+    distance: usize,
+}
+
+/// --log-file で指定したファイル (指定がなければ何もしない)
+pub struct DecisionLog {
+    file: Option<File>,
+}
+
+impl DecisionLog {
+    pub fn open(path: Option<&Path>) -> Result<Self> {
+        let file = match path {
+            Some(path) => Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| tr!("Failed to open {:?}", "{:?} を開けません", path))?,
+            ),
+            None => None,
+        };
+        Ok(Self { file })
+    }
+
+    /// 1つの入力についての決定を追記する
+    pub fn record(&mut self, md_path: &Path, input: &str, resolution: &Resolution) -> Result<()> {
+        self.write(
+            md_path,
+            input,
+            &resolution.tags,
+            resolution.method,
+            &resolution.candidates,
+            resolution.distance(),
+        )
+    }
+
+    /// 「残りすべてに適用」で前の決定を使い回したとき
+    pub fn record_batch(&mut self, md_path: &Path, input: &str, choice: &[String]) -> Result<()> {
+        self.write(md_path, input, choice, "batch", &[], None)
+    }
+
+    fn write(
+        &mut self,
+        md_path: &Path,
+        input: &str,
+        choice: &[String],
+        method: &str,
+        candidates: &[(String, usize)],
+        distance: Option<usize>,
+    ) -> Result<()> {
+        let Some(file) = &mut self.file else {
+            return Ok(());
+        };
+        let decision = Decision {
+            timestamp: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            file: md_path,
+            input,
+            candidates: candidates
+                .iter()
+                .map(|(tag, distance)| Candidate {
+                    tag,
+                    distance: *distance,
+                })
+                .collect(),
+            choice,
+            method,
+            distance,
+        };
+        writeln!(file, "{}", serde_json::to_string(&decision)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+// --- add --editor-protocol (エディタの拡張機能向けの出力) ---
+//
+// ファイルは書き換えずに、元の内容に対する編集 (バイトの位置と置き換える文字列) とタグのキーの位置を JSON で返す。
+// VS Code などの拡張機能はこの編集を WorkspaceEdit として当てるので、エディタの元に戻す (Ctrl+Z) で取り消せる。
+// 位置はバイトの位置 (offset) と、LSP と同じ行・列 (列は UTF-16 で数える) の両方で返す。
+
+use mdtagger::markdown::{FrontMatter, FrontMatterOptions, find_inline_field};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// 出力の形の版 (互換性のない変更をしたら上げる)
+const VERSION: u32 = 1;
+
+#[derive(Serialize, JsonSchema)]
+pub struct EditorReport {
+    /// 出力の形の版
+    version: u32,
+    /// タグデータベースを更新したか (--editor-protocol ではDBも書き換えないので常に false。版1の形のために残す)
+    db_updated: bool,
+    files: Vec<EditorFile>,
+}
+
+impl EditorReport {
+    pub fn new(db_updated: bool, files: Vec<EditorFile>) -> Self {
+        Self {
+            version: VERSION,
+            db_updated,
+            files,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct EditorFile {
+    path: PathBuf,
+    /// 元の内容でのタグのキーの位置 (Front Matter か本文のフィールドにキーがなければ null)
+    tags_key: Option<Range>,
+    /// 元の内容に当てる編集 (変わらなければ空。重ならず、前から順に並ぶ)
+    edits: Vec<Edit>,
+    /// 解決後のタグ
+    tags: Vec<String>,
+    /// 外したタグ
+    removed: Vec<String>,
+}
+
+impl EditorFile {
+    pub fn new(
+        path: PathBuf,
+        before: &str,
+        after: &str,
+        options: &FrontMatterOptions,
+        tags: Vec<String>,
+        removed: Vec<String>,
+    ) -> Self {
+        Self {
+            path,
+            tags_key: tags_key(before, options),
+            edits: edits(before, after),
+            tags,
+            removed,
+        }
+    }
+}
+
+/// 内容の中の位置
+#[derive(Serialize, JsonSchema)]
+struct Position {
+    /// 先頭からのバイトの位置
+    offset: usize,
+    /// 行 (0 から)
+    line: usize,
+    /// 行の中の列 (0 から。UTF-16 で数える)
+    character: usize,
+}
+
+impl Position {
+    fn at(text: &str, offset: usize) -> Self {
+        let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            offset,
+            line: text[..offset].matches('\n').count(),
+            character: text[line_start..offset].encode_utf16().count(),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Range {
+    start: Position,
+    end: Position,
+}
+
+impl Range {
+    fn new(text: &str, range: std::ops::Range<usize>) -> Self {
+        Self {
+            start: Position::at(text, range.start),
+            end: Position::at(text, range.end),
+        }
+    }
+}
+
+/// range を new_text に置き換える編集
+#[derive(Serialize, JsonSchema)]
+struct Edit {
+    range: Range,
+    new_text: String,
+}
+
+/// before を after にする編集 (共通の先頭と末尾の行を除いた1つの置き換え)
+fn edits(before: &str, after: &str) -> Vec<Edit> {
+    if before == after {
+        return Vec::new();
+    }
+    let common = before
+        .bytes()
+        .zip(after.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    // 行の境目で切る (エディタで見たときに、行ごと足したり消したりする編集になる)
+    let prefix = before[..common].rfind('\n').map_or(0, |i| i + 1);
+    let mut suffix = before.as_bytes()[prefix..]
+        .iter()
+        .rev()
+        .zip(after.as_bytes()[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    while suffix > 0
+        && before.len() - suffix > prefix
+        && before.as_bytes()[before.len() - suffix - 1] != b'\n'
+    {
+        suffix -= 1;
+    }
+    vec![Edit {
+        range: Range::new(before, prefix..before.len() - suffix),
+        new_text: after[prefix..after.len() - suffix].to_string(),
+    }]
+}
+
+/// タグのキーの名前の位置 (入れ子のキー taxonomies.tags なら tags の部分)
+fn tags_key(content: &str, options: &FrontMatterOptions) -> Option<Range> {
+    if let Some(style) = options.inline_field
+        && let Ok((_, body)) = FrontMatter::parse(content)
+        && let Some(field) = find_inline_field(body, &options.tag_key, style)
+    {
+        let line = &body[field.range.clone()];
+        let start =
+            content.len() - body.len() + field.range.start + line.len() - line.trim_start().len();
+        let len = line
+            .trim_start()
+            .split("::")
+            .next()
+            .unwrap_or_default()
+            .len();
+        return Some(Range::new(content, start..start + len));
+    }
+    let name = options.tag_key.rsplit('.').next().unwrap_or_default();
+    let mut lines = content.split_inclusive('\n');
+    let first = lines.next()?;
+    let fence = first.trim_end();
+    if fence != "---" && fence != "+++" {
+        return None;
+    }
+    let mut start = first.len();
+    for line in lines {
+        let line_start = start;
+        start += line.len();
+        let trimmed = line.trim();
+        if trimmed == fence {
+            break;
+        }
+        let is_key = trimmed
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.starts_with(':') || rest.trim_start().starts_with('='));
+        if is_key {
+            let key_start = line_start + line.len() - line.trim_start().len();
+            return Some(Range::new(content, key_start..key_start + name.len()));
+        }
+    }
+    None
+}
@@ -0,0 +1,165 @@
+// --- 埋め込みベクトルによる意味的なタグ候補 (feature = "embeddings") ---
+//
+// OpenAI 互換の `/embeddings` API (OpenAI, Ollama, llama.cpp など) を使って
+// タグ名をベクトル化し、入力と意味が近い既存タグを候補に出す。
+// 取得したベクトルはDBの隣のキャッシュファイルに保存し、同じタグを何度も問い合わせない。
+
+use crate::error::{IoContext, MdtaggerError, Result};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct EmbeddingCache {
+    model: String,
+    #[serde(default)]
+    vectors: HashMap<String, Vec<f32>>,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+}
+
+pub struct SemanticIndex {
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    threshold: f32,
+    cache_path: PathBuf,
+    cache: RefCell<EmbeddingCache>,
+    dirty: RefCell<bool>,
+}
+
+impl SemanticIndex {
+    /// キャッシュは `<DB名>.embeddings.json` に置く (モデルが変わったら作り直す)
+    pub fn new(
+        url: &str,
+        model: &str,
+        api_key: Option<String>,
+        threshold: f32,
+        db_path: &Path,
+    ) -> Result<Self> {
+        let cache_path = db_path.with_extension("embeddings.json");
+        let cache = match fs::read_to_string(&cache_path) {
+            Ok(content) => serde_json::from_str::<EmbeddingCache>(&content)
+                .ok()
+                .filter(|c| c.model == model)
+                .unwrap_or_default(),
+            Err(_) => EmbeddingCache::default(),
+        };
+        Ok(Self {
+            url: url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            api_key,
+            threshold,
+            cache_path,
+            cache: RefCell::new(cache),
+            dirty: RefCell::new(false),
+        })
+    }
+
+    /// `candidates` のうち入力との類似度がしきい値以上のものを、似ている順に返す
+    pub fn similar(&self, input: &str, candidates: &[String]) -> Result<Vec<(usize, f32)>> {
+        let missing: Vec<String> = std::iter::once(input.to_string())
+            .chain(candidates.iter().cloned())
+            .filter(|name| !self.cache.borrow().vectors.contains_key(name))
+            .collect();
+        if !missing.is_empty() {
+            let vectors = self.fetch(&missing)?;
+            let mut cache = self.cache.borrow_mut();
+            for (name, vector) in missing.into_iter().zip(vectors) {
+                cache.vectors.insert(name, vector);
+            }
+            *self.dirty.borrow_mut() = true;
+        }
+
+        let cache = self.cache.borrow();
+        let query = &cache.vectors[input];
+        let mut scored: Vec<(usize, f32)> = candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| name.as_str() != input)
+            .map(|(i, name)| (i, cosine(query, &cache.vectors[name])))
+            .filter(|(_, score)| *score >= self.threshold)
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(scored)
+    }
+
+    /// 新しく取得したベクトルがあればキャッシュファイルに書き出す
+    pub fn save(&self) -> Result<()> {
+        if !*self.dirty.borrow() {
+            return Ok(());
+        }
+        let mut cache = self.cache.borrow_mut();
+        cache.model = self.model.clone();
+        let content = serde_json::to_string(&*cache).map_err(|source| MdtaggerError::DbError {
+            path: self.cache_path.clone(),
+            source,
+        })?;
+        fs::write(&self.cache_path, content)
+            .io_context(|| format!("Failed to write embedding cache: {:?}", self.cache_path))?;
+        Ok(())
+    }
+
+    fn fetch(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut request = ureq::post(format!("{}/embeddings", self.url));
+        if let Some(key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", key));
+        }
+        let response: EmbeddingResponse = request
+            .send_json(EmbeddingRequest {
+                model: &self.model,
+                input: inputs,
+            })
+            .map_err(|err| {
+                embeddings_error(format!("Embedding request to {} failed", self.url), err)
+            })?
+            .body_mut()
+            .read_json()
+            .map_err(|err| embeddings_error("Invalid embedding response".to_string(), err))?;
+        if response.data.len() != inputs.len() {
+            return Err(MdtaggerError::EmbeddingsError {
+                message: format!(
+                    "Embedding API returned {} vectors for {} inputs",
+                    response.data.len(),
+                    inputs.len()
+                ),
+                source: None,
+            });
+        }
+        Ok(response.data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+fn embeddings_error(message: String, err: ureq::Error) -> MdtaggerError {
+    MdtaggerError::EmbeddingsError {
+        message,
+        source: Some(Box::new(err)),
+    }
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
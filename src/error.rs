@@ -0,0 +1,144 @@
+// --- ライブラリのエラー ---
+//
+// ライブラリの利用者が失敗の種類で分岐できるように、ライブラリの関数はこの型のエラーを返す。
+// バイナリ側では anyhow でメッセージを足して表示する。
+
+use crate::tr;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub type Result<T, E = MdtaggerError> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MdtaggerError {
+    /// Front Matter を YAML として解析できなかった
+    #[error("{}", match .path {
+        Some(path) => tr!("Invalid front matter in {:?}", "{:?} の Front Matter が不正です", path),
+        None => tr!("Invalid front matter", "Front Matter が不正です"),
+    })]
+    ParseError {
+        path: Option<PathBuf>,
+        #[source]
+        source: serde_yaml::Error,
+    },
+
+    /// タグデータベースを JSON として解析・書き出しできなかった
+    #[error("{}", tr!("Failed to parse DB file: {:?}", "DBファイル {:?} を解析できません", .path))]
+    DbError {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// 指定したタグデータベースのファイルがない (パスの打ち間違いとみなす)
+    #[error("{}", tr!(
+        "Tag database {:?} does not exist (pass --db to use another one, or --create-db to start a new one)",
+        "タグデータベース {:?} がありません (別のDBなら --db で指定し、新しく作るなら --create-db を付けてください)",
+        .0
+    ))]
+    DbNotFound(PathBuf),
+
+    /// ファイルの読み書きに失敗した
+    #[error("{message}")]
+    IoError {
+        message: String,
+        #[source]
+        source: io::Error,
+    },
+
+    /// 指定したタグがタグデータベースにない
+    #[error("{}", tr!("Tag '{}' is not in the database", "タグ '{}' はデータベースにありません", .0))]
+    TagNotFound(String),
+
+    /// 名前の重複など、タグデータベースに対して行えない操作
+    #[error("{0}")]
+    InvalidEdit(String),
+
+    /// 関数に渡した値が不正 (C API に NULL や UTF-8 でない文字列を渡したなど)
+    #[error("{0}")]
+    InvalidArgument(String),
+
+    /// 非対話モードで未知のタグを見つけ、ポリシーが fail だった
+    #[error("{}", tr!("Unknown tag '{}' (non-interactive mode)", "未登録のタグ '{}' (非対話モード)", .0))]
+    UnknownTag(String),
+
+    /// 対話プロンプトが Ctrl-C で中断された
+    #[error("{}", tr!("Aborted by user", "中断しました"))]
+    ResolutionAborted,
+
+    /// 端末がないなど、対話プロンプトを出せなかった
+    #[cfg(feature = "interactive")]
+    #[error("{}", tr!("Failed to show the prompt", "プロンプトを表示できません"))]
+    PromptError(#[source] dialoguer::Error),
+
+    /// 確認が必要になったが、プロンプトを出さない設定だった (NeverPrompt や答えの尽きた ScriptedPrompter)
+    #[error("{}", tr!("Cannot ask {:?}: prompts are disabled", "{:?} を尋ねられません (プロンプトを出さない設定です)", .0))]
+    PromptUnavailable(String),
+
+    /// 書き換えた内容を読み直したら、本文かタグ以外の Front Matter が変わっていた (元に戻した)
+    #[error("{}", tr!("Verification failed for {:?}: {}", "{:?} の書き換えを検証できませんでした: {}", .path, .reason))]
+    VerificationFailed { path: PathBuf, reason: String },
+
+    /// 入力の書き換え規則 (normalize) の正規表現が不正
+    #[error("{}", tr!("Invalid normalize rule pattern: {:?}", "normalize の規則のパターンが不正です: {:?}", .pattern))]
+    InvalidPattern {
+        pattern: String,
+        #[source]
+        source: regex::Error,
+    },
+
+    /// 埋め込みAPIへの問い合わせに失敗した
+    #[cfg(feature = "embeddings")]
+    #[error("{message}")]
+    EmbeddingsError {
+        message: String,
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
+}
+
+impl MdtaggerError {
+    /// Front Matter の解析エラーに、どのファイルかを付け加える
+    pub fn in_file(self, file: &Path) -> Self {
+        match self {
+            MdtaggerError::ParseError { source, .. } => MdtaggerError::ParseError {
+                path: Some(file.to_path_buf()),
+                source,
+            },
+            err => err,
+        }
+    }
+}
+
+impl From<serde_yaml::Error> for MdtaggerError {
+    fn from(source: serde_yaml::Error) -> Self {
+        MdtaggerError::ParseError { path: None, source }
+    }
+}
+
+#[cfg(feature = "interactive")]
+impl From<dialoguer::Error> for MdtaggerError {
+    fn from(err: dialoguer::Error) -> Self {
+        let dialoguer::Error::IO(io_err) = &err;
+        // console は Ctrl-C を Interrupted として返す
+        if io_err.kind() == io::ErrorKind::Interrupted {
+            MdtaggerError::ResolutionAborted
+        } else {
+            MdtaggerError::PromptError(err)
+        }
+    }
+}
+
+/// io::Result にメッセージを付けて MdtaggerError::IoError にする
+pub(crate) trait IoContext<T> {
+    fn io_context(self, message: impl FnOnce() -> String) -> Result<T>;
+}
+
+impl<T> IoContext<T> for io::Result<T> {
+    fn io_context(self, message: impl FnOnce() -> String) -> Result<T> {
+        self.map_err(|source| MdtaggerError::IoError {
+            message: message(),
+            source,
+        })
+    }
+}
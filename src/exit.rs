@@ -0,0 +1,179 @@
+// --- 終了コードとエラーコード ---
+//
+// シェルスクリプトや git hook が結果で分岐できるように、終了コードとエラーコードは固定する。
+// 番号や名前を変えると利用者のスクリプトが壊れるので、追加だけにすること。
+
+use mdtagger::MdtaggerError;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::io;
+use std::process::ExitCode;
+
+use crate::hooks;
+use crate::journal;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// 成功 (add では1つ以上のファイルを書き換えた)
+    Success = 0,
+    /// その他のエラー
+    Failure = 1,
+    /// 引数の誤り (clap が返す)
+    Usage = 2,
+    /// 成功したが、書き換えたファイルはなかった
+    Unchanged = 3,
+    /// 非対話モードで未知のタグを見つけた (--on-unknown fail)
+    UnknownTag = 4,
+    /// Front Matter・タグデータベース・設定ファイルを解析できなかった
+    Parse = 5,
+    /// ファイルの読み書きに失敗した
+    Io = 6,
+    /// lint で規則違反が見つかった
+    Lint = 7,
+    /// 指定したタグデータベースがない (--create-db なし)
+    DbNotFound = 8,
+    /// 同じタグデータベースで別の実行がファイルを書き換えている
+    DbLocked = 9,
+}
+
+/// エラーの種類を表す、メッセージに依らない識別子 (JSON では as_str と同じ文字列)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub enum ErrorCode {
+    #[serde(rename = "E_UNKNOWN_TAG")]
+    UnknownTag,
+    #[serde(rename = "E_FRONTMATTER_PARSE")]
+    FrontMatterParse,
+    #[serde(rename = "E_DB_PARSE")]
+    DbParse,
+    #[serde(rename = "E_CONFIG_PARSE")]
+    ConfigParse,
+    #[serde(rename = "E_PROMPT")]
+    Prompt,
+    #[serde(rename = "E_IO")]
+    Io,
+    #[serde(rename = "E_OTHER")]
+    Other,
+    /// --verify で書き換えた内容を確かめられず、元に戻した
+    #[serde(rename = "E_VERIFY")]
+    Verify,
+    /// pre_write フックが書き込みを断った
+    #[serde(rename = "E_HOOK_REJECTED")]
+    HookRejected,
+    /// 指定したタグデータベースがない
+    #[serde(rename = "E_DB_NOT_FOUND")]
+    DbNotFound,
+    /// 同じタグデータベースで別の実行がファイルを書き換えている
+    #[serde(rename = "E_DB_LOCKED")]
+    DbLocked,
+}
+
+/// --output json で失敗したときに出力する内容
+#[derive(Serialize, JsonSchema)]
+pub struct ErrorReport {
+    pub error: ErrorDetail,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ErrorDetail {
+    pub code: ErrorCode,
+    pub message: String,
+    /// 原因のエラーのメッセージ (外側から順に)
+    pub causes: Vec<String>,
+}
+
+impl ErrorReport {
+    pub fn new(err: &anyhow::Error) -> Self {
+        Self {
+            error: ErrorDetail {
+                code: ErrorCode::from_error(err),
+                message: err.to_string(),
+                causes: err.chain().skip(1).map(|c| c.to_string()).collect(),
+            },
+        }
+    }
+}
+
+impl ErrorCode {
+    /// エラーの原因をたどって種類を決める
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        for cause in err.chain() {
+            if let Some(err) = cause.downcast_ref::<MdtaggerError>() {
+                match err {
+                    MdtaggerError::UnknownTag(_) => return ErrorCode::UnknownTag,
+                    MdtaggerError::ParseError { .. } => return ErrorCode::FrontMatterParse,
+                    MdtaggerError::DbError { .. } => return ErrorCode::DbParse,
+                    MdtaggerError::DbNotFound(_) => return ErrorCode::DbNotFound,
+                    MdtaggerError::ResolutionAborted
+                    | MdtaggerError::PromptError(_)
+                    | MdtaggerError::PromptUnavailable(_) => {
+                        return ErrorCode::Prompt;
+                    }
+                    MdtaggerError::IoError { .. } => return ErrorCode::Io,
+                    MdtaggerError::VerificationFailed { .. } => return ErrorCode::Verify,
+                    // 原因のエラー (あれば) で決める
+                    _ => continue,
+                }
+            }
+            if cause.is::<hooks::Rejected>() {
+                return ErrorCode::HookRejected;
+            }
+            if cause.is::<journal::Locked>() {
+                return ErrorCode::DbLocked;
+            }
+            if cause.is::<serde_yaml::Error>() {
+                return ErrorCode::FrontMatterParse;
+            }
+            if cause.is::<serde_json::Error>() {
+                return ErrorCode::DbParse;
+            }
+            if cause.is::<toml::de::Error>() {
+                return ErrorCode::ConfigParse;
+            }
+            // 端末がないなど、対話プロンプトを出せなかった
+            if cause.is::<dialoguer::Error>() {
+                return ErrorCode::Prompt;
+            }
+            if cause.is::<io::Error>() {
+                return ErrorCode::Io;
+            }
+        }
+        ErrorCode::Other
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::UnknownTag => "E_UNKNOWN_TAG",
+            ErrorCode::FrontMatterParse => "E_FRONTMATTER_PARSE",
+            ErrorCode::DbParse => "E_DB_PARSE",
+            ErrorCode::ConfigParse => "E_CONFIG_PARSE",
+            ErrorCode::Prompt => "E_PROMPT",
+            ErrorCode::Io => "E_IO",
+            ErrorCode::Other => "E_OTHER",
+            ErrorCode::Verify => "E_VERIFY",
+            ErrorCode::HookRejected => "E_HOOK_REJECTED",
+            ErrorCode::DbNotFound => "E_DB_NOT_FOUND",
+            ErrorCode::DbLocked => "E_DB_LOCKED",
+        }
+    }
+
+    pub fn exit(self) -> Exit {
+        match self {
+            ErrorCode::UnknownTag => Exit::UnknownTag,
+            ErrorCode::FrontMatterParse | ErrorCode::DbParse | ErrorCode::ConfigParse => {
+                Exit::Parse
+            }
+            ErrorCode::Io => Exit::Io,
+            ErrorCode::DbNotFound => Exit::DbNotFound,
+            ErrorCode::DbLocked => Exit::DbLocked,
+            ErrorCode::Prompt | ErrorCode::Other | ErrorCode::Verify | ErrorCode::HookRejected => {
+                Exit::Failure
+            }
+        }
+    }
+}
+
+impl From<Exit> for ExitCode {
+    fn from(exit: Exit) -> Self {
+        ExitCode::from(exit as u8)
+    }
+}
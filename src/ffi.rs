@@ -0,0 +1,143 @@
+// --- C から呼べる関数 (feature = "ffi") ---
+//
+// Rust 以外のエディタやノートアプリが、コマンドを起動せずにタグ付けを組み込めるようにする。
+// 宣言は include/mdtagger.h。文字列はすべて UTF-8 の NUL 終端で受け渡す。
+// 失敗したときのメッセージは mdtagger_last_error で、同じスレッドから読める。
+
+use crate::db::TagDatabase;
+use crate::error::{MdtaggerError, Result};
+use crate::operation::TagOperation;
+use crate::resolver::{StrictResolver, TagResolver, UnknownPolicy};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::path::Path;
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: Option<String>) {
+    let message = message.map(|m| CString::new(m.replace('\0', " ")).unwrap_or_default());
+    LAST_ERROR.with(|e| *e.borrow_mut() = message);
+}
+
+/// エラーを最後のエラーとして覚え、None を返す
+fn record<T>(result: Result<T>) -> Option<T> {
+    match result {
+        Ok(value) => {
+            set_last_error(None);
+            Some(value)
+        }
+        Err(err) => {
+            let mut message = err.to_string();
+            let mut source = std::error::Error::source(&err);
+            while let Some(cause) = source {
+                message.push_str(": ");
+                message.push_str(&cause.to_string());
+                source = cause.source();
+            }
+            set_last_error(Some(message));
+            None
+        }
+    }
+}
+
+/// # Safety
+///
+/// ptr は NULL か、NUL 終端の文字列を指していること
+unsafe fn to_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str> {
+    if ptr.is_null() {
+        return Err(MdtaggerError::InvalidArgument(format!("{} is NULL", name)));
+    }
+    // SAFETY: 呼び出し側が NUL 終端の文字列を渡している
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|_| MdtaggerError::InvalidArgument(format!("{} is not valid UTF-8", name)))
+}
+
+/// Markdown ファイルにタグを付け、タグデータベースを保存する (DBにないタグは登録する)
+///
+/// 新しく付いたタグの数を返す。失敗したら -1 を返し、mdtagger_last_error で理由を読める。
+///
+/// # Safety
+///
+/// db_path と md_path は NUL 終端の文字列、tags は n_tags 個の NUL 終端の文字列を指していること
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mdtagger_add_tags(
+    db_path: *const c_char,
+    md_path: *const c_char,
+    tags: *const *const c_char,
+    n_tags: usize,
+) -> c_int {
+    let result = (|| {
+        // SAFETY: 関数の約束どおり
+        let (db_path, md_path) =
+            unsafe { (to_str(db_path, "db_path")?, to_str(md_path, "md_path")?) };
+        let mut names = Vec::with_capacity(n_tags);
+        for i in 0..n_tags {
+            // SAFETY: tags は n_tags 個の文字列を指している
+            names.push(unsafe { to_str(*tags.add(i), "tags")? });
+        }
+        let mut db = TagDatabase::open(Path::new(db_path))?;
+        let changes = TagOperation::new(md_path).add(names).execute(&mut db)?;
+        if changes.applied || !changes.new_tags.is_empty() {
+            db.save(Path::new(db_path))?;
+        }
+        Ok(changes.added.len() as c_int)
+    })();
+    record(result).unwrap_or(-1)
+}
+
+/// 入力を既存のタグに対応付ける (完全一致・エイリアス・略語のみ。確認は出さない)
+///
+/// 対応するタグ名 (略語が複数のタグになる場合は改行区切り) を返す。
+/// 返した文字列は mdtagger_string_free で解放すること。
+/// 見つからなければ NULL を返す (エラーのときも NULL で、mdtagger_last_error が NULL でなくなる)。
+///
+/// # Safety
+///
+/// db_path と input は NUL 終端の文字列を指していること
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mdtagger_resolve(
+    db_path: *const c_char,
+    input: *const c_char,
+) -> *mut c_char {
+    let result = (|| {
+        // SAFETY: 関数の約束どおり
+        let (db_path, input) = unsafe { (to_str(db_path, "db_path")?, to_str(input, "input")?) };
+        let mut db = TagDatabase::open(Path::new(db_path))?;
+        let mut resolver = StrictResolver {
+            policy: UnknownPolicy::Skip,
+        };
+        let resolution = db.edit(|config| resolver.resolve(input, config))?;
+        Ok(resolution.tags.join("\n"))
+    })();
+    match record(result) {
+        Some(tags) if !tags.is_empty() => {
+            CString::new(tags).map_or(ptr::null_mut(), CString::into_raw)
+        }
+        _ => ptr::null_mut(),
+    }
+}
+
+/// mdtagger_resolve が返した文字列を解放する (NULL なら何もしない)
+///
+/// # Safety
+///
+/// s は NULL か、mdtagger_resolve が返してまだ解放していない文字列であること
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mdtagger_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: CString::into_raw で渡したもの
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// 同じスレッドで最後に失敗した呼び出しのメッセージ (成功していれば NULL)
+///
+/// 返した文字列はライブラリが持っているので解放しないこと。次の呼び出しまで有効。
+#[unsafe(no_mangle)]
+pub extern "C" fn mdtagger_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |m| m.as_ptr()))
+}
@@ -0,0 +1,119 @@
+// --- git の呼び出し ---
+//
+// hook と --changed、stats --timeline で使う。git の出力するパスはリポジトリの一番上からの相対パスなので、
+// ほかのファイルと比べるときは一番上のディレクトリとつないで正規化する。
+
+use anyhow::{Context, Result, bail};
+use mdtagger::tr;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// git を実行して標準出力を返す (失敗したら git のメッセージでエラーにする)
+pub fn run(args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .with_context(|| tr!("Failed to run git", "git を実行できません"))?;
+    if !output.status.success() {
+        bail!(
+            "git {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// 出力が1行のパスのコマンド (rev-parse など)
+pub fn path(args: &[&str]) -> Result<PathBuf> {
+    Ok(PathBuf::from(String::from_utf8(run(args)?)?.trim()))
+}
+
+/// -z で NUL 区切りにした出力のパス
+pub fn split_paths(output: &[u8]) -> impl Iterator<Item = &str> {
+    output
+        .split(|b| *b == 0)
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| std::str::from_utf8(name).ok())
+}
+
+/// 変更したファイル (正規化した絶対パス)
+///
+/// reference がなければ git status と同じく、ステージしたもの・ステージしていない変更・追跡していないファイル。
+/// reference があれば、そのコミットから作業ツリーまでに変わったものと、追跡していないファイル
+fn changed_files(reference: Option<&str>) -> Result<HashSet<PathBuf>> {
+    let top = path(&["rev-parse", "--show-toplevel"])?;
+    let mut names: Vec<String> = Vec::new();
+    match reference {
+        None => {
+            let output = run(&["status", "--porcelain=v1", "-z", "--untracked-files=all"])?;
+            let mut entries = split_paths(&output);
+            while let Some(entry) = entries.next() {
+                let (status, name) = entry.split_at_checked(3).unwrap_or_default();
+                names.push(name.to_string());
+                // 名前の変更とコピーは、次の項目が元の名前
+                if status.starts_with('R') || status.starts_with('C') {
+                    entries.next();
+                }
+            }
+        }
+        Some(reference) => {
+            let diff = run(&["diff", "--name-only", "-z", reference, "--"])?;
+            names.extend(split_paths(&diff).map(str::to_string));
+            // :/ でリポジトリ全体を見る
+            let untracked = run(&[
+                "ls-files",
+                "--others",
+                "--exclude-standard",
+                "-z",
+                "--full-name",
+                ":/",
+            ])?;
+            names.extend(split_paths(&untracked).map(str::to_string));
+        }
+    }
+    // 消したファイルは正規化できないので入らない
+    Ok(names
+        .iter()
+        .filter_map(|name| fs::canonicalize(top.join(name)).ok())
+        .collect())
+}
+
+/// files のうち、変更したものだけを残す
+pub fn retain_changed(files: &mut Vec<PathBuf>, reference: Option<&str>) -> Result<()> {
+    let changed = changed_files(reference)?;
+    files.retain(|path| fs::canonicalize(path).is_ok_and(|path| changed.contains(&path)));
+    Ok(())
+}
+
+/// ファイルを最初にコミットした日 (YYYY-MM-DD) (正規化した絶対パス -> 日付)
+///
+/// 名前を変えたファイルは、新しい名前でコミットした日になる。
+pub fn added_dates() -> Result<HashMap<PathBuf, String>> {
+    let top = path(&["rev-parse", "--show-toplevel"])?;
+    // 日付の行は \x01 で始め、パスの行と分ける
+    let output = run(&[
+        "-c",
+        "core.quotePath=false",
+        "log",
+        "--diff-filter=A",
+        "--format=%x01%as",
+        "--name-only",
+        ":/",
+    ])?;
+    let mut dates = HashMap::new();
+    let mut date = "";
+    for line in std::str::from_utf8(&output)?.lines() {
+        if let Some(rest) = line.strip_prefix('\x01') {
+            date = rest;
+        } else if !line.is_empty()
+            && let Ok(path) = fs::canonicalize(top.join(line))
+        {
+            // 新しいコミットから順に出るので、一番古い日で上書きする
+            dates.insert(path, date.to_string());
+        }
+    }
+    Ok(dates)
+}
@@ -0,0 +1,189 @@
+// --- 変更履歴 (.mdtagger/history.jsonl) ---
+//
+// 共有しているノート群の監査用に、書き換えのたびに1行のJSONを追記する。
+// 追記するだけで、書き換えたり消したりはしない。
+
+use anyhow::{Context, Result};
+use mdtagger::db::TagConfig;
+use mdtagger::markdown::{FrontMatterOptions, content_tags};
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::undo::{FileChange, state_dir};
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct HistoryEntry {
+    /// RFC 3339 形式 (UTC)
+    pub timestamp: String,
+    /// add, rename, merge, alias, undo など
+    pub operation: String,
+    /// 実行したコマンドライン
+    pub command: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<FileHistory>,
+    #[serde(default, skip_serializing_if = "DbHistory::is_empty")]
+    pub db: DbHistory,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct FileHistory {
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<String>,
+}
+
+/// タグデータベースへの変更
+#[derive(Serialize, Deserialize, Default, JsonSchema)]
+pub struct DbHistory {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags_added: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags_removed: Vec<String>,
+    /// "alias -> tag" の形
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases_added: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases_removed: Vec<String>,
+}
+
+impl DbHistory {
+    fn is_empty(&self) -> bool {
+        self.tags_added.is_empty()
+            && self.tags_removed.is_empty()
+            && self.aliases_added.is_empty()
+            && self.aliases_removed.is_empty()
+    }
+}
+
+impl HistoryEntry {
+    /// 変更前後の内容から履歴の1行を作る
+    pub fn new(
+        operation: &str,
+        front_matter: &FrontMatterOptions,
+        db_before: Option<&str>,
+        db_after: Option<&str>,
+        files: &[FileChange],
+    ) -> Self {
+        let files = files
+            .iter()
+            .map(|change| {
+                let before = file_tags(&change.before, front_matter);
+                let after = file_tags(&change.after, front_matter);
+                FileHistory {
+                    path: change.path.clone(),
+                    added: after.difference(&before).cloned().collect(),
+                    removed: before.difference(&after).cloned().collect(),
+                }
+            })
+            .collect();
+
+        let (tags_before, aliases_before) = db_vocabulary(db_before);
+        let (tags_after, aliases_after) = db_vocabulary(db_after);
+        let db = DbHistory {
+            tags_added: tags_after.difference(&tags_before).cloned().collect(),
+            tags_removed: tags_before.difference(&tags_after).cloned().collect(),
+            aliases_added: aliases_after.difference(&aliases_before).cloned().collect(),
+            aliases_removed: aliases_before.difference(&aliases_after).cloned().collect(),
+        };
+
+        Self {
+            timestamp: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            operation: operation.to_string(),
+            command: std::env::args().collect(),
+            files,
+            db,
+        }
+    }
+
+    /// タグ (ファイルでもDBでも) に関わる変更か
+    pub fn mentions_tag(&self, tag: &str) -> bool {
+        let alias_of = |entry: &String| entry.rsplit(" -> ").next() == Some(tag);
+        self.files
+            .iter()
+            .any(|f| f.added.iter().chain(&f.removed).any(|t| t == tag))
+            || self
+                .db
+                .tags_added
+                .iter()
+                .chain(&self.db.tags_removed)
+                .any(|t| t == tag)
+            || self
+                .db
+                .aliases_added
+                .iter()
+                .chain(&self.db.aliases_removed)
+                .any(alias_of)
+    }
+}
+
+fn file_tags(content: &str, front_matter: &FrontMatterOptions) -> BTreeSet<String> {
+    content_tags(content, front_matter)
+        .map(|tags| tags.into_iter().collect())
+        .unwrap_or_default()
+}
+
+fn db_vocabulary(db: Option<&str>) -> (BTreeSet<String>, BTreeSet<String>) {
+    let config: TagConfig = db
+        .and_then(|s| serde_json::from_str(s).ok())
+        .unwrap_or_default();
+    let tags = config.tags.iter().map(|e| e.name.clone()).collect();
+    let aliases = config
+        .tags
+        .iter()
+        .flat_map(|e| {
+            e.aliases
+                .iter()
+                .map(move |a| format!("{} -> {}", a, e.name))
+        })
+        .collect();
+    (tags, aliases)
+}
+
+/// 履歴に1行追記する
+pub fn append(db_path: &Path, entry: &HistoryEntry) -> Result<()> {
+    let dir = state_dir(db_path);
+    fs::create_dir_all(&dir)
+        .with_context(|| tr!("Failed to create {:?}", "{:?} を作成できません", dir))?;
+    let path = dir.join(HISTORY_FILE);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| tr!("Failed to open {:?}", "{:?} を開けません", path))?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// 履歴をすべて読む (古い順)
+pub fn read(db_path: &Path) -> Result<Vec<HistoryEntry>> {
+    let path = state_dir(db_path).join(HISTORY_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(&path)
+        .with_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).with_context(|| {
+                tr!(
+                    "Invalid history entry at {:?} line {}",
+                    "{:?} の {} 行目の履歴が不正です",
+                    path,
+                    i + 1
+                )
+            })
+        })
+        .collect()
+}
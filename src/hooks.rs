@@ -0,0 +1,276 @@
+// --- 外部コマンドのフック (設定ファイルの [hooks]) ---
+//
+// クレートを書き換えずに、タグの決め方を足したり書き換えを通知したりするための仕組み。
+// フックは外部のコマンドで、標準入力に1つのJSONを受け取り、標準出力にJSONを返す (返さなくてもよい)。
+// 環境変数 MDTAGGER_HOOK にフックの名前を入れて起動する。
+//
+// pre_write はファイルを書き換える直前に呼び、タグを差し替えたり書き込みを断ったりできる。
+// 差し替えられるのはタグだけにして、本文やほかのキーはフックからは変えさせない。
+
+use anyhow::{Context, Result, anyhow, bail};
+use log::{debug, warn};
+use mdtagger::db::{TagConfig, register_new_tag};
+use mdtagger::markdown::{FrontMatter, FrontMatterOptions, content_tags, set_tags};
+use mdtagger::resolver::Resolution;
+use mdtagger::tr;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+
+use crate::history::HistoryEntry;
+use crate::settings::HookSettings;
+
+static HOOKS: OnceLock<HookSettings> = OnceLock::new();
+
+/// 設定ファイルのフックを使うようにする (main で1回だけ呼ぶ)
+pub fn init(hooks: &HookSettings) {
+    let _ = HOOKS.set(hooks.clone());
+}
+
+fn hooks() -> &'static HookSettings {
+    HOOKS.get_or_init(HookSettings::default)
+}
+
+/// pre_resolve に渡す内容
+#[derive(Serialize)]
+struct PreResolve<'a> {
+    hook: &'static str,
+    db: &'a Path,
+    file: &'a Path,
+    /// 整形ルールを通したあとの入力
+    input: &'a str,
+    /// DBに登録されているタグの名前
+    tags: Vec<&'a str>,
+}
+
+/// pre_resolve の答え (どちらも省略できる。何も出力しなければいつもどおりに決める)
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PreResolveReply {
+    /// 入力をこの文字列に置き換えてから、いつもどおりに決める
+    pub input: Option<String>,
+    /// 付けるタグをこれに決める (空ならタグを付けない)。DBにないタグは登録する
+    pub tags: Option<Vec<String>>,
+}
+
+impl PreResolveReply {
+    /// tags で決めたときの Resolution
+    pub fn resolution(tags: Vec<String>, config: &mut TagConfig) -> Resolution {
+        let mut created = false;
+        let tags = tags
+            .iter()
+            .map(|tag| match config.tags.iter().find(|e| &e.name == tag) {
+                Some(entry) => entry.name.clone(),
+                None => {
+                    created = true;
+                    register_new_tag(tag, config)
+                }
+            })
+            .collect();
+        let resolution = Resolution::many(tags, created).via("hook");
+        if created {
+            resolution.created()
+        } else {
+            resolution
+        }
+    }
+}
+
+/// 入力したタグを決める前に呼ぶ (フックがなければ何もしない)
+pub fn pre_resolve(
+    db: &Path,
+    file: &Path,
+    input: &str,
+    config: &TagConfig,
+) -> Result<PreResolveReply> {
+    let Some(command) = &hooks().pre_resolve else {
+        return Ok(PreResolveReply::default());
+    };
+    let request = PreResolve {
+        hook: "pre_resolve",
+        db,
+        file,
+        input,
+        tags: config.tags.iter().map(|e| e.name.as_str()).collect(),
+    };
+    let output = run("pre_resolve", command, &request)?;
+    if output.trim().is_empty() {
+        return Ok(PreResolveReply::default());
+    }
+    // DBの JSON と区別できるよう、解析のエラーは文字列にする
+    serde_json::from_str(&output).map_err(|err| {
+        anyhow!(tr!(
+            "Invalid reply from the pre_resolve hook ({}): {}",
+            "pre_resolve フックの出力が不正です ({}): {}",
+            err,
+            output.trim()
+        ))
+    })
+}
+
+/// pre_write に渡す内容
+#[derive(Serialize)]
+struct PreWrite<'a> {
+    hook: &'static str,
+    db_path: &'a Path,
+    file: &'a Path,
+    /// add, pick, rename, merge など
+    operation: &'a str,
+    tag_key: &'a str,
+    tags_before: Vec<String>,
+    tags_after: Vec<String>,
+    /// 書き換えた後の Front Matter (JSON にできなければ null)
+    front_matter: serde_json::Value,
+}
+
+/// pre_write の答え (何も出力しなければそのまま書き込む)
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct PreWriteReply {
+    /// 書き込みを断る理由
+    reject: Option<String>,
+    /// タグをこれに差し替えて書き込む
+    tags: Option<Vec<String>>,
+}
+
+/// pre_write フックが書き込みを断った
+#[derive(Debug, thiserror::Error)]
+#[error("{}", tr!("The pre_write hook rejected {:?}: {}", "pre_write フックが {:?} の書き込みを断りました: {}", .path, .reason))]
+pub struct Rejected {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// ファイルを書き換える直前に呼び、書き込む内容を返す (フックがなければ after のまま)
+pub fn pre_write(
+    db: &Path,
+    operation: &str,
+    file: &Path,
+    before: &str,
+    after: String,
+    options: &FrontMatterOptions,
+) -> Result<String> {
+    let Some(command) = &hooks().pre_write else {
+        return Ok(after);
+    };
+    let tags = |content: &str| content_tags(content, options).unwrap_or_default();
+    let front_matter = FrontMatter::parse(&after)
+        .ok()
+        .and_then(|(front_matter, _)| serde_json::to_value(front_matter.mapping()).ok())
+        .unwrap_or_default();
+    let request = PreWrite {
+        hook: "pre_write",
+        db_path: db,
+        file,
+        operation,
+        tag_key: &options.tag_key,
+        tags_before: tags(before),
+        tags_after: tags(&after),
+        front_matter,
+    };
+    let output = run("pre_write", command, &request)?;
+    if output.trim().is_empty() {
+        return Ok(after);
+    }
+    let reply: PreWriteReply = serde_json::from_str(&output).map_err(|err| {
+        anyhow!(tr!(
+            "Invalid reply from the pre_write hook ({}): {}",
+            "pre_write フックの出力が不正です ({}): {}",
+            err,
+            output.trim()
+        ))
+    })?;
+    if let Some(reason) = reply.reject {
+        return Err(Rejected {
+            path: file.to_path_buf(),
+            reason,
+        }
+        .into());
+    }
+    match reply.tags {
+        Some(tags) => {
+            debug!("pre_write hook set tags of {:?}: {:?}", file, tags);
+            Ok(set_tags(&after, &tags, options)?)
+        }
+        None => Ok(after),
+    }
+}
+
+/// post_write に渡す内容 (履歴の1行にフックの名前とDBのパスを足したもの)
+#[derive(Serialize)]
+struct PostWrite<'a> {
+    hook: &'static str,
+    db_path: &'a Path,
+    #[serde(flatten)]
+    entry: &'a HistoryEntry,
+}
+
+/// ファイルかDBを書き換えたあとに呼ぶ。書き換えは済んでいるので、失敗しても警告だけにする
+pub fn post_write(db: &Path, entry: &HistoryEntry) {
+    let Some(command) = &hooks().post_write else {
+        return;
+    };
+    let request = PostWrite {
+        hook: "post_write",
+        db_path: db,
+        entry,
+    };
+    if let Err(err) = run("post_write", command, &request) {
+        warn!("⚠️  {:#}", err);
+    }
+}
+
+/// フックを起動して request を渡し、標準出力を返す (標準エラーはそのまま端末へ出す)
+fn run(name: &str, command: &[String], request: &impl Serialize) -> Result<String> {
+    let Some((program, args)) = command.split_first() else {
+        bail!(tr!(
+            "The {} hook has no command",
+            "{} フックのコマンドが空です",
+            name
+        ));
+    };
+    debug!("hook {}: {:?}", name, command);
+    let input = serde_json::to_vec(request)?;
+    let mut child = Command::new(program)
+        .args(args)
+        .env("MDTAGGER_HOOK", name)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| {
+            tr!(
+                "Failed to run the {} hook {:?}",
+                "{} フック {:?} を起動できません",
+                name,
+                program
+            )
+        })?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // 出力を読みながら書かないと、大きな入力でパイプが詰まる
+    let output = thread::scope(|scope| {
+        scope.spawn(move || {
+            // 入力を読まずに終わるフックもあるので、書き込みの失敗は無視する
+            let _ = stdin.write_all(&input);
+        });
+        child.wait_with_output()
+    })
+    .with_context(|| tr!("The {} hook failed", "{} フックが失敗しました", name))?;
+    if !output.status.success() {
+        bail!(tr!(
+            "The {} hook exited with {}",
+            "{} フックが {} で終了しました",
+            name,
+            output.status
+        ));
+    }
+    String::from_utf8(output.stdout).with_context(|| {
+        tr!(
+            "The {} hook printed invalid UTF-8",
+            "{} フックの出力が UTF-8 ではありません",
+            name
+        )
+    })
+}
@@ -0,0 +1,139 @@
+// --- Hugo のサイト (hugo.toml / config.toml のあるディレクトリ) ---
+//
+// サイトの設定の [taxonomies] を読み、タグを書き込むキーがタクソノミーかを確かめる。
+// [taxonomies] がなければ Hugo と同じく tags と categories の2つとみなす。
+// 公開しているページにまだない語を付けると、Hugo は新しい一覧のページ (/tags/<語>/) を作るので、add で警告する。
+
+use anyhow::{Context, Result};
+use log::warn;
+use mdtagger::markdown::{collect_markdown_files, read_front_matter};
+use mdtagger::tr;
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// サイトの設定ファイルの場所 (上のものを優先する)
+const CONFIG_FILES: [&str; 4] = [
+    "hugo.toml",
+    "config.toml",
+    "config/_default/hugo.toml",
+    "config/_default/config.toml",
+];
+
+#[derive(Debug)]
+pub struct Site {
+    pub root: PathBuf,
+    /// 単数形 -> 複数形 (複数形が Front Matter のキーになる)
+    pub taxonomies: BTreeMap<String, String>,
+    content_dir: PathBuf,
+}
+
+impl Site {
+    /// カレントディレクトリから親へたどって、最初に見つかったサイト
+    ///
+    /// config.toml はほかのツールでも使う名前なので、content ディレクトリもあるときだけサイトとみなす
+    pub fn detect() -> Result<Option<Self>> {
+        let Ok(cwd) = env::current_dir() else {
+            return Ok(None);
+        };
+        for dir in cwd.ancestors() {
+            for name in CONFIG_FILES {
+                let path = dir.join(name);
+                if path.is_file()
+                    && let Some(site) = Site::read(dir, &path)?
+                {
+                    return Ok(Some(site));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn read(root: &Path, path: &Path) -> Result<Option<Self>> {
+        let text = fs::read_to_string(path)
+            .with_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+        let config: toml::Table = toml::from_str(&text).with_context(|| {
+            tr!(
+                "Invalid Hugo config {:?}",
+                "Hugo の設定ファイル {:?} が不正です",
+                path
+            )
+        })?;
+        let content_dir = root.join(
+            config
+                .get("contentDir")
+                .and_then(|v| v.as_str())
+                .unwrap_or("content"),
+        );
+        // base_url (Hugo では baseURL) があれば Zola の config.toml
+        if !path.ends_with("hugo.toml")
+            && (!content_dir.is_dir() || config.contains_key("base_url"))
+        {
+            return Ok(None);
+        }
+        let taxonomies = match config.get("taxonomies").and_then(|v| v.as_table()) {
+            Some(table) => table
+                .iter()
+                .filter_map(|(singular, plural)| {
+                    Some((singular.clone(), plural.as_str()?.to_string()))
+                })
+                .collect(),
+            None => [("tag", "tags"), ("category", "categories")]
+                .into_iter()
+                .map(|(singular, plural)| (singular.to_string(), plural.to_string()))
+                .collect(),
+        };
+        Ok(Some(Self {
+            root: root.to_path_buf(),
+            taxonomies,
+            content_dir,
+        }))
+    }
+
+    /// 単数形か複数形で指定したタクソノミーの、Front Matter のキー
+    pub fn taxonomy(&self, name: &str) -> Option<&str> {
+        self.taxonomies
+            .iter()
+            .find(|(singular, plural)| *singular == name || *plural == name)
+            .map(|(_, plural)| plural.as_str())
+    }
+
+    /// タクソノミーのキーの一覧 (メッセージ用)
+    pub fn keys(&self) -> String {
+        let keys: Vec<&str> = self.taxonomies.values().map(String::as_str).collect();
+        keys.join(", ")
+    }
+
+    /// タグを書き込むキーがタクソノミーでなければ警告する (Hugo はそのキーの値から一覧のページを作らない)
+    pub fn check_key(&self, key: &str) {
+        if !self.taxonomies.values().any(|plural| plural == key) {
+            warn!(
+                "⚠️  {}",
+                tr!(
+                    "'{}' is not a taxonomy of the Hugo site {:?}, so Hugo will not list these tags (taxonomies: {})",
+                    "'{}' は Hugo のサイト {:?} のタクソノミーではないため、Hugo はこのタグの一覧を作りません (タクソノミー: {})",
+                    key,
+                    self.root,
+                    self.keys()
+                )
+            );
+        }
+    }
+
+    /// content の下のページで key に使われている語
+    pub fn terms(&self, key: &str) -> HashSet<String> {
+        content_terms(&self.content_dir, key)
+    }
+}
+
+/// content_dir の下のページで key に使われている語 (Hugo や Zola と同じく大文字・小文字は区別しない)
+pub fn content_terms(content_dir: &Path, key: &str) -> HashSet<String> {
+    let files = collect_markdown_files(content_dir).unwrap_or_default();
+    files
+        .iter()
+        .filter_map(|path| read_front_matter(path).ok())
+        .flat_map(|(front_matter, _)| front_matter.list(key))
+        .map(|term| term.to_lowercase())
+        .collect()
+}
@@ -0,0 +1,68 @@
+// --- メッセージの言語 ---
+//
+// 設定ファイルの language、なければ LC_ALL / LC_MESSAGES / LANG から言語を決める。
+// メッセージは呼び出し側で英語と日本語を並べて書く (tr! マクロ)。
+
+use std::env;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Ja,
+}
+
+static LANG: AtomicU8 = AtomicU8::new(0);
+
+impl Lang {
+    /// "ja", "ja_JP.UTF-8", "en" などを読む
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        if value.starts_with("ja") {
+            Some(Lang::Ja)
+        } else if value.starts_with("en") || value == "c" || value == "posix" {
+            Some(Lang::En)
+        } else {
+            None
+        }
+    }
+
+    /// ロケールの環境変数から決める (最初に値がある変数を使う)
+    pub fn from_env() -> Option<Self> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|key| env::var(key).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Lang::parse(&value))
+    }
+}
+
+pub fn set_lang(lang: Lang) {
+    LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+pub fn lang() -> Lang {
+    match LANG.load(Ordering::Relaxed) {
+        1 => Lang::Ja,
+        _ => Lang::En,
+    }
+}
+
+/// 現在の言語でメッセージを作る: tr!("英語", "日本語", 引数...)
+///
+/// 語順が違う場合は、書式の中で {0} や {1} のように位置を指定する。
+#[macro_export]
+macro_rules! tr {
+    ($en:literal, $ja:literal $(,)?) => {
+        match $crate::i18n::lang() {
+            $crate::i18n::Lang::Ja => String::from($ja),
+            $crate::i18n::Lang::En => String::from($en),
+        }
+    };
+    ($en:literal, $ja:literal, $($arg:expr),+ $(,)?) => {
+        match $crate::i18n::lang() {
+            $crate::i18n::Lang::Ja => format!($ja, $($arg),+),
+            $crate::i18n::Lang::En => format!($en, $($arg),+),
+        }
+    };
+}
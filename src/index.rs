@@ -0,0 +1,477 @@
+// --- ファイルのタグの索引 ---
+//
+// ディレクトリの Markdown ファイルを読み、ファイルごとのタグを集める (find などで使う)。
+// タグはDBの正式名に揃え (エイリアスや大文字・小文字だけ違う書き方のタグも正式名として数える)、Jekyll の defaults のタグも入れる。
+//
+// 読んだタグはDBの隣の .mdtagger/file_index.json に、ファイルの更新日時と大きさと一緒に残す。
+// ファイル -> タグと、その逆のタグ -> ファイルの両方を持ち、mdtagger が書き換えたファイルは書き込んだタグで更新する。
+// find は一度読み終えたディレクトリなら索引だけで答え、ファイルを読まない (stat もしない)。
+// stats などは更新日時と大きさの変わったファイルだけを読み直す。
+// 索引が壊れたり古くなったりしたら (ほかのツールで書き換えたなど) reindex で作り直す。
+
+use log::{debug, warn};
+use mdtagger::db::TagDatabase;
+use mdtagger::markdown::{FrontMatterOptions, collect_markdown_files, content_tags, read_tags};
+use mdtagger::tr;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::settings::Settings;
+use crate::undo::state_dir;
+
+const CACHE_FILE: &str = "file_index.json";
+/// 索引の形を変えたら上げる (違う版の索引は捨てて作り直す)
+const CACHE_VERSION: u32 = 2;
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct IndexCache {
+    version: u32,
+    /// すべてのファイルを読み終えたディレクトリ (正規化した絶対パス)。この下は索引だけで答えられる
+    roots: BTreeSet<String>,
+    /// 正規化した絶対パス -> 読んだタグ
+    files: BTreeMap<String, CachedFile>,
+    /// ファイルに書いてあるとおりのタグ -> 付いているファイル (正規化した絶対パス)
+    tags: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedFile {
+    /// 更新日時 (UNIX 時間のナノ秒)
+    modified: u128,
+    size: u64,
+    /// 読んだときのタグのキーと本文のフィールドの書き方 (設定を変えたら読み直す)
+    options: String,
+    /// ファイルに書いてあるとおりのタグ (DBの正式名に揃える前)
+    tags: Vec<String>,
+}
+
+impl IndexCache {
+    /// 読めなければ空の索引にする (作り直せばよいので、エラーにはしない)
+    fn load(db_path: &Path) -> Self {
+        let path = state_dir(db_path).join(CACHE_FILE);
+        let cache: Self = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        if cache.version == CACHE_VERSION {
+            cache
+        } else {
+            Self {
+                version: CACHE_VERSION,
+                ..Self::default()
+            }
+        }
+    }
+
+    /// 書き込めなくても (読み取り専用のディレクトリなど) 索引を使わないだけなので、エラーにはしない
+    fn save(&self, db_path: &Path) {
+        let dir = state_dir(db_path);
+        let result = fs::create_dir_all(&dir)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(serde_json::to_string(self)?))
+            .and_then(|json| Ok(fs::write(dir.join(CACHE_FILE), json)?));
+        if let Err(err) = result {
+            debug!("{}: {}", CACHE_FILE, err);
+        }
+    }
+
+    /// ファイルのタグを入れ替える (タグ -> ファイルも合わせて直す)
+    fn insert(&mut self, key: String, file: CachedFile) {
+        self.remove(&key);
+        for tag in &file.tags {
+            self.tags
+                .entry(tag.clone())
+                .or_default()
+                .insert(key.clone());
+        }
+        self.files.insert(key, file);
+    }
+
+    /// ファイルを索引から外す (入っていなければ false)
+    fn remove(&mut self, key: &str) -> bool {
+        let Some(file) = self.files.remove(key) else {
+            return false;
+        };
+        for tag in file.tags {
+            if let Some(keys) = self.tags.get_mut(&tag) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.tags.remove(&tag);
+                }
+            }
+        }
+        true
+    }
+
+    /// root (正規化した絶対パス) の下のファイル
+    fn under<'a>(&'a self, root: &'a str) -> impl Iterator<Item = (&'a String, &'a CachedFile)> {
+        // 同じ文字列で始まるキーは並びの中で続いている (/a/b-c のようなものは Path で除く)
+        self.files
+            .range::<str, _>((Bound::Included(root), Bound::Unbounded))
+            .take_while(move |(key, _)| key.starts_with(root))
+            .filter(move |(key, _)| Path::new(key.as_str()).starts_with(root))
+    }
+
+    /// root を読み終えたディレクトリ (かその下) として索引だけで答えられるか
+    fn covers(&self, root: &str) -> bool {
+        self.roots
+            .iter()
+            .any(|dir| Path::new(root).starts_with(dir))
+    }
+}
+
+/// 索引のキー (正規化した絶対パス)
+fn cache_key(path: &Path) -> String {
+    fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// ファイルの更新日時と大きさ
+fn stamp(path: &Path) -> Option<(u128, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((modified.as_nanos(), metadata.len()))
+}
+
+/// 読んだときの設定 (タグのキーと本文のフィールドの書き方)
+fn options_key(options: &FrontMatterOptions) -> String {
+    format!("{}:{:?}", options.tag_key, options.inline_field)
+}
+
+/// root からの相対パス (区切りは /)
+pub fn relative(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    parts.join("/")
+}
+
+/// mdtagger が書き換えたファイルの索引を、書き込んだ内容 (ファイルの先頭か全体) のタグで更新する
+pub fn update<'a>(
+    db_path: &Path,
+    options: &FrontMatterOptions,
+    files: impl IntoIterator<Item = (&'a Path, &'a str)>,
+) {
+    let mut cache = IndexCache::load(db_path);
+    let mut changed = false;
+    for (path, content) in files {
+        let key = cache_key(path);
+        match (content_tags(content, options), stamp(path)) {
+            (Ok(tags), Some((modified, size))) => {
+                cache.insert(
+                    key,
+                    CachedFile {
+                        modified,
+                        size,
+                        options: options_key(options),
+                        tags,
+                    },
+                );
+                changed = true;
+            }
+            // 読めない内容なら、次に読み直す
+            _ => changed |= cache.remove(&key),
+        }
+    }
+    if changed {
+        cache.save(db_path);
+    }
+}
+
+/// タグの付いたファイル
+pub struct IndexedFile {
+    pub path: PathBuf,
+    /// DBの正式名に揃えたタグ (DBにないタグはそのまま)
+    pub tags: Vec<String>,
+}
+
+pub struct FileIndex {
+    /// 読んだディレクトリ (ファイルを指定したらその親)
+    pub root: PathBuf,
+    pub files: Vec<IndexedFile>,
+    /// DBの正式名に揃えたタグ -> 付いているファイル (files の位置)
+    pub tags: BTreeMap<String, BTreeSet<usize>>,
+}
+
+impl FileIndex {
+    /// path の Markdown ファイルを読む (読めないファイルは警告して飛ばす)
+    ///
+    /// db_path の隣の索引を使い、変わっていないファイルは読まない。
+    pub fn build(
+        path: &Path,
+        settings: &Settings,
+        db: &TagDatabase,
+        db_path: &Path,
+    ) -> anyhow::Result<Self> {
+        let mut cache = IndexCache::load(db_path);
+        let (index, changed) = Self::read(path, settings, db, &mut cache)?;
+        if changed {
+            cache.save(db_path);
+        }
+        Ok(index)
+    }
+
+    /// path の下のファイルを索引だけから返す (ファイルは読まない。まだ読んだことがなければ build する)
+    ///
+    /// ほかのツールでの書き換えや、足したり消したりしたファイルは reindex するまで反映されない。
+    pub fn load(
+        path: &Path,
+        settings: &Settings,
+        db: &TagDatabase,
+        db_path: &Path,
+    ) -> anyhow::Result<Self> {
+        let cache = IndexCache::load(db_path);
+        let root_key = cache_key(path);
+        if !cache.covers(&root_key) {
+            return Self::build(path, settings, db, db_path);
+        }
+        let names = Names::new(db);
+        let mut files = Vec::new();
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        // ファイルにタグがない (Jekyll の defaults のタグが付くことがある) ファイル
+        let mut untagged = Vec::new();
+        for (key, cached) in cache.under(&root_key) {
+            // 表示するパスは、読んだときと同じく path からのパスにする
+            let file_path = if *key == root_key {
+                path.to_path_buf()
+            } else {
+                path.join(relative(Path::new(&root_key), Path::new(key)))
+            };
+            positions.insert(key, files.len());
+            if cached.tags.is_empty() {
+                untagged.push(files.len());
+            }
+            let tags = effective_tags(&file_path, cached.tags.clone(), settings, &names);
+            files.push(IndexedFile {
+                path: file_path,
+                tags,
+            });
+        }
+        // タグ -> ファイルは索引のものを正式名に揃えて使う
+        let mut tags: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+        for (tag, keys) in &cache.tags {
+            let name = names.get(tag).unwrap_or(tag);
+            for key in keys {
+                if let Some(&i) = positions.get(key.as_str()) {
+                    tags.entry(name.to_string()).or_default().insert(i);
+                }
+            }
+        }
+        for i in untagged {
+            for tag in &files[i].tags {
+                tags.entry(tag.clone()).or_default().insert(i);
+            }
+        }
+        Ok(Self {
+            root: root_of(path),
+            files,
+            tags,
+        })
+    }
+
+    /// 索引を使わずにすべてのファイルを読み直し、索引を作り直す (reindex)
+    ///
+    /// ほかのディレクトリの分は残し、なくなったファイルの分だけ消す。
+    pub fn rebuild(
+        path: &Path,
+        settings: &Settings,
+        db: &TagDatabase,
+        db_path: &Path,
+    ) -> anyhow::Result<Self> {
+        let mut cache = IndexCache::load(db_path);
+        let root = cache_key(path);
+        let stale: Vec<String> = cache
+            .files
+            .keys()
+            .filter(|key| Path::new(key.as_str()).starts_with(&root) || !Path::new(key).exists())
+            .cloned()
+            .collect();
+        for key in stale {
+            cache.remove(&key);
+        }
+        let (index, _) = Self::read(path, settings, db, &mut cache)?;
+        cache.save(db_path);
+        Ok(index)
+    }
+
+    /// 索引にあって変わっていないファイルは索引のタグを使う (索引を書き換えたら true も返す)
+    fn read(
+        path: &Path,
+        settings: &Settings,
+        db: &TagDatabase,
+        cache: &mut IndexCache,
+    ) -> anyhow::Result<(Self, bool)> {
+        let paths = collect_markdown_files(path)?;
+        let names = Names::new(db);
+        let root_key = cache_key(path);
+        let mut files = Vec::new();
+        let mut seen = HashSet::new();
+        let mut changed = cache.roots.insert(root_key.clone());
+        for path in paths {
+            let options = settings.front_matter_for(&path);
+            let key = cache_key(&path);
+            let stamp = stamp(&path);
+            let options_key = options_key(&options);
+            let cached = cache.files.get(&key).filter(|cached| {
+                stamp == Some((cached.modified, cached.size)) && cached.options == options_key
+            });
+            let tags = match cached {
+                Some(cached) => cached.tags.clone(),
+                None => match read_tags(&path, &options) {
+                    Ok(tags) => {
+                        if let Some((modified, size)) = stamp {
+                            cache.insert(
+                                key.clone(),
+                                CachedFile {
+                                    modified,
+                                    size,
+                                    options: options_key,
+                                    tags: tags.clone(),
+                                },
+                            );
+                            changed = true;
+                        }
+                        tags
+                    }
+                    Err(err) => {
+                        warn!(
+                            "⚠️  {}",
+                            tr!("Skipped {:?}: {}", "{:?} をスキップしました: {}", path, err)
+                        );
+                        continue;
+                    }
+                },
+            };
+            seen.insert(key);
+            let tags = effective_tags(&path, tags, settings, &names);
+            files.push(IndexedFile { path, tags });
+        }
+        // 読んだディレクトリで見つからなかった (消した) ファイルの分を索引から外す
+        let gone: Vec<String> = cache
+            .under(&root_key)
+            .map(|(key, _)| key)
+            .filter(|key| !seen.contains(*key))
+            .cloned()
+            .collect();
+        for key in gone {
+            changed |= cache.remove(&key);
+        }
+        let mut tags: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+        for (i, file) in files.iter().enumerate() {
+            for tag in &file.tags {
+                tags.entry(tag.clone()).or_default().insert(i);
+            }
+        }
+        Ok((
+            Self {
+                root: root_of(path),
+                files,
+                tags,
+            },
+            changed,
+        ))
+    }
+
+    /// タグ -> 付いているファイルの数
+    pub fn tag_counts(&self) -> BTreeMap<&str, usize> {
+        self.tags
+            .iter()
+            .map(|(tag, files)| (tag.as_str(), files.len()))
+            .collect()
+    }
+
+    /// tags のどれかと同じファイルに付いていたほかのタグと、一緒に付いていたファイルの数 (多い順)
+    pub fn related(&self, tags: &[String]) -> Vec<(String, usize)> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for file in &self.files {
+            if !file.tags.iter().any(|tag| tags.contains(tag)) {
+                continue;
+            }
+            for tag in &file.tags {
+                if !tags.contains(tag) {
+                    *counts.entry(tag.as_str()).or_insert(0) += 1;
+                }
+            }
+        }
+        let mut related: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(tag, count)| (tag.to_string(), count))
+            .collect();
+        related.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        related
+    }
+
+    /// root からの相対パス (区切りは /)
+    pub fn relative(&self, path: &Path) -> String {
+        relative(&self.root, path)
+    }
+}
+
+/// 読んだディレクトリ (ファイルを指定したらその親)
+fn root_of(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.to_path_buf()
+    } else {
+        path.parent().unwrap_or(Path::new("")).to_path_buf()
+    }
+}
+
+/// タグ -> DBの正式名 (lint の casing と同じく、大文字・小文字だけ違う書き方も正式名にする)
+struct Names<'a> {
+    db: &'a TagDatabase,
+    /// 小文字にした正式名 -> 正式名
+    lowercase: HashMap<String, &'a str>,
+}
+
+impl<'a> Names<'a> {
+    fn new(db: &'a TagDatabase) -> Self {
+        let lowercase = db
+            .tags()
+            .iter()
+            .map(|e| (e.name.to_lowercase(), e.name.as_str()))
+            .collect();
+        Self { db, lowercase }
+    }
+
+    /// DBになければ None
+    fn get(&self, tag: &str) -> Option<&'a str> {
+        match self.db.lookup(tag) {
+            Some(entry) => Some(&entry.name),
+            None => self.lowercase.get(&tag.to_lowercase()).copied(),
+        }
+    }
+}
+
+/// ファイルに書いてあるタグを、DBの正式名に揃える (タグがなければ Jekyll の defaults のタグ)
+fn effective_tags(
+    path: &Path,
+    mut tags: Vec<String>,
+    settings: &Settings,
+    names: &Names,
+) -> Vec<String> {
+    if tags.is_empty()
+        && let Some(site) = &settings.jekyll_site
+    {
+        let options = settings.front_matter_for(path);
+        tags = site
+            .default_list(path, &options.tag_key)
+            .unwrap_or_default();
+    }
+    let mut canonical: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = names.get(&tag).map_or(tag, str::to_string);
+        if !canonical.contains(&tag) {
+            canonical.push(tag);
+        }
+    }
+    canonical
+}
@@ -0,0 +1,164 @@
+// --- Jekyll のサイト (_config.yml のあるディレクトリ) ---
+//
+// _config.yml の defaults で付くタグを lint で付いているものとして扱い、
+// 設定ファイルの [jekyll] tag_keys でコレクションごとにタグのキーを変えられるようにする。
+// ファイルの種類は Jekyll と同じく、_posts なら posts、_drafts なら drafts、_<コレクション> ならコレクションの名前、それ以外は pages。
+
+use anyhow::{Context, Result};
+use mdtagger::tr;
+use regex::Regex;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CONFIG_FILE: &str = "_config.yml";
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Config {
+    collections: Option<Value>,
+    collections_dir: Option<String>,
+    defaults: Vec<FrontMatterDefault>,
+}
+
+/// _config.yml の defaults の1件
+#[derive(Debug, Deserialize)]
+struct FrontMatterDefault {
+    #[serde(default)]
+    scope: Scope,
+    #[serde(default)]
+    values: Mapping,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct Scope {
+    #[serde(default)]
+    path: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Site {
+    pub root: PathBuf,
+    /// コレクションの名前 (posts と drafts を含む)
+    collections: Vec<String>,
+    collections_dir: PathBuf,
+    defaults: Vec<FrontMatterDefault>,
+}
+
+impl Site {
+    /// カレントディレクトリから親へたどって、最初に見つかったサイト
+    pub fn detect() -> Result<Option<Self>> {
+        let Ok(cwd) = env::current_dir() else {
+            return Ok(None);
+        };
+        let Some(root) = cwd.ancestors().find(|dir| dir.join(CONFIG_FILE).is_file()) else {
+            return Ok(None);
+        };
+        let path = root.join(CONFIG_FILE);
+        let text = fs::read_to_string(&path)
+            .with_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+        // 空の _config.yml もある
+        let config: Config = serde_yaml::from_str::<Option<Config>>(&text)
+            .with_context(|| {
+                tr!(
+                    "Invalid Jekyll config {:?}",
+                    "Jekyll の設定ファイル {:?} が不正です",
+                    path
+                )
+            })?
+            .unwrap_or_default();
+        // collections はコレクションの名前の配列か、名前をキーにしたマッピング
+        let mut collections = vec!["posts".to_string(), "drafts".to_string()];
+        match &config.collections {
+            Some(Value::Sequence(names)) => collections.extend(
+                names
+                    .iter()
+                    .filter_map(|name| name.as_str().map(str::to_string)),
+            ),
+            Some(Value::Mapping(map)) => collections.extend(
+                map.keys()
+                    .filter_map(|name| name.as_str().map(str::to_string)),
+            ),
+            _ => {}
+        }
+        Ok(Some(Self {
+            root: root.to_path_buf(),
+            collections,
+            collections_dir: root.join(config.collections_dir.unwrap_or_default()),
+            defaults: config.defaults,
+        }))
+    }
+
+    /// ファイルのコレクションの名前 (どのコレクションでもなければ None)
+    pub fn collection(&self, path: &Path) -> Option<&str> {
+        let path = fs::canonicalize(path).ok()?;
+        let dir = fs::canonicalize(&self.collections_dir).ok()?;
+        let first = path.strip_prefix(dir).ok()?.components().next()?;
+        let label = first.as_os_str().to_str()?.strip_prefix('_')?;
+        self.collections
+            .iter()
+            .find(|name| *name == label)
+            .map(String::as_str)
+    }
+
+    /// defaults で key に付く値 (当てはまるものがなければ None)
+    ///
+    /// Jekyll と同じく、パスが長いもの、type のあるものを優先し、同じなら後に書いたものを使う
+    pub fn default_list(&self, path: &Path, key: &str) -> Option<Vec<String>> {
+        let relative = fs::canonicalize(path).ok()?;
+        let root = fs::canonicalize(&self.root).ok()?;
+        let relative = relative.strip_prefix(root).ok()?;
+        let kind = self.collection(path).unwrap_or("pages");
+        let mut matching: Vec<&FrontMatterDefault> = self
+            .defaults
+            .iter()
+            .filter(|d| d.values.contains_key(key))
+            .filter(|d| d.scope.kind.as_deref().is_none_or(|k| k == kind))
+            .filter(|d| scope_matches(&d.scope.path, relative))
+            .collect();
+        matching.sort_by_key(|d| {
+            (
+                Path::new(&d.scope.path).components().count(),
+                d.scope.kind.is_some(),
+            )
+        });
+        let value = matching.last()?.values.get(key)?;
+        Some(match value {
+            // Jekyll はタグの文字列を空白で区切る
+            Value::String(s) => s.split_whitespace().map(str::to_string).collect(),
+            Value::Sequence(seq) => seq
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        })
+    }
+
+    /// コレクションのファイルに使うタグのキー (tag_keys になければ None)
+    pub fn tag_key<'a>(
+        &self,
+        path: &Path,
+        tag_keys: &'a BTreeMap<String, String>,
+    ) -> Option<&'a str> {
+        tag_keys.get(self.collection(path)?).map(String::as_str)
+    }
+}
+
+/// scope.path がサイトからの相対パスに当てはまるか ("" はすべて、* はディレクトリの名前の一部)
+fn scope_matches(scope: &str, relative: &Path) -> bool {
+    let scope = scope.trim_matches('/');
+    if scope.is_empty() || scope == "." {
+        return true;
+    }
+    if !scope.contains('*') {
+        return relative.starts_with(scope);
+    }
+    let pattern = regex::escape(scope).replace(r"\*", "[^/]*");
+    Regex::new(&format!("^{}(/|$)", pattern))
+        .is_ok_and(|re| re.is_match(&relative.to_string_lossy()))
+}
@@ -0,0 +1,323 @@
+// --- 書き換えの途中経過 (.mdtagger/journal.jsonl) ---
+//
+// まとめて書き換えるあいだ、ファイルを1つ書き換える直前に「これから書き換える」記録 (パスと元の先頭のハッシュ) を
+// 追記してディスクに書き出し、最後まで終われば (undo.json を書いたら) 消す。
+// 電源が落ちるなどして途中で止まると記録が残るので、次に起動したときに見つけて、書き換えたファイルとDBを
+// 実行の前に戻すかを尋ねる。1行目は実行の記録 (操作、プロセス、実行前のDB)、2行目からはファイルごとの記録。
+// 書き換えているあいだは記録のファイルを排他ロックしておく。ロックはプロセスが止まればOSが外すので、
+// ロックできる記録は途中で止まった実行のもの、できなければまだ動いている実行のものとわかる。
+
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use mdtagger::markdown::{replace_head, temp_file};
+use mdtagger::prompt::confirm;
+use mdtagger::tr;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, TryLockError};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::commands::GlobalArgs;
+use crate::history::{self, HistoryEntry};
+use crate::hooks;
+use crate::index;
+use crate::undo::{FileChange, state_dir};
+
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// 1行目: 実行の記録
+#[derive(Serialize, Deserialize)]
+struct Header {
+    operation: String,
+    /// 書き換えていたプロセス
+    pid: u32,
+    /// RFC 3339 形式 (UTC)
+    started: String,
+    db_path: PathBuf,
+    /// 実行前のDB (None ならDBはまだなかった)
+    db_before: Option<String>,
+}
+
+/// 2行目から: これから書き換えるファイル
+#[derive(Serialize, Deserialize)]
+struct Intent {
+    path: PathBuf,
+    /// 元の先頭 (元に戻すときに書き込む)
+    before: String,
+    before_hash: String,
+    /// 書き込む先頭の長さとハッシュ (書き換え終わっていたかを見る)
+    after_len: usize,
+    after_hash: String,
+}
+
+/// 同じタグデータベースで、別の実行がファイルを書き換えている
+#[derive(Debug, thiserror::Error)]
+#[error("{}", tr!(
+    "Another mdtagger is rewriting files with this tag database; try again when it finishes",
+    "別の mdtagger がこのタグデータベースでファイルを書き換えています。終わってからもう一度実行してください"
+))]
+pub struct Locked;
+
+/// 書き換えの途中経過の記録 (Recorder が最初のファイルを書き換える直前に始める)
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+}
+
+/// 内容のハッシュ (FNV-1a、64ビット)。同じ内容かを見るだけなので速いものにする
+fn hash(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+impl Journal {
+    pub fn start(db_path: &Path, operation: &str, db_before: Option<&str>) -> Result<Self> {
+        let dir = state_dir(db_path);
+        fs::create_dir_all(&dir)
+            .with_context(|| tr!("Failed to create {:?}", "{:?} を作成できません", dir))?;
+        let path = dir.join(JOURNAL_FILE);
+        // ほかの実行の記録を消さないように、ロックできてから中身を空にする
+        let file = File::options()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| tr!("Failed to open {:?}", "{:?} を開けません", path))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Err(Locked.into()),
+            Err(TryLockError::Error(e)) => {
+                return Err(e)
+                    .with_context(|| tr!("Failed to lock {:?}", "{:?} をロックできません", path));
+            }
+        }
+        file.set_len(0)
+            .with_context(|| tr!("Failed to write {:?}", "{:?} に書き込めません", path))?;
+        let mut journal = Self { path, file };
+        journal.append(&Header {
+            operation: operation.to_string(),
+            pid: std::process::id(),
+            started: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            db_path: fs::canonicalize(db_path).unwrap_or_else(|_| db_path.to_path_buf()),
+            db_before: db_before.map(str::to_string),
+        })?;
+        Ok(journal)
+    }
+
+    /// ファイルの先頭の before を after に書き換える直前に呼ぶ (ディスクに書き出してから戻る)
+    pub fn intend(&mut self, path: &Path, before: &str, after: &str) -> Result<()> {
+        self.append(&Intent {
+            path: fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+            before: before.to_string(),
+            before_hash: hash(before.as_bytes()),
+            after_len: after.len(),
+            after_hash: hash(after.as_bytes()),
+        })
+    }
+
+    fn append(&mut self, line: &impl Serialize) -> Result<()> {
+        writeln!(self.file, "{}", serde_json::to_string(line)?)
+            .and_then(|_| self.file.sync_data())
+            .with_context(|| tr!("Failed to write {:?}", "{:?} に書き込めません", self.path))
+    }
+
+    /// 最後まで書き換え終わったら消す
+    pub fn finish(self) -> Result<()> {
+        drop(self.file);
+        fs::remove_file(&self.path)
+            .with_context(|| tr!("Failed to remove {:?}", "{:?} を消せません", self.path))
+    }
+}
+
+/// ファイルがどこまで書き換わっていたか
+enum State {
+    /// まだ書き換えていない
+    Original,
+    /// 書き換え終わっていた (いまの先頭)
+    Written(String),
+    /// どちらでもない (そのあとに手で編集されたなど)
+    Unknown,
+}
+
+fn state(intent: &Intent) -> State {
+    let Ok(file) = File::open(&intent.path) else {
+        return State::Unknown;
+    };
+    let len = intent.before.len().max(intent.after_len);
+    let mut prefix = Vec::with_capacity(len);
+    if file.take(len as u64).read_to_end(&mut prefix).is_err() {
+        return State::Unknown;
+    }
+    let matches = |n: usize, expected: &str| prefix.len() >= n && hash(&prefix[..n]) == expected;
+    let written = matches(intent.after_len, &intent.after_hash);
+    let original = matches(intent.before.len(), &intent.before_hash);
+    // 短いほうはもう一方の先頭にもなりうるので、両方に合えば長いほうとみなす
+    if written && (!original || intent.after_len >= intent.before.len()) {
+        match String::from_utf8(prefix[..intent.after_len].to_vec()) {
+            Ok(current) => State::Written(current),
+            Err(_) => State::Unknown,
+        }
+    } else if original {
+        State::Original
+    } else {
+        State::Unknown
+    }
+}
+
+/// 途中で止まった書き換えの記録が残っていれば、実行の前に戻すかを尋ねる (起動したときに呼ぶ)
+///
+/// 端末でなければ (--yes もなければ) 警告だけを出し、記録は次に起動したときのために残す。
+pub fn recover(global: &GlobalArgs) -> Result<()> {
+    let path = state_dir(global.db_path()).join(JOURNAL_FILE);
+    let Ok(mut file) = File::options().read(true).write(true).open(&path) else {
+        return Ok(());
+    };
+    // ロックできなければ、書き換えている実行がまだ動いている
+    if file.try_lock().is_err() {
+        return Ok(());
+    }
+    let mut text = String::new();
+    file.read_to_string(&mut text)
+        .with_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+    let mut lines = text.lines();
+    let Some(header) = lines
+        .next()
+        .and_then(|line| serde_json::from_str::<Header>(line).ok())
+    else {
+        // 1行目を書き終える前に止まった (まだ何も書き換えていない)
+        drop(file);
+        fs::remove_file(&path)?;
+        return Ok(());
+    };
+    // 最後の行は書きかけかもしれない (その行のファイルはまだ書き換えていない)
+    let intents: Vec<Intent> = lines
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+
+    warn!(
+        "{}",
+        tr!(
+            "⚠️  The last run ('{}', started at {}) stopped before finishing ({} files may have been rewritten)",
+            "⚠️  前回の実行 ({1} に始めた '{0}') が途中で止まりました ({2} ファイルを書き換えたかもしれません)",
+            header.operation,
+            header.started,
+            intents.len()
+        )
+    );
+    if !global.interactive && !global.yes {
+        warn!(
+            "{}",
+            tr!(
+                "   Run mdtagger again in a terminal (or with --yes) to roll it back",
+                "   元に戻すには、端末で (か --yes を付けて) もう一度 mdtagger を実行してください"
+            )
+        );
+        return Ok(());
+    }
+    debug!("recovering the run of process {}", header.pid);
+    let prompt = tr!(
+        "Roll back the files and the tag database to before that run?",
+        "ファイルとタグデータベースをその実行の前に戻しますか?"
+    );
+    if confirm(&prompt, true, global.yes)? {
+        rollback(global, &header, &intents)?;
+    } else {
+        info!(
+            "{}",
+            tr!(
+                "   Kept the files as they are",
+                "   ファイルはそのままにしました"
+            )
+        );
+    }
+    drop(file);
+    fs::remove_file(&path).with_context(|| tr!("Failed to remove {:?}", "{:?} を消せません", path))
+}
+
+/// 記録したファイルとDBを実行の前に戻し、履歴に残す
+fn rollback(global: &GlobalArgs, header: &Header, intents: &[Intent]) -> Result<()> {
+    let mut reverted = Vec::new();
+    for intent in intents.iter().rev() {
+        // 置き換える前に止まったときの一時ファイル
+        let _ = fs::remove_file(temp_file(&intent.path));
+        match state(intent) {
+            State::Original => {}
+            State::Written(current) => {
+                replace_head(&intent.path, &current, &intent.before)?;
+                info!(
+                    "{}",
+                    tr!(
+                        "↩️  Restored {:?}",
+                        "↩️  {:?} を元に戻しました",
+                        intent.path
+                    )
+                );
+                reverted.push(FileChange {
+                    path: intent.path.clone(),
+                    before: current,
+                    after: intent.before.clone(),
+                    head_only: true,
+                });
+            }
+            State::Unknown => warn!(
+                "{}",
+                tr!(
+                    "⚠️  {:?} has changed since it was rewritten; check it by hand",
+                    "⚠️  {:?} は書き換えたあとに変更されています。手で確かめてください",
+                    intent.path
+                )
+            ),
+        }
+    }
+
+    let db_path = &header.db_path;
+    let db_after = fs::read_to_string(db_path).ok();
+    if db_after != header.db_before {
+        match &header.db_before {
+            Some(content) => fs::write(db_path, content)?,
+            None if db_path.exists() => fs::remove_file(db_path)?,
+            None => {}
+        }
+        info!(
+            "{}",
+            tr!(
+                "↩️  Restored tag database {:?}",
+                "↩️  タグデータベース {:?} を元に戻しました",
+                db_path
+            )
+        );
+    }
+    if reverted.is_empty() && db_after == header.db_before {
+        info!(
+            "{}",
+            tr!(
+                "   Nothing had been rewritten yet",
+                "   まだ何も書き換えていませんでした"
+            )
+        );
+        return Ok(());
+    }
+
+    index::update(
+        db_path,
+        &global.settings.front_matter(),
+        reverted
+            .iter()
+            .map(|change| (change.path.as_path(), change.after.as_str())),
+    );
+    let entry = HistoryEntry::new(
+        "rollback",
+        &global.settings.front_matter(),
+        db_after.as_deref(),
+        header.db_before.as_deref(),
+        &reverted,
+    );
+    history::append(db_path, &entry)?;
+    hooks::post_write(db_path, &entry);
+    Ok(())
+}
@@ -0,0 +1,24 @@
+//! Markdown の YAML Front Matter にタグを追加・管理するためのライブラリ。
+//!
+//! CLI (`mdtagger`) と同じタグ解決ロジックを、他のツールやテストから使えるようにしている。
+
+#[cfg(feature = "async")]
+pub mod async_batch;
+pub mod db;
+#[cfg(feature = "embeddings")]
+pub mod embeddings;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod i18n;
+pub mod markdown;
+pub mod normalize;
+pub mod operation;
+pub mod prompt;
+#[cfg(feature = "python")]
+mod python;
+pub mod resolver;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use error::MdtaggerError;
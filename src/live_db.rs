@@ -0,0 +1,99 @@
+// --- 読み込んだままのDB (serve・mcp・lsp・tui) ---
+//
+// 長く動き続けるモードでは、DBを一度だけ読み込んでメモリに置き、ファイルの更新時刻 (と大きさ) が
+// 変わったときだけ読み直す。保存するときにファイルがほかから書き換えられていれば、読み込んだときの内容から
+// の両方の変更を db::merge_configs で合わせてから書き込み、ほかのプロセスや手での編集を消さない。
+
+use anyhow::Result;
+use log::debug;
+use mdtagger::db::{TagConfig, TagDatabase, merge_configs};
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::commands::GlobalArgs;
+
+/// ファイルの更新時刻と大きさ (同じ時刻のうちに書き換えられても気づけるように大きさも見る)
+type Stamp = Option<(SystemTime, u64)>;
+
+pub struct LiveDb<'a> {
+    global: &'a GlobalArgs,
+    loaded: Option<Loaded>,
+}
+
+struct Loaded {
+    db: TagDatabase,
+    /// 最後に読んだ (書いた) ときのファイルの内容。メモリ上の変更はこれとの差
+    base: TagConfig,
+    stamp: Stamp,
+}
+
+impl<'a> LiveDb<'a> {
+    pub fn new(global: &'a GlobalArgs) -> Self {
+        Self {
+            global,
+            loaded: None,
+        }
+    }
+
+    /// 読み込んでから (保存してから) ファイルがほかから書き換えられたか
+    pub fn changed(&self) -> bool {
+        self.loaded
+            .as_ref()
+            .is_none_or(|loaded| loaded.stamp != stamp(self.global.db_path()))
+    }
+
+    /// DBを返す。初めてか、ファイルが書き換えられていれば読み直す (保存していない変更は合わせて残す)
+    pub fn get(&mut self) -> Result<&mut TagDatabase> {
+        if self.changed() {
+            self.reload()?;
+        }
+        Ok(&mut self.loaded.as_mut().expect("loaded above").db)
+    }
+
+    /// 読み込んだDB (まだ読み込んでいなければ None。ファイルは確かめない)
+    #[cfg(feature = "tui")]
+    pub fn peek(&self) -> Option<&TagDatabase> {
+        self.loaded.as_ref().map(|loaded| &loaded.db)
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let stamp = stamp(self.global.db_path());
+        debug!("loading {:?}", self.global.db_path());
+        let theirs = self.global.load_db()?;
+        let db = match self.loaded.take() {
+            Some(loaded) if *loaded.db.config() != loaded.base => {
+                debug!("merging unsaved changes into {:?}", self.global.db_path());
+                TagDatabase::new(merge_configs(&loaded.base, loaded.db.config(), &theirs))
+            }
+            _ => TagDatabase::new(theirs.clone()),
+        };
+        self.loaded = Some(Loaded {
+            db,
+            base: theirs,
+            stamp,
+        });
+        Ok(())
+    }
+
+    /// メモリ上のDBを保存する (ファイルがほかから書き換えられていれば、その変更と合わせてから書き込む)
+    pub fn save(&mut self) -> Result<()> {
+        if self.loaded.is_none() {
+            return Ok(());
+        }
+        if self.changed() {
+            self.reload()?;
+        }
+        let path = self.global.db_path();
+        let loaded = self.loaded.as_mut().expect("loaded above");
+        loaded.db.save(path)?;
+        loaded.base = loaded.db.config().clone();
+        loaded.stamp = stamp(path);
+        Ok(())
+    }
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
@@ -0,0 +1,58 @@
+// --- ログ出力 ---
+//
+// 通常のメッセージ (info) は標準出力、警告とエラーは標準エラー出力に出す。
+// --output json や --print のときは標準出力を結果だけにするため、info も標準エラー出力に出す。
+// -v / -vv のときは解決の過程 (debug / trace) も標準エラー出力に出す。
+// 端末でないときや設定で絵文字を止めたときは、メッセージの先頭の絵文字を外して出す。
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use crate::terminal::strip_emoji;
+
+struct Logger {
+    info_to_stderr: bool,
+    plain: bool,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        let message = if self.plain {
+            strip_emoji(&message)
+        } else {
+            &message
+        };
+        match record.level() {
+            Level::Info if self.info_to_stderr => eprintln!("{}", message),
+            Level::Info => println!("{}", message),
+            Level::Warn | Level::Error => eprintln!("{}", message),
+            Level::Debug => eprintln!("[debug] {}", message),
+            Level::Trace => eprintln!("[trace] {}", message),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// -q / -v の指定からログの詳細さを決めて初期化する
+pub fn init(quiet: bool, verbose: u8, info_to_stderr: bool, plain: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Warn,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    // 二重に初期化されることはないので、失敗は無視してよい
+    let _ = log::set_boxed_logger(Box::new(Logger {
+        info_to_stderr,
+        plain,
+    }));
+    log::set_max_level(level);
+}
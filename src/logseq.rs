@@ -0,0 +1,18 @@
+// --- Logseq のグラフ (logseq/config.edn のあるディレクトリ) ---
+//
+// Logseq のページは Front Matter を使わず、ページの先頭に tags:: a, b のようなプロパティの行を並べる。
+// グラフの中ではタグをこのプロパティから読み書きし、ファイルに --- のブロックは作らない。
+
+use std::env;
+use std::path::PathBuf;
+
+const CONFIG_FILE: &str = "logseq/config.edn";
+
+/// カレントディレクトリから親へたどって、最初に見つかったグラフの一番上のディレクトリ
+pub fn detect_graph() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    let root = cwd
+        .ancestors()
+        .find(|dir| dir.join(CONFIG_FILE).is_file())?;
+    Some(root.to_path_buf())
+}
@@ -1,225 +1,262 @@
-use anyhow::{Context, Result};
-use clap::Parser;
-use dialoguer::{Confirm, Select};
-use regex::Regex;
-use serde::{Deserialize, Serialize};
-use serde_yaml::Value;
-use std::fs;
-use std::path::{Path, PathBuf};
-use strsim::levenshtein;
-
-// --- データ構造 (変更なし) ---
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct TagEntry {
-    name: String,
-    #[serde(default)]
-    aliases: Vec<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Default)]
-struct TagConfig {
-    tags: Vec<TagEntry>,
-}
-
-// --- CLI引数定義 (ここを変更) ---
+use anyhow::{Result, bail};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use exit::{ErrorCode, ErrorReport, Exit};
+use std::process::ExitCode;
+
+#[cfg(feature = "clipboard")]
+mod clipboard;
+mod commands;
+mod decisions;
+mod editor;
+mod exit;
+mod git;
+mod history;
+mod hooks;
+mod hugo;
+mod index;
+mod jekyll;
+mod journal;
+mod live_db;
+mod logging;
+mod logseq;
+mod minhash;
+mod notion;
+mod obsidian;
+mod preview;
+mod query;
+mod rules;
+mod schema;
+mod settings;
+mod table;
+mod taxonomy;
+mod terminal;
+mod undo;
+mod usage;
+mod zola;
+
+// --- CLI引数定義 ---
 #[derive(Parser)]
 #[command(author, version, about)]
 struct Cli {
-    #[arg(value_name = "FILE")]
-    path: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    #[arg(value_name = "TAGS", num_args = 1..)]
-    tags: Vec<String>,
+    /// タグデータベースか --output json の出力の JSON Schema を表示して終了する
+    #[arg(long, value_enum, value_name = "NAME")]
+    schema: Option<schema::SchemaKind>,
 
-    /// タグデータベースのパスを指定 (環境変数 SMART_TAGS_DB でも設定可)
-    #[arg(
-        long,
-        value_name = "DB_PATH", 
-        env = "SMART_TAGS_DB",      // 環境変数を読みに行く
-        default_value = "tags_db.json" // デフォルトはカレントディレクトリ
-    )]
-    db: PathBuf,
+    #[command(flatten)]
+    global: commands::GlobalArgs,
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let md_path = &cli.path;
-    let db_path = &cli.db; // 引数からパスを取得
-
-    // 1. 指定されたパスからロード
-    let mut config = load_config(db_path)?;
-
-    let mut resolved_tags = Vec::new();
-    let mut config_updated = false;
-
-    println!("Using DB: {:?}", db_path); // 現在どのDBを使っているか表示
-
-    println!("Checking tags...");
-    for raw_tag in &cli.tags {
-        let (final_tag, updated) = resolve_tag(raw_tag, &mut config)?;
-        resolved_tags.push(final_tag);
-        if updated {
-            config_updated = true;
-        }
-    }
-
-    // 2. 指定されたパスへ保存
-    if config_updated {
-        save_config(db_path, &config)?;
-        println!("✨ Tag database updated at {:?}", db_path);
-    }
-
-    // 3. Markdownファイルを更新
-    update_markdown(md_path, &resolved_tags)?;
-
-    println!(
-        "✅ Successfully added tags to {:?}: {:?}",
-        md_path, resolved_tags
-    );
-    Ok(())
+#[derive(Subcommand)]
+enum Command {
+    /// Markdownファイルにタグを追加する
+    Add(commands::add::AddArgs),
+    /// 本文やタイトルのよく似たノート (二重に取り込んだノートなど) を探し、片方にしか付いていないタグを表示する (--copy-tags で揃える)
+    Duplicates(commands::duplicates::DuplicatesArgs),
+    /// タグごとにページのパス・タイトル・日付を並べた JSON (tags.json) を、静的サイトや検索の索引向けに書き出す
+    Export(commands::export::ExportArgs),
+    /// タグでファイルを探す (タグを指定しなければタグの一覧。--output script-filter で Alfred / Raycast 向け)
+    Find(commands::find::FindArgs),
+    /// man ページと、サブコマンドごとの Markdown のリファレンスを書き出す
+    GenDocs(commands::gen_docs::GenDocsArgs),
+    /// タグの規則違反 (未登録のタグ、必須タグの不足など) を検査する。ファイルは書き換えない
+    Lint(commands::lint::LintArgs),
+    /// git の pre-commit フックとして、ステージした Markdown ファイルのタグを検査する (install でフックを置く)
+    Hook(commands::hook::HookArgs),
+    /// ほかのツールの書き出したファイルのタグを取り込む (notion: Notion の Markdown エクスポートのプロパティ、dendron: Dendron のファイル名の階層)
+    Import(commands::import::ImportArgs),
+    /// DBのタグのうち、どのファイルにも付いていないものを表示する (--prune でDBから消す)
+    Orphans(commands::orphans::OrphansArgs),
+    /// DBのタグの一覧から選んで、1つのファイルのタグを付け直す
+    Pick(commands::pick::PickArgs),
+    /// タグの名前を変え (元の名前はエイリアスに残す)、ファイルのタグも書き換える。書き換えるファイルを表示して確認する
+    Rename(commands::rename::RenameArgs),
+    /// タグを別のタグに統合し (名前とエイリアスは統合先のエイリアスになる)、ファイルのタグも書き換える
+    Merge(commands::rename::RenameArgs),
+    /// find や stats の使うファイルのタグの索引を、すべてのファイルを読み直して作り直す
+    Reindex(commands::reindex::ReindexArgs),
+    /// ファイルとタグの似ているノートを、タグの集合の Jaccard 係数の高い順に表示する (--weighted で珍しいタグを重く見る)
+    Similar(commands::similar::SimilarArgs),
+    /// 本文のキーワードから付けるべきタグを提案する
+    Suggest(commands::suggest::SuggestArgs),
+    /// 全画面でタグの木を見ながら、名前の変更・統合・エイリアスの追加を行う
+    #[cfg(feature = "tui")]
+    Tui(commands::tui::TuiArgs),
+    /// タグデータベースの概要と、タグの決まり方の集計 (--resolver) を表示する
+    Stats(commands::stats::StatsArgs),
+    /// これまでの変更 (ファイルのタグとタグデータベース) の履歴を表示する
+    History(commands::history::HistoryArgs),
+    /// 直前の操作で書き換えたファイルとタグデータベースを元に戻す
+    Undo(commands::undo::UndoArgs),
+    /// エディタのプラグイン向けに、標準入出力で JSON-RPC を受け付ける
+    Serve(commands::serve::ServeArgs),
+    /// AI アシスタント向けに、タグデータベースとタグの操作を MCP (Model Context Protocol) で公開する
+    Mcp(commands::mcp::McpArgs),
+    /// Front Matter のタグの補完と診断を行う Language Server として動く
+    #[cfg(feature = "lsp")]
+    Lsp(commands::lsp::LspArgs),
 }
 
-// --- ロジック: タグ解決 (変更なし) ---
-fn resolve_tag(input: &str, config: &mut TagConfig) -> Result<(String, bool)> {
-    // 省略 (前回のコードと同じ)
-    // A. 完全一致
-    for entry in &config.tags {
-        if entry.name == input || entry.aliases.iter().any(|a| a == input) {
-            if entry.name != input {
-                println!("   Mapping '{}' -> '{}'", input, entry.name);
-            }
-            return Ok((entry.name.clone(), false));
+fn main() -> ExitCode {
+    let mut command = Cli::command();
+    command.build();
+    let args = commands::add::expand_removals(&command, std::env::args_os().collect());
+    let parsed = Cli::try_parse_from(args).and_then(|cli| {
+        // サブコマンドを省略できるのは --schema のときだけ
+        if cli.command.is_none() && cli.schema.is_none() {
+            return Err(Cli::command().error(
+                ErrorKind::MissingSubcommand,
+                mdtagger::tr!("A subcommand is required", "サブコマンドを指定してください"),
+            ));
         }
-    }
-    // B. あいまい検索
-    let suggestions: Vec<(usize, usize)> = config
-        .tags
-        .iter()
-        .enumerate()
-        .map(|(i, t)| (i, levenshtein(&t.name, input)))
-        .filter(|(_, dist)| *dist <= 3)
-        .collect();
-
-    if !suggestions.is_empty() {
-        println!("Tag '{}' is unknown.", input);
-        let mut selections = Vec::new();
-        for (idx, _dist) in &suggestions {
-            let tag_name = &config.tags[*idx].name;
-            selections.push(format!("Use existing '{}' (Typo correction)", tag_name));
+        Ok(cli)
+    });
+    let cli = match parsed {
+        Ok(cli) => cli,
+        Err(err) => {
+            let _ = err.print();
+            // --help と --version は成功扱い
+            return if err.use_stderr() {
+                Exit::Usage
+            } else {
+                Exit::Success
+            }
+            .into();
         }
-        let best_match_idx = suggestions[0].0;
-        let best_match_name = config.tags[best_match_idx].name.clone();
-        selections.push(format!(
-            "Register '{}' as alias for '{}'",
-            input, best_match_name
-        ));
-        selections.push(format!("Create new tag '{}'", input));
-
-        let selection = Select::new()
-            .with_prompt("How to handle this?")
-            .items(&selections)
-            .default(0)
-            .interact()?;
-
-        if selection < suggestions.len() {
-            let target_idx = suggestions[selection].0;
-            return Ok((config.tags[target_idx].name.clone(), false));
-        } else if selection == suggestions.len() {
-            config.tags[best_match_idx].aliases.push(input.to_string());
-            return Ok((best_match_name.clone(), true));
+    };
+    let json = cli.global.output == commands::OutputFormat::Json;
+    match run(cli) {
+        Ok(exit) => exit.into(),
+        Err(err) => {
+            let code = ErrorCode::from_error(&err);
+            if json {
+                let report = ErrorReport::new(&err);
+                println!("{}", serde_json::to_string(&report).unwrap_or_default());
+            } else {
+                eprintln!(
+                    "{}",
+                    mdtagger::tr!("Error [{}]: {:?}", "エラー [{}]: {:?}", code.as_str(), err)
+                );
+            }
+            code.exit().into()
         }
     }
-
-    // C. 新規登録
-    let confirm = Confirm::new()
-        .with_prompt(format!("Register new tag '{}' to database?", input))
-        .default(true)
-        .interact()?;
-
-    if confirm {
-        config.tags.push(TagEntry {
-            name: input.to_string(),
-            aliases: Vec::new(),
-        });
-        Ok((input.to_string(), true))
-    } else {
-        Ok((input.to_string(), false))
-    }
 }
 
-// --- I/O周りの修正: PathBufを受け取るように変更 ---
-
-fn load_config(path: &Path) -> Result<TagConfig> {
-    if !path.exists() {
-        return Ok(TagConfig::default());
+fn run(mut cli: Cli) -> Result<Exit> {
+    if let Some(kind) = cli.schema {
+        println!("{}", serde_json::to_string_pretty(&kind.schema())?);
+        return Ok(Exit::Success);
     }
-    let content =
-        fs::read_to_string(path).with_context(|| format!("Failed to read DB file: {:?}", path))?;
-    let config = serde_json::from_str(&content).unwrap_or_default();
-    Ok(config)
-}
-
-fn save_config(path: &Path, config: &TagConfig) -> Result<()> {
-    // 親ディレクトリが存在しない場合は作成する（親切設計）
-    if let Some(parent) = path.parent()
-        && !parent.exists()
+    // main でサブコマンドがなければエラーにしている
+    let Some(command) = &cli.command else {
+        return Ok(Exit::Usage);
+    };
+    if cli.global.output == commands::OutputFormat::ScriptFilter
+        && !matches!(command, Command::Find(_))
     {
-        fs::create_dir_all(parent)?;
+        bail!(mdtagger::tr!(
+            "--output script-filter works only with find",
+            "--output script-filter は find でだけ使えます"
+        ));
     }
-
-    let content = serde_json::to_string_pretty(config)?;
-    fs::write(path, content).with_context(|| format!("Failed to write DB file: {:?}", path))?;
-    Ok(())
-}
-
-// --- Markdown更新 (変更なし) ---
-fn update_markdown(path: &PathBuf, new_tags: &[String]) -> Result<()> {
-    // 省略 (前回のコードと同じ)
-    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
-
-    let re = Regex::new(r"(?s)^---\n(.*?)\n---\n(.*)").unwrap();
-
-    let (mut yaml_val, body) = if let Some(caps) = re.captures(&content) {
-        let yaml_str = caps.get(1).unwrap().as_str();
-        let body_str = caps.get(2).unwrap().as_str();
-        let val: Value =
-            serde_yaml::from_str(yaml_str).unwrap_or(Value::Mapping(serde_yaml::Mapping::new()));
-        (val, body_str.to_string())
-    } else {
-        (Value::Mapping(serde_yaml::Mapping::new()), content)
-    };
-
-    let mapping = yaml_val.as_mapping_mut().context("Invalid Front Matter")?;
-    let tags_key = Value::String("tags".to_string());
-
-    if !mapping.contains_key(&tags_key) {
-        mapping.insert(tags_key.clone(), Value::Sequence(Vec::new()));
+    if cli.global.output.is_table()
+        && !matches!(
+            command,
+            Command::Stats(_) | Command::Lint(_) | Command::Find(_) | Command::Similar(_)
+        )
+    {
+        bail!(mdtagger::tr!(
+            "--output table, csv and markdown work only with stats, lint, find and similar",
+            "--output table・csv・markdown は stats・lint・find・similar でだけ使えます"
+        ));
     }
-
-    let tags_val = mapping.get_mut(&tags_key).unwrap();
-
-    if tags_val.is_string() {
-        let s = tags_val.as_str().unwrap().to_string();
-        *tags_val = Value::Sequence(vec![Value::String(s)]);
+    cli.global.settings = settings::Settings::load()?;
+    if let Some(taxonomy) = &cli.global.taxonomy {
+        cli.global.settings.select_taxonomy(taxonomy)?;
+    }
+    mdtagger::i18n::set_lang(cli.global.settings.lang());
+    mdtagger::prompt::set_appearance(&cli.global.settings.appearance);
+    hooks::init(&cli.global.settings.hooks);
+    if let Some(timeout) = cli.global.prompt_timeout {
+        mdtagger::prompt::set_timeout(timeout);
+    } else if let Some(timeout) = cli.global.settings.prompt_timeout()? {
+        mdtagger::prompt::set_timeout(timeout);
+    }
+    cli.global.interactive = terminal::is_interactive();
+    let appearance = &cli.global.settings.appearance;
+    terminal::init(cli.global.interactive && appearance.colors());
+    let global = &cli.global;
+    let stdout_is_output = global.output == commands::OutputFormat::Json
+        || global.output.is_table()
+        || matches!(command, Command::Add(args) if args.prints_document())
+        || matches!(command, Command::Export(args) if args.prints_index())
+        || matches!(
+            command,
+            Command::Duplicates(_)
+                | Command::Find(_)
+                | Command::Similar(_)
+                | Command::Serve(_)
+                | Command::Mcp(_)
+        );
+    #[cfg(feature = "lsp")]
+    let stdout_is_output = stdout_is_output || matches!(command, Command::Lsp(_));
+    logging::init(
+        global.quiet,
+        global.verbose,
+        stdout_is_output,
+        !global.interactive || !global.settings.appearance.emoji(),
+    );
+    if let Some(path) = &global.settings.ignored_hooks {
+        log::warn!(
+            "{}",
+            mdtagger::tr!(
+                "⚠️  Ignoring [hooks] in {:?}: add its directory to trusted_projects in the user config to run them",
+                "⚠️  {:?} の [hooks] は使いません。使うには、ユーザー設定の trusted_projects にそのディレクトリを書いてください",
+                path
+            )
+        );
+    }
+    if let Some(site) = &global.settings.zola_site {
+        site.check_key(&global.settings.front_matter().tag_key);
+    } else if let Some(site) = &global.settings.hugo_site {
+        site.check_key(&global.settings.front_matter().tag_key);
+    }
+    if !matches!(command, Command::GenDocs(_)) {
+        journal::recover(global)?;
     }
 
-    if let Some(seq) = tags_val.as_sequence_mut() {
-        let mut current_strings: Vec<String> = seq
-            .iter()
-            .filter_map(|v| v.as_str().map(|s| s.to_string()))
-            .collect();
-
-        for tag in new_tags {
-            current_strings.push(tag.clone());
+    match command {
+        Command::Add(args) => commands::add::run(global, args),
+        Command::Duplicates(args) => commands::duplicates::run(global, args),
+        Command::Export(args) => commands::export::run(global, args),
+        Command::Find(args) => commands::find::run(global, args),
+        Command::GenDocs(args) => {
+            let mut command = Cli::command();
+            command.build();
+            commands::gen_docs::run(args, &command)
         }
-        current_strings.sort();
-        current_strings.dedup();
-        *seq = current_strings.into_iter().map(Value::String).collect();
+        Command::Lint(args) => commands::lint::run(global, args),
+        Command::Hook(args) => commands::hook::run(global, args),
+        Command::Import(args) => commands::import::run(global, args),
+        Command::Orphans(args) => commands::orphans::run(global, args),
+        Command::Pick(args) => commands::pick::run(global, args),
+        Command::Reindex(args) => commands::reindex::run(global, args),
+        Command::Rename(args) => commands::rename::run(global, args, false),
+        Command::Merge(args) => commands::rename::run(global, args, true),
+        Command::Similar(args) => commands::similar::run(global, args),
+        Command::Stats(args) => commands::stats::run(global, args),
+        Command::Suggest(args) => commands::suggest::run(global, args),
+        #[cfg(feature = "tui")]
+        Command::Tui(args) => commands::tui::run(global, args),
+        Command::History(args) => commands::history::run(global, args),
+        Command::Undo(args) => commands::undo::run(global, args),
+        Command::Serve(args) => commands::serve::run(global, args),
+        Command::Mcp(args) => commands::mcp::run(global, args),
+        #[cfg(feature = "lsp")]
+        Command::Lsp(args) => commands::lsp::run(global, args),
     }
-
-    let new_yaml = serde_yaml::to_string(&yaml_val)?;
-    let new_content = format!("---\n{}---\n{}", new_yaml, body);
-    fs::write(path, new_content)?;
-
-    Ok(())
 }
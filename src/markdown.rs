@@ -0,0 +1,1220 @@
+// --- Markdown ファイルの読み書き ---
+
+use crate::error::{IoContext, MdtaggerError, Result};
+use crate::tr;
+use serde::de::Error as _;
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+use std::fs::{self, File};
+use std::hash::{DefaultHasher, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// ファイルならそのまま、ディレクトリなら配下の .md ファイルを再帰的に集める
+pub fn collect_markdown_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let message = || {
+            tr!(
+                "Failed to read directory {:?}",
+                "ディレクトリ {:?} を読み込めません",
+                dir
+            )
+        };
+        let entries = fs::read_dir(&dir).io_context(message)?;
+        for entry in entries {
+            let entry_path = entry.io_context(message)?.path();
+            // .git や .obsidian などの隠しディレクトリは対象外
+            let hidden = entry_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'));
+            if hidden {
+                continue;
+            }
+            if entry_path.is_dir() {
+                dirs.push(entry_path);
+            } else if entry_path.extension().is_some_and(|ext| ext == "md") {
+                files.push(entry_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Front Matter のタグの書き方
+#[derive(Debug, Clone)]
+pub struct FrontMatterOptions {
+    /// タグを入れるキー (デフォルト: tags)。taxonomies.tags のように . で区切ると入れ子のキーになる
+    pub tag_key: String,
+    /// タグを並べ替えるか (false なら既存の順序のまま末尾に追加する)
+    pub sort: bool,
+    /// Front Matter のないファイルに作るときの形式 (あるファイルは元の形式のまま書き戻す)
+    pub format: FrontMatterFormat,
+    /// 本文のフィールド (tags:: a, b) のタグも読み書きする (None なら Front Matter だけ)
+    ///
+    /// 本文にフィールドがあればそこを、なければ Front Matter のタグのキーを使う。
+    /// どちらもなければ本文の先頭にフィールドを作る (Front Matter は作らない)。
+    pub inline_field: Option<InlineFieldStyle>,
+}
+
+impl Default for FrontMatterOptions {
+    fn default() -> Self {
+        Self {
+            tag_key: "tags".to_string(),
+            sort: true,
+            format: FrontMatterFormat::Yaml,
+            inline_field: None,
+        }
+    }
+}
+
+/// Front Matter の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrontMatterFormat {
+    /// `---` で囲んだ YAML
+    #[default]
+    Yaml,
+    /// `+++` で囲んだ TOML (Zola や Hugo。読み書きには toml フィーチャーが必要)
+    Toml,
+}
+
+/// 本文に書くタグのフィールドの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InlineFieldStyle {
+    /// Obsidian の Dataview のインラインフィールド (本文のどこかの tags:: a, b の行)
+    Dataview,
+    /// Logseq のページのプロパティ (ページの先頭に並んだ tags:: a, b の行)
+    Logseq,
+}
+
+impl FrontMatterFormat {
+    /// Front Matter を囲む行
+    pub fn fence(self) -> &'static str {
+        match self {
+            FrontMatterFormat::Yaml => "---",
+            FrontMatterFormat::Toml => "+++",
+        }
+    }
+}
+
+// --- Front Matter ---
+/// Front Matter のあとの本文
+pub type Body<'a> = &'a str;
+
+/// Markdown の先頭の Front Matter (YAML のマッピング)
+///
+/// 読み込んだときの YAML の文字列を覚えておき、値を変えていなければ
+/// `to_string()` でそのまま (コメントや書式も含めて) 書き戻す。
+/// 値を変えた場合だけ YAML を書き直す。
+///
+/// TOML の Front Matter も YAML のマッピングとして読めるようにする (日付は文字列になる)。
+/// 書き戻すときは元の TOML の値を使い、変えたキーだけを置き換える。
+#[derive(Debug, Clone, Default)]
+pub struct FrontMatter {
+    mapping: Mapping,
+    /// 読み込んだときの YAML か TOML (Front Matter がなければ None)
+    raw: Option<String>,
+    modified: bool,
+    format: FrontMatterFormat,
+    /// 改行が CRLF か (書き戻すときも同じ改行にする)
+    crlf: bool,
+    /// TOML の Front Matter の値
+    #[cfg(feature = "toml")]
+    table: toml::Table,
+}
+
+impl FrontMatter {
+    /// Front Matter と本文に分ける (Front Matter がなければ空のマッピングと内容全体)
+    ///
+    /// Front Matter が YAML のマッピングとして読めない場合はエラーにする。
+    /// 空のマッピングとして扱うと、書き戻したときに既存のメタデータが消えてしまうため。
+    /// TOML の Front Matter も、読めなければ (toml フィーチャーがなければ) エラーにする。
+    pub fn parse(content: &str) -> Result<(FrontMatter, Body<'_>), serde_yaml::Error> {
+        let crlf = line_ending(content) == "\r\n";
+        let (mut front_matter, body) = if let Some((yaml, body)) = raw_front_matter(content) {
+            (FrontMatter::from_raw(yaml.to_string())?, body)
+        } else if let Some((toml, body)) = raw_toml_front_matter(content) {
+            (FrontMatter::from_toml(toml.to_string())?, body)
+        } else {
+            (FrontMatter::default(), content)
+        };
+        front_matter.crlf = crlf;
+        Ok((front_matter, body))
+    }
+
+    /// `---` の間の YAML の文字列から作る
+    fn from_raw(yaml: String) -> Result<FrontMatter, serde_yaml::Error> {
+        let mapping: Option<Mapping> = serde_yaml::from_str(&yaml)?;
+        Ok(FrontMatter {
+            mapping: mapping.unwrap_or_default(),
+            raw: Some(yaml),
+            ..Default::default()
+        })
+    }
+
+    /// `+++` の間の TOML の文字列から作る
+    #[cfg(feature = "toml")]
+    fn from_toml(raw: String) -> Result<FrontMatter, serde_yaml::Error> {
+        let table: toml::Table = toml::from_str(&raw).map_err(serde_yaml::Error::custom)?;
+        Ok(FrontMatter {
+            mapping: toml_table_to_mapping(&table),
+            raw: Some(raw),
+            modified: false,
+            format: FrontMatterFormat::Toml,
+            crlf: false,
+            table,
+        })
+    }
+
+    #[cfg(not(feature = "toml"))]
+    fn from_toml(_raw: String) -> Result<FrontMatter, serde_yaml::Error> {
+        Err(serde_yaml::Error::custom(tr!(
+            "TOML front matter (+++) needs the toml feature",
+            "TOML の Front Matter (+++) を読むには toml フィーチャーが必要です"
+        )))
+    }
+
+    /// 元の内容に Front Matter があったか
+    pub fn is_present(&self) -> bool {
+        self.raw.is_some()
+    }
+
+    /// Front Matter の形式 (元の内容に Front Matter がなければ、書き戻すときの形式)
+    pub fn format(&self) -> FrontMatterFormat {
+        self.format
+    }
+
+    /// 値を変えたか (変えていなければ to_string() は元の文字列のまま)
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    pub fn mapping(&self) -> &Mapping {
+        &self.mapping
+    }
+
+    /// 値を読む (a.b のように . で区切ったキーは入れ子のマッピングをたどる)
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        lookup(&self.mapping, key)
+    }
+
+    /// 値を設定する (元と同じ値なら変更とはみなさない)
+    pub fn insert(&mut self, key: &str, value: Value) {
+        if self.get(key) != Some(&value) {
+            #[cfg(feature = "toml")]
+            if self.format == FrontMatterFormat::Toml {
+                // TOML にできない値 (null など) は入れない
+                match toml::Value::try_from(&value) {
+                    Ok(toml) => insert_toml(&mut self.table, key, toml),
+                    Err(_) => return,
+                }
+            }
+            insert_path(&mut self.mapping, key, value);
+            self.modified = true;
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let removed = remove_path(&mut self.mapping, key);
+        #[cfg(feature = "toml")]
+        if removed.is_some() && self.format == FrontMatterFormat::Toml {
+            remove_toml(&mut self.table, key);
+        }
+        self.modified |= removed.is_some();
+        removed
+    }
+
+    /// 文字列のリストとして読む (tags: rust のような1つの文字列も1要素のリストにする)
+    pub fn list(&self, key: &str) -> Vec<String> {
+        match self.get(key) {
+            Some(Value::String(s)) => vec![s.clone()],
+            Some(Value::Sequence(seq)) => seq
+                .iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// 文字列のリストを設定する
+    pub fn set_list(&mut self, key: &str, values: Vec<String>) {
+        self.insert(
+            key,
+            Value::Sequence(values.into_iter().map(Value::String).collect()),
+        );
+    }
+
+    /// Front Matter と本文をつなげて Markdown の内容に戻す
+    pub fn with_body(&self, body: &str) -> String {
+        format!("{}{}", self, body)
+    }
+}
+
+/// `---` (TOML なら `+++`) で囲んだ Front Matter (元の内容に Front Matter がなく、変更もなければ空文字列)
+impl fmt::Display for FrontMatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fence = self.format.fence();
+        let eol = if self.crlf { "\r\n" } else { "\n" };
+        match (&self.raw, self.modified) {
+            (Some(raw), false) => write!(f, "{fence}{eol}{raw}{eol}{fence}{eol}"),
+            (None, false) => Ok(()),
+            (_, true) => {
+                #[cfg(feature = "toml")]
+                if self.format == FrontMatterFormat::Toml {
+                    let toml = toml::to_string(&self.table).map_err(|_| fmt::Error)?;
+                    return write!(f, "+++{eol}{}+++{eol}", toml.replace('\n', eol));
+                }
+                let yaml = serde_yaml::to_string(&self.mapping).map_err(|_| fmt::Error)?;
+                write!(f, "---{eol}{}---{eol}", yaml.replace('\n', eol))
+            }
+        }
+    }
+}
+
+// --- 入れ子のキー ---
+// taxonomies.tags のように . で区切ったキーは入れ子のマッピングをたどる。
+// ただし . を含むキーがそのまま書かれていれば、そちらを使う。
+
+fn lookup<'a>(mapping: &'a Mapping, key: &str) -> Option<&'a Value> {
+    if let Some(value) = mapping.get(key) {
+        return Some(value);
+    }
+    let (head, rest) = key.split_once('.')?;
+    lookup(mapping.get(head)?.as_mapping()?, rest)
+}
+
+fn insert_path(mapping: &mut Mapping, key: &str, value: Value) {
+    if !mapping.contains_key(key)
+        && let Some((head, rest)) = key.split_once('.')
+        && matches!(mapping.get(head), None | Some(Value::Mapping(_)))
+    {
+        let inner = mapping
+            .entry(Value::String(head.to_string()))
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+        if let Value::Mapping(inner) = inner {
+            insert_path(inner, rest, value);
+        }
+        return;
+    }
+    mapping.insert(Value::String(key.to_string()), value);
+}
+
+/// 取り除いて空になった入れ子のマッピングも取り除く
+fn remove_path(mapping: &mut Mapping, key: &str) -> Option<Value> {
+    if let Some(value) = mapping.remove(key) {
+        return Some(value);
+    }
+    let (head, rest) = key.split_once('.')?;
+    let inner = mapping.get_mut(head)?.as_mapping_mut()?;
+    let removed = remove_path(inner, rest)?;
+    if inner.is_empty() {
+        mapping.remove(head);
+    }
+    Some(removed)
+}
+
+#[cfg(feature = "toml")]
+fn insert_toml(table: &mut toml::Table, key: &str, value: toml::Value) {
+    if !table.contains_key(key)
+        && let Some((head, rest)) = key.split_once('.')
+        && matches!(table.get(head), None | Some(toml::Value::Table(_)))
+    {
+        let inner = table
+            .entry(head)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if let toml::Value::Table(inner) = inner {
+            insert_toml(inner, rest, value);
+        }
+        return;
+    }
+    table.insert(key.to_string(), value);
+}
+
+#[cfg(feature = "toml")]
+fn remove_toml(table: &mut toml::Table, key: &str) {
+    if table.remove(key).is_some() {
+        return;
+    }
+    if let Some((head, rest)) = key.split_once('.')
+        && let Some(toml::Value::Table(inner)) = table.get_mut(head)
+    {
+        remove_toml(inner, rest);
+        if inner.is_empty() {
+            table.remove(head);
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+fn toml_table_to_mapping(table: &toml::Table) -> Mapping {
+    table
+        .iter()
+        .map(|(key, value)| (Value::String(key.clone()), toml_to_yaml(value)))
+        .collect()
+}
+
+#[cfg(feature = "toml")]
+fn toml_to_yaml(value: &toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s.clone()),
+        toml::Value::Integer(i) => Value::Number((*i).into()),
+        toml::Value::Float(f) => Value::Number((*f).into()),
+        toml::Value::Boolean(b) => Value::Bool(*b),
+        // 日付は文字列として見せる (書き戻すときは元の TOML の値を使う)
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Sequence(items.iter().map(toml_to_yaml).collect()),
+        toml::Value::Table(table) => Value::Mapping(toml_table_to_mapping(table)),
+    }
+}
+
+// --- Front Matter の読み取り ---
+/// Front Matter と本文に分ける (Front Matter がなければ空のマッピング)
+///
+/// Front Matter が YAML のマッピングとして読めない場合はエラーにする。
+pub fn split_front_matter(content: &str) -> Result<(Value, String), serde_yaml::Error> {
+    let (front_matter, body) = FrontMatter::parse(content)?;
+    Ok((Value::Mapping(front_matter.mapping), body.to_string()))
+}
+
+/// Front Matter の YAML を解析せずに、YAML の文字列と本文に分ける (Front Matter がなければ None)
+///
+/// 改行が CRLF なら区切りの行も CRLF で探す (YAML の文字列の中の改行は CRLF のまま)。
+pub fn raw_front_matter(content: &str) -> Option<(&str, &str)> {
+    split_fenced(content, "---")
+}
+
+/// raw_front_matter の TOML (`+++` で囲んだもの) 版
+pub fn raw_toml_front_matter(content: &str) -> Option<(&str, &str)> {
+    split_fenced(content, "+++")
+}
+
+fn split_fenced<'a>(content: &'a str, fence: &str) -> Option<(&'a str, &'a str)> {
+    let eol = line_ending(content);
+    let rest = content.strip_prefix(fence)?.strip_prefix(eol)?;
+    let closing = format!("{eol}{fence}{eol}");
+    let end = rest.find(&closing)?;
+    Some((&rest[..end], &rest[end + closing.len()..]))
+}
+
+/// 1行目の改行 ("\r\n" か "\n")
+pub fn line_ending(content: &str) -> &'static str {
+    match content.split_once('\n') {
+        Some((first, _)) if first.ends_with('\r') => "\r\n",
+        _ => "\n",
+    }
+}
+
+// --- ファイルの先頭と本文 ---
+// 本文はバイト列のまま扱い、文字列として読んだり書き直したりしない。
+// タグを書き換えるときはファイルの先頭の Front Matter の部分 (区切りの行まで) だけを文字列として読み、
+// 書き込むときは新しい先頭のあとに、元のファイルの本文をバイト単位でそのままつなぐ。
+// データを埋め込んだ数MBのノートでも本文をメモリに載せず、UTF-8 でない本文も壊さない。
+// 本文のフィールド (options.inline_field) はタグが本文にあるので、そのときだけファイル全体を先頭として読む。
+
+/// ファイルの先頭の Front Matter の部分 (形式、改行、区切りの行を除いた中身、区切りの行までの長さ)
+///
+/// Front Matter の見つけ方は FrontMatter::parse と同じ。本文は読まない。
+fn scan_front_matter(
+    path: &Path,
+) -> Result<Option<(FrontMatterFormat, &'static str, String, u64)>> {
+    let read_error = || tr!("Failed to read {:?}", "{:?} を読み込めません", path);
+    let file = File::open(path).io_context(read_error)?;
+    let mut reader = BufReader::new(file);
+
+    // 1行目が長くても読み切らないように、最初の4バイトだけで判定する
+    let mut opening = Vec::with_capacity(4);
+    (&mut reader)
+        .take(4)
+        .read_to_end(&mut opening)
+        .io_context(read_error)?;
+    let format = match &opening[..opening.len().min(3)] {
+        b"---" => FrontMatterFormat::Yaml,
+        b"+++" => FrontMatterFormat::Toml,
+        _ => return Ok(None),
+    };
+    let eol = match opening.get(3) {
+        Some(b'\n') => "\n",
+        Some(b'\r') => {
+            let mut lf = [0];
+            if reader.read(&mut lf).io_context(read_error)? == 0 || lf != *b"\n" {
+                return Ok(None);
+            }
+            opening.push(b'\n');
+            "\r\n"
+        }
+        _ => return Ok(None),
+    };
+    let closing = format!("{}{}", format.fence(), eol);
+
+    let mut raw = String::new();
+    let mut offset = opening.len() as u64;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line).io_context(read_error)?;
+        if read == 0 {
+            // 閉じていなければ Front Matter ではない
+            return Ok(None);
+        }
+        offset += read as u64;
+        if line == closing && raw.ends_with(eol) {
+            break;
+        }
+        raw.push_str(&line);
+    }
+    raw.truncate(raw.len() - eol.len());
+    Ok(Some((format, eol, raw, offset)))
+}
+
+/// ファイルの先頭の Front Matter だけを読む (本文は読まない)
+///
+/// 返り値の u64 は本文が始まる位置 (バイト数、Front Matter がなければ 0)。
+pub fn read_front_matter(path: &Path) -> Result<(FrontMatter, u64)> {
+    let Some((format, eol, raw, offset)) = scan_front_matter(path)? else {
+        return Ok((FrontMatter::default(), 0));
+    };
+    let mut front_matter = match format {
+        FrontMatterFormat::Yaml => FrontMatter::from_raw(raw),
+        FrontMatterFormat::Toml => FrontMatter::from_toml(raw),
+    }
+    .map_err(|err| MdtaggerError::from(err).in_file(path))?;
+    front_matter.crlf = eol == "\r\n";
+    Ok((front_matter, offset))
+}
+
+/// タグを書き換えるために、ファイルの先頭を文字列として読む (本文は読まない)
+///
+/// Front Matter があれば区切りの行までで、なければ空文字列。options.inline_field があればファイル全体。
+/// add_tags などの内容を受け取る関数にそのまま渡し、書き換えた結果は replace_head で書き込む。
+pub fn read_head(path: &Path, options: &FrontMatterOptions) -> Result<String> {
+    if options.inline_field.is_some() {
+        return fs::read_to_string(path)
+            .io_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path));
+    }
+    Ok(match scan_front_matter(path)? {
+        Some((format, eol, raw, _)) => {
+            let fence = format.fence();
+            format!("{fence}{eol}{raw}{eol}{fence}{eol}")
+        }
+        None => String::new(),
+    })
+}
+
+/// ファイルが head で始まっているか (読めなければ false。本文は読まない)
+pub fn starts_with(path: &Path, head: &str) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    let mut current = Vec::with_capacity(head.len());
+    file.take(head.len() as u64)
+        .read_to_end(&mut current)
+        .is_ok_and(|_| current == head.as_bytes())
+}
+
+/// ファイルの先頭の before を after に置き換える (before より後ろの本文は読み込まずにバイト列のままつなぐ)
+///
+/// before は read_head で読んだ先頭 (かファイル全体) で、読んだあとにファイルの先頭が変わっていればエラーにする。
+/// 一時ファイルに書いてから置き換えるので、途中で失敗しても元のファイルは壊れない。
+pub fn replace_head(path: &Path, before: &str, after: &str) -> Result<()> {
+    splice(
+        path,
+        after.as_bytes(),
+        before.len() as u64,
+        Some(before.as_bytes()),
+    )
+}
+
+/// Front Matter を書き換え、body_offset から後ろの本文は読み込まずにそのままつなぐ
+///
+/// 一時ファイルに書いてから置き換えるので、途中で失敗しても元のファイルは壊れない。
+pub fn write_front_matter(path: &Path, front_matter: &FrontMatter, body_offset: u64) -> Result<()> {
+    splice(path, front_matter.to_string().as_bytes(), body_offset, None)
+}
+
+/// 書き換えるときに使う一時ファイル (書き換えの途中で止まると残る)
+pub fn temp_file(path: &Path) -> PathBuf {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let file_name = target.file_name().unwrap_or_default().to_string_lossy();
+    target.with_file_name(format!(".{}.mdtagger-tmp", file_name))
+}
+
+/// head のあとに、元のファイルの body_offset から後ろをそのままつないで書き込む
+/// (expected があれば、元のファイルの body_offset までがそれと同じかを先に確かめる)
+fn splice(path: &Path, head: &[u8], body_offset: u64, expected: Option<&[u8]>) -> Result<()> {
+    let write_error = || tr!("Failed to write {:?}", "{:?} に書き込めません", path);
+    // シンボリックリンクはリンク先を書き換える
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut source = File::open(&target)
+        .io_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+    let permissions = source.metadata().io_context(write_error)?.permissions();
+    if let Some(expected) = expected {
+        let mut current = Vec::with_capacity(expected.len());
+        (&mut source)
+            .take(body_offset)
+            .read_to_end(&mut current)
+            .io_context(write_error)?;
+        if current != expected {
+            return Err(io::Error::other(tr!(
+                "the file changed after it was read",
+                "読み込んだあとにファイルが変わりました"
+            )))
+            .io_context(write_error);
+        }
+    }
+
+    let temp = temp_file(&target);
+    let written = (|| -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(&temp)?);
+        out.write_all(head)?;
+        source.seek(SeekFrom::Start(body_offset))?;
+        io::copy(&mut source, &mut out)?;
+        out.into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+        fs::set_permissions(&temp, permissions)?;
+        fs::rename(&temp, &target)
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written.io_context(write_error)
+}
+
+/// ファイルのタグを edit で書き換える (本文は読まない)。内容が変わらなければ書き込まずに false を返す
+pub fn edit_file_tags(
+    path: &Path,
+    options: &FrontMatterOptions,
+    edit: impl FnOnce(&mut Vec<String>),
+) -> Result<bool> {
+    // インラインフィールドは本文にあるので、ファイル全体を読み書きする
+    if options.inline_field.is_some() {
+        let content = fs::read_to_string(path)
+            .io_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+        let new_content = edit_tags(&content, options, edit).map_err(|err| err.in_file(path))?;
+        if new_content == content {
+            return Ok(false);
+        }
+        replace_head(path, &content, &new_content)?;
+        return Ok(true);
+    }
+    let (mut front_matter, body_offset) = read_front_matter(path)?;
+    edit_front_matter_tags(&mut front_matter, options, edit);
+    if !front_matter.is_modified() {
+        return Ok(false);
+    }
+    write_front_matter(path, &front_matter, body_offset)?;
+    Ok(true)
+}
+
+// --- 書き換えの検証 ---
+/// before を after に書き換えたとき、本文がバイト単位で同じで、
+/// Front Matter はタグのキーしか変わっていないかを確かめる (問題があれば理由を返す)
+pub fn verify_rewrite(
+    before: &str,
+    after: &str,
+    options: &FrontMatterOptions,
+) -> std::result::Result<(), String> {
+    let (old, old_body) = FrontMatter::parse(before).map_err(|err| {
+        tr!(
+            "cannot parse the original: {}",
+            "元の内容を解析できません: {}",
+            err
+        )
+    })?;
+    let (new, new_body) = FrontMatter::parse(after).map_err(|err| {
+        tr!(
+            "cannot parse the result: {}",
+            "書き換えた内容を解析できません: {}",
+            err
+        )
+    })?;
+    // 本文のフィールドを書き換えたなら、フィールドの行 (と前後の空行) を除いて比べる
+    let same_body = if let Some(style) = options.inline_field {
+        let strip = |body: &str| {
+            let body = match find_inline_field(body, &options.tag_key, style) {
+                Some(field) => {
+                    let end = (field.range.end + 1).min(body.len());
+                    format!("{}{}", &body[..field.range.start], &body[end..])
+                }
+                None => body.to_string(),
+            };
+            body.trim_matches('\n').to_string()
+        };
+        strip(old_body) == strip(new_body)
+    } else {
+        old_body == new_body
+    };
+    if !same_body {
+        return Err(tr!("the body changed", "本文が変わりました"));
+    }
+    if old.is_present() && old.format() != new.format() {
+        return Err(tr!(
+            "the front matter format changed",
+            "Front Matter の形式が変わりました"
+        ));
+    }
+    // タグのキー (入れ子でも) を除いて比べる
+    let without_tags = |front_matter: &FrontMatter| {
+        let mut front_matter = front_matter.clone();
+        front_matter.remove(&options.tag_key);
+        front_matter.mapping
+    };
+    let (old, new) = (without_tags(&old), without_tags(&new));
+    let changed = old
+        .keys()
+        .chain(new.keys())
+        .find(|k| old.get(*k) != new.get(*k));
+    if let Some(changed) = changed {
+        let name = changed
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{:?}", changed));
+        return Err(tr!(
+            "front matter key '{}' changed",
+            "Front Matter の '{}' が変わりました",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// ファイルの先頭の before を after に置き換え (replace_head)、読み直して verify_rewrite で確かめる。
+/// 問題があれば before に戻してエラーを返す
+///
+/// 本文はメモリに読み込まないので、書き込む前と後の本文の長さとハッシュを比べて、そのままつながったかを確かめる。
+pub fn write_verified(
+    path: &Path,
+    before: &str,
+    after: &str,
+    options: &FrontMatterOptions,
+) -> Result<()> {
+    let body = File::open(path)
+        .and_then(|mut file| {
+            file.seek(SeekFrom::Start(before.len() as u64))?;
+            digest(file)
+        })
+        .io_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+    replace_head(path, before, after)?;
+    let written = File::open(path).and_then(|file| {
+        let mut reader = BufReader::new(file);
+        let mut written = Vec::with_capacity(after.len());
+        (&mut reader)
+            .take(after.len() as u64)
+            .read_to_end(&mut written)?;
+        let written_body = digest(reader)?;
+        let written = String::from_utf8(written).map_err(io::Error::other)?;
+        Ok((written, written_body))
+    });
+    let (reason, written) = match written {
+        Ok((written, written_body)) => match verify_rewrite(before, &written, options) {
+            Ok(()) if written_body == body => return Ok(()),
+            Ok(()) => (
+                tr!(
+                    "the body changed while rewriting the front matter",
+                    "Front Matter を書き換えるあいだに本文が変わりました"
+                ),
+                written,
+            ),
+            Err(reason) => (reason, written),
+        },
+        Err(err) => (
+            tr!("cannot read it back: {}", "読み直せません: {}", err),
+            after.to_string(),
+        ),
+    };
+    replace_head(path, &written, before)?;
+    Err(MdtaggerError::VerificationFailed {
+        path: path.to_path_buf(),
+        reason,
+    })
+}
+
+/// 読み込んだ内容の長さとハッシュ (本文を読み込まずに比べる)
+fn digest(mut reader: impl Read) -> io::Result<(u64, u64)> {
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = [0; 8192];
+    let mut len = 0;
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            return Ok((len, hasher.finish()));
+        }
+        hasher.write(&buffer[..n]);
+        len += n as u64;
+    }
+}
+
+/// Front Matter のタグを文字列のリストとして取り出す (FrontMatter があれば FrontMatter::list と同じ)
+pub fn front_matter_tags(yaml: &Value, options: &FrontMatterOptions) -> Vec<String> {
+    match yaml
+        .as_mapping()
+        .and_then(|mapping| lookup(mapping, &options.tag_key))
+    {
+        Some(Value::String(s)) => vec![s.clone()],
+        Some(Value::Sequence(seq)) => seq
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// --- Markdown更新 ---
+/// タグを追加して書き戻す。内容が変わらなければ書き込まずに false を返す
+pub fn update_markdown(
+    path: &Path,
+    new_tags: &[String],
+    options: &FrontMatterOptions,
+) -> Result<bool> {
+    edit_file_tags(path, options, |tags| push_missing(tags, new_tags))
+}
+
+/// Markdown の内容に付いているタグ (options.inline_field があれば、本文のフィールドを先に見る)
+pub fn content_tags(content: &str, options: &FrontMatterOptions) -> Result<Vec<String>> {
+    let (front_matter, body) = FrontMatter::parse(content)?;
+    if let Some(style) = options.inline_field
+        && let Some(field) = find_inline_field(body, &options.tag_key, style)
+    {
+        return Ok(field.values);
+    }
+    Ok(front_matter.list(&options.tag_key))
+}
+
+/// ファイルの Front Matter に付いているタグを読む (本文は読まない。options.inline_field があればファイル全体を読む)
+pub fn read_tags(path: &Path, options: &FrontMatterOptions) -> Result<Vec<String>> {
+    if options.inline_field.is_some() {
+        let content = fs::read_to_string(path)
+            .io_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+        return content_tags(&content, options).map_err(|err| err.in_file(path));
+    }
+    let (front_matter, _) = read_front_matter(path)?;
+    Ok(front_matter.list(&options.tag_key))
+}
+
+/// Markdown の内容にタグを追加した結果を返す (ファイルには書き込まない)
+pub fn add_tags(
+    content: &str,
+    new_tags: &[String],
+    options: &FrontMatterOptions,
+) -> Result<String> {
+    edit_tags(content, options, |tags| push_missing(tags, new_tags))
+}
+
+fn push_missing(tags: &mut Vec<String>, new_tags: &[String]) {
+    for tag in new_tags {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+}
+
+/// Markdown の内容からタグを外した結果を返す (外すタグがなければ内容はそのまま)
+pub fn remove_tags(content: &str, tags: &[String], options: &FrontMatterOptions) -> Result<String> {
+    if !content_tags(content, options)?
+        .iter()
+        .any(|t| tags.contains(t))
+    {
+        return Ok(content.to_string());
+    }
+    edit_tags(content, options, |current| {
+        current.retain(|t| !tags.contains(t))
+    })
+}
+
+/// Markdown の内容のタグを tags そのものに置き換えた結果を返す (ファイルには書き込まない)
+pub fn set_tags(content: &str, tags: &[String], options: &FrontMatterOptions) -> Result<String> {
+    edit_tags(content, options, |current| {
+        current.clear();
+        for tag in tags {
+            if !current.contains(tag) {
+                current.push(tag.clone());
+            }
+        }
+    })
+}
+
+/// Markdown の内容のタグ from を to に置き換えた結果を返す (ファイルには書き込まない)
+pub fn replace_tag(
+    content: &str,
+    from: &str,
+    to: &str,
+    options: &FrontMatterOptions,
+) -> Result<String> {
+    edit_tags(content, options, |tags| {
+        let mut replaced = Vec::new();
+        for tag in tags.drain(..) {
+            let tag = if tag == from { to.to_string() } else { tag };
+            if !replaced.contains(&tag) {
+                replaced.push(tag);
+            }
+        }
+        *tags = replaced;
+    })
+}
+
+/// Front Matter のタグの一覧を edit で書き換える
+///
+/// タグのキーに文字列でもリストでもない値が入っている場合は、そのままにしておく。
+fn edit_tags(
+    content: &str,
+    options: &FrontMatterOptions,
+    edit: impl FnOnce(&mut Vec<String>),
+) -> Result<String> {
+    let (mut front_matter, body) = FrontMatter::parse(content)?;
+    // Front Matter にタグのキーがあって本文にフィールドがなければ、Front Matter のほうを書き換える
+    if let Some(style) = options.inline_field
+        && (front_matter.get(&options.tag_key).is_none()
+            || find_inline_field(body, &options.tag_key, style).is_some())
+    {
+        let body = edit_inline_field(body, &options.tag_key, style, options.sort, edit);
+        return Ok(front_matter.with_body(&body));
+    }
+    edit_front_matter_tags(&mut front_matter, options, edit);
+    Ok(front_matter.with_body(body))
+}
+
+fn edit_front_matter_tags(
+    front_matter: &mut FrontMatter,
+    options: &FrontMatterOptions,
+    edit: impl FnOnce(&mut Vec<String>),
+) {
+    let key = options.tag_key.as_str();
+    // Front Matter がなければ、options の形式で作る
+    #[cfg(feature = "toml")]
+    if !front_matter.is_present() {
+        front_matter.format = options.format;
+    }
+    if matches!(
+        front_matter.get(key),
+        None | Some(Value::String(_)) | Some(Value::Sequence(_))
+    ) {
+        let mut tags = front_matter.list(key);
+        edit(&mut tags);
+        if options.sort {
+            tags.sort();
+            tags.dedup();
+        }
+        front_matter.set_list(key, tags);
+    }
+}
+
+// --- 本文のフィールド (Dataview のインラインフィールドと Logseq のページのプロパティ) ---
+/// 本文の中の `tags:: a, b` の行
+#[derive(Debug)]
+pub struct InlineField {
+    /// 本文の中での行の位置 (バイト。改行は含まない)
+    pub range: std::ops::Range<usize>,
+    /// 本文の中での行番号 (0 から)
+    pub line: usize,
+    /// カンマで区切った値 (#rust の # と、リンク [[rust]] の括弧は取り除く)
+    pub values: Vec<String>,
+    /// 値の前までの長さ (`tags:: ` のように、キーと :: と空白)
+    prefix_len: usize,
+    /// 値を #rust のように書いていたか (書き戻すときも # を付ける)
+    hashed: bool,
+    /// 値を [[rust]] のように書いていたか (書き戻すときも括弧で囲む)
+    linked: bool,
+}
+
+/// `name:: value` の行ならキーの名前と、値の前までの長さ
+fn field_line(line: &str) -> Option<(&str, usize)> {
+    let trimmed = line.trim_start();
+    let (name, rest) = trimmed.split_once("::")?;
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    let value = rest.trim_start();
+    let prefix_len = line.len() - value.len();
+    Some((name, prefix_len))
+}
+
+/// 本文で最初の key:: の行 (キーの大文字・小文字は区別しない)
+///
+/// Dataview なら本文のどこでも (コードブロックの中は飛ばす)、Logseq ならページの先頭に並んだプロパティの中だけを探す
+pub fn find_inline_field(body: &str, key: &str, style: InlineFieldStyle) -> Option<InlineField> {
+    let mut start = 0;
+    let mut fence: Option<&str> = None;
+    for (line_number, line) in body.split('\n').enumerate() {
+        let line_start = start;
+        start += line.len() + 1;
+        let line = line.trim_end_matches('\r');
+        let trimmed = line.trim_start();
+        if let Some(open) = fence {
+            if trimmed.starts_with(open) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+        let Some((name, prefix_len)) = field_line(line) else {
+            // Logseq のページのプロパティは先頭の行だけ
+            if style == InlineFieldStyle::Logseq {
+                return None;
+            }
+            continue;
+        };
+        if !name.eq_ignore_ascii_case(key) {
+            continue;
+        }
+        let value = &line[prefix_len..];
+        return Some(InlineField {
+            range: line_start..line_start + line.len(),
+            line: line_number,
+            values: value
+                .trim_end()
+                .split(',')
+                .map(|v| {
+                    v.trim()
+                        .trim_start_matches('#')
+                        .trim_start_matches("[[")
+                        .trim_end_matches("]]")
+                        .to_string()
+                })
+                .filter(|v| !v.is_empty())
+                .collect(),
+            prefix_len,
+            hashed: value.starts_with('#'),
+            linked: value.starts_with("[["),
+        });
+    }
+    None
+}
+
+/// 本文のフィールドのタグを edit で書き換えた本文 (タグが変わらなければ元のまま)
+fn edit_inline_field(
+    body: &str,
+    key: &str,
+    style: InlineFieldStyle,
+    sort: bool,
+    edit: impl FnOnce(&mut Vec<String>),
+) -> String {
+    let field = find_inline_field(body, key, style);
+    let mut tags = field.as_ref().map(|f| f.values.clone()).unwrap_or_default();
+    let before = tags.clone();
+    edit(&mut tags);
+    if sort {
+        tags.sort();
+        tags.dedup();
+    }
+    if tags == before {
+        return body.to_string();
+    }
+    let Some(field) = field else {
+        let line = format!("{}:: {}\n", key, tags.join(", "));
+        // Logseq のページにほかのプロパティがあれば、その並びの最後に足す
+        if style == InlineFieldStyle::Logseq {
+            let end: usize = body
+                .split_inclusive('\n')
+                .take_while(|line| field_line(line.trim_end_matches(['\n', '\r'])).is_some())
+                .map(str::len)
+                .sum();
+            if end > 0 {
+                let separator = if body[..end].ends_with('\n') {
+                    ""
+                } else {
+                    "\n"
+                };
+                return format!("{}{}{}{}", &body[..end], separator, line, &body[end..]);
+            }
+        }
+        // 本文の先頭に作り、本文とは空行で分ける
+        if body.is_empty() || body.starts_with('\n') {
+            return format!("{}{}", line, body);
+        }
+        return format!("{}\n{}", line, body);
+    };
+    let prefix = &body[field.range.start..field.range.start + field.prefix_len];
+    let prefix = if prefix.ends_with(' ') || tags.is_empty() {
+        prefix.to_string()
+    } else {
+        format!("{} ", prefix)
+    };
+    let values: Vec<String> = tags
+        .iter()
+        .map(|tag| match (field.hashed, field.linked) {
+            (true, _) => format!("#{}", tag),
+            (false, true) => format!("[[{}]]", tag),
+            (false, false) => tag.clone(),
+        })
+        .collect();
+    format!(
+        "{}{}{}{}",
+        &body[..field.range.start],
+        prefix,
+        values.join(", "),
+        &body[field.range.end..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    /// テスト用の一時ファイル (テストごとに別の名前にする)
+    fn temp_markdown(name: &str, content: &[u8]) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("mdtagger-test-{}-{}.md", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    const CRLF: &str = "---\r\ntitle: Hello\r\ntags:\r\n- rust\r\n---\r\nBody\r\n\r\nmore\r\n";
+
+    #[test]
+    fn crlf_front_matter_round_trips_byte_exact() {
+        let (front_matter, body) = FrontMatter::parse(CRLF).unwrap();
+        assert!(front_matter.is_present());
+        assert_eq!(body, "Body\r\n\r\nmore\r\n");
+        assert_eq!(front_matter.list("tags"), tags(&["rust"]));
+        assert_eq!(front_matter.with_body(body), CRLF);
+
+        // 付いているタグを付けても変わらない
+        let options = FrontMatterOptions::default();
+        assert_eq!(add_tags(CRLF, &tags(&["rust"]), &options).unwrap(), CRLF);
+    }
+
+    #[test]
+    fn crlf_front_matter_keeps_crlf_when_rewritten() {
+        let options = FrontMatterOptions::default();
+        let after = add_tags(CRLF, &tags(&["cli"]), &options).unwrap();
+        assert_eq!(
+            after,
+            "---\r\ntitle: Hello\r\ntags:\r\n- cli\r\n- rust\r\n---\r\nBody\r\n\r\nmore\r\n"
+        );
+        assert_eq!(after.matches('\n').count(), after.matches("\r\n").count());
+        assert_eq!(
+            content_tags(&after, &options).unwrap(),
+            tags(&["cli", "rust"])
+        );
+        assert_eq!(verify_rewrite(CRLF, &after, &options), Ok(()));
+    }
+
+    #[test]
+    fn crlf_body_without_front_matter_gets_crlf_front_matter() {
+        let options = FrontMatterOptions::default();
+        let after = add_tags("Body\r\n", &tags(&["rust"]), &options).unwrap();
+        assert_eq!(after, "---\r\ntags:\r\n- rust\r\n---\r\nBody\r\n");
+    }
+
+    #[test]
+    fn raw_front_matter_splits_lf_and_crlf() {
+        assert_eq!(
+            raw_front_matter("---\na: 1\n---\nbody"),
+            Some(("a: 1", "body"))
+        );
+        assert_eq!(
+            raw_front_matter("---\r\na: 1\r\nb: 2\r\n---\r\nbody"),
+            Some(("a: 1\r\nb: 2", "body"))
+        );
+        // 閉じていなければ Front Matter ではない
+        assert_eq!(raw_front_matter("---\na: 1\n"), None);
+        assert_eq!(raw_front_matter("--- \na: 1\n---\n"), None);
+        assert_eq!(
+            raw_toml_front_matter("+++\r\na = 1\r\n+++\r\n"),
+            Some(("a = 1", ""))
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_front_matter_round_trips_byte_exact() {
+        let content = "+++\ntitle = \"Hello\"\ndate = 2024-01-02\ntags = [\"rust\"]\n+++\nBody\n";
+        let (front_matter, body) = FrontMatter::parse(content).unwrap();
+        assert_eq!(front_matter.format(), FrontMatterFormat::Toml);
+        assert_eq!(front_matter.with_body(body), content);
+
+        let options = FrontMatterOptions::default();
+        assert_eq!(
+            add_tags(content, &tags(&["rust"]), &options).unwrap(),
+            content
+        );
+        let after = add_tags(content, &tags(&["cli"]), &options).unwrap();
+        assert!(after.starts_with("+++\n"));
+        assert!(after.ends_with("+++\nBody\n"));
+        // 日付は TOML の値のまま書き戻す
+        assert!(after.contains("date = 2024-01-02\n"));
+        assert!(after.contains("tags = [\"cli\", \"rust\"]\n"));
+        assert_eq!(verify_rewrite(content, &after, &options), Ok(()));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn crlf_toml_front_matter_keeps_crlf_when_rewritten() {
+        let content = "+++\r\ntitle = \"Hello\"\r\n+++\r\nBody\r\n";
+        let (front_matter, body) = FrontMatter::parse(content).unwrap();
+        assert_eq!(front_matter.with_body(body), content);
+
+        let options = FrontMatterOptions::default();
+        let after = add_tags(content, &tags(&["rust"]), &options).unwrap();
+        assert!(after.starts_with("+++\r\n"));
+        assert!(after.ends_with("\r\n+++\r\nBody\r\n"));
+        assert_eq!(after.matches('\n').count(), after.matches("\r\n").count());
+        assert_eq!(verify_rewrite(content, &after, &options), Ok(()));
+    }
+
+    #[test]
+    fn verify_rewrite_rejects_other_changes() {
+        let options = FrontMatterOptions::default();
+        let before = "---\ntitle: Hello\ntags: [rust]\n---\nBody\n";
+        assert_eq!(
+            verify_rewrite(
+                before,
+                "---\ntitle: Hello\ntags: [cli]\n---\nBody\n",
+                &options
+            ),
+            Ok(())
+        );
+        assert!(
+            verify_rewrite(
+                before,
+                "---\ntitle: Hello\ntags: [rust]\n---\nBody!\n",
+                &options
+            )
+            .is_err()
+        );
+        assert!(
+            verify_rewrite(
+                before,
+                "---\ntitle: Bye\ntags: [rust]\n---\nBody\n",
+                &options
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn crlf_file_is_rewritten_without_touching_the_body() {
+        // 本文は UTF-8 でなくてもそのまま残る
+        let mut content = CRLF.as_bytes().to_vec();
+        content.extend_from_slice(b"\xff\xfe\r\n");
+        let path = temp_markdown("crlf", &content);
+        let options = FrontMatterOptions::default();
+
+        let head = read_head(&path, &options).unwrap();
+        assert_eq!(head, "---\r\ntitle: Hello\r\ntags:\r\n- rust\r\n---\r\n");
+        let (front_matter, offset) = read_front_matter(&path).unwrap();
+        assert_eq!(offset, head.len() as u64);
+        assert_eq!(front_matter.to_string(), head);
+
+        let after = add_tags(&head, &tags(&["cli"]), &options).unwrap();
+        write_verified(&path, &head, &after, &options).unwrap();
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(&written[..after.len()], after.as_bytes());
+        assert_eq!(&written[after.len()..], &content[head.len()..]);
+    }
+}
@@ -0,0 +1,81 @@
+// --- MinHash (本文の似ているノートを探す) ---
+//
+// 本文を空白を詰めて小文字にし、文字の SHINGLE 個ずつの並び (シングル) の集合にする。
+// 日本語は単語を空白で区切らないので、単語ではなく文字で区切る。
+// 集合の Jaccard 係数は、NUM_HASHES 個のハッシュ関数それぞれの最小値 (署名) の一致する割合で見積もる。
+// すべての組を比べると遅いので、署名を BANDS 個の帯に分け、どれかの帯が同じ組だけを比べる (LSH)。
+
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// シングルの文字数
+const SHINGLE: usize = 5;
+/// 署名の長さ
+const NUM_HASHES: usize = 64;
+/// LSH の帯の数 (1つの帯は NUM_HASHES / BANDS 個)
+const BANDS: usize = 16;
+
+pub type Signature = [u64; NUM_HASHES];
+
+/// splitmix64 (1つのハッシュ値から、種ごとに別のハッシュ値を作る)
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// 本文の署名 (シングルが1つもない短い本文は None)
+pub fn signature(text: &str) -> Option<Signature> {
+    let chars: Vec<char> = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .chars()
+        .collect();
+    if chars.len() < SHINGLE {
+        return None;
+    }
+    let mut signature = [u64::MAX; NUM_HASHES];
+    for shingle in chars.windows(SHINGLE) {
+        let mut hasher = DefaultHasher::new();
+        shingle.hash(&mut hasher);
+        let hash = hasher.finish();
+        for (i, min) in signature.iter_mut().enumerate() {
+            *min = (*min).min(mix(hash ^ (i as u64).wrapping_mul(0xa076_1d64_78bd_642f)));
+        }
+    }
+    Some(signature)
+}
+
+/// 見積もった Jaccard 係数 (0.0 から 1.0)
+pub fn similarity(a: &Signature, b: &Signature) -> f64 {
+    let same = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    same as f64 / NUM_HASHES as f64
+}
+
+/// どれかの帯が同じ署名の組 (i < j の添字)
+pub fn candidates(signatures: &[Option<Signature>]) -> HashSet<(usize, usize)> {
+    let rows = NUM_HASHES / BANDS;
+    let mut pairs = HashSet::new();
+    for band in 0..BANDS {
+        let mut buckets: HashMap<&[u64], Vec<usize>> = HashMap::new();
+        for (i, signature) in signatures.iter().enumerate() {
+            if let Some(signature) = signature {
+                buckets
+                    .entry(&signature[band * rows..(band + 1) * rows])
+                    .or_default()
+                    .push(i);
+            }
+        }
+        for bucket in buckets.values() {
+            for (n, &i) in bucket.iter().enumerate() {
+                for &j in &bucket[n + 1..] {
+                    pairs.insert((i, j));
+                }
+            }
+        }
+    }
+    pairs
+}
@@ -0,0 +1,224 @@
+// --- 入力の正規化と比較用の変換 ---
+
+use crate::db::NormalizeConfig;
+use crate::error::{MdtaggerError, Result};
+use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+pub struct Normalizer {
+    lowercase: bool,
+    rules: Vec<(Regex, String)>,
+}
+
+impl Normalizer {
+    pub fn new(config: &NormalizeConfig) -> Result<Self> {
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| {
+                let re =
+                    Regex::new(&rule.pattern).map_err(|source| MdtaggerError::InvalidPattern {
+                        pattern: rule.pattern.clone(),
+                        source,
+                    })?;
+                Ok((re, rule.replacement.clone()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            lowercase: config.lowercase,
+            rules,
+        })
+    }
+
+    /// 全角英数字や半角カナなど、IMEの入力揺れを NFKC で吸収してから規則を適用する (ｒｕｓｔ -> rust)
+    pub fn apply(&self, raw: &str) -> String {
+        let mut tag: String = raw.nfkc().collect::<String>().trim().to_string();
+        if self.lowercase {
+            tag = tag.to_lowercase();
+        }
+        for (re, replacement) in &self.rules {
+            tag = re.replace_all(&tag, replacement.as_str()).into_owned();
+        }
+        tag
+    }
+}
+
+/// 比較用にアクセント記号を落とす (濁点・半濁点は別の文字として残す)
+pub fn fold_diacritics(s: &str) -> String {
+    s.nfd()
+        .filter(|c| !is_combining_mark(*c) || matches!(c, '\u{3099}' | '\u{309A}'))
+        .nfc()
+        .collect()
+}
+
+/// 英単語の複数形を単数形に戻した語幹 (英小文字以外を含む語はそのまま)
+///
+/// 単語どうしを比べるためのもので、実在の単語になるとは限らない (movies -> movy)。
+/// タグに対応付けるときは singular_forms でDBにある形を探す。
+pub fn stem(word: &str) -> String {
+    if !word.chars().all(|c| c.is_ascii_lowercase() || c == '-') {
+        return word.to_string();
+    }
+    if let Some(base) = word.strip_suffix("ies")
+        && base.len() > 1
+    {
+        return format!("{}y", base);
+    }
+    for suffix in ["sses", "shes", "ches", "xes", "zes"] {
+        if word.ends_with(suffix) {
+            return word[..word.len() - 2].to_string();
+        }
+    }
+    if let Some(base) = word.strip_suffix('s')
+        && base.len() > 2
+        && !["ss", "us", "is"].iter().any(|s| word.ends_with(s))
+    {
+        return base.to_string();
+    }
+    word.to_string()
+}
+
+/// 英単語の複数形だったときの単数形の候補 (英小文字以外を含む語や、複数形に見えない語は空)
+///
+/// -ies は -y と -ie (movies -> movy, movie)、-es は -es と -s を外した形 (caches -> cach, cache) の両方を返す。
+/// どれが正しいかはわからないので、DBにあるタグと突き合わせて使う。
+pub fn singular_forms(word: &str) -> Vec<String> {
+    if !word.chars().all(|c| c.is_ascii_lowercase() || c == '-') {
+        return Vec::new();
+    }
+    let mut forms = Vec::new();
+    if let Some(base) = word.strip_suffix("ies")
+        && base.len() > 1
+    {
+        forms.push(format!("{}y", base));
+    } else if let Some(base) = word.strip_suffix("es")
+        && base.len() > 1
+    {
+        forms.push(base.to_string());
+    }
+    if let Some(base) = word.strip_suffix('s')
+        && base.len() > 2
+        && !["ss", "us", "is"].iter().any(|s| word.ends_with(s))
+    {
+        forms.push(base.to_string());
+    }
+    forms
+}
+
+/// Soundex コード (英字を含まない入力は None)
+pub fn soundex(input: &str) -> Option<String> {
+    fn digit(c: char) -> Option<char> {
+        match c {
+            'b' | 'f' | 'p' | 'v' => Some('1'),
+            'c' | 'g' | 'j' | 'k' | 'q' | 's' | 'x' | 'z' => Some('2'),
+            'd' | 't' => Some('3'),
+            'l' => Some('4'),
+            'm' | 'n' => Some('5'),
+            'r' => Some('6'),
+            _ => None,
+        }
+    }
+
+    let mut letters = input
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_lowercase());
+    let first = letters.next()?;
+    let mut code = first.to_ascii_uppercase().to_string();
+    let mut last = digit(first);
+    for c in letters {
+        let d = digit(c);
+        if let Some(d) = d
+            && last != Some(d)
+        {
+            code.push(d);
+            if code.len() == 4 {
+                break;
+            }
+        }
+        // h と w は同じ子音の間に入っても区切りにならない
+        if c != 'h' && c != 'w' {
+            last = d;
+        }
+    }
+    while code.len() < 4 {
+        code.push('0');
+    }
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::RewriteRule;
+
+    #[test]
+    fn stem_strips_english_plurals() {
+        assert_eq!(stem("tags"), "tag");
+        assert_eq!(stem("libraries"), "library");
+        assert_eq!(stem("movies"), "movy");
+        assert_eq!(stem("classes"), "class");
+        assert_eq!(stem("boxes"), "box");
+        assert_eq!(stem("patches"), "patch");
+        assert_eq!(stem("side-projects"), "side-project");
+    }
+
+    #[test]
+    fn stem_leaves_words_that_do_not_look_plural() {
+        for word in ["class", "status", "analysis", "gas", "ies", "rust"] {
+            assert_eq!(stem(word), word);
+        }
+        // 英小文字以外を含む語はそのまま
+        for word in ["Tags", "タグs", "c++s", "v2s"] {
+            assert_eq!(stem(word), word);
+        }
+    }
+
+    #[test]
+    fn singular_forms_returns_every_candidate() {
+        assert_eq!(singular_forms("movies"), ["movy", "movie"]);
+        assert_eq!(singular_forms("caches"), ["cach", "cache"]);
+        assert_eq!(singular_forms("boxes"), ["box", "boxe"]);
+        assert_eq!(singular_forms("tags"), ["tag"]);
+        assert_eq!(singular_forms("does"), ["do", "doe"]);
+    }
+
+    #[test]
+    fn singular_forms_is_empty_for_non_plurals() {
+        for word in [
+            "rust", "class", "status", "analysis", "gas", "es", "Tags", "タグ",
+        ] {
+            assert!(singular_forms(word).is_empty(), "{}", word);
+        }
+    }
+
+    #[test]
+    fn normalizer_folds_width_then_applies_rules() {
+        let normalizer = Normalizer::new(&NormalizeConfig {
+            lowercase: true,
+            rules: vec![RewriteRule {
+                pattern: r"\s+".to_string(),
+                replacement: "-".to_string(),
+            }],
+        })
+        .unwrap();
+        assert_eq!(normalizer.apply(" Ｍachine  Learning "), "machine-learning");
+
+        let invalid = Normalizer::new(&NormalizeConfig {
+            lowercase: false,
+            rules: vec![RewriteRule {
+                pattern: "(".to_string(),
+                replacement: String::new(),
+            }],
+        });
+        assert!(matches!(invalid, Err(MdtaggerError::InvalidPattern { .. })));
+    }
+
+    #[test]
+    fn fold_diacritics_keeps_dakuten() {
+        assert_eq!(fold_diacritics("café"), "cafe");
+        assert_eq!(fold_diacritics("ガジェット"), "ガジェット");
+        assert_eq!(fold_diacritics("パン"), "パン");
+    }
+}
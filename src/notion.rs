@@ -0,0 +1,93 @@
+// --- Notion の Markdown エクスポート ---
+//
+// Notion はデータベースのページを、見出し (# タイトル) のすぐ下に「名前: 値」のプロパティの行を並べて書き出す。
+// マルチセレクトの値はカンマで区切られる。ほかのツールで変換したものは、Front Matter に
+// Tags: a, b のような文字列か、リストとして入っていることもあるので、どちらも読む。
+
+use mdtagger::markdown::FrontMatter;
+use serde_yaml::Value;
+use std::ops::Range;
+
+/// プロパティの名前として読む長さの上限 (本文の「注: ...」のような行を拾わないように)
+const MAX_NAME_LEN: usize = 64;
+
+/// 見出しの下のプロパティの1行
+#[derive(Debug)]
+pub struct PropertyLine {
+    pub name: String,
+    pub value: String,
+    /// 本文の中での位置 (改行を含む)
+    pub range: Range<usize>,
+}
+
+/// 本文の最初の見出しのすぐ下 (空行は飛ばす) に並んだプロパティの行 (見出しで始まらなければ空)
+pub fn property_lines(body: &str) -> Vec<PropertyLine> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut heading = false;
+    for line in body.split_inclusive('\n') {
+        let range = start..start + line.len();
+        start += line.len();
+        let text = line.trim_end_matches(['\n', '\r']);
+        if !heading {
+            if text.trim().is_empty() {
+                continue;
+            }
+            if !text.starts_with("# ") {
+                break;
+            }
+            heading = true;
+            continue;
+        }
+        if text.trim().is_empty() {
+            if lines.is_empty() {
+                continue;
+            }
+            break;
+        }
+        let Some((name, value)) = text.split_once(": ") else {
+            break;
+        };
+        if name.is_empty() || name.len() > MAX_NAME_LEN || name.starts_with(['#', '-', '*', '>']) {
+            break;
+        }
+        lines.push(PropertyLine {
+            name: name.trim().to_string(),
+            value: value.trim().to_string(),
+            range,
+        });
+    }
+    lines
+}
+
+/// マルチセレクトの値 (カンマ区切り) を分ける
+pub fn split_values(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// Front Matter の property (大文字・小文字は区別しない) の、実際のキーと値
+pub fn front_matter_values(
+    front_matter: &FrontMatter,
+    property: &str,
+) -> Option<(String, Vec<String>)> {
+    front_matter.mapping().iter().find_map(|(key, value)| {
+        let key = key.as_str()?;
+        if !key.eq_ignore_ascii_case(property) {
+            return None;
+        }
+        let values = match value {
+            Value::String(s) => split_values(s),
+            Value::Sequence(seq) => seq
+                .iter()
+                .filter_map(|v| v.as_str())
+                .flat_map(split_values)
+                .collect(),
+            _ => return None,
+        };
+        Some((key.to_string(), values))
+    })
+}
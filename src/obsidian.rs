@@ -0,0 +1,78 @@
+// --- Obsidian の保管庫 (.obsidian のあるディレクトリ) ---
+//
+// カレントディレクトリから親へたどって .obsidian を見つけたら、保管庫のノートに付いているタグから
+// 入れ子のタグ (a/b) を使っているか、大文字・小文字や区切りの書き方を推定し、新しいタグの名前をそれに揃える。
+// Obsidian のタグに使えるのは文字・数字・_・-・/ だけなので、記号は取り除き、空白は区切りの文字にする。
+
+use log::debug;
+use mdtagger::db::SlugConfig;
+use mdtagger::markdown::{FrontMatterOptions, collect_markdown_files, read_front_matter};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const CONFIG_DIR: &str = ".obsidian";
+
+#[derive(Debug)]
+pub struct Vault {
+    pub root: PathBuf,
+    slug: OnceLock<SlugConfig>,
+}
+
+impl Vault {
+    /// カレントディレクトリから親へたどって、最初に見つかった保管庫
+    pub fn detect() -> Option<Self> {
+        let cwd = env::current_dir().ok()?;
+        let root = cwd.ancestors().find(|dir| dir.join(CONFIG_DIR).is_dir())?;
+        Some(Self {
+            root: root.to_path_buf(),
+            slug: OnceLock::new(),
+        })
+    }
+
+    /// 保管庫のノートのタグの書き方に合わせた、新しいタグ名の整形ルール (DBには保存しない)
+    pub fn slug(&self, options: &FrontMatterOptions) -> SlugConfig {
+        self.slug
+            .get_or_init(|| {
+                let tags = vault_tags(&self.root, options);
+                let slug = infer_slug(&tags);
+                debug!(
+                    "Obsidian vault {:?}: {} tags, {:?}",
+                    self.root,
+                    tags.len(),
+                    slug
+                );
+                slug
+            })
+            .clone()
+    }
+}
+
+/// 保管庫のノートの Front Matter に付いているタグ (読めないノートは飛ばす)
+fn vault_tags(root: &Path, options: &FrontMatterOptions) -> Vec<String> {
+    let files = collect_markdown_files(root).unwrap_or_default();
+    files
+        .iter()
+        .filter_map(|path| read_front_matter(path).ok())
+        .flat_map(|(front_matter, _)| front_matter.list(&options.tag_key))
+        .collect()
+}
+
+fn infer_slug(tags: &[String]) -> SlugConfig {
+    // 大文字と小文字のあるタグがすべて小文字なら、小文字に揃える
+    let cased: Vec<&String> = tags
+        .iter()
+        .filter(|tag| tag.chars().any(|c| c.is_uppercase() || c.is_lowercase()))
+        .collect();
+    let lowercase = !cased.is_empty() && cased.iter().all(|tag| **tag == tag.to_lowercase());
+    // 空白の代わりの区切りは、多く使われているほう (どちらもなければ -)
+    let count = |sep: char| tags.iter().filter(|tag| tag.contains(sep)).count();
+    let separator = if count('_') > count('-') { "_" } else { "-" };
+    SlugConfig {
+        lowercase,
+        separator: Some(separator.to_string()),
+        strip_punctuation: true,
+        nested: tags.iter().any(|tag| tag.contains('/')),
+        inferred: true,
+    }
+}
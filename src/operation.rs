@@ -0,0 +1,358 @@
+// --- ファイル1つへのタグの操作 (ビルダー) ---
+//
+// 付けるタグ・外すタグ・試すだけか、を組み合わせて1回の操作として実行し、
+// 何が変わったかを ChangeSet で返す。CLI を通さずに複雑な組み合わせを試すためのもの。
+
+use crate::db::{TagDatabase, record_usage};
+use crate::error::Result;
+use crate::markdown::{
+    FrontMatterOptions, content_tags, read_head, replace_head, set_tags, write_verified,
+};
+use crate::resolver::{StrictResolver, TagResolver, UnknownPolicy};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// 1つのファイルに対する操作 (TagOperation::new(path).add([..]).remove([..]).execute(&mut db))
+#[derive(Debug, Clone)]
+pub struct TagOperation {
+    path: PathBuf,
+    add: Vec<String>,
+    remove: Vec<String>,
+    dry_run: bool,
+    verify: bool,
+    on_unknown: UnknownPolicy,
+    front_matter: FrontMatterOptions,
+}
+
+/// 操作の結果
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeSet {
+    pub path: PathBuf,
+    /// ファイルに新しく付いたタグ
+    pub added: Vec<String>,
+    /// ファイルから外れたタグ
+    pub removed: Vec<String>,
+    /// 入力 -> 対応付けたタグ (エイリアスや略語を正式名にしたもの)
+    pub resolved: Vec<(String, Vec<String>)>,
+    /// DBに新しく登録したタグ
+    pub new_tags: Vec<String>,
+    /// 書き換える前のファイルの先頭 (read_head で読んだ部分。本文は含まない)
+    #[serde(skip)]
+    pub before: String,
+    /// 書き換えたあとのファイルの先頭
+    #[serde(skip)]
+    pub after: String,
+    /// ファイルとDBを書き換えたか (dry_run なら常に false)
+    pub applied: bool,
+}
+
+impl ChangeSet {
+    /// 付いたタグの使用回数を数える
+    pub fn record_usage(&self, db: &mut TagDatabase) {
+        db.edit(|config| record_usage(config, &self.added));
+    }
+
+    /// ファイルの内容が変わらないか
+    pub fn is_empty(&self) -> bool {
+        self.before == self.after
+    }
+}
+
+impl TagOperation {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            add: Vec::new(),
+            remove: Vec::new(),
+            dry_run: false,
+            verify: false,
+            on_unknown: UnknownPolicy::Create,
+            front_matter: FrontMatterOptions::default(),
+        }
+    }
+
+    /// 付けるタグ (エイリアスと略語は正式名にする)
+    // ビルダーのメソッドなので std::ops::Add とは関係ない
+    #[allow(clippy::should_implement_trait)]
+    pub fn add<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.add.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// 外すタグ (エイリアスなら正式名も外す)
+    pub fn remove<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.remove.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// true ならファイルもDBも書き換えず、変わる内容だけを返す
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// true なら書き込んだファイルを読み直し、本文とタグ以外の Front Matter が
+    /// 変わっていないか確かめる。変わっていれば元に戻して VerificationFailed を返す
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// DBにないタグの扱い (デフォルト: create)
+    pub fn on_unknown(mut self, policy: UnknownPolicy) -> Self {
+        self.on_unknown = policy;
+        self
+    }
+
+    pub fn front_matter(mut self, options: FrontMatterOptions) -> Self {
+        self.front_matter = options;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn front_matter_options(&self) -> &FrontMatterOptions {
+        &self.front_matter
+    }
+
+    /// 操作を実行する。DBはメモリ上で更新するだけなので、保存は呼び出し側で行う
+    pub fn execute(&self, db: &mut TagDatabase) -> Result<ChangeSet> {
+        let before = read_head(&self.path, &self.front_matter)?;
+        let changes = self.plan(before, db)?;
+        if changes.applied {
+            if self.verify {
+                write_verified(
+                    &self.path,
+                    &changes.before,
+                    &changes.after,
+                    &self.front_matter,
+                )?;
+            } else {
+                replace_head(&self.path, &changes.before, &changes.after)?;
+            }
+            changes.record_usage(db);
+        }
+        Ok(changes)
+    }
+
+    /// ファイルの先頭 before (read_head で読んだ部分) に操作を当てはめる (ファイルの読み書きはしない)
+    ///
+    /// タグの解決でDBを更新するが、使用回数は書き込んだあとに ChangeSet::record_usage で数える。
+    pub fn plan(&self, before: String, db: &mut TagDatabase) -> Result<ChangeSet> {
+        let current =
+            content_tags(&before, &self.front_matter).map_err(|err| err.in_file(&self.path))?;
+
+        // 試すだけのときは、DBの写しに対して解決する
+        let mut scratch;
+        let target = if self.dry_run {
+            scratch = db.clone();
+            &mut scratch
+        } else {
+            db
+        };
+        let names_before: Vec<String> = target.tags().iter().map(|e| e.name.clone()).collect();
+        let mut resolver = StrictResolver {
+            policy: self.on_unknown,
+        };
+        let mut resolved = Vec::new();
+        for input in &self.add {
+            let resolution = target.edit(|config| resolver.resolve(input, config))?;
+            resolved.push((input.clone(), resolution.tags));
+        }
+        let new_tags = target
+            .tags()
+            .iter()
+            .filter(|e| !names_before.contains(&e.name))
+            .map(|e| e.name.clone())
+            .collect();
+
+        let removals: Vec<String> = self
+            .remove
+            .iter()
+            .flat_map(|tag| {
+                let canonical = target.lookup(tag).map(|e| e.name.clone());
+                std::iter::once(tag.clone()).chain(canonical)
+            })
+            .collect();
+
+        let mut tags: Vec<String> = current
+            .iter()
+            .filter(|t| !removals.contains(t))
+            .cloned()
+            .collect();
+        for tag in resolved.iter().flat_map(|(_, tags)| tags) {
+            if !tags.contains(tag) && !removals.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        if self.front_matter.sort {
+            tags.sort();
+            tags.dedup();
+        }
+        let after = if tags != current {
+            set_tags(&before, &tags, &self.front_matter).map_err(|err| err.in_file(&self.path))?
+        } else {
+            before.clone()
+        };
+
+        let added: Vec<String> = tags
+            .iter()
+            .filter(|t| !current.contains(t))
+            .cloned()
+            .collect();
+        let removed = current
+            .iter()
+            .filter(|t| !tags.contains(t))
+            .cloned()
+            .collect();
+
+        let applied = !self.dry_run && after != before;
+        Ok(ChangeSet {
+            path: self.path.clone(),
+            added,
+            removed,
+            resolved,
+            new_tags,
+            before,
+            after,
+            applied,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MdtaggerError;
+    use crate::db::{TagConfig, TagEntry};
+    use std::fs;
+
+    fn db() -> TagDatabase {
+        let mut rust = TagEntry::new("rust");
+        rust.aliases.push("rs".to_string());
+        TagDatabase::new(TagConfig {
+            tags: vec![rust, TagEntry::new("draft"), TagEntry::new("cli")],
+            ..Default::default()
+        })
+    }
+
+    const HEAD: &str = "---\ntitle: Hello\ntags:\n- draft\n- rust\n---\n";
+
+    #[test]
+    fn add_and_remove_in_one_operation() {
+        let mut db = db();
+        let changes = TagOperation::new("note.md")
+            .add(["rs", "cli"])
+            .remove(["draft"])
+            .plan(HEAD.to_string(), &mut db)
+            .unwrap();
+        assert_eq!(changes.added, ["cli"]);
+        assert_eq!(changes.removed, ["draft"]);
+        assert_eq!(
+            changes.resolved,
+            [
+                ("rs".to_string(), vec!["rust".to_string()]),
+                ("cli".to_string(), vec!["cli".to_string()]),
+            ]
+        );
+        assert!(changes.new_tags.is_empty());
+        assert!(changes.applied);
+        assert_eq!(
+            changes.after,
+            "---\ntitle: Hello\ntags:\n- cli\n- rust\n---\n"
+        );
+    }
+
+    #[test]
+    fn removing_an_alias_removes_the_tag() {
+        let mut db = db();
+        let changes = TagOperation::new("note.md")
+            .remove(["rs"])
+            .plan(HEAD.to_string(), &mut db)
+            .unwrap();
+        assert_eq!(changes.removed, ["rust"]);
+    }
+
+    #[test]
+    fn nothing_to_change_is_not_applied() {
+        let mut db = db();
+        let changes = TagOperation::new("note.md")
+            .add(["rust"])
+            .remove(["cli"])
+            .plan(HEAD.to_string(), &mut db)
+            .unwrap();
+        assert!(changes.is_empty());
+        assert!(!changes.applied);
+        assert_eq!(changes.after, HEAD);
+    }
+
+    #[test]
+    fn dry_run_leaves_the_database_alone() {
+        let mut db = db();
+        let changes = TagOperation::new("note.md")
+            .add(["python"])
+            .dry_run(true)
+            .plan(HEAD.to_string(), &mut db)
+            .unwrap();
+        assert_eq!(changes.new_tags, ["python"]);
+        assert_eq!(changes.added, ["python"]);
+        assert!(!changes.applied);
+        assert!(!changes.is_empty());
+        assert!(db.lookup("python").is_none());
+
+        let changes = TagOperation::new("note.md")
+            .add(["python"])
+            .plan(HEAD.to_string(), &mut db)
+            .unwrap();
+        assert!(changes.applied);
+        assert!(db.contains("python"));
+    }
+
+    #[test]
+    fn unknown_tags_follow_the_policy() {
+        let mut db = db();
+        let skip = TagOperation::new("note.md")
+            .add(["python"])
+            .on_unknown(UnknownPolicy::Skip)
+            .plan(HEAD.to_string(), &mut db)
+            .unwrap();
+        assert!(skip.is_empty());
+        assert_eq!(skip.resolved, [("python".to_string(), Vec::new())]);
+
+        let fail = TagOperation::new("note.md")
+            .add(["python"])
+            .on_unknown(UnknownPolicy::Fail)
+            .plan(HEAD.to_string(), &mut db);
+        assert!(matches!(fail, Err(MdtaggerError::UnknownTag(_))));
+        assert!(db.lookup("python").is_none());
+    }
+
+    #[test]
+    fn execute_rewrites_the_head_and_counts_usage() {
+        let path =
+            std::env::temp_dir().join(format!("mdtagger-test-{}-operation.md", std::process::id()));
+        fs::write(&path, format!("{}Body\n", HEAD)).unwrap();
+        let mut db = db();
+        let changes = TagOperation::new(&path)
+            .add(["cli"])
+            .verify(true)
+            .execute(&mut db)
+            .unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, format!("{}Body\n", changes.after));
+        assert_eq!(db.lookup("cli").unwrap().uses, 1);
+        assert_eq!(db.lookup("rust").unwrap().uses, 0);
+    }
+}
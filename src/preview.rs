@@ -0,0 +1,30 @@
+// --- 書き込み前の差分表示 ---
+
+use console::style;
+use similar::TextDiff;
+use std::path::Path;
+
+/// 変更前後の内容を色付きの unified diff として表示する
+pub fn print_diff(path: &Path, old: &str, new: &str) {
+    let name = path.display().to_string();
+    let diff = TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&name, &name)
+        .to_string();
+
+    for line in diff.lines() {
+        let styled = if line.starts_with("---") || line.starts_with("+++") {
+            style(line).bold()
+        } else if line.starts_with("@@") {
+            style(line).cyan()
+        } else if line.starts_with('+') {
+            style(line).green()
+        } else if line.starts_with('-') {
+            style(line).red()
+        } else {
+            style(line)
+        };
+        eprintln!("{}", styled);
+    }
+}
@@ -0,0 +1,134 @@
+// --- 対話プロンプト ---
+//
+// タグ解決は `Prompter` を通して尋ねるので、GUI やテストからは答え方を差し替えられる。
+// 端末で尋ねる実装 (dialoguer) は interactive フィーチャーのときだけ使える。
+
+use crate::error::{MdtaggerError, Result};
+use std::collections::VecDeque;
+
+#[cfg(feature = "interactive")]
+mod terminal;
+#[cfg(feature = "interactive")]
+pub use terminal::*;
+
+// --- 問い合わせの差し替え ---
+/// タグ解決がユーザーに尋ねるときの窓口
+pub trait Prompter {
+    /// 選択肢から1つ選ばせる
+    fn ask_select(&mut self, prompt: &str, items: &[String], default: usize) -> Result<usize>;
+
+    /// はい/いいえ を尋ねる
+    fn ask_confirm(&mut self, prompt: &str, default: bool) -> Result<bool>;
+
+    /// 複数選ばせる。checked[i] が true の項目は最初から選んでおく
+    ///
+    /// 実装しなければ、項目ごとに ask_confirm で尋ねる。
+    fn ask_multi_select(
+        &mut self,
+        prompt: &str,
+        items: &[String],
+        checked: &[bool],
+    ) -> Result<Vec<usize>> {
+        let mut picked = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            let default = checked.get(i) == Some(&true);
+            if self.ask_confirm(&format!("{} {}", prompt, item), default)? {
+                picked.push(i);
+            }
+        }
+        Ok(picked)
+    }
+}
+
+/// ScriptedPrompter に積んでおく答え
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Select(usize),
+    Confirm(bool),
+    MultiSelect(Vec<usize>),
+    /// 何を尋ねられても既定の答えを使う
+    Default,
+}
+
+/// 積んでおいた答えを順に返す (テストや、答えが決まっている自動処理向け)
+///
+/// 答えが尽きたり、尋ね方と答えの種類が合わなかったりしたら PromptUnavailable を返す。
+#[derive(Debug, Default, Clone)]
+pub struct ScriptedPrompter {
+    answers: VecDeque<Answer>,
+    /// 尋ねられた質問 (古い順)
+    pub asked: Vec<String>,
+}
+
+impl ScriptedPrompter {
+    pub fn new(answers: impl IntoIterator<Item = Answer>) -> Self {
+        Self {
+            answers: answers.into_iter().collect(),
+            asked: Vec::new(),
+        }
+    }
+
+    /// まだ使われていない答えの数
+    pub fn remaining(&self) -> usize {
+        self.answers.len()
+    }
+
+    fn next(&mut self, prompt: &str) -> Result<Answer> {
+        self.asked.push(prompt.to_string());
+        self.answers
+            .pop_front()
+            .ok_or_else(|| MdtaggerError::PromptUnavailable(prompt.to_string()))
+    }
+}
+
+impl Prompter for ScriptedPrompter {
+    fn ask_select(&mut self, prompt: &str, items: &[String], default: usize) -> Result<usize> {
+        match self.next(prompt)? {
+            Answer::Select(i) if i < items.len() => Ok(i),
+            Answer::Default => Ok(default),
+            _ => Err(MdtaggerError::PromptUnavailable(prompt.to_string())),
+        }
+    }
+
+    fn ask_confirm(&mut self, prompt: &str, default: bool) -> Result<bool> {
+        match self.next(prompt)? {
+            Answer::Confirm(answer) => Ok(answer),
+            Answer::Default => Ok(default),
+            _ => Err(MdtaggerError::PromptUnavailable(prompt.to_string())),
+        }
+    }
+
+    fn ask_multi_select(
+        &mut self,
+        prompt: &str,
+        items: &[String],
+        checked: &[bool],
+    ) -> Result<Vec<usize>> {
+        match self.next(prompt)? {
+            Answer::MultiSelect(picked) if picked.iter().all(|&i| i < items.len()) => Ok(picked),
+            Answer::Default => Ok((0..items.len())
+                .filter(|&i| checked.get(i) == Some(&true))
+                .collect()),
+            _ => Err(MdtaggerError::PromptUnavailable(prompt.to_string())),
+        }
+    }
+}
+
+/// 決して尋ねない。確認が必要になったら PromptUnavailable で失敗する
+/// (GUI など、ポリシーで決めきれないときに呼び出し側で扱いたい場合向け)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NeverPrompt;
+
+impl Prompter for NeverPrompt {
+    fn ask_select(&mut self, prompt: &str, _: &[String], _: usize) -> Result<usize> {
+        Err(MdtaggerError::PromptUnavailable(prompt.to_string()))
+    }
+
+    fn ask_confirm(&mut self, prompt: &str, _: bool) -> Result<bool> {
+        Err(MdtaggerError::PromptUnavailable(prompt.to_string()))
+    }
+
+    fn ask_multi_select(&mut self, prompt: &str, _: &[String], _: &[bool]) -> Result<Vec<usize>> {
+        Err(MdtaggerError::PromptUnavailable(prompt.to_string()))
+    }
+}
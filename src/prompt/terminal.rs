@@ -0,0 +1,360 @@
+// --- 端末での対話 (feature = "interactive") ---
+//
+// dialoguer の呼び出しをまとめ、--yes のときは端末に触れずに既定の答えを返す。
+// 見た目 (テーマ、印、絵文字) は set_appearance で一度だけ決める。
+// set_timeout で待ち時間を決めると、答えがないまま時間が過ぎたとき既定の答えを使い、
+// それ以降の確認もすべて既定の答えで進める (夜間のバッチが止まったままにならないように)。
+
+use super::Prompter;
+use crate::error::Result;
+use crate::tr;
+use console::style;
+use dialoguer::Completion;
+use dialoguer::theme::{ColorfulTheme, SimpleTheme, Theme};
+use dialoguer::{Confirm, Editor, Input, MultiSelect, Select};
+use log::{info, warn};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{OnceLock, mpsc};
+use std::thread;
+use std::time::Duration;
+
+// --- 見た目 ---
+/// プロンプトのテーマ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeKind {
+    /// 装飾のない表示 ([x] などの ASCII の印)
+    Simple,
+    /// 色と記号の付いた表示
+    Colorful,
+}
+
+/// プロンプトとメッセージの見た目 (設定ファイルの [appearance])
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Appearance {
+    /// テーマ (印を指定したときのデフォルトは colorful、それ以外は simple)
+    pub theme: Option<ThemeKind>,
+    /// 色を使うか
+    pub colors: Option<bool>,
+    /// メッセージの絵文字やプロンプトの記号を使うか
+    pub emoji: Option<bool>,
+    /// 質問の前の印
+    pub prompt_prefix: Option<String>,
+    /// 答えたあとの印
+    pub success_prefix: Option<String>,
+    /// 入力が不正なときの印
+    pub error_prefix: Option<String>,
+    /// 選択中の項目の印
+    pub active_item: Option<String>,
+    /// 選択にチェックを付けた項目の印
+    pub checked_item: Option<String>,
+    /// チェックを付けていない項目の印
+    pub unchecked_item: Option<String>,
+}
+
+impl Appearance {
+    /// other で指定された値で上書きする
+    pub fn merge(&mut self, other: Appearance) {
+        self.theme = other.theme.or(self.theme);
+        self.colors = other.colors.or(self.colors);
+        self.emoji = other.emoji.or(self.emoji);
+        self.prompt_prefix = other.prompt_prefix.or(self.prompt_prefix.take());
+        self.success_prefix = other.success_prefix.or(self.success_prefix.take());
+        self.error_prefix = other.error_prefix.or(self.error_prefix.take());
+        self.active_item = other.active_item.or(self.active_item.take());
+        self.checked_item = other.checked_item.or(self.checked_item.take());
+        self.unchecked_item = other.unchecked_item.or(self.unchecked_item.take());
+    }
+
+    pub fn colors(&self) -> bool {
+        self.colors.unwrap_or(true)
+    }
+
+    pub fn emoji(&self) -> bool {
+        self.emoji.unwrap_or(true)
+    }
+
+    fn has_markers(&self) -> bool {
+        self.prompt_prefix.is_some()
+            || self.success_prefix.is_some()
+            || self.error_prefix.is_some()
+            || self.active_item.is_some()
+            || self.checked_item.is_some()
+            || self.unchecked_item.is_some()
+    }
+
+    fn build_theme(&self) -> Box<dyn Theme + Send + Sync> {
+        let kind = self.theme.unwrap_or(if self.has_markers() {
+            ThemeKind::Colorful
+        } else {
+            ThemeKind::Simple
+        });
+        if kind == ThemeKind::Simple {
+            return Box::new(SimpleTheme);
+        }
+
+        let mut theme = ColorfulTheme::default();
+        if !self.emoji() {
+            theme.prompt_suffix = style(":".to_string()).for_stderr().black().bright();
+            theme.success_prefix = style("+".to_string()).for_stderr().green();
+            theme.success_suffix = style("-".to_string()).for_stderr().black().bright();
+            theme.error_prefix = style("x".to_string()).for_stderr().red();
+            theme.active_item_prefix = style(">".to_string()).for_stderr().green();
+            theme.checked_item_prefix = style("[x]".to_string()).for_stderr().green();
+            theme.unchecked_item_prefix = style("[ ]".to_string()).for_stderr().magenta();
+            theme.picked_item_prefix = style(">".to_string()).for_stderr().green();
+        }
+        if let Some(marker) = &self.prompt_prefix {
+            theme.prompt_prefix = style(marker.clone()).for_stderr().yellow();
+        }
+        if let Some(marker) = &self.success_prefix {
+            theme.success_prefix = style(marker.clone()).for_stderr().green();
+        }
+        if let Some(marker) = &self.error_prefix {
+            theme.error_prefix = style(marker.clone()).for_stderr().red();
+        }
+        if let Some(marker) = &self.active_item {
+            theme.active_item_prefix = style(marker.clone()).for_stderr().green();
+            theme.picked_item_prefix = style(marker.clone()).for_stderr().green();
+        }
+        if let Some(marker) = &self.checked_item {
+            theme.checked_item_prefix = style(marker.clone()).for_stderr().green();
+        }
+        if let Some(marker) = &self.unchecked_item {
+            theme.unchecked_item_prefix = style(marker.clone()).for_stderr().magenta();
+        }
+        Box::new(theme)
+    }
+}
+
+static THEME: OnceLock<Box<dyn Theme + Send + Sync>> = OnceLock::new();
+
+/// プロンプトの見た目を決める (最初の呼び出しだけが有効)
+pub fn set_appearance(appearance: &Appearance) {
+    let _ = THEME.set(appearance.build_theme());
+}
+
+fn theme() -> &'static dyn Theme {
+    match THEME.get() {
+        Some(theme) => theme.as_ref(),
+        None => &SimpleTheme,
+    }
+}
+
+// --- 待ち時間 ---
+static TIMEOUT: OnceLock<Duration> = OnceLock::new();
+static TIMED_OUT: AtomicBool = AtomicBool::new(false);
+
+/// 確認を待つ時間を決める (最初の呼び出しだけが有効)
+pub fn set_timeout(timeout: Duration) {
+    let _ = TIMEOUT.set(timeout);
+}
+
+/// ask を別のスレッドで尋ね、時間内に答えがなければ fallback を返す
+fn with_timeout<T: Send + 'static>(
+    prompt: &str,
+    fallback: T,
+    fallback_label: &str,
+    ask: impl FnOnce() -> dialoguer::Result<T> + Send + 'static,
+) -> Result<T> {
+    if TIMED_OUT.load(Ordering::Relaxed) {
+        info!(
+            "{} {}",
+            prompt,
+            tr!("{} (timed out)", "{} (時間切れ)", fallback_label)
+        );
+        return Ok(fallback);
+    }
+    let Some(timeout) = TIMEOUT.get() else {
+        return Ok(ask()?);
+    };
+
+    let saved = terminal_state::save();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(ask());
+    });
+    match rx.recv_timeout(*timeout) {
+        Ok(answer) => Ok(answer?),
+        Err(_) => {
+            // 入力待ちのスレッドは残るので、端末の状態だけ元に戻して二度と尋ねない
+            TIMED_OUT.store(true, Ordering::Relaxed);
+            terminal_state::restore(saved);
+            let stderr = console::Term::stderr();
+            let _ = stderr.show_cursor();
+            let _ = stderr.write_line("");
+            warn!(
+                "{}",
+                tr!(
+                    "No answer within {}; using the default answers from now on",
+                    "{} 以内に答えがなかったので、ここからは既定の答えを使います",
+                    humantime::format_duration(*timeout)
+                )
+            );
+            info!("{} {}", prompt, fallback_label);
+            Ok(fallback)
+        }
+    }
+}
+
+#[cfg(unix)]
+mod terminal_state {
+    /// 標準入力の端末の設定を覚えておく (端末でなければ None)
+    pub fn save() -> Option<libc::termios> {
+        let mut termios = std::mem::MaybeUninit::uninit();
+        // SAFETY: 標準入力の fd と、書き込み先として十分な大きさの領域を渡している
+        let ok = unsafe { libc::tcgetattr(libc::STDIN_FILENO, termios.as_mut_ptr()) } == 0;
+        // SAFETY: tcgetattr が成功したときだけ初期化済みとして読む
+        ok.then(|| unsafe { termios.assume_init() })
+    }
+
+    pub fn restore(saved: Option<libc::termios>) {
+        if let Some(termios) = saved {
+            // SAFETY: save で取得した設定をそのまま戻している
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) };
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod terminal_state {
+    pub fn save() -> Option<()> {
+        None
+    }
+
+    pub fn restore(_: Option<()>) {}
+}
+
+/// はい/いいえ を尋ねる。assume_yes なら尋ねずに「はい」と答える
+pub fn confirm(prompt: &str, default: bool, assume_yes: bool) -> Result<bool> {
+    if assume_yes {
+        info!("{} {}", prompt, tr!("yes (--yes)", "はい (--yes)"));
+        return Ok(true);
+    }
+    let label = if default {
+        tr!("yes", "はい")
+    } else {
+        tr!("no", "いいえ")
+    };
+    let owned = prompt.to_string();
+    with_timeout(prompt, default, &label, move || {
+        Confirm::with_theme(theme())
+            .with_prompt(owned)
+            .default(default)
+            .interact()
+    })
+}
+
+/// 選択肢から1つ選ばせる。assume_yes なら尋ねずに既定の選択肢を返す
+pub fn select(prompt: &str, items: &[String], default: usize, assume_yes: bool) -> Result<usize> {
+    if assume_yes {
+        info!("{} {} (--yes)", prompt, items[default]);
+        return Ok(default);
+    }
+    let (owned, items) = (prompt.to_string(), items.to_vec());
+    let label = items[default].clone();
+    with_timeout(prompt, default, &label, move || {
+        Select::with_theme(theme())
+            .with_prompt(owned)
+            .items(&items)
+            .default(default)
+            .interact()
+    })
+}
+
+/// 複数選ばせる (長い一覧はページ送りで表示する)。checked[i] が true の項目は最初から選んでおく
+pub fn multi_select(prompt: &str, items: &[String], checked: &[bool]) -> Result<Vec<usize>> {
+    let defaults: Vec<usize> = (0..items.len())
+        .filter(|&i| checked.get(i) == Some(&true))
+        .collect();
+    let label = format!(
+        "{:?}",
+        defaults.iter().map(|&i| &items[i]).collect::<Vec<_>>()
+    );
+    let (owned, items, checked) = (prompt.to_string(), items.to_vec(), checked.to_vec());
+    with_timeout(prompt, defaults, &label, move || {
+        MultiSelect::with_theme(theme())
+            .with_prompt(owned)
+            .items(&items)
+            .defaults(&checked)
+            .max_length(15)
+            .interact()
+    })
+}
+
+/// 1行入力させる。Tab (または →) で候補の前方一致を補完する。空欄のままでもよい
+pub fn input_with_completion(prompt: &str, candidates: &[String]) -> Result<String> {
+    let completion = PrefixCompletion(candidates.to_vec());
+    let owned = prompt.to_string();
+    with_timeout(
+        prompt,
+        String::new(),
+        &tr!("(empty)", "(空欄)"),
+        move || {
+            Input::<String>::with_theme(theme())
+                .with_prompt(owned)
+                .allow_empty(true)
+                .completion_with(&completion)
+                .interact_text()
+        },
+    )
+}
+
+// --- Prompter ---
+/// 端末で dialoguer を使って尋ねる (CLI の既定)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TerminalPrompter {
+    /// 尋ねずに既定の答えを使う (--yes)
+    pub assume_yes: bool,
+}
+
+impl TerminalPrompter {
+    pub fn new(assume_yes: bool) -> Self {
+        Self { assume_yes }
+    }
+}
+
+impl Prompter for TerminalPrompter {
+    fn ask_select(&mut self, prompt: &str, items: &[String], default: usize) -> Result<usize> {
+        select(prompt, items, default, self.assume_yes)
+    }
+
+    fn ask_confirm(&mut self, prompt: &str, default: bool) -> Result<bool> {
+        confirm(prompt, default, self.assume_yes)
+    }
+
+    fn ask_multi_select(
+        &mut self,
+        prompt: &str,
+        items: &[String],
+        checked: &[bool],
+    ) -> Result<Vec<usize>> {
+        if self.assume_yes {
+            return Ok((0..items.len())
+                .filter(|&i| checked.get(i) == Some(&true))
+                .collect());
+        }
+        multi_select(prompt, items, checked)
+    }
+}
+
+/// text を $VISUAL / $EDITOR で開き、保存された内容を返す (保存せずに閉じたら None)
+pub fn edit_text(text: &str, extension: &str) -> Result<Option<String>> {
+    Ok(Editor::new().extension(extension).edit(text)?)
+}
+
+struct PrefixCompletion(Vec<String>);
+
+impl Completion for PrefixCompletion {
+    fn get(&self, input: &str) -> Option<String> {
+        if input.is_empty() {
+            return None;
+        }
+        self.0
+            .iter()
+            .find(|c| c.starts_with(input) && c.as_str() != input)
+            .cloned()
+    }
+}
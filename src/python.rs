@@ -0,0 +1,186 @@
+// --- Python バインディング (feature = "python") ---
+//
+// ノートブックからノート群の手入れをスクリプトで行えるように、
+// Front Matter の読み取り・タグの解決・ファイルの書き換えを Python に公開する。
+// maturin でビルドする (pyproject.toml を参照)。
+
+use crate::db::TagDatabase;
+use crate::error::MdtaggerError;
+use crate::markdown::{FrontMatter, FrontMatterOptions, read_tags as read_file_tags};
+use crate::normalize::Normalizer;
+use crate::operation::TagOperation;
+use crate::prompt::NeverPrompt;
+use crate::resolver::{FuzzyResolver, ResolveOptions, TagResolver, UnknownPolicy};
+use pyo3::exceptions::{PyIOError, PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde_yaml::Value;
+use std::path::PathBuf;
+
+fn to_py_err(err: MdtaggerError) -> PyErr {
+    match err {
+        MdtaggerError::IoError { .. } => PyIOError::new_err(err.to_string()),
+        MdtaggerError::TagNotFound(_) | MdtaggerError::UnknownTag(_) => {
+            PyKeyError::new_err(err.to_string())
+        }
+        _ => PyValueError::new_err(err.to_string()),
+    }
+}
+
+fn parse_policy(policy: &str) -> PyResult<UnknownPolicy> {
+    policy.parse().map_err(to_py_err)
+}
+
+/// YAML の値を Python の値にする
+fn yaml_to_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => b.into_pyobject(py)?.to_owned().into_any(),
+        Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => i.into_pyobject(py)?.into_any(),
+            (None, Some(f)) => f.into_pyobject(py)?.into_any(),
+            _ => py.None().into_bound(py),
+        },
+        Value::String(s) => s.into_pyobject(py)?.into_any(),
+        Value::Sequence(seq) => {
+            let list = PyList::empty(py);
+            for item in seq {
+                list.append(yaml_to_py(py, item)?)?;
+            }
+            list.into_any()
+        }
+        Value::Mapping(mapping) => {
+            let dict = PyDict::new(py);
+            for (k, v) in mapping {
+                dict.set_item(yaml_to_py(py, k)?, yaml_to_py(py, v)?)?;
+            }
+            dict.into_any()
+        }
+        Value::Tagged(tagged) => yaml_to_py(py, &tagged.value)?,
+    })
+}
+
+/// Front Matter を辞書に、残りを本文の文字列にして返す
+#[pyfunction]
+fn parse_front_matter<'py>(
+    py: Python<'py>,
+    content: &str,
+) -> PyResult<(Bound<'py, PyAny>, String)> {
+    let (front_matter, body) =
+        FrontMatter::parse(content).map_err(|e| to_py_err(MdtaggerError::from(e)))?;
+    let mapping = Value::Mapping(front_matter.mapping().clone());
+    Ok((yaml_to_py(py, &mapping)?, body.to_string()))
+}
+
+/// ファイルに付いているタグを読む
+#[pyfunction]
+#[pyo3(signature = (path, tag_key = "tags"))]
+fn read_tags(path: PathBuf, tag_key: &str) -> PyResult<Vec<String>> {
+    let options = FrontMatterOptions {
+        tag_key: tag_key.to_string(),
+        ..Default::default()
+    };
+    read_file_tags(&path, &options).map_err(to_py_err)
+}
+
+/// タグデータベース (tags_db.json)。変更は save() を呼ぶまで書き出さない
+#[pyclass(name = "Database")]
+struct PyDatabase {
+    path: PathBuf,
+    db: TagDatabase,
+}
+
+#[pymethods]
+impl PyDatabase {
+    /// データベースを開く (ファイルがなければ空のデータベース)
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        let db = TagDatabase::open(&path).map_err(to_py_err)?;
+        Ok(Self { path, db })
+    }
+
+    /// 登録されているタグ名
+    fn tags(&self) -> Vec<String> {
+        self.db.tags().iter().map(|e| e.name.clone()).collect()
+    }
+
+    /// タグ名かエイリアスから正式なタグ名を引く (なければ None)
+    fn lookup(&self, name: &str) -> Option<String> {
+        self.db.lookup(name).map(|e| e.name.clone())
+    }
+
+    /// 入力を CLI の非対話モードと同じ規則で既存のタグに対応付ける
+    #[pyo3(signature = (input, on_unknown = "fail", auto_accept = None))]
+    fn resolve(
+        &mut self,
+        input: &str,
+        on_unknown: &str,
+        auto_accept: Option<usize>,
+    ) -> PyResult<Vec<String>> {
+        let normalizer = Normalizer::new(&self.db.config().normalize).map_err(to_py_err)?;
+        let input = normalizer.apply(input);
+        if input.is_empty() {
+            return Ok(Vec::new());
+        }
+        let options = ResolveOptions {
+            policy: Some(parse_policy(on_unknown)?),
+            auto_accept: auto_accept.or(self.db.config().defaults.auto_accept_distance),
+            ..Default::default()
+        };
+        let mut resolver = FuzzyResolver::with_prompter(options, NeverPrompt);
+        let resolution = self
+            .db
+            .edit(|config| resolver.resolve(&input, config))
+            .map_err(to_py_err)?;
+        Ok(resolution.tags)
+    }
+
+    /// ファイルのタグを付け外しし、変更の内容を辞書で返す
+    #[pyo3(signature = (path, add = Vec::new(), remove = Vec::new(), dry_run = false, on_unknown = "create", tag_key = "tags", sort = true))]
+    #[allow(clippy::too_many_arguments)]
+    fn update_file<'py>(
+        &mut self,
+        py: Python<'py>,
+        path: PathBuf,
+        add: Vec<String>,
+        remove: Vec<String>,
+        dry_run: bool,
+        on_unknown: &str,
+        tag_key: &str,
+        sort: bool,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let changes = TagOperation::new(&path)
+            .add(add)
+            .remove(remove)
+            .dry_run(dry_run)
+            .on_unknown(parse_policy(on_unknown)?)
+            .front_matter(FrontMatterOptions {
+                tag_key: tag_key.to_string(),
+                sort,
+                ..Default::default()
+            })
+            .execute(&mut self.db)
+            .map_err(to_py_err)?;
+        let result = PyDict::new(py);
+        result.set_item("path", changes.path)?;
+        result.set_item("added", changes.added)?;
+        result.set_item("removed", changes.removed)?;
+        result.set_item("new_tags", changes.new_tags)?;
+        result.set_item("applied", changes.applied)?;
+        result.set_item("content", changes.after)?;
+        Ok(result)
+    }
+
+    /// データベースを書き出す
+    fn save(&self) -> PyResult<()> {
+        self.db.save(&self.path).map_err(to_py_err)
+    }
+}
+
+#[pymodule]
+fn mdtagger(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse_front_matter, m)?)?;
+    m.add_function(wrap_pyfunction!(read_tags, m)?)?;
+    m.add_class::<PyDatabase>()?;
+    Ok(())
+}
@@ -0,0 +1,334 @@
+// --- タグの検索式 (find) ---
+//
+// 例: rust AND (cli OR tui) AND NOT draft
+// - AND・OR・NOT は大文字で書く (小文字の and や not はタグ名として読む)。優先順位は NOT > AND > OR
+// - 演算子を書かずに並べたタグは AND でつなぐ (rust cli は rust AND cli)
+// - 空白や括弧を含むタグは "..." で囲む
+
+use anyhow::{Result, bail};
+use mdtagger::tr;
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Query {
+    Tag(String),
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Tag(String),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Tag(tag) => write!(f, "'{}'", tag),
+            Token::And => f.write_str("AND"),
+            Token::Or => f.write_str("OR"),
+            Token::Not => f.write_str("NOT"),
+            Token::Open => f.write_str("'('"),
+            Token::Close => f.write_str("')'"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '"' => {
+                chars.next();
+                let mut tag = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => tag.push(c),
+                        None => bail!(tr!(
+                            "Unclosed quote in the query",
+                            "検索式の \" が閉じていません"
+                        )),
+                    }
+                }
+                tokens.push(Token::Tag(tag));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Tag(word),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        self.pos += 1;
+        self.tokens.get(self.pos - 1)
+    }
+
+    fn or(&mut self) -> Result<Query> {
+        let mut terms = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Query::Or(terms)
+        })
+    }
+
+    fn and(&mut self) -> Result<Query> {
+        let mut terms = vec![self.not()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                // 並べただけのタグも AND
+                Some(Token::Tag(_) | Token::Not | Token::Open) => {}
+                _ => break,
+            }
+            terms.push(self.not()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Query::And(terms)
+        })
+    }
+
+    fn not(&mut self) -> Result<Query> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Query> {
+        match self.next() {
+            Some(Token::Tag(tag)) => Ok(Query::Tag(tag.clone())),
+            Some(Token::Open) => {
+                let query = self.or()?;
+                if self.next() != Some(&Token::Close) {
+                    bail!(tr!("Missing ')' in the query", "検索式の ')' が足りません"));
+                }
+                Ok(query)
+            }
+            Some(token) => bail!(tr!(
+                "Unexpected {} in the query (expected a tag or '(')",
+                "検索式の {} の位置が不正です (タグか '(' が必要です)",
+                token
+            )),
+            None => bail!(tr!(
+                "The query ends too early (expected a tag or '(')",
+                "検索式が途中で終わっています (タグか '(' が必要です)"
+            )),
+        }
+    }
+}
+
+impl Query {
+    /// 検索式を読む (空なら None)
+    pub fn parse(input: &str) -> Result<Option<Self>> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.or()?;
+        if let Some(token) = parser.peek() {
+            bail!(tr!(
+                "Unexpected {} in the query",
+                "検索式の {} の位置が不正です",
+                token
+            ));
+        }
+        Ok(Some(query))
+    }
+
+    /// 式の中のタグを書き換える (DBの正式名に揃えるなど)
+    pub fn map_tags(self, f: &impl Fn(String) -> String) -> Self {
+        match self {
+            Query::Tag(tag) => Query::Tag(f(tag)),
+            Query::Not(query) => Query::Not(Box::new(query.map_tags(f))),
+            Query::And(terms) => Query::And(terms.into_iter().map(|q| q.map_tags(f)).collect()),
+            Query::Or(terms) => Query::Or(terms.into_iter().map(|q| q.map_tags(f)).collect()),
+        }
+    }
+
+    /// tagged(タグ) がそのタグの付いたものを返すとして、all のうち式に合うものを返す
+    pub fn select<T: Ord + Clone>(
+        &self,
+        all: &BTreeSet<T>,
+        tagged: &impl Fn(&str) -> BTreeSet<T>,
+    ) -> BTreeSet<T> {
+        match self {
+            Query::Tag(tag) => tagged(tag),
+            Query::Not(query) => all
+                .difference(&query.select(all, tagged))
+                .cloned()
+                .collect(),
+            Query::And(terms) => terms
+                .iter()
+                .map(|q| q.select(all, tagged))
+                .reduce(|a, b| a.intersection(&b).cloned().collect())
+                .unwrap_or_else(|| all.clone()),
+            Query::Or(terms) => terms.iter().flat_map(|q| q.select(all, tagged)).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 式の形を S 式で見る
+    fn show(query: &Query) -> String {
+        let list = |op: &str, terms: &[Query]| {
+            let terms: Vec<String> = terms.iter().map(show).collect();
+            format!("({} {})", op, terms.join(" "))
+        };
+        match query {
+            Query::Tag(tag) => tag.clone(),
+            Query::Not(query) => format!("(not {})", show(query)),
+            Query::And(terms) => list("and", terms),
+            Query::Or(terms) => list("or", terms),
+        }
+    }
+
+    fn parse(input: &str) -> String {
+        show(&Query::parse(input).unwrap().unwrap())
+    }
+
+    fn error(input: &str) -> String {
+        Query::parse(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_and_and_than_or() {
+        assert_eq!(parse("a OR b AND c"), "(or a (and b c))");
+        assert_eq!(parse("a AND b OR c"), "(or (and a b) c)");
+        assert_eq!(parse("NOT a AND b"), "(and (not a) b)");
+        assert_eq!(parse("NOT NOT a"), "(not (not a))");
+        assert_eq!(
+            parse("rust AND (cli OR tui) AND NOT draft"),
+            "(and rust (or cli tui) (not draft))"
+        );
+    }
+
+    #[test]
+    fn adjacent_terms_are_joined_with_and() {
+        assert_eq!(parse("rust cli"), "(and rust cli)");
+        assert_eq!(
+            parse("rust NOT draft OR tui"),
+            "(or (and rust (not draft)) tui)"
+        );
+        assert_eq!(parse("a(b OR c)"), "(and a (or b c))");
+    }
+
+    #[test]
+    fn lowercase_operators_and_quoted_words_are_tags() {
+        assert_eq!(parse("and OR not"), "(or and not)");
+        assert_eq!(
+            parse("\"machine learning\" \"OR\""),
+            "(and machine learning OR)"
+        );
+        assert!(Query::parse("  ").unwrap().is_none());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(error("\"rust"), "Unclosed quote in the query");
+        assert_eq!(error("(rust OR cli"), "Missing ')' in the query");
+        assert_eq!(error("rust)"), "Unexpected ')' in the query");
+        assert_eq!(
+            error("rust AND"),
+            "The query ends too early (expected a tag or '(')"
+        );
+        assert_eq!(
+            error("OR rust"),
+            "Unexpected OR in the query (expected a tag or '(')"
+        );
+        assert_eq!(
+            error("()"),
+            "Unexpected ')' in the query (expected a tag or '(')"
+        );
+    }
+
+    #[test]
+    fn select_evaluates_over_the_tag_sets() {
+        let files: BTreeSet<usize> = (0..4).collect();
+        // 0: rust cli, 1: rust tui draft, 2: python cli, 3: タグなし
+        let tagged = |tag: &str| -> BTreeSet<usize> {
+            match tag {
+                "rust" => [0, 1].into(),
+                "cli" => [0, 2].into(),
+                "tui" => [1].into(),
+                "draft" => [1].into(),
+                "python" => [2].into(),
+                _ => BTreeSet::new(),
+            }
+        };
+        let select = |input: &str| -> Vec<usize> {
+            let query = Query::parse(input).unwrap().unwrap();
+            query.select(&files, &tagged).into_iter().collect()
+        };
+        assert_eq!(select("rust AND (cli OR tui) AND NOT draft"), [0]);
+        assert_eq!(select("rust OR python"), [0, 1, 2]);
+        assert_eq!(select("NOT rust"), [2, 3]);
+        assert_eq!(select("NOT (cli OR tui)"), [3]);
+        assert!(select("go").is_empty());
+    }
+
+    #[test]
+    fn map_tags_rewrites_every_tag() {
+        let query = Query::parse("RS AND NOT (Cli OR x)").unwrap().unwrap();
+        let query = query.map_tags(&|tag| tag.to_lowercase());
+        assert_eq!(show(&query), "(and rs (not (or cli x)))");
+    }
+}
@@ -0,0 +1,1062 @@
+// --- タグ解決 ---
+//
+// 入力されたタグをDBの正式名に対応付ける。解決の仕方は `TagResolver` で差し替えられる。
+// CLI は `FuzzyResolver` (あいまい検索 + 対話) を使う。確認の出し方は `Prompter` で差し替えられる。
+
+use crate::db::{TagConfig, TagEntry, register_new_tag};
+use crate::error::{IoContext, MdtaggerError, Result};
+use crate::normalize::{fold_diacritics, singular_forms, soundex};
+use crate::prompt::Prompter;
+use crate::tr;
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use log::{debug, info, trace};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use strsim::levenshtein;
+
+/// 未知のタグに対する非対話時の解決ポリシー
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum UnknownPolicy {
+    /// 新しいタグとしてDBに登録する
+    Create,
+    /// そのタグを追加しない
+    Skip,
+    /// エラーとして終了する
+    Fail,
+    /// 最も近い既存タグを使う (候補がなければ skip と同じ)
+    BestMatch,
+}
+
+/// create / skip / fail / best-match (大文字・小文字は区別しない)
+impl FromStr for UnknownPolicy {
+    type Err = MdtaggerError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "create" => Ok(UnknownPolicy::Create),
+            "skip" => Ok(UnknownPolicy::Skip),
+            "fail" => Ok(UnknownPolicy::Fail),
+            "best-match" => Ok(UnknownPolicy::BestMatch),
+            _ => Err(MdtaggerError::InvalidArgument(tr!(
+                "Unknown policy {:?} (expected create, skip, fail or best-match)",
+                "未知のタグの扱い {:?} は使えません (create / skip / fail / best-match のどれか)",
+                s
+            ))),
+        }
+    }
+}
+
+/// タグ解決の挙動を決めるオプション
+#[derive(Default)]
+pub struct ResolveOptions {
+    /// None のときは対話的に確認する
+    pub policy: Option<UnknownPolicy>,
+    /// 発音ベースの候補探索を行うか
+    pub phonetic: bool,
+    /// この距離以内の候補が1つだけなら確認せずに採用する
+    pub auto_accept: Option<usize>,
+    /// 同義語のグループ
+    pub synonyms: Vec<Vec<String>>,
+    /// あいまい検索で許す編集距離の上限 (None なら 3)
+    pub max_distance: Option<usize>,
+    /// 確認をすべて「はい」(既定の選択肢) で答える (FuzzyResolver::new の TerminalPrompter に渡す)
+    pub assume_yes: bool,
+    /// 埋め込みによる意味的な候補探索
+    #[cfg(feature = "embeddings")]
+    pub semantic: Option<crate::embeddings::SemanticIndex>,
+}
+
+/// 1つの入力タグに対する解決結果
+#[derive(Debug, Clone)]
+pub struct Resolution {
+    /// 付与するタグ (空ならスキップ)
+    pub tags: Vec<String>,
+    /// DBを更新したか
+    pub updated: bool,
+    /// ユーザーに確認して決めたか
+    pub prompted: bool,
+    /// DBになかった新しいタグか
+    pub created: bool,
+    /// どうやって決めたか (exact, alias, typo-correction など。判断の記録に使う)
+    pub method: &'static str,
+    /// あいまい検索などで見つかった候補 (タグ名, 編集距離)
+    pub candidates: Vec<(String, usize)>,
+}
+
+impl Resolution {
+    pub fn new(tag: Option<String>, updated: bool) -> Self {
+        Self::many(tag.into_iter().collect(), updated)
+    }
+
+    pub fn many(tags: Vec<String>, updated: bool) -> Self {
+        Self {
+            tags,
+            updated,
+            prompted: false,
+            created: false,
+            method: "exact",
+            candidates: Vec::new(),
+        }
+    }
+
+    /// 決まったタグの、候補の中での編集距離 (候補から選ばれなかったときは None)
+    pub fn distance(&self) -> Option<usize> {
+        self.candidates
+            .iter()
+            .find(|(tag, _)| self.tags.contains(tag))
+            .map(|(_, distance)| *distance)
+    }
+
+    pub fn via(mut self, method: &'static str) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn prompted(mut self) -> Self {
+        self.prompted = true;
+        self
+    }
+
+    pub fn created(mut self) -> Self {
+        self.created = true;
+        self
+    }
+}
+
+/// 正規化済みの入力タグを解決する戦略
+pub trait TagResolver {
+    fn resolve(&mut self, input: &str, config: &mut TagConfig) -> Result<Resolution>;
+}
+
+/// クロージャでもその場限りの戦略を書けるようにする (テストやスクリプト向け)
+impl<F> TagResolver for F
+where
+    F: FnMut(&str, &mut TagConfig) -> Result<Resolution>,
+{
+    fn resolve(&mut self, input: &str, config: &mut TagConfig) -> Result<Resolution> {
+        self(input, config)
+    }
+}
+
+/// 完全一致・エイリアス・略語だけを使い、未知のタグは確認せずポリシーに従う
+pub struct StrictResolver {
+    pub policy: UnknownPolicy,
+}
+
+impl TagResolver for StrictResolver {
+    fn resolve(&mut self, input: &str, config: &mut TagConfig) -> Result<Resolution> {
+        if let Some(resolution) = resolve_abbreviation(input, config) {
+            return Ok(resolution);
+        }
+        if let Some(entry) = find_exact(input, config) {
+            let method = if entry.name == input {
+                "exact"
+            } else {
+                "alias"
+            };
+            return Ok(Resolution::new(Some(entry.name.clone()), false).via(method));
+        }
+        resolve_unknown_non_interactive(input, config, &[], self.policy)
+    }
+}
+
+/// あいまい検索で候補を探し、必要ならユーザーに確認する標準の戦略
+pub struct FuzzyResolver {
+    pub options: ResolveOptions,
+    pub prompter: Box<dyn Prompter>,
+}
+
+impl FuzzyResolver {
+    /// 端末で尋ねる (options.assume_yes なら既定の答えを使う)。
+    /// interactive フィーチャーがなければ尋ねずに失敗する (NeverPrompt)
+    pub fn new(options: ResolveOptions) -> Self {
+        #[cfg(feature = "interactive")]
+        let prompter = Box::new(crate::prompt::TerminalPrompter::new(options.assume_yes));
+        #[cfg(not(feature = "interactive"))]
+        let prompter = Box::new(crate::prompt::NeverPrompt);
+        Self { options, prompter }
+    }
+
+    /// 尋ね方を差し替える
+    pub fn with_prompter(options: ResolveOptions, prompter: impl Prompter + 'static) -> Self {
+        Self {
+            options,
+            prompter: Box::new(prompter),
+        }
+    }
+}
+
+impl TagResolver for FuzzyResolver {
+    fn resolve(&mut self, input: &str, config: &mut TagConfig) -> Result<Resolution> {
+        resolve_fuzzy(input, config, &self.options, self.prompter.as_mut())
+    }
+}
+
+fn find_exact<'a>(input: &str, config: &'a TagConfig) -> Option<&'a TagEntry> {
+    config
+        .tags
+        .iter()
+        .find(|entry| entry.name == input || entry.aliases.iter().any(|a| a == input))
+}
+
+/// 略語の展開 (確認なしで正式名にする)
+fn resolve_abbreviation(input: &str, config: &TagConfig) -> Option<Resolution> {
+    let expanded = config.abbreviations.get(input)?;
+    let name = config
+        .tags
+        .iter()
+        .find(|e| &e.name == expanded || e.aliases.contains(expanded))
+        .map_or(expanded, |e| &e.name)
+        .clone();
+    info!(
+        "{}",
+        tr!(
+            "   Expanding '{}' -> '{}'",
+            "   '{}' を '{}' に展開",
+            input,
+            name
+        )
+    );
+    Some(Resolution::new(Some(name), false).via("abbreviation"))
+}
+
+fn resolve_fuzzy(
+    input: &str,
+    config: &mut TagConfig,
+    options: &ResolveOptions,
+    prompter: &mut dyn Prompter,
+) -> Result<Resolution> {
+    trace!("Resolving '{}'", input);
+    // 0. 略語の展開
+    if let Some(resolution) = resolve_abbreviation(input, config) {
+        return Ok(resolution);
+    }
+    // A. 完全一致
+    if let Some(entry) = find_exact(input, config) {
+        if entry.name != input {
+            info!(
+                "{}",
+                tr!(
+                    "   Mapping '{}' -> '{}'",
+                    "   '{}' を '{}' として扱います",
+                    input,
+                    entry.name
+                )
+            );
+        }
+        let method = if entry.name == input {
+            "exact"
+        } else {
+            "alias"
+        };
+        return Ok(Resolution::new(Some(entry.name.clone()), false).via(method));
+    }
+    // A'. アクセント記号の有無だけの違いは同じタグとみなす (cafe -> café)
+    let folded = fold_diacritics(input);
+    for entry in &config.tags {
+        if fold_diacritics(&entry.name) == folded
+            || entry.aliases.iter().any(|a| fold_diacritics(a) == folded)
+        {
+            info!(
+                "{}",
+                tr!(
+                    "   Mapping '{}' -> '{}'",
+                    "   '{}' を '{}' として扱います",
+                    input,
+                    entry.name
+                )
+            );
+            return Ok(Resolution::new(Some(entry.name.clone()), false).via("diacritics"));
+        }
+    }
+    // A''. 整形ルールを通すと既存のタグと同じになる ("Machine Learning" -> "machine-learning")
+    if config.slug.is_enabled() {
+        let slug = config.slug.apply(input);
+        if let Some(entry) = config.tags.iter().find(|e| e.name == slug) {
+            info!(
+                "{}",
+                tr!(
+                    "   Mapping '{}' -> '{}'",
+                    "   '{}' を '{}' として扱います",
+                    input,
+                    entry.name
+                )
+            );
+            return Ok(Resolution::new(Some(entry.name.clone()), false).via("slug"));
+        }
+    }
+    let defaults = config.defaults.clone();
+
+    // B. あいまい検索
+    // 短い入力 (go など) は距離3以内だと何にでも一致してしまうので、
+    // 一定の長さ未満なら行わず、それ以上でも長さに応じて許す距離を絞る
+    let input_len = folded.chars().count();
+    let fuzzy_allowed = input_len >= defaults.min_fuzzy_length.unwrap_or(3);
+    let max_dist = options.max_distance.unwrap_or(3).min(input_len / 2);
+    let mut suggestions: Vec<(usize, usize)> = if fuzzy_allowed {
+        config
+            .tags
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (i, levenshtein(&fold_diacritics(&t.name), &folded)))
+            .filter(|(_, dist)| *dist <= max_dist)
+            .collect()
+    } else {
+        debug!(
+            "Skipping fuzzy search for '{}' (shorter than {} characters)",
+            input,
+            defaults.min_fuzzy_length.unwrap_or(3)
+        );
+        Vec::new()
+    };
+    trace!("Fuzzy search for '{}' within distance {}", input, max_dist);
+    // 発音が同じタグは距離が離れていても候補に加える
+    if options.phonetic
+        && fuzzy_allowed
+        && let Some(code) = soundex(input)
+    {
+        for (i, t) in config.tags.iter().enumerate() {
+            if !suggestions.iter().any(|(idx, _)| *idx == i)
+                && soundex(&t.name) == Some(code.clone())
+            {
+                suggestions.push((i, levenshtein(&t.name, input)));
+            }
+        }
+    }
+    // 単数形・複数形の違いだけのタグも候補に加える (notes -> note)。
+    // 複数形に見えるだけの語 (news -> new) もあるので、そのまま対応付けずに確かめる
+    let mut input_forms = singular_forms(input);
+    input_forms.push(input.to_string());
+    for (i, t) in config.tags.iter().enumerate() {
+        let is_plural = std::iter::once(&t.name).chain(&t.aliases).any(|name| {
+            let mut forms = singular_forms(name);
+            forms.push(name.clone());
+            forms.iter().any(|form| input_forms.contains(form))
+        });
+        if is_plural && !suggestions.iter().any(|(idx, _)| *idx == i) {
+            suggestions.push((i, levenshtein(&t.name, input)));
+        }
+    }
+    // 同義語ファイルで同じグループにあるタグも候補に加える (明示的な対応なので最優先)
+    for group in options
+        .synonyms
+        .iter()
+        .filter(|g| g.iter().any(|w| w == input))
+    {
+        for (i, t) in config.tags.iter().enumerate() {
+            let is_synonym = group.iter().any(|w| *w == t.name || t.aliases.contains(w));
+            if is_synonym {
+                suggestions.retain(|(idx, _)| *idx != i);
+                suggestions.push((i, 0));
+            }
+        }
+    }
+    // 埋め込みで意味が近いタグを上位3件まで加える
+    #[cfg(feature = "embeddings")]
+    if let Some(semantic) = &options.semantic {
+        let names: Vec<String> = config.tags.iter().map(|t| t.name.clone()).collect();
+        for (i, _score) in semantic.similar(input, &names)?.into_iter().take(3) {
+            if !suggestions.iter().any(|(idx, _)| *idx == i) {
+                suggestions.push((i, levenshtein(&config.tags[i].name, input)));
+            }
+        }
+    }
+    // 過去に断られた候補は後ろへ回し、距離が同じならよく使われているタグを優先する
+    suggestions.sort_by_key(|(i, dist)| {
+        let entry = &config.tags[*i];
+        (is_rejected(entry, input), *dist, Reverse(entry.uses))
+    });
+    let candidates: Vec<(String, usize)> = suggestions
+        .iter()
+        .map(|(idx, dist)| (config.tags[*idx].name.clone(), *dist))
+        .collect();
+    let mut resolution = choose(input, config, options, prompter, &suggestions)?;
+    resolution.candidates = candidates;
+    Ok(resolution)
+}
+
+/// 候補 (suggestions) から決める。自動で決まらなければユーザーに尋ねる
+fn choose(
+    input: &str,
+    config: &mut TagConfig,
+    options: &ResolveOptions,
+    prompter: &mut dyn Prompter,
+    suggestions: &[(usize, usize)],
+) -> Result<Resolution> {
+    let defaults = config.defaults.clone();
+    for (idx, dist) in suggestions {
+        let entry = &config.tags[*idx];
+        debug!(
+            "Candidate for '{}': '{}' (distance {}, uses {}{})",
+            input,
+            entry.name,
+            dist,
+            entry.uses,
+            if is_rejected(entry, input) {
+                ", rejected before"
+            } else {
+                ""
+            }
+        );
+    }
+
+    // 十分に近い候補が1つしかなければ迷う余地がないので、そのまま採用する
+    if let Some(max_dist) = options.auto_accept {
+        let mut close = suggestions
+            .iter()
+            .filter(|(idx, dist)| *dist <= max_dist && !is_rejected(&config.tags[*idx], input));
+        if let (Some((idx, dist)), None) = (close.next(), close.next()) {
+            let name = config.tags[*idx].name.clone();
+            info!(
+                "{}",
+                tr!(
+                    "   Mapping '{}' -> '{}' (auto-accepted, distance {})",
+                    "   '{}' を '{}' として扱います (自動採用、距離 {})",
+                    input,
+                    name,
+                    dist
+                )
+            );
+            return Ok(Resolution::new(Some(name), false).via("auto-accept"));
+        }
+    }
+
+    if let Some(policy) = options.policy {
+        return resolve_unknown_non_interactive(input, config, suggestions, policy);
+    }
+
+    let mut rejected_any = false;
+
+    if !suggestions.is_empty() {
+        let best_match_idx = suggestions[0].0;
+        let best_match_name = config.tags[best_match_idx].name.clone();
+
+        if defaults.register_aliases == Some(true) {
+            info!(
+                "{}",
+                tr!(
+                    "   Registering '{}' as alias for '{}' (configured default)",
+                    "   '{}' を '{}' のエイリアスとして登録 (既定の設定)",
+                    input,
+                    best_match_name
+                )
+            );
+            config.tags[best_match_idx].aliases.push(input.to_string());
+            return Ok(Resolution::new(Some(best_match_name), true).via("default-alias"));
+        }
+
+        let mut selections = Vec::new();
+        for (idx, _dist) in suggestions {
+            let tag_name = &config.tags[*idx].name;
+            selections.push(tr!(
+                "Use existing '{}' (Typo correction)",
+                "既存の '{}' を使う (打ち間違いの修正)",
+                tag_name
+            ));
+        }
+        let offer_alias = defaults.register_aliases != Some(false);
+        let alias_choice = offer_alias.then_some(selections.len());
+        if offer_alias {
+            selections.push(tr!(
+                "Register '{}' as alias for '{}'",
+                "'{}' を '{}' のエイリアスとして登録",
+                input,
+                best_match_name
+            ));
+        }
+        // 候補が複数あるときは、いくつかまとめて付けられるようにする
+        let multi_choice = (suggestions.len() > 1).then_some(selections.len());
+        if multi_choice.is_some() {
+            selections.push(tr!(
+                "Use several of the suggested tags...",
+                "候補からいくつか選んで使う..."
+            ));
+        }
+        selections.push(tr!("Create new tag '{}'", "新しいタグ '{}' を作る", input));
+
+        // 未登録であることも問いの一部として Prompter に渡す (スクリプトの Prompter でも受け取れる)
+        let selection = prompter.ask_select(
+            &tr!(
+                "Tag '{}' is unknown. How to handle this?",
+                "タグ '{}' は未登録です。どうしますか?",
+                input
+            ),
+            &selections,
+            0,
+        )?;
+
+        if selection < suggestions.len() {
+            let target_idx = suggestions[selection].0;
+            let threshold = defaults.suggest_alias_after.unwrap_or(3);
+            let entry = &mut config.tags[target_idx];
+            forget_rejection(entry, input);
+            learn_correction(entry, input, threshold, offer_alias, prompter)?;
+            return Ok(Resolution::new(Some(entry.name.clone()), true)
+                .prompted()
+                .via("typo-correction"));
+        } else if alias_choice == Some(selection) {
+            forget_rejection(&mut config.tags[best_match_idx], input);
+            config.tags[best_match_idx].aliases.push(input.to_string());
+            return Ok(Resolution::new(Some(best_match_name.clone()), true)
+                .prompted()
+                .via("new-alias"));
+        } else if multi_choice == Some(selection) {
+            let names: Vec<String> = suggestions
+                .iter()
+                .map(|(idx, _)| config.tags[*idx].name.clone())
+                .collect();
+            let picked = prompter.ask_multi_select(
+                &tr!(
+                    "Tags to use for '{}' (space to toggle)",
+                    "'{}' の代わりに使うタグ (スペースで選択)",
+                    input
+                ),
+                &names,
+                &[],
+            )?;
+            let mut updated = false;
+            for &i in &picked {
+                updated |= forget_rejection(&mut config.tags[suggestions[i].0], input);
+            }
+            let tags = picked.into_iter().map(|i| names[i].clone()).collect();
+            return Ok(Resolution::many(tags, updated).prompted().via("multiple"));
+        }
+
+        // 新規作成を選んだ = 提示した候補はすべて断られた
+        for (idx, _dist) in suggestions {
+            let entry = &mut config.tags[*idx];
+            if !is_rejected(entry, input) {
+                entry.rejected.push(input.to_string());
+                rejected_any = true;
+            }
+        }
+    }
+
+    // C. 新規登録
+    let name = config.slug.apply(input);
+    let mut prompted = !suggestions.is_empty();
+    let confirm = match defaults.register_new_tags {
+        Some(answer) => answer,
+        None => {
+            prompted = true;
+            prompter.ask_confirm(
+                &tr!(
+                    "Register new tag '{}' to database?",
+                    "新しいタグ '{}' をデータベースに登録しますか?",
+                    name
+                ),
+                true,
+            )?
+        }
+    };
+
+    let resolution = if confirm {
+        Resolution::new(Some(register_new_tag(input, config)), true).via("new-tag")
+    } else {
+        Resolution::new(Some(name), rejected_any).via("unregistered")
+    }
+    .created();
+    Ok(if prompted {
+        resolution.prompted()
+    } else {
+        resolution
+    })
+}
+
+/// 同じ打ち間違いを何度も直しているなら、エイリアスとして覚えるか尋ねる
+fn learn_correction(
+    entry: &mut TagEntry,
+    input: &str,
+    threshold: u32,
+    offer_alias: bool,
+    prompter: &mut dyn Prompter,
+) -> Result<()> {
+    let count = entry.corrections.entry(input.to_string()).or_default();
+    *count += 1;
+    if !offer_alias || *count < threshold {
+        return Ok(());
+    }
+
+    let register = prompter.ask_confirm(
+        &tr!(
+            "You have corrected '{}' to '{}' {} times. Register it as an alias?",
+            "'{}' を '{}' に {} 回直しています。エイリアスとして登録しますか?",
+            input,
+            entry.name,
+            count
+        ),
+        true,
+    )?;
+    // 断られた場合も数え直して、しばらくは尋ねない
+    entry.corrections.remove(input);
+    if register {
+        entry.aliases.push(input.to_string());
+    }
+    Ok(())
+}
+
+fn is_rejected(entry: &TagEntry, input: &str) -> bool {
+    entry.rejected.iter().any(|r| r == input)
+}
+
+fn forget_rejection(entry: &mut TagEntry, input: &str) -> bool {
+    let before = entry.rejected.len();
+    entry.rejected.retain(|r| r != input);
+    entry.rejected.len() != before
+}
+
+// --- ロジック: 非対話モードでの未知タグの扱い ---
+fn resolve_unknown_non_interactive(
+    input: &str,
+    config: &mut TagConfig,
+    suggestions: &[(usize, usize)],
+    policy: UnknownPolicy,
+) -> Result<Resolution> {
+    match policy {
+        UnknownPolicy::Create => {
+            let name = register_new_tag(input, config);
+            info!(
+                "{}",
+                tr!(
+                    "   Registering new tag '{}'",
+                    "   新しいタグ '{}' を登録",
+                    name
+                )
+            );
+            Ok(Resolution::new(Some(name), true)
+                .created()
+                .via("policy-create"))
+        }
+        UnknownPolicy::Skip => {
+            info!(
+                "{}",
+                tr!(
+                    "   Skipping unknown tag '{}'",
+                    "   未登録のタグ '{}' をスキップ",
+                    input
+                )
+            );
+            Ok(Resolution::new(None, false).via("policy-skip"))
+        }
+        UnknownPolicy::Fail => Err(MdtaggerError::UnknownTag(input.to_string())),
+        UnknownPolicy::BestMatch => {
+            let best = suggestions
+                .iter()
+                .min_by_key(|(idx, dist)| (is_rejected(&config.tags[*idx], input), *dist));
+            match best {
+                Some((idx, _)) => {
+                    let name = config.tags[*idx].name.clone();
+                    info!(
+                        "{}",
+                        tr!(
+                            "   Mapping '{}' -> '{}' (best match)",
+                            "   '{}' を '{}' として扱います (最も近い候補)",
+                            input,
+                            name
+                        )
+                    );
+                    Ok(Resolution::new(Some(name), false).via("policy-best-match"))
+                }
+                None => {
+                    info!(
+                        "{}",
+                        tr!(
+                            "   No close match for '{}', skipping",
+                            "   '{}' に近いタグがないためスキップ",
+                            input
+                        )
+                    );
+                    Ok(Resolution::new(None, false).via("policy-best-match"))
+                }
+            }
+        }
+    }
+}
+
+/// 新しいタグと編集距離が 1〜2 の既存タグ
+pub fn near_duplicates<'a>(config: &'a TagConfig, tag: &str) -> Vec<&'a str> {
+    let folded = fold_diacritics(tag);
+    config
+        .tags
+        .iter()
+        .filter(|e| e.name != tag)
+        .filter(|e| (1..=2).contains(&levenshtein(&fold_diacritics(&e.name), &folded)))
+        .map(|e| e.name.as_str())
+        .collect()
+}
+
+/// 同義語ファイルを読む (空行と # で始まる行は無視)
+pub fn load_synonyms(path: &Path) -> Result<Vec<Vec<String>>> {
+    let message = || {
+        tr!(
+            "Failed to read synonyms file: {:?}",
+            "同義語ファイル {:?} を読み込めません",
+            path
+        )
+    };
+    let file = File::open(path).io_context(message)?;
+    let mut groups = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.io_context(message)?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let group: Vec<String> = line
+            .split(',')
+            .map(|w| w.trim().to_string())
+            .filter(|w| !w.is_empty())
+            .collect();
+        if group.len() > 1 {
+            groups.push(group);
+        }
+    }
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{Answer, NeverPrompt, ScriptedPrompter};
+
+    fn config(names: &[&str]) -> TagConfig {
+        TagConfig {
+            tags: names.iter().map(|name| TagEntry::new(name)).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn interactive() -> ResolveOptions {
+        ResolveOptions::default()
+    }
+
+    fn with_policy(policy: UnknownPolicy) -> ResolveOptions {
+        ResolveOptions {
+            policy: Some(policy),
+            ..Default::default()
+        }
+    }
+
+    /// 積んだ答えで解決し、尋ねられた質問と一緒に返す (答えが余ったら失敗にする)
+    fn resolve_scripted(
+        input: &str,
+        config: &mut TagConfig,
+        options: &ResolveOptions,
+        answers: impl IntoIterator<Item = Answer>,
+    ) -> (Result<Resolution>, Vec<String>) {
+        let mut prompter = ScriptedPrompter::new(answers);
+        let resolution = resolve_fuzzy(input, config, options, &mut prompter);
+        assert_eq!(prompter.remaining(), 0, "unused answers");
+        (resolution, prompter.asked)
+    }
+
+    #[test]
+    fn known_tags_resolve_without_asking() {
+        let mut config = config(&["rust", "café", "machine-learning"]);
+        config.tags[0].aliases.push("rs".to_string());
+        config
+            .abbreviations
+            .insert("r".to_string(), "rs".to_string());
+        config.slug.separator = Some("-".to_string());
+        config.slug.lowercase = true;
+        let mut resolve = |input: &str| {
+            let resolution =
+                resolve_fuzzy(input, &mut config, &interactive(), &mut NeverPrompt).unwrap();
+            assert!(!resolution.prompted);
+            (resolution.tags, resolution.method)
+        };
+        assert_eq!(resolve("rust"), (vec!["rust".to_string()], "exact"));
+        assert_eq!(resolve("rs"), (vec!["rust".to_string()], "alias"));
+        assert_eq!(resolve("r"), (vec!["rust".to_string()], "abbreviation"));
+        assert_eq!(resolve("cafe"), (vec!["café".to_string()], "diacritics"));
+        assert_eq!(
+            resolve("Machine Learning"),
+            (vec!["machine-learning".to_string()], "slug")
+        );
+    }
+
+    #[test]
+    fn typo_correction_is_asked_and_counted() {
+        let mut config = config(&["rust"]);
+        let (resolution, asked) =
+            resolve_scripted("rusr", &mut config, &interactive(), [Answer::Select(0)]);
+        let resolution = resolution.unwrap();
+        assert_eq!(resolution.tags, ["rust"]);
+        assert_eq!(resolution.method, "typo-correction");
+        assert!(resolution.prompted);
+        assert_eq!(resolution.distance(), Some(1));
+        assert_eq!(asked, ["Tag 'rusr' is unknown. How to handle this?"]);
+        assert_eq!(config.tags[0].corrections["rusr"], 1);
+    }
+
+    #[test]
+    fn repeated_corrections_offer_an_alias() {
+        let mut config = config(&["rust"]);
+        config.tags[0].corrections.insert("rusr".to_string(), 2);
+        let (resolution, asked) = resolve_scripted(
+            "rusr",
+            &mut config,
+            &interactive(),
+            [Answer::Select(0), Answer::Confirm(true)],
+        );
+        assert_eq!(resolution.unwrap().tags, ["rust"]);
+        assert_eq!(
+            asked[1],
+            "You have corrected 'rusr' to 'rust' 3 times. Register it as an alias?"
+        );
+        assert_eq!(config.tags[0].aliases, ["rusr"]);
+        assert!(config.tags[0].corrections.is_empty());
+    }
+
+    #[test]
+    fn alias_choice_registers_the_input() {
+        let mut config = config(&["rust"]);
+        // 選択肢: rust を使う / エイリアスにする / 新しいタグを作る
+        let (resolution, _) =
+            resolve_scripted("rusr", &mut config, &interactive(), [Answer::Select(1)]);
+        let resolution = resolution.unwrap();
+        assert_eq!(resolution.method, "new-alias");
+        assert!(resolution.updated);
+        assert_eq!(config.tags[0].aliases, ["rusr"]);
+    }
+
+    #[test]
+    fn several_candidates_can_be_picked_together() {
+        let mut config = config(&["rust", "rest"]);
+        // 選択肢: rust / rest / エイリアスにする / いくつか選ぶ / 新しいタグを作る
+        let (resolution, asked) = resolve_scripted(
+            "rast",
+            &mut config,
+            &interactive(),
+            [Answer::Select(3), Answer::MultiSelect(vec![1, 0])],
+        );
+        let resolution = resolution.unwrap();
+        assert_eq!(resolution.tags, ["rest", "rust"]);
+        assert_eq!(resolution.method, "multiple");
+        assert_eq!(asked[1], "Tags to use for 'rast' (space to toggle)");
+    }
+
+    #[test]
+    fn creating_a_new_tag_asks_to_register_it() {
+        let mut config = config(&["rust"]);
+        let (resolution, asked) = resolve_scripted(
+            "python",
+            &mut config,
+            &interactive(),
+            [Answer::Confirm(true)],
+        );
+        let resolution = resolution.unwrap();
+        assert_eq!(resolution.tags, ["python"]);
+        assert_eq!(resolution.method, "new-tag");
+        assert!(resolution.created && resolution.prompted && resolution.updated);
+        assert_eq!(asked, ["Register new tag 'python' to database?"]);
+        assert!(config.tags.iter().any(|e| e.name == "python"));
+
+        let (resolution, _) = resolve_scripted(
+            "golang",
+            &mut config,
+            &interactive(),
+            [Answer::Confirm(false)],
+        );
+        let resolution = resolution.unwrap();
+        assert_eq!(resolution.tags, ["golang"]);
+        assert_eq!(resolution.method, "unregistered");
+        assert!(!resolution.updated);
+        assert!(!config.tags.iter().any(|e| e.name == "golang"));
+    }
+
+    #[test]
+    fn rejected_candidates_are_remembered_and_moved_back() {
+        let mut config = config(&["rust", "rest"]);
+        // 新しいタグを作る (登録はしない) = 候補はすべて断られた
+        let (resolution, _) = resolve_scripted(
+            "rast",
+            &mut config,
+            &interactive(),
+            [Answer::Select(4), Answer::Confirm(false)],
+        );
+        assert!(resolution.unwrap().updated);
+        assert_eq!(config.tags[0].rejected, ["rast"]);
+        assert_eq!(config.tags[1].rejected, ["rast"]);
+
+        // 一度使えば断った記録は消え、その候補が先に出る
+        resolve_scripted("rast", &mut config, &interactive(), [Answer::Select(1)])
+            .0
+            .unwrap();
+        let resolution = resolve_fuzzy(
+            "rast",
+            &mut config,
+            &with_policy(UnknownPolicy::Skip),
+            &mut NeverPrompt,
+        )
+        .unwrap();
+        assert_eq!(resolution.candidates[0].0, "rest");
+    }
+
+    #[test]
+    fn prompts_fail_when_no_answer_is_available() {
+        let mut config = config(&["rust"]);
+        let never = resolve_fuzzy("rusr", &mut config, &interactive(), &mut NeverPrompt);
+        assert!(matches!(never, Err(MdtaggerError::PromptUnavailable(_))));
+
+        // 答えが尽きた
+        let mut prompter = ScriptedPrompter::new([]);
+        let empty = resolve_fuzzy("python", &mut config, &interactive(), &mut prompter);
+        assert!(matches!(empty, Err(MdtaggerError::PromptUnavailable(_))));
+        assert_eq!(prompter.asked, ["Register new tag 'python' to database?"]);
+
+        // 尋ね方と答えの種類が合わない・範囲外の選択肢
+        for answer in [Answer::Confirm(true), Answer::Select(9)] {
+            let mut prompter = ScriptedPrompter::new([answer]);
+            let mismatch = resolve_fuzzy("rusr", &mut config, &interactive(), &mut prompter);
+            assert!(matches!(mismatch, Err(MdtaggerError::PromptUnavailable(_))));
+        }
+        assert!(config.tags[0].rejected.is_empty());
+        assert_eq!(config.tags.len(), 1);
+    }
+
+    #[test]
+    fn default_answers_pick_the_first_choice() {
+        let mut config = config(&["rust"]);
+        let (resolution, _) =
+            resolve_scripted("rusr", &mut config, &interactive(), [Answer::Default]);
+        assert_eq!(resolution.unwrap().method, "typo-correction");
+    }
+
+    #[test]
+    fn plural_forms_are_offered_but_never_mapped_silently() {
+        let mut config = config(&["movie", "news"]);
+        // あいまい検索を切って、単数形・複数形の候補だけを見る
+        let options = ResolveOptions {
+            max_distance: Some(0),
+            ..Default::default()
+        };
+        let (resolution, asked) =
+            resolve_scripted("movies", &mut config, &options, [Answer::Select(0)]);
+        let resolution = resolution.unwrap();
+        assert_eq!(resolution.tags, ["movie"]);
+        assert!(resolution.prompted);
+        assert_eq!(asked.len(), 1);
+
+        let skip = ResolveOptions {
+            max_distance: Some(0),
+            policy: Some(UnknownPolicy::Skip),
+            ..Default::default()
+        };
+        let resolution = resolve_fuzzy("movies", &mut config, &skip, &mut NeverPrompt).unwrap();
+        assert!(resolution.tags.is_empty());
+        assert_eq!(resolution.candidates, [("movie".to_string(), 1)]);
+        // DBのタグが複数形でも、単数形の入力の候補になる
+        let resolution = resolve_fuzzy("new", &mut config, &skip, &mut NeverPrompt).unwrap();
+        assert_eq!(resolution.candidates, [("news".to_string(), 1)]);
+    }
+
+    #[test]
+    fn synonyms_are_candidates() {
+        let mut config = config(&["automobile", "rust"]);
+        let options = ResolveOptions {
+            synonyms: vec![vec!["car".to_string(), "automobile".to_string()]],
+            policy: Some(UnknownPolicy::BestMatch),
+            ..Default::default()
+        };
+        let resolution = resolve_fuzzy("car", &mut config, &options, &mut NeverPrompt).unwrap();
+        assert_eq!(resolution.tags, ["automobile"]);
+        assert_eq!(resolution.candidates, [("automobile".to_string(), 0)]);
+    }
+
+    #[test]
+    fn single_close_candidate_is_auto_accepted() {
+        let options = ResolveOptions {
+            auto_accept: Some(1),
+            ..Default::default()
+        };
+        let mut one = config(&["rust", "python"]);
+        let resolution = resolve_fuzzy("rusr", &mut one, &options, &mut NeverPrompt).unwrap();
+        assert_eq!(resolution.tags, ["rust"]);
+        assert_eq!(resolution.method, "auto-accept");
+        assert!(!resolution.prompted);
+
+        // 近い候補が2つあれば迷うので尋ねる
+        let mut two = config(&["rust", "rest"]);
+        let never = resolve_fuzzy("rast", &mut two, &options, &mut NeverPrompt);
+        assert!(matches!(never, Err(MdtaggerError::PromptUnavailable(_))));
+    }
+
+    #[test]
+    fn fuzzy_resolver_uses_the_given_prompter() {
+        let mut config = config(&["rust"]);
+        let mut resolver =
+            FuzzyResolver::with_prompter(interactive(), ScriptedPrompter::new([Answer::Select(0)]));
+        let resolution = resolver.resolve("rusr", &mut config).unwrap();
+        assert_eq!(resolution.tags, ["rust"]);
+        // 答えを使い切ったので、次に尋ねれば失敗する
+        assert!(matches!(
+            resolver.resolve("rusr", &mut config),
+            Err(MdtaggerError::PromptUnavailable(_))
+        ));
+    }
+
+    #[test]
+    fn strict_resolver_follows_the_policy() {
+        let mut config = config(&["rust"]);
+        config.tags[0].aliases.push("rs".to_string());
+        let strict = |policy| StrictResolver { policy };
+
+        let resolution = strict(UnknownPolicy::Fail)
+            .resolve("rs", &mut config)
+            .unwrap();
+        assert_eq!(
+            (resolution.tags, resolution.method),
+            (vec!["rust".to_string()], "alias")
+        );
+        // 打ち間違いも候補を探さない
+        let resolution = strict(UnknownPolicy::BestMatch)
+            .resolve("rusr", &mut config)
+            .unwrap();
+        assert!(resolution.tags.is_empty());
+        assert!(matches!(
+            strict(UnknownPolicy::Fail).resolve("rusr", &mut config),
+            Err(MdtaggerError::UnknownTag(tag)) if tag == "rusr"
+        ));
+        let resolution = strict(UnknownPolicy::Skip)
+            .resolve("go", &mut config)
+            .unwrap();
+        assert!(resolution.tags.is_empty());
+        let resolution = strict(UnknownPolicy::Create)
+            .resolve("go", &mut config)
+            .unwrap();
+        assert!(resolution.created);
+        assert_eq!(config.tags.last().unwrap().name, "go");
+    }
+
+    #[test]
+    fn closures_are_resolvers() {
+        let mut config = config(&[]);
+        let mut upper = |input: &str, _: &mut TagConfig| {
+            Ok(Resolution::new(Some(input.to_uppercase()), false).via("closure"))
+        };
+        assert_eq!(upper.resolve("rust", &mut config).unwrap().tags, ["RUST"]);
+    }
+
+    #[test]
+    fn unknown_policy_from_str() {
+        assert_eq!(
+            "Best-Match".parse::<UnknownPolicy>().unwrap(),
+            UnknownPolicy::BestMatch
+        );
+        assert!(matches!(
+            "maybe".parse::<UnknownPolicy>(),
+            Err(MdtaggerError::InvalidArgument(_))
+        ));
+    }
+}
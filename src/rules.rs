@@ -0,0 +1,417 @@
+// --- lint の規則 ---
+//
+// 規則ごとに ID・重大度 (error / warning / off)・設定を持つ。設定ファイルの [lint.rules.<ID>] で変え、
+// [[lint.overrides]] でディレクトリごとに上書きする (あとに書いたものほど優先)。
+// タグの検査は lint・hook pre-commit・lsp で共通にし、ファイルの中の位置はそれぞれが決める。
+
+use anyhow::{Result, bail};
+use mdtagger::db::TagDatabase;
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::settings::LintSettings;
+
+/// 規則違反の重大度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// lint と hook pre-commit を失敗させる
+    Error,
+    /// 表示するだけで、失敗させない
+    Warning,
+    /// 検査しない
+    Off,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Off => "off",
+        }
+    }
+}
+
+/// 規則の ID とデフォルトの重大度
+pub const RULES: &[(&str, Severity)] = &[
+    ("unknown-tag", Severity::Error),
+    ("alias", Severity::Error),
+    ("casing", Severity::Error),
+    ("deprecated-tag", Severity::Error),
+    ("required-tag", Severity::Error),
+    ("required-group", Severity::Error),
+    ("too-many-tags", Severity::Error),
+    ("front-matter", Severity::Error),
+    ("read-error", Severity::Error),
+    ("not-in-taxonomy", Severity::Error),
+    ("taxonomy-parent", Severity::Error),
+    ("too-few-tags", Severity::Error),
+    ("exclusive-tags", Severity::Error),
+];
+
+/// 規則の説明 (lint --list-rules)
+pub fn description(id: &str) -> String {
+    match id {
+        "unknown-tag" => tr!("Tag not in the database", "DBにないタグ"),
+        "alias" => tr!(
+            "Tag written as an alias (shows the canonical name)",
+            "エイリアスで書かれたタグ (正式名を表示)"
+        ),
+        "casing" => tr!(
+            "Tag differs from the database only in case",
+            "大文字・小文字だけがDBのタグと違う"
+        ),
+        "deprecated-tag" => tr!(
+            "Tag that should no longer be used (replacements)",
+            "使わなくなったタグ (replacements)"
+        ),
+        "required-tag" => tr!(
+            "A required tag is missing (tags)",
+            "必須のタグが付いていない (tags)"
+        ),
+        "required-group" => tr!(
+            "No tag from a required group (groups)",
+            "必須の組のタグが1つも付いていない (groups)"
+        ),
+        "too-many-tags" => tr!(
+            "More tags than the limit (max)",
+            "タグの数が上限を超えている (max)"
+        ),
+        "front-matter" => tr!("Front matter cannot be read", "Front Matter を読めない"),
+        "read-error" => tr!("File cannot be read", "ファイルを読めない"),
+        "not-in-taxonomy" => tr!(
+            "Tag not in the taxonomy (lint --schema)",
+            "タクソノミーにないタグ (lint --schema)"
+        ),
+        "taxonomy-parent" => tr!(
+            "Tag with children used by itself when leaf_only is set (lint --schema)",
+            "leaf_only のとき、子のあるタグをそのまま付けている (lint --schema)"
+        ),
+        "too-few-tags" => tr!(
+            "Fewer tags than min_tags (lint --schema)",
+            "タグの数が min_tags に足りない (lint --schema)"
+        ),
+        "exclusive-tags" => tr!(
+            "More than one tag from an exclusive group (lint --schema)",
+            "同時に付けられない組のタグが2つ以上ある (lint --schema)"
+        ),
+        _ => String::new(),
+    }
+}
+
+/// 1つの規則の設定 (設定ファイルの [lint.rules.<ID>])。使わない項目は無視する
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleSettings {
+    /// 重大度 (省略時は規則のデフォルト)
+    pub severity: Option<Severity>,
+    /// too-many-tags: 1つのファイルに付けてよいタグの数の上限
+    pub max: Option<usize>,
+    /// required-tag: すべてのファイルに付いていなければならないタグ
+    pub tags: Option<Vec<String>>,
+    /// required-group: 組ごとに、どれか1つが付いていなければならないタグ (lang/* なら lang/ の下のどれか)
+    pub groups: Option<Vec<Vec<String>>>,
+    /// deprecated-tag: 使わなくなったタグ -> 代わりのタグ (空なら外すだけ)
+    pub replacements: Option<BTreeMap<String, String>>,
+}
+
+impl RuleSettings {
+    /// 指定されている項目だけ other で上書きする
+    pub fn merge(&mut self, other: &RuleSettings) {
+        self.severity = other.severity.or(self.severity);
+        self.max = other.max.or(self.max);
+        if other.tags.is_some() {
+            self.tags = other.tags.clone();
+        }
+        if other.groups.is_some() {
+            self.groups = other.groups.clone();
+        }
+        if other.replacements.is_some() {
+            self.replacements = other.replacements.clone();
+        }
+    }
+}
+
+/// ディレクトリごとの規則の上書き (設定ファイルの [[lint.overrides]])
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintOverride {
+    /// 当てはめるディレクトリ (設定ファイルからの相対パスも可)
+    pub dir: PathBuf,
+    pub rules: BTreeMap<String, RuleSettings>,
+}
+
+/// 知らない規則の ID があればエラーにする (綴りの誤りで規則が効かないのを防ぐ)
+pub fn validate(lint: &LintSettings) -> Result<()> {
+    let ids = lint
+        .rules
+        .keys()
+        .chain(lint.overrides.iter().flat_map(|o| o.rules.keys()));
+    for id in ids {
+        if !RULES.iter().any(|(known, _)| known == id) {
+            let known: Vec<&str> = RULES.iter().map(|(id, _)| *id).collect();
+            bail!(tr!(
+                "Unknown lint rule '{}' (rules: {})",
+                "lint の規則 '{}' はありません (規則: {})",
+                id,
+                known.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 1つのファイルに当てはめる規則の設定
+pub struct RuleSet {
+    rules: BTreeMap<&'static str, RuleSettings>,
+}
+
+impl RuleSet {
+    /// path に当てはめる設定 ([lint] の required_tags と max_tags、[lint.rules]、path を含む [[lint.overrides]] の順に重ねる)
+    pub fn for_path(lint: &LintSettings, path: &Path) -> Self {
+        let mut rules: BTreeMap<&'static str, RuleSettings> = RULES
+            .iter()
+            .map(|(id, severity)| {
+                (
+                    *id,
+                    RuleSettings {
+                        severity: Some(*severity),
+                        ..RuleSettings::default()
+                    },
+                )
+            })
+            .collect();
+        if let Some(rule) = rules.get_mut("required-tag") {
+            rule.tags = lint.required_tags.clone();
+        }
+        if let Some(rule) = rules.get_mut("too-many-tags") {
+            rule.max = lint.max_tags;
+        }
+        let mut layers = vec![&lint.rules];
+        let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        for o in &lint.overrides {
+            let dir = fs::canonicalize(&o.dir).unwrap_or_else(|_| o.dir.clone());
+            if canonical.starts_with(&dir) {
+                layers.push(&o.rules);
+            }
+        }
+        for layer in layers {
+            for (id, settings) in layer {
+                if let Some(rule) = rules.get_mut(id.as_str()) {
+                    rule.merge(settings);
+                }
+            }
+        }
+        Self { rules }
+    }
+
+    /// 規則の重大度
+    pub fn severity(&self, id: &str) -> Severity {
+        self.rules
+            .get(id)
+            .and_then(|rule| rule.severity)
+            .unwrap_or(Severity::Error)
+    }
+
+    fn get(&self, id: &str) -> Option<&RuleSettings> {
+        self.rules.get(id)
+    }
+
+    /// rule の違反 (重大度が off なら None)
+    pub fn finding(
+        &self,
+        rule: &'static str,
+        tag: Option<usize>,
+        message: String,
+        replacement: Option<String>,
+    ) -> Option<Finding> {
+        let severity = self.severity(rule);
+        (severity != Severity::Off).then_some(Finding {
+            rule,
+            severity,
+            tag,
+            message,
+            replacement,
+        })
+    }
+
+    /// required-tag のタグを足す (lint --require)
+    pub fn require(&mut self, tags: &[String]) {
+        if let Some(rule) = self.rules.get_mut("required-tag") {
+            rule.tags
+                .get_or_insert_with(Vec::new)
+                .extend_from_slice(tags);
+        }
+    }
+
+    /// too-many-tags の上限を変える (lint --max-tags)
+    pub fn limit(&mut self, max: usize) {
+        if let Some(rule) = self.rules.get_mut("too-many-tags") {
+            rule.max = Some(max);
+        }
+    }
+}
+
+/// タグの規則違反
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    /// 問題のタグの添字 (ファイル全体の問題なら None で、タグのキーの行に出す)
+    pub tag: Option<usize>,
+    pub message: String,
+    /// 代わりに書くタグ (alias・casing・deprecated-tag。lsp の置き換えに使う)
+    #[cfg_attr(not(feature = "lsp"), allow(dead_code))]
+    pub replacement: Option<String>,
+}
+
+/// 1つのタグの問題 (deprecated-tag・alias・casing・unknown-tag の順に調べ、最初のものだけ)
+fn tag_finding(
+    rules: &RuleSet,
+    db: &TagDatabase,
+    tag: &str,
+) -> Option<(&'static str, String, Option<String>)> {
+    let deprecated = rules
+        .get("deprecated-tag")
+        .and_then(|rule| rule.replacements.as_ref())
+        .and_then(|replacements| replacements.get(tag));
+    if let Some(replacement) = deprecated {
+        let message = if replacement.is_empty() {
+            tr!(
+                "Tag '{}' is deprecated (remove it)",
+                "タグ '{}' は使わなくなりました (外してください)",
+                tag
+            )
+        } else {
+            tr!(
+                "Tag '{}' is deprecated (use '{}')",
+                "タグ '{}' は使わなくなりました ('{}' を使ってください)",
+                tag,
+                replacement
+            )
+        };
+        let replacement = (!replacement.is_empty()).then(|| replacement.clone());
+        return Some(("deprecated-tag", message, replacement));
+    }
+    if db.contains(tag) {
+        return None;
+    }
+    if let Some(entry) = db.lookup(tag) {
+        return Some((
+            "alias",
+            tr!(
+                "'{}' is an alias of '{}'",
+                "'{}' は '{}' のエイリアスです",
+                tag,
+                entry.name
+            ),
+            Some(entry.name.clone()),
+        ));
+    }
+    if let Some(entry) = db
+        .tags()
+        .iter()
+        .find(|e| e.name.to_lowercase() == tag.to_lowercase())
+    {
+        return Some((
+            "casing",
+            tr!(
+                "'{}' should be written '{}'",
+                "'{}' は '{}' と書いてください",
+                tag,
+                entry.name
+            ),
+            Some(entry.name.clone()),
+        ));
+    }
+    Some((
+        "unknown-tag",
+        tr!(
+            "Tag '{}' is not in the database",
+            "タグ '{}' はデータベースにありません",
+            tag
+        ),
+        None,
+    ))
+}
+
+/// タグを1つずつ調べる (重大度が off の規則は出さない)
+pub fn check_each_tag(rules: &RuleSet, db: &TagDatabase, tags: &[String]) -> Vec<Finding> {
+    tags.iter()
+        .enumerate()
+        .filter_map(|(i, tag)| {
+            let (rule, message, replacement) = tag_finding(rules, db, tag)?;
+            rules.finding(rule, Some(i), message, replacement)
+        })
+        .collect()
+}
+
+/// tag が pattern に当てはまるか (lang/* なら lang/ の下のどれか)
+pub fn matches_pattern(pattern: &str, tag: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(parent) => tag
+            .strip_prefix(parent)
+            .is_some_and(|rest| rest.starts_with('/')),
+        None => tag == pattern,
+    }
+}
+
+/// ファイルのタグ全体を調べる (required-tag・required-group・too-many-tags)
+pub fn check_file_tags(rules: &RuleSet, tags: &[String]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut report = |rule: &'static str, message: String| {
+        findings.extend(rules.finding(rule, None, message, None));
+    };
+
+    let required = rules
+        .get("required-tag")
+        .and_then(|rule| rule.tags.as_ref());
+    for required in required.into_iter().flatten() {
+        if !tags.contains(required) {
+            report(
+                "required-tag",
+                tr!(
+                    "Missing required tag '{}'",
+                    "必須のタグ '{}' が付いていません",
+                    required
+                ),
+            );
+        }
+    }
+
+    let groups = rules
+        .get("required-group")
+        .and_then(|rule| rule.groups.as_ref());
+    for group in groups.into_iter().flatten() {
+        let matches = |tag: &String| group.iter().any(|pattern| matches_pattern(pattern, tag));
+        if !group.is_empty() && !tags.iter().any(matches) {
+            report(
+                "required-group",
+                tr!(
+                    "Needs one of: {}",
+                    "次のどれかのタグが必要です: {}",
+                    group.join(", ")
+                ),
+            );
+        }
+    }
+
+    if let Some(max) = rules.get("too-many-tags").and_then(|rule| rule.max)
+        && tags.len() > max
+    {
+        report(
+            "too-many-tags",
+            tr!(
+                "{} tags (at most {})",
+                "タグが {} 個あります (上限は {} 個)",
+                tags.len(),
+                max
+            ),
+        );
+    }
+    findings
+}
@@ -0,0 +1,108 @@
+// --- JSON Schema (--schema) ---
+//
+// タグデータベースと --output json の出力の形を、下流のツールが検証やバインディングの生成に
+// 使えるように JSON Schema で公開する。型の定義から作るので、実際の出力と食い違うことはない。
+// 同じ版のあいだはフィールドの追加だけにすること。名前を変えたり消したりするときは
+// SCHEMA_VERSION を上げる ($id に版が入る)。
+
+use clap::ValueEnum;
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, Schema};
+
+use crate::commands;
+use crate::exit::ErrorReport;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// 出力するだけの型の JSON Schema (skip_serializing_if の付いたフィールドは省略できる)
+pub fn for_output<T: JsonSchema>() -> Schema {
+    SchemaSettings::default()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+/// 表示する JSON Schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SchemaKind {
+    /// タグデータベース (tags_db.json)
+    Db,
+    /// lint --schema で読むタクソノミー (taxonomy.yaml)
+    Taxonomy,
+    /// add --output json の結果
+    Add,
+    /// lint --output json の結果
+    Lint,
+    /// duplicates --output json の結果
+    Duplicates,
+    /// find --output json の結果
+    Find,
+    /// orphans --output json の結果
+    Orphans,
+    /// rename / merge --output json の結果
+    Rename,
+    /// similar --output json の結果
+    Similar,
+    /// suggest --output json の結果
+    Suggest,
+    /// stats --output json の結果
+    Stats,
+    /// stats --resolver --output json の結果
+    StatsResolver,
+    /// stats --cooccurrence --output json の結果
+    StatsCooccurrence,
+    /// stats --timeline --output json の結果
+    StatsTimeline,
+    /// stats --by-dir --output json の結果
+    StatsByDir,
+    /// history --output json の結果
+    History,
+    /// export が書き出すタグの索引 (tags.json)
+    Export,
+    /// add --editor-protocol の結果
+    EditorProtocol,
+    /// --output json で失敗したときの出力
+    Error,
+}
+
+impl SchemaKind {
+    /// --schema に渡す名前 (db, stats-resolver など)
+    pub fn name(self) -> String {
+        self.to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn schema(self) -> Schema {
+        let mut schema = match self {
+            SchemaKind::Db => schemars::schema_for!(mdtagger::db::TagConfig),
+            SchemaKind::Taxonomy => schemars::schema_for!(crate::taxonomy::TaxonomyFile),
+            SchemaKind::Add => commands::add::output_schema(),
+            SchemaKind::Lint => commands::lint::output_schema(),
+            SchemaKind::Duplicates => commands::duplicates::output_schema(),
+            SchemaKind::Find => commands::find::output_schema(),
+            SchemaKind::Orphans => commands::orphans::output_schema(),
+            SchemaKind::Rename => commands::rename::output_schema(),
+            SchemaKind::Similar => commands::similar::output_schema(),
+            SchemaKind::Suggest => commands::suggest::output_schema(),
+            SchemaKind::Stats => commands::stats::output_schema(),
+            SchemaKind::StatsResolver => commands::stats::resolver_output_schema(),
+            SchemaKind::StatsCooccurrence => commands::stats::cooccurrence_output_schema(),
+            SchemaKind::StatsTimeline => commands::stats::timeline_output_schema(),
+            SchemaKind::StatsByDir => commands::stats::by_dir_output_schema(),
+            SchemaKind::History => commands::history::output_schema(),
+            SchemaKind::Export => commands::export::output_schema(),
+            SchemaKind::EditorProtocol => commands::add::editor_schema(),
+            SchemaKind::Error => for_output::<ErrorReport>(),
+        };
+        schema.insert(
+            "title".to_string(),
+            format!("mdtagger {}", self.name()).into(),
+        );
+        schema.insert(
+            "$id".to_string(),
+            format!("urn:mdtagger:schema:{}:v{}", self.name(), SCHEMA_VERSION).into(),
+        );
+        schema
+    }
+}
@@ -0,0 +1,429 @@
+// --- 設定ファイル (config.toml) ---
+//
+// ユーザー設定 (~/.config/mdtagger/config.toml) を読み、
+// カレントディレクトリから親へたどって最初に見つかったプロジェクト設定 (.mdtagger.toml) で上書きする。
+// コマンドラインで指定した値は、どちらよりも優先される。
+
+use anyhow::{Context, Result, bail};
+use mdtagger::i18n::Lang;
+use mdtagger::markdown::{FrontMatterFormat, FrontMatterOptions, InlineFieldStyle};
+use mdtagger::prompt::Appearance;
+use mdtagger::resolver::UnknownPolicy;
+use mdtagger::tr;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::obsidian::Vault;
+use crate::rules::{self, LintOverride, RuleSettings};
+use crate::{hugo, jekyll, logseq, zola};
+
+const PROJECT_FILE: &str = ".mdtagger.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// タグデータベースのパス (設定ファイルからの相対パスも可)
+    pub db: Option<PathBuf>,
+    /// タグを入れる Front Matter のキー
+    pub tag_key: Option<String>,
+    /// タグを並べ替えるか
+    pub sort: Option<bool>,
+    /// あいまい検索で許す編集距離の上限
+    pub max_distance: Option<usize>,
+    /// 対話プロンプトを出さずに実行するか
+    pub non_interactive: Option<bool>,
+    /// 非対話モードで未知のタグを見つけたときの扱い
+    pub on_unknown: Option<UnknownPolicy>,
+    /// 確認の答えを待つ時間 (例: "30s")
+    pub prompt_timeout: Option<String>,
+    /// add で毎回かならず付けるタグ
+    pub default_tags: Option<Vec<String>>,
+    /// タグの決まり方を手元に集計するか (stats --resolver で見る)
+    pub usage_stats: Option<bool>,
+    /// 対話モードの add で、一緒に付けることの多いタグを勧めるか (デフォルト: true)
+    pub related_tags: Option<bool>,
+    /// メッセージの言語 (en / ja)。省略時は LANG などから決める
+    pub language: Option<String>,
+    /// プロンプトとメッセージの見た目
+    pub appearance: Appearance,
+    /// lint の規則
+    pub lint: LintSettings,
+    /// 外部コマンドのフック
+    pub hooks: HookSettings,
+    /// [hooks] を使ってよいプロジェクトのディレクトリ (ユーザー設定でだけ書ける)
+    pub trusted_projects: Option<Vec<PathBuf>>,
+    /// 信頼していないので使わなかったプロジェクト設定の [hooks] (その設定ファイル)
+    #[serde(skip)]
+    pub ignored_hooks: Option<PathBuf>,
+    /// Obsidian の保管庫 (.obsidian) の中では、新しいタグの名前を保管庫の書き方に揃えるか (デフォルト: true)
+    pub obsidian: Option<bool>,
+    /// カレントディレクトリを含む Obsidian の保管庫
+    #[serde(skip)]
+    pub vault: Option<Vault>,
+    /// 本文の Dataview のインラインフィールド (tags:: a, b) のタグも読み書きするか (デフォルト: false)
+    pub dataview: Option<bool>,
+    /// Logseq のグラフの中では、ページの先頭のプロパティ (tags:: a, b) にタグを読み書きするか (デフォルト: true)
+    pub logseq: Option<bool>,
+    /// カレントディレクトリを含む Logseq のグラフ
+    #[serde(skip)]
+    pub logseq_graph: Option<PathBuf>,
+    /// Hugo のサイトの中では、サイトのタクソノミーを読むか (デフォルト: true)
+    pub hugo: Option<bool>,
+    /// カレントディレクトリを含む Hugo のサイト
+    #[serde(skip)]
+    pub hugo_site: Option<hugo::Site>,
+    /// Jekyll のサイトの扱い
+    pub jekyll: JekyllSettings,
+    /// カレントディレクトリを含む Jekyll のサイト
+    #[serde(skip)]
+    pub jekyll_site: Option<jekyll::Site>,
+    /// Zola のサイトの中では、タグを taxonomies.tags に TOML で書くか (デフォルト: true)
+    pub zola: Option<bool>,
+    /// カレントディレクトリを含む Zola のサイト
+    #[serde(skip)]
+    pub zola_site: Option<zola::Site>,
+}
+
+/// lint の規則 (設定ファイルの [lint])
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintSettings {
+    /// すべてのファイルに付いていなければならないタグ
+    pub required_tags: Option<Vec<String>>,
+    /// 1つのファイルに付けてよいタグの数の上限
+    pub max_tags: Option<usize>,
+    /// 規則の ID -> 重大度と設定 ([lint.rules.<ID>])
+    pub rules: BTreeMap<String, RuleSettings>,
+    /// ディレクトリごとの上書き ([[lint.overrides]])
+    pub overrides: Vec<LintOverride>,
+}
+
+/// Jekyll のサイトの扱い (設定ファイルの [jekyll])
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JekyllSettings {
+    /// _config.yml を読むか (デフォルト: true)
+    pub enabled: Option<bool>,
+    /// コレクションの名前 -> そのコレクションのファイルでタグを入れるキー
+    pub tag_keys: Option<BTreeMap<String, String>>,
+}
+
+impl JekyllSettings {
+    fn merge(&mut self, other: JekyllSettings) {
+        self.enabled = other.enabled.or(self.enabled);
+        self.tag_keys = other.tag_keys.or(self.tag_keys.take());
+    }
+}
+
+/// 外部コマンドのフック (設定ファイルの [hooks])。コマンドと引数を配列で書く
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HookSettings {
+    /// 入力したタグを決める前に呼ぶ (入力の置き換えか、付けるタグを返せる)
+    pub pre_resolve: Option<Vec<String>>,
+    /// ファイルを書き換える直前に呼ぶ (タグの差し替えか、書き込みを断れる)
+    pub pre_write: Option<Vec<String>>,
+    /// ファイルかDBを書き換えたあとに呼ぶ (通知などに使う)
+    pub post_write: Option<Vec<String>>,
+}
+
+impl HookSettings {
+    fn is_empty(&self) -> bool {
+        self.pre_resolve.is_none() && self.pre_write.is_none() && self.post_write.is_none()
+    }
+
+    fn merge(&mut self, other: HookSettings) {
+        self.pre_resolve = other.pre_resolve.or(self.pre_resolve.take());
+        self.pre_write = other.pre_write.or(self.pre_write.take());
+        self.post_write = other.post_write.or(self.post_write.take());
+    }
+
+    /// ./hooks/x.py のようにパスで書いたコマンドは、設定ファイルの場所から解決する
+    fn resolve_paths(&mut self, dir: &Path) {
+        for command in [
+            &mut self.pre_resolve,
+            &mut self.pre_write,
+            &mut self.post_write,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Some(program) = command.first_mut()
+                && program.contains('/')
+            {
+                let path = expand_home(Path::new(program.as_str()));
+                if path.is_relative() {
+                    *program = dir.join(path).to_string_lossy().into_owned();
+                } else {
+                    *program = path.to_string_lossy().into_owned();
+                }
+            }
+        }
+    }
+}
+
+impl LintSettings {
+    fn merge(&mut self, other: LintSettings) {
+        self.required_tags = other.required_tags.or(self.required_tags.take());
+        self.max_tags = other.max_tags.or(self.max_tags);
+        // 規則は項目ごとに重ねる (ユーザー設定の severity をプロジェクト設定の max で消さない)
+        for (id, rule) in other.rules {
+            self.rules.entry(id).or_default().merge(&rule);
+        }
+        self.overrides.extend(other.overrides);
+    }
+
+    /// 上書きのディレクトリは、設定ファイルの場所から解決する
+    fn resolve_paths(&mut self, dir: &Path) {
+        for o in &mut self.overrides {
+            let path = expand_home(&o.dir);
+            o.dir = if path.is_relative() {
+                dir.join(path)
+            } else {
+                path
+            };
+        }
+    }
+}
+
+impl Settings {
+    /// ユーザー設定とプロジェクト設定を読み込んでまとめる
+    ///
+    /// フックは外部コマンドを実行するので、プロジェクト設定の [hooks] はユーザー設定の trusted_projects に
+    /// そのディレクトリがあるときだけ使う (ほかの人から受け取ったノートを開くだけでコマンドが動かないように)。
+    pub fn load() -> Result<Self> {
+        let mut settings = Settings::default();
+        if let Some(path) = user_config_path().filter(|p| p.is_file()) {
+            settings.merge(Settings::read(&path)?);
+        }
+        if let Some(path) = project_config_path() {
+            let mut project = Settings::read(&path)?;
+            project.trusted_projects = None;
+            if !project.hooks.is_empty() && !settings.trusts(&path) {
+                project.hooks = HookSettings::default();
+                settings.ignored_hooks = Some(path);
+            }
+            settings.merge(project);
+        }
+        if settings.obsidian != Some(false) {
+            settings.vault = Vault::detect();
+        }
+        if settings.logseq != Some(false) {
+            settings.logseq_graph = logseq::detect_graph();
+        }
+        if settings.hugo != Some(false) {
+            settings.hugo_site = hugo::Site::detect()?;
+        }
+        if settings.jekyll.enabled != Some(false) {
+            settings.jekyll_site = jekyll::Site::detect()?;
+        }
+        if settings.zola != Some(false) {
+            settings.zola_site = zola::Site::detect()?;
+        }
+        Ok(settings)
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+        let mut settings: Settings = toml::from_str(&text).with_context(|| {
+            tr!(
+                "Invalid config file {:?}",
+                "設定ファイル {:?} が不正です",
+                path
+            )
+        })?;
+        // 相対パスは設定ファイルの場所から解決する
+        if let Some(db) = &settings.db {
+            let db = expand_home(db);
+            settings.db = Some(match path.parent() {
+                Some(dir) if db.is_relative() => dir.join(db),
+                _ => db,
+            });
+        }
+        if let Some(dir) = path.parent() {
+            settings.hooks.resolve_paths(dir);
+            settings.lint.resolve_paths(dir);
+        }
+        rules::validate(&settings.lint).with_context(|| {
+            tr!(
+                "Invalid config file {:?}",
+                "設定ファイル {:?} が不正です",
+                path
+            )
+        })?;
+        Ok(settings)
+    }
+
+    /// プロジェクト設定 path のディレクトリが trusted_projects にあるか
+    fn trusts(&self, path: &Path) -> bool {
+        let Some(dir) = path.parent().and_then(|dir| fs::canonicalize(dir).ok()) else {
+            return false;
+        };
+        self.trusted_projects.iter().flatten().any(|trusted| {
+            fs::canonicalize(expand_home(trusted)).is_ok_and(|trusted| trusted == dir)
+        })
+    }
+
+    /// 指定されている項目だけ other で上書きする
+    fn merge(&mut self, other: Settings) {
+        self.db = other.db.or(self.db.take());
+        self.tag_key = other.tag_key.or(self.tag_key.take());
+        self.sort = other.sort.or(self.sort);
+        self.max_distance = other.max_distance.or(self.max_distance);
+        self.non_interactive = other.non_interactive.or(self.non_interactive);
+        self.on_unknown = other.on_unknown.or(self.on_unknown);
+        self.prompt_timeout = other.prompt_timeout.or(self.prompt_timeout.take());
+        self.default_tags = other.default_tags.or(self.default_tags.take());
+        self.usage_stats = other.usage_stats.or(self.usage_stats);
+        self.related_tags = other.related_tags.or(self.related_tags);
+        self.language = other.language.or(self.language.take());
+        self.appearance.merge(other.appearance);
+        self.lint.merge(other.lint);
+        self.hooks.merge(other.hooks);
+        self.trusted_projects = other.trusted_projects.or(self.trusted_projects.take());
+        self.obsidian = other.obsidian.or(self.obsidian);
+        self.dataview = other.dataview.or(self.dataview);
+        self.logseq = other.logseq.or(self.logseq);
+        self.hugo = other.hugo.or(self.hugo);
+        self.jekyll.merge(other.jekyll);
+        self.zola = other.zola.or(self.zola);
+    }
+
+    /// Hugo (単数形か複数形) か Zola のサイトのタクソノミーをタグを書き込むキーにする
+    pub fn select_taxonomy(&mut self, name: &str) -> Result<()> {
+        if let Some(site) = &self.zola_site {
+            let Some(key) = site.taxonomy(name) else {
+                bail!(tr!(
+                    "The Zola site has no taxonomy '{}' (taxonomies: {})",
+                    "Zola のサイトに '{}' というタクソノミーはありません (タクソノミー: {})",
+                    name,
+                    site.keys()
+                ));
+            };
+            self.tag_key = Some(key);
+            return Ok(());
+        }
+        let Some(site) = &self.hugo_site else {
+            bail!(tr!(
+                "--taxonomy works only inside a Hugo or Zola site",
+                "--taxonomy は Hugo か Zola のサイトの中でだけ使えます"
+            ));
+        };
+        let Some(key) = site.taxonomy(name) else {
+            bail!(tr!(
+                "The Hugo site has no taxonomy '{}' (taxonomies: {})",
+                "Hugo のサイトに '{}' というタクソノミーはありません (タクソノミー: {})",
+                name,
+                site.keys()
+            ));
+        };
+        self.tag_key = Some(key.to_string());
+        Ok(())
+    }
+
+    /// 確認の答えを待つ時間
+    pub fn prompt_timeout(&self) -> Result<Option<Duration>> {
+        self.prompt_timeout
+            .as_deref()
+            .map(|s| {
+                humantime::parse_duration(s).with_context(|| {
+                    tr!(
+                        "Invalid prompt_timeout {:?}",
+                        "prompt_timeout {:?} が不正です",
+                        s
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    /// メッセージの言語 (設定ファイル、ロケールの環境変数の順)
+    pub fn lang(&self) -> Lang {
+        self.language
+            .as_deref()
+            .and_then(Lang::parse)
+            .or_else(Lang::from_env)
+            .unwrap_or(Lang::En)
+    }
+
+    pub fn front_matter(&self) -> FrontMatterOptions {
+        let defaults = FrontMatterOptions::default();
+        // Zola のページはタグを [taxonomies] の中に書き、Front Matter は TOML で作る
+        let zola = self.zola_site.as_ref();
+        FrontMatterOptions {
+            tag_key: self
+                .tag_key
+                .clone()
+                .or_else(|| zola.map(zola::Site::default_key))
+                .unwrap_or(defaults.tag_key),
+            sort: self.sort.unwrap_or(defaults.sort),
+            format: match zola {
+                Some(_) => FrontMatterFormat::Toml,
+                None => defaults.format,
+            },
+            inline_field: if self.dataview == Some(true) {
+                Some(InlineFieldStyle::Dataview)
+            } else if self.logseq_graph.is_some() {
+                Some(InlineFieldStyle::Logseq)
+            } else {
+                defaults.inline_field
+            },
+        }
+    }
+
+    /// path に使う Front Matter の書き方 (Jekyll のコレクションに tag_keys があればそのキー)
+    pub fn front_matter_for(&self, path: &Path) -> FrontMatterOptions {
+        let mut options = self.front_matter();
+        if let (Some(site), Some(tag_keys)) = (&self.jekyll_site, &self.jekyll.tag_keys)
+            && let Some(key) = site.tag_key(path, tag_keys)
+        {
+            options.tag_key = key.to_string();
+        }
+        options
+    }
+
+    /// まとめて書き換えるファイルに使う Front Matter の書き方 (タグのキーが1つに決まらなければエラー)
+    pub fn front_matter_for_files(&self, paths: &[PathBuf]) -> Result<FrontMatterOptions> {
+        let mut options = self.front_matter();
+        let mut iter = paths.iter().map(|path| self.front_matter_for(path));
+        if let Some(first) = iter.next() {
+            if let Some(other) = iter.find(|o| o.tag_key != first.tag_key) {
+                bail!(tr!(
+                    "The files use different tag keys ('{}' and '{}') by their Jekyll collection; tag each collection separately",
+                    "Jekyll のコレクションによってファイルのタグのキーが違います ('{}' と '{}')。コレクションごとに分けて実行してください",
+                    first.tag_key,
+                    other.tag_key
+                ));
+            }
+            options = first;
+        }
+        Ok(options)
+    }
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("mdtagger").join("config.toml"))
+}
+
+fn project_config_path() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
+
+/// 先頭の ~/ をホームディレクトリに置き換える
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,134 @@
+// --- 表の出力 (--output table / csv / markdown) ---
+//
+// stats・lint・find の結果を、列を揃えた表、CSV、Markdown の表で出す。
+// 列の名前はなるべく --output json のフィールドの名前と同じにして、ツールで読むときに迷わないようにする。
+
+use crate::commands::OutputFormat;
+
+pub struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+/// 数の列か (table と markdown で右に揃える)
+fn is_number(cell: &str) -> bool {
+    !cell.is_empty() && cell.parse::<f64>().is_ok()
+}
+
+/// CSV のセル (区切りや引用符、改行、前後の空白があれば "" で囲む)
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) || cell.trim() != cell {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Markdown の表のセル (| と改行が表を崩さないようにする)
+fn markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+impl Table {
+    pub fn new(headers: &[&'static str]) -> Self {
+        Self {
+            headers: headers.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// 行を足す (セルの数は列の数に合わせる)
+    pub fn row(&mut self, cells: Vec<String>) {
+        debug_assert_eq!(cells.len(), self.headers.len());
+        self.rows.push(cells);
+    }
+
+    /// 右に揃える列 (空でないセルがすべて数の列)
+    fn numeric_columns(&self) -> Vec<bool> {
+        (0..self.headers.len())
+            .map(|i| {
+                let mut cells = self.rows.iter().map(|row| row[i].as_str());
+                cells.clone().any(|cell| !cell.is_empty())
+                    && cells.all(|cell| cell.is_empty() || is_number(cell))
+            })
+            .collect()
+    }
+
+    fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Csv => self.csv(),
+            OutputFormat::Markdown => self.markdown(),
+            _ => self.aligned(),
+        }
+    }
+
+    /// 標準出力に出す
+    pub fn print(&self, format: OutputFormat) {
+        print!("{}", self.render(format));
+    }
+
+    fn csv(&self) -> String {
+        let mut out = String::new();
+        let header: Vec<String> = self.headers.iter().map(|h| csv_cell(h)).collect();
+        out.push_str(&header.join(","));
+        out.push('\n');
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(|cell| csv_cell(cell)).collect();
+            out.push_str(&cells.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    fn markdown(&self) -> String {
+        let numeric = self.numeric_columns();
+        let mut out = format!("| {} |\n", self.headers.join(" | "));
+        let rule: Vec<&str> = numeric
+            .iter()
+            .map(|&numeric| if numeric { "---:" } else { "---" })
+            .collect();
+        out.push_str(&format!("| {} |\n", rule.join(" | ")));
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(|cell| markdown_cell(cell)).collect();
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+        out
+    }
+
+    /// 列を揃えた表 (幅は全角の文字を2桁として数える)
+    fn aligned(&self) -> String {
+        let numeric = self.numeric_columns();
+        let widths: Vec<usize> = (0..self.headers.len())
+            .map(|i| {
+                self.rows
+                    .iter()
+                    .map(|row| console::measure_text_width(&row[i]))
+                    .chain([self.headers[i].len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let line = |cells: Vec<&str>| -> String {
+            let padded: Vec<String> = cells
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    let padding = " ".repeat(widths[i] - console::measure_text_width(cell));
+                    if numeric[i] {
+                        format!("{}{}", padding, cell)
+                    } else {
+                        format!("{}{}", cell, padding)
+                    }
+                })
+                .collect();
+            format!("{}\n", padded.join("  ").trim_end())
+        };
+        let mut out = line(self.headers.clone());
+        let rule: Vec<String> = widths.iter().map(|&w| "-".repeat(w)).collect();
+        out.push_str(&line(rule.iter().map(String::as_str).collect()));
+        for row in &self.rows {
+            out.push_str(&line(row.iter().map(String::as_str).collect()));
+        }
+        out
+    }
+}
@@ -0,0 +1,370 @@
+// --- タクソノミー (使ってよいタグの木と付け方の制約) ---
+//
+// taxonomy.yaml に使ってよいタグを木の形で書き (lang の下の rust なら lang/rust)、タグの数・必須の組・
+// 同時に付けられない組の制約も書く。lint --schema taxonomy.yaml ですべてのファイルを検査すれば、
+// 語彙から外れたタグを CI で止められる。
+//
+//   tags:
+//     status: [draft, review, done]
+//     lang:
+//       rust:
+//       python:
+//     project: ["*"]       # project/ の下は何でもよい
+//   leaf_only: true        # 子のあるタグ (lang など) だけを付けたら違反
+//   min_tags: 1
+//   max_tags: 6
+//   required: [status/*]   # 組ごとにどれか1つ (リストならそのどれか)
+//   exclusive: [status/*]  # 組ごとに高々1つ
+
+use anyhow::{Context, Result, anyhow, bail};
+use mdtagger::db::TagDatabase;
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::rules::{Finding, RuleSet, matches_pattern};
+
+/// taxonomy.yaml の形
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaxonomyFile {
+    /// 使ってよいタグの木 (名前をキーにしたマッピングの入れ子か、名前のリスト。"*" はその下の何でも)
+    #[schemars(with = "serde_json::Value")]
+    tags: Value,
+    /// 子のあるタグをそのまま付けるのを禁止する (lang ではなく lang/rust と付ける)
+    leaf_only: bool,
+    /// 1つのファイルに付けなければならないタグの数の下限
+    min_tags: Option<usize>,
+    /// 1つのファイルに付けてよいタグの数の上限
+    max_tags: Option<usize>,
+    /// 組ごとに、どれか1つが付いていなければならないタグ (lang/* なら lang/ の下のどれか)
+    required: Vec<Group>,
+    /// 組ごとに、高々1つしか付けられないタグ
+    exclusive: Vec<Group>,
+}
+
+/// タグの組 (1つならリストにしなくてよい)
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum Group {
+    One(String),
+    Any(Vec<String>),
+}
+
+impl Group {
+    fn into_patterns(self) -> Vec<String> {
+        match self {
+            Group::One(pattern) => vec![pattern],
+            Group::Any(patterns) => patterns,
+        }
+    }
+}
+
+pub struct Taxonomy {
+    /// 木のタグ -> 子があるか
+    nodes: BTreeMap<String, bool>,
+    /// 下に何でも付けられるタグ ("*" を書いたタグ)
+    open: Vec<String>,
+    leaf_only: bool,
+    min_tags: Option<usize>,
+    max_tags: Option<usize>,
+    required: Vec<Vec<String>>,
+    exclusive: Vec<Vec<String>>,
+}
+
+/// YAML のキーや要素をタグの名前にする (2024 のような数も名前にする)
+fn node_name(value: &Value) -> Option<String> {
+    match value {
+        Value::String(name) => Some(name.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn join(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+impl Taxonomy {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+        let file: TaxonomyFile = serde_yaml::from_str::<Option<TaxonomyFile>>(&text)
+            .map_err(|err| {
+                anyhow!(tr!(
+                    "Invalid taxonomy {:?}: {}",
+                    "タクソノミー {:?} が不正です: {}",
+                    path,
+                    err
+                ))
+            })?
+            .unwrap_or_default();
+        Self::from_file(file).with_context(|| {
+            tr!(
+                "Invalid taxonomy {:?}",
+                "タクソノミー {:?} が不正です",
+                path
+            )
+        })
+    }
+
+    fn from_file(file: TaxonomyFile) -> Result<Self> {
+        let mut taxonomy = Self {
+            nodes: BTreeMap::new(),
+            open: Vec::new(),
+            leaf_only: file.leaf_only,
+            min_tags: file.min_tags,
+            max_tags: file.max_tags,
+            required: file
+                .required
+                .into_iter()
+                .map(Group::into_patterns)
+                .collect(),
+            exclusive: file
+                .exclusive
+                .into_iter()
+                .map(Group::into_patterns)
+                .collect(),
+        };
+        taxonomy.walk(&file.tags, "")?;
+        if taxonomy.nodes.is_empty() {
+            bail!(tr!("No tags in 'tags'", "tags にタグがありません"));
+        }
+        // 制約に書いたタグが木にあるかを確かめる (綴りの誤りで制約が効かないのを防ぐ)
+        for pattern in taxonomy
+            .required
+            .iter()
+            .chain(&taxonomy.exclusive)
+            .flatten()
+        {
+            let tag = pattern.strip_suffix("/*").unwrap_or(pattern);
+            if !taxonomy.nodes.contains_key(tag) && !taxonomy.is_open(tag) {
+                bail!(tr!(
+                    "'{}' in the constraints is not in 'tags'",
+                    "制約の '{}' は tags にありません",
+                    pattern
+                ));
+            }
+        }
+        Ok(taxonomy)
+    }
+
+    /// 木をたどって、parent の下のタグを集める
+    fn walk(&mut self, value: &Value, parent: &str) -> Result<()> {
+        match value {
+            Value::Null => {}
+            Value::Sequence(items) => {
+                for item in items {
+                    self.walk(item, parent)?;
+                }
+            }
+            Value::Mapping(map) => {
+                for (key, children) in map {
+                    let name = node_name(key).ok_or_else(|| {
+                        anyhow!(tr!(
+                            "Invalid tag name under '{}': {:?}",
+                            "'{}' の下のタグの名前が不正です: {:?}",
+                            parent,
+                            key
+                        ))
+                    })?;
+                    let node = self.add(parent, &name)?;
+                    self.walk(children, &node)?;
+                }
+            }
+            value => {
+                let name = node_name(value).ok_or_else(|| {
+                    anyhow!(tr!(
+                        "Invalid tag name under '{}': {:?}",
+                        "'{}' の下のタグの名前が不正です: {:?}",
+                        parent,
+                        value
+                    ))
+                })?;
+                self.add(parent, &name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// parent の下に name を足す ("*" なら parent の下を何でも付けられるようにする)
+    fn add(&mut self, parent: &str, name: &str) -> Result<String> {
+        if !parent.is_empty() {
+            self.nodes.insert(parent.to_string(), true);
+        }
+        if name == "*" {
+            if parent.is_empty() {
+                bail!(tr!(
+                    "'*' needs a parent tag",
+                    "'*' は親のタグの下に書いてください"
+                ));
+            }
+            self.open.push(parent.to_string());
+            return Ok(parent.to_string());
+        }
+        let node = join(parent, name);
+        self.nodes.entry(node.clone()).or_insert(false);
+        Ok(node)
+    }
+
+    /// tag が木にあるか ("*" を書いたタグの下も含む)
+    pub fn allows(&self, tag: &str) -> bool {
+        self.nodes.contains_key(tag) || self.is_open(tag)
+    }
+
+    /// tag が "*" を書いたタグの下にあるか
+    fn is_open(&self, tag: &str) -> bool {
+        self.open.iter().any(|parent| {
+            tag.strip_prefix(parent.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// 木の中で名前の最後が tag と同じタグ (rust なら lang/rust。1つに決まるときだけ)
+    fn suggestion(&self, tag: &str) -> Option<&str> {
+        let name = tag.rsplit('/').next().unwrap_or(tag);
+        let mut found = self
+            .nodes
+            .keys()
+            .filter(|node| node.rsplit('/').next() == Some(name) && node.as_str() != tag);
+        match (found.next(), found.next()) {
+            (Some(node), None) => Some(node),
+            _ => None,
+        }
+    }
+
+    /// タグを1つずつ調べる (not-in-taxonomy・taxonomy-parent。エイリアスはDBの正式名で調べる)
+    pub fn check_each_tag(
+        &self,
+        rules: &RuleSet,
+        db: &TagDatabase,
+        tags: &[String],
+    ) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for (i, tag) in tags.iter().enumerate() {
+            let tag = db.lookup(tag).map_or(tag.as_str(), |entry| &entry.name);
+            match self.nodes.get(tag) {
+                Some(true) if self.leaf_only => findings.extend(rules.finding(
+                    "taxonomy-parent",
+                    Some(i),
+                    tr!(
+                        "'{}' has child tags (use one of them)",
+                        "'{}' には子のタグがあります (そのどれかを付けてください)",
+                        tag
+                    ),
+                    None,
+                )),
+                Some(_) => {}
+                None if self.is_open(tag) => {}
+                None => {
+                    let suggestion = self.suggestion(tag);
+                    let message = match suggestion {
+                        Some(node) => tr!(
+                            "Tag '{}' is not in the taxonomy (did you mean '{}'?)",
+                            "タグ '{}' はタクソノミーにありません ('{}' ですか?)",
+                            tag,
+                            node
+                        ),
+                        None => tr!(
+                            "Tag '{}' is not in the taxonomy",
+                            "タグ '{}' はタクソノミーにありません",
+                            tag
+                        ),
+                    };
+                    findings.extend(rules.finding(
+                        "not-in-taxonomy",
+                        Some(i),
+                        message,
+                        suggestion.map(str::to_string),
+                    ));
+                }
+            }
+        }
+        findings
+    }
+
+    /// ファイルのタグ全体を調べる (too-few-tags・too-many-tags・required-group・exclusive-tags)
+    pub fn check_file_tags(
+        &self,
+        rules: &RuleSet,
+        db: &TagDatabase,
+        tags: &[String],
+    ) -> Vec<Finding> {
+        let tags: Vec<&str> = tags
+            .iter()
+            .map(|tag| db.lookup(tag).map_or(tag.as_str(), |entry| &entry.name))
+            .collect();
+        let mut findings = Vec::new();
+        let mut report = |rule: &'static str, message: String| {
+            findings.extend(rules.finding(rule, None, message, None));
+        };
+
+        if let Some(min) = self.min_tags
+            && tags.len() < min
+        {
+            report(
+                "too-few-tags",
+                tr!(
+                    "{} tags (at least {} in the taxonomy)",
+                    "タグが {} 個です (タクソノミーの下限は {} 個)",
+                    tags.len(),
+                    min
+                ),
+            );
+        }
+        if let Some(max) = self.max_tags
+            && tags.len() > max
+        {
+            report(
+                "too-many-tags",
+                tr!(
+                    "{} tags (at most {} in the taxonomy)",
+                    "タグが {} 個あります (タクソノミーの上限は {} 個)",
+                    tags.len(),
+                    max
+                ),
+            );
+        }
+        for group in &self.required {
+            let found = tags
+                .iter()
+                .any(|tag| group.iter().any(|pattern| matches_pattern(pattern, tag)));
+            if !group.is_empty() && !found {
+                report(
+                    "required-group",
+                    tr!(
+                        "Needs one of: {} (taxonomy)",
+                        "次のどれかのタグが必要です: {} (タクソノミー)",
+                        group.join(", ")
+                    ),
+                );
+            }
+        }
+        for group in &self.exclusive {
+            let found: Vec<&str> = tags
+                .iter()
+                .copied()
+                .filter(|tag| group.iter().any(|pattern| matches_pattern(pattern, tag)))
+                .collect();
+            if found.len() > 1 {
+                report(
+                    "exclusive-tags",
+                    tr!(
+                        "Only one of these can be used: {}",
+                        "次のタグは1つしか付けられません: {}",
+                        found.join(", ")
+                    ),
+                );
+            }
+        }
+        findings
+    }
+}
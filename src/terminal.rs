@@ -0,0 +1,30 @@
+// --- 端末かどうかの判定 ---
+//
+// 標準出力がパイプやファイルのときは、色も絵文字も使わず、対話プロンプトも出さない
+// (未知のタグは非対話モードの方針で扱う)。NO_COLOR が設定されていれば色だけを止める。
+
+use std::io::IsTerminal;
+
+/// 人が端末の前にいるか (標準出力と標準入力がどちらも端末)
+pub fn is_interactive() -> bool {
+    std::io::stdout().is_terminal() && std::io::stdin().is_terminal()
+}
+
+/// 色を使うかを決める (https://no-color.org/)
+pub fn init(colors: bool) {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if no_color || !colors {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+}
+
+/// メッセージの先頭の絵文字を取り除く
+pub fn strip_emoji(message: &str) -> &str {
+    let rest = message.trim_start_matches(|c: char| !c.is_ascii() && !c.is_alphanumeric());
+    if rest.len() == message.len() {
+        message
+    } else {
+        rest.trim_start()
+    }
+}
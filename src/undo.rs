@@ -0,0 +1,199 @@
+// --- 直前の操作の記録 (undo 用) ---
+//
+// 書き換える前のファイルとタグデータベースの内容を、DBの隣の .mdtagger/undo.json に残す。
+// 残すのは最後の1回分だけで、次に何かを書き換えると上書きされる。
+// あわせて、変更の内容を履歴 (history.jsonl) に追記する。
+
+use anyhow::{Context, Result};
+use mdtagger::markdown::{FrontMatterOptions, replace_head, write_verified};
+use mdtagger::tr;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::history::{self, HistoryEntry};
+use crate::hooks;
+use crate::index;
+use crate::journal::Journal;
+
+const UNDO_FILE: &str = "undo.json";
+
+/// 1回の実行で書き換えた内容
+#[derive(Serialize, Deserialize)]
+pub struct UndoLog {
+    pub db_path: PathBuf,
+    /// 実行前のDB (None ならDBはまだなかった)
+    pub db_before: Option<String>,
+    pub db_after: Option<String>,
+    pub files: Vec<FileChange>,
+}
+
+/// 書き換えたファイルの先頭 (Front Matter の部分。本文のフィールドを使うときはファイル全体)
+#[derive(Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+    /// before と after のあとに本文が続くか (false ならファイル全体。前の版の記録もファイル全体)
+    #[serde(default)]
+    pub head_only: bool,
+}
+
+/// 実行中の書き込みを集め、最後に undo.json として保存する
+pub struct Recorder {
+    operation: String,
+    front_matter: FrontMatterOptions,
+    db_path: PathBuf,
+    db_before: Option<String>,
+    files: Vec<FileChange>,
+    /// 最初のファイルを書き換える直前に始める
+    journal: Option<Journal>,
+}
+
+impl Recorder {
+    /// DBを書き換える前に呼ぶ
+    pub fn start(operation: &str, db_path: &Path, front_matter: &FrontMatterOptions) -> Self {
+        Self {
+            operation: operation.to_string(),
+            front_matter: front_matter.clone(),
+            db_path: db_path.to_path_buf(),
+            db_before: fs::read_to_string(db_path).ok(),
+            files: Vec::new(),
+            journal: None,
+        }
+    }
+
+    /// pre_write フックに書き込む内容を確かめさせる (フックが差し替えた内容を返す)
+    pub fn pre_write(&self, path: &Path, before: &str, after: String) -> Result<String> {
+        hooks::pre_write(
+            &self.db_path,
+            &self.operation,
+            path,
+            before,
+            after,
+            &self.front_matter,
+        )
+    }
+
+    /// ファイルの先頭の before を after に置き換え (本文はそのまま)、元の先頭を覚えておく
+    pub fn write_file(&mut self, path: &Path, before: &str, after: String) -> Result<()> {
+        self.intend(path, before, &after)?;
+        replace_head(path, before, &after)?;
+        self.record(path, before, after);
+        Ok(())
+    }
+
+    /// write_file と同じだが、書き込んだ内容を読み直して確かめ、だめなら元に戻してエラーにする
+    pub fn write_file_verified(
+        &mut self,
+        path: &Path,
+        before: &str,
+        after: String,
+        options: &FrontMatterOptions,
+    ) -> Result<()> {
+        self.intend(path, before, &after)?;
+        write_verified(path, before, &after, options)?;
+        self.record(path, before, after);
+        Ok(())
+    }
+
+    /// 書き換える直前に、途中経過の記録 (journal.jsonl) に追記する
+    fn intend(&mut self, path: &Path, before: &str, after: &str) -> Result<()> {
+        let journal = match &mut self.journal {
+            Some(journal) => journal,
+            None => self.journal.insert(Journal::start(
+                &self.db_path,
+                &self.operation,
+                self.db_before.as_deref(),
+            )?),
+        };
+        journal.intend(path, before, after)
+    }
+
+    fn record(&mut self, path: &Path, before: &str, after: String) {
+        let head_only = fs::metadata(path).is_ok_and(|meta| meta.len() > after.len() as u64);
+        self.files.push(FileChange {
+            path: fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+            before: before.to_string(),
+            after,
+            head_only,
+        });
+    }
+
+    /// 何か書き換えていれば undo.json に保存し、履歴に追記する
+    pub fn finish(self) -> Result<()> {
+        let db_after = fs::read_to_string(&self.db_path).ok();
+        if self.files.is_empty() && db_after == self.db_before {
+            if let Some(journal) = self.journal {
+                journal.finish()?;
+            }
+            return Ok(());
+        }
+        let entry = HistoryEntry::new(
+            &self.operation,
+            &self.front_matter,
+            self.db_before.as_deref(),
+            db_after.as_deref(),
+            &self.files,
+        );
+        history::append(&self.db_path, &entry)?;
+        hooks::post_write(&self.db_path, &entry);
+        index::update(
+            &self.db_path,
+            &self.front_matter,
+            self.files
+                .iter()
+                .map(|change| (change.path.as_path(), change.after.as_str())),
+        );
+
+        let log = UndoLog {
+            db_path: fs::canonicalize(&self.db_path).unwrap_or(self.db_path.clone()),
+            db_before: self.db_before,
+            db_after,
+            files: self.files,
+        };
+        let dir = state_dir(&self.db_path);
+        fs::create_dir_all(&dir)
+            .with_context(|| tr!("Failed to create {:?}", "{:?} を作成できません", dir))?;
+        fs::write(dir.join(UNDO_FILE), serde_json::to_string(&log)?)?;
+        if let Some(journal) = self.journal {
+            journal.finish()?;
+        }
+        Ok(())
+    }
+}
+
+/// 記録を置くディレクトリ (DBと同じ場所の .mdtagger)
+pub fn state_dir(db_path: &Path) -> PathBuf {
+    match db_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(".mdtagger"),
+        _ => PathBuf::from(".mdtagger"),
+    }
+}
+
+/// 最後の記録を読む
+pub fn last(db_path: &Path) -> Result<Option<UndoLog>> {
+    let path = state_dir(db_path).join(UNDO_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(&path)
+        .with_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+    let log = serde_json::from_str(&text).with_context(|| {
+        tr!(
+            "Invalid undo log {:?}",
+            "取り消し用の記録 {:?} が不正です",
+            path
+        )
+    })?;
+    Ok(Some(log))
+}
+
+/// 元に戻し終えた記録を消す
+pub fn clear(db_path: &Path) -> Result<()> {
+    let path = state_dir(db_path).join(UNDO_FILE);
+    if path.exists() {
+        fs::remove_file(&path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,98 @@
+// --- タグ解決の利用状況 (.mdtagger/resolver_stats.json) ---
+//
+// 設定ファイルで usage_stats = true にしたときだけ、add でのタグの決まり方を集計する。
+// 手元のファイルに残すだけで、外には送らない。stats --resolver で見て、
+// auto_accept_distance や max_distance を実際の使われ方に合わせて調整するためのもの。
+
+use anyhow::{Context, Result};
+use mdtagger::resolver::Resolution;
+use mdtagger::tr;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::undo::state_dir;
+
+const STATS_FILE: &str = "resolver_stats.json";
+
+#[derive(Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ResolverStats {
+    /// 解決した入力の数
+    pub resolutions: usize,
+    /// ユーザーに確認した回数
+    pub prompts: usize,
+    /// あいまい検索などで候補が見つかった回数
+    pub suggested: usize,
+    /// 候補のどれかに決まった回数
+    pub accepted: usize,
+    /// 候補があったのに、どれも選ばなかった回数
+    pub declined: usize,
+    /// 決め方 (exact, auto-accept, typo-correction など) ごとの回数
+    pub methods: BTreeMap<String, usize>,
+    /// 候補に決まったときの編集距離ごとの回数
+    pub accepted_distances: BTreeMap<usize, usize>,
+}
+
+impl ResolverStats {
+    pub fn load(db_path: &Path) -> Result<Self> {
+        let path = state_dir(db_path).join(STATS_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(&path)
+            .with_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+        serde_json::from_str(&text).with_context(|| {
+            tr!(
+                "Invalid usage statistics {:?}",
+                "利用状況の記録 {:?} が不正です",
+                path
+            )
+        })
+    }
+
+    pub fn save(&self, db_path: &Path) -> Result<()> {
+        let dir = state_dir(db_path);
+        fs::create_dir_all(&dir)
+            .with_context(|| tr!("Failed to create {:?}", "{:?} を作成できません", dir))?;
+        fs::write(dir.join(STATS_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, resolution: &Resolution) {
+        self.resolutions += 1;
+        if resolution.prompted {
+            self.prompts += 1;
+        }
+        *self
+            .methods
+            .entry(resolution.method.to_string())
+            .or_default() += 1;
+        if resolution.candidates.is_empty() {
+            return;
+        }
+        self.suggested += 1;
+        match resolution.distance() {
+            Some(distance) => {
+                self.accepted += 1;
+                *self.accepted_distances.entry(distance).or_default() += 1;
+            }
+            None => self.declined += 1,
+        }
+    }
+
+    /// 候補に決まったときの編集距離の中央値
+    pub fn median_distance(&self) -> Option<usize> {
+        let half = self.accepted.div_ceil(2);
+        let mut seen = 0;
+        self.accepted_distances
+            .iter()
+            .find(|(_, count)| {
+                seen += **count;
+                seen >= half.max(1)
+            })
+            .map(|(distance, _)| *distance)
+    }
+}
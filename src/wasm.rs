@@ -0,0 +1,124 @@
+// --- JavaScript から呼べる API (feature = "wasm") ---
+//
+// Obsidian などのプラグインが、CLI とまったく同じ Front Matter の書き換えとタグの解決を使えるようにする。
+// ファイルの読み書きと対話プロンプトは使わず、DBの JSON とノートの内容を文字列で受け渡す。
+// 未知のタグや候補の扱いは、CLI の非対話モード (--non-interactive --on-unknown) と同じ。
+
+use crate::db::{TagDatabase, record_usage};
+use crate::markdown::{FrontMatterOptions, InlineFieldStyle, add_tags, content_tags, remove_tags};
+use crate::normalize::Normalizer;
+use crate::prompt::NeverPrompt;
+use crate::resolver::{FuzzyResolver, ResolveOptions, TagResolver, UnknownPolicy};
+use wasm_bindgen::prelude::*;
+
+/// タグデータベースを持ち、ノートの内容にタグを付け外しする
+#[wasm_bindgen]
+pub struct TagEngine {
+    db: TagDatabase,
+    front_matter: FrontMatterOptions,
+    policy: UnknownPolicy,
+    auto_accept: Option<usize>,
+}
+
+#[wasm_bindgen]
+impl TagEngine {
+    /// tags_db.json の内容から作る (空文字列なら空のデータベース)
+    #[wasm_bindgen(constructor)]
+    pub fn new(db_json: &str) -> Result<TagEngine, JsError> {
+        let config = if db_json.trim().is_empty() {
+            Default::default()
+        } else {
+            serde_json::from_str(db_json)?
+        };
+        let db = TagDatabase::new(config);
+        let auto_accept = db.config().defaults.auto_accept_distance;
+        Ok(TagEngine {
+            db,
+            front_matter: FrontMatterOptions::default(),
+            policy: UnknownPolicy::Fail,
+            auto_accept,
+        })
+    }
+
+    /// 未知のタグの扱い (create / skip / fail / best-match。デフォルト: fail)
+    #[wasm_bindgen(js_name = setOnUnknown)]
+    pub fn set_on_unknown(&mut self, policy: &str) -> Result<(), JsError> {
+        self.policy = policy.parse()?;
+        Ok(())
+    }
+
+    /// タグを入れる Front Matter のキーと、並べ替えるか
+    #[wasm_bindgen(js_name = setFrontMatter)]
+    pub fn set_front_matter(&mut self, tag_key: &str, sort: bool) {
+        self.front_matter = FrontMatterOptions {
+            tag_key: tag_key.to_string(),
+            sort,
+            ..Default::default()
+        };
+    }
+
+    /// 本文の Dataview のインラインフィールド (tags:: a, b) のタグも読み書きするか
+    #[wasm_bindgen(js_name = setInlineField)]
+    pub fn set_inline_field(&mut self, enabled: bool) {
+        self.front_matter.inline_field = enabled.then_some(InlineFieldStyle::Dataview);
+    }
+
+    /// この距離以内の候補が1つだけなら採用する (--auto-accept と同じ)
+    #[wasm_bindgen(js_name = setAutoAccept)]
+    pub fn set_auto_accept(&mut self, distance: Option<usize>) {
+        self.auto_accept = distance;
+    }
+
+    /// 入力を既存のタグに対応付ける (skip されたら空の配列)
+    pub fn resolve(&mut self, input: &str) -> Result<Vec<String>, JsError> {
+        let normalizer = Normalizer::new(&self.db.config().normalize)?;
+        let input = normalizer.apply(input);
+        if input.is_empty() {
+            return Ok(Vec::new());
+        }
+        let options = ResolveOptions {
+            policy: Some(self.policy),
+            auto_accept: self.auto_accept,
+            ..Default::default()
+        };
+        let mut resolver = FuzzyResolver::with_prompter(options, NeverPrompt);
+        let resolution = self.db.edit(|config| resolver.resolve(&input, config))?;
+        Ok(resolution.tags)
+    }
+
+    /// ノートの内容のタグを読む
+    #[wasm_bindgen(js_name = readTags)]
+    pub fn read_tags(&self, content: &str) -> Result<Vec<String>, JsError> {
+        Ok(content_tags(content, &self.front_matter)?)
+    }
+
+    /// タグを解決してノートの内容に付けた結果を返す
+    #[wasm_bindgen(js_name = addTags)]
+    pub fn add_tags(&mut self, content: &str, tags: Vec<String>) -> Result<String, JsError> {
+        let mut resolved = Vec::new();
+        for tag in &tags {
+            resolved.extend(self.resolve(tag)?);
+        }
+        let new_content = add_tags(content, &resolved, &self.front_matter)?;
+        self.db.edit(|config| record_usage(config, &resolved));
+        Ok(new_content)
+    }
+
+    /// ノートの内容からタグを外した結果を返す (エイリアスなら正式名も外す)
+    #[wasm_bindgen(js_name = removeTags)]
+    pub fn remove_tags(&self, content: &str, tags: Vec<String>) -> Result<String, JsError> {
+        let mut removals = tags.clone();
+        removals.extend(
+            tags.iter()
+                .filter_map(|t| self.db.lookup(t))
+                .map(|e| e.name.clone()),
+        );
+        Ok(remove_tags(content, &removals, &self.front_matter)?)
+    }
+
+    /// タグデータベースの JSON (新しいタグやエイリアスを保存するために書き戻す)
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string_pretty(self.db.config())?)
+    }
+}
@@ -0,0 +1,117 @@
+// --- Zola のサイト (base_url のある config.toml のディレクトリ) ---
+//
+// Zola のページはタグを Front Matter の [taxonomies] の中に書く (taxonomies.tags)。
+// サイトの中ではタグのキーを taxonomies.tags にし、Front Matter のないページには TOML (+++) で作る。
+// サイトの設定の taxonomies = [{ name = "tags" }, ...] を読み、Hugo と同じく --taxonomy と新しい語の警告に使う。
+
+use anyhow::{Context, Result};
+use log::warn;
+use mdtagger::tr;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CONFIG_FILE: &str = "config.toml";
+const TAXONOMIES_KEY: &str = "taxonomies";
+
+#[derive(Debug)]
+pub struct Site {
+    pub root: PathBuf,
+    /// タクソノミーの名前 (Front Matter のキーは taxonomies.<名前>)
+    pub taxonomies: Vec<String>,
+}
+
+impl Site {
+    /// カレントディレクトリから親へたどって、最初に見つかったサイト
+    pub fn detect() -> Result<Option<Self>> {
+        let Ok(cwd) = env::current_dir() else {
+            return Ok(None);
+        };
+        for dir in cwd.ancestors() {
+            let path = dir.join(CONFIG_FILE);
+            if path.is_file()
+                && let Some(site) = Site::read(dir, &path)?
+            {
+                return Ok(Some(site));
+            }
+        }
+        Ok(None)
+    }
+
+    fn read(root: &Path, path: &Path) -> Result<Option<Self>> {
+        let text = fs::read_to_string(path)
+            .with_context(|| tr!("Failed to read {:?}", "{:?} を読み込めません", path))?;
+        let config: toml::Table = toml::from_str(&text).with_context(|| {
+            tr!(
+                "Invalid Zola config {:?}",
+                "Zola の設定ファイル {:?} が不正です",
+                path
+            )
+        })?;
+        // Zola の設定には base_url が必須
+        if !config.contains_key("base_url") {
+            return Ok(None);
+        }
+        let taxonomies = config
+            .get(TAXONOMIES_KEY)
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.get("name")?.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Some(Self {
+            root: root.to_path_buf(),
+            taxonomies,
+        }))
+    }
+
+    /// 名前で指定したタクソノミーの、Front Matter のキー
+    pub fn taxonomy(&self, name: &str) -> Option<String> {
+        let name = name.strip_prefix("taxonomies.").unwrap_or(name);
+        self.taxonomies
+            .iter()
+            .find(|t| *t == name)
+            .map(|t| format!("{}.{}", TAXONOMIES_KEY, t))
+    }
+
+    /// サイトの中でのタグのキー (tags がなければ最初のタクソノミー)
+    pub fn default_key(&self) -> String {
+        let name = self
+            .taxonomies
+            .iter()
+            .find(|t| *t == "tags")
+            .or(self.taxonomies.first())
+            .map_or("tags", String::as_str);
+        format!("{}.{}", TAXONOMIES_KEY, name)
+    }
+
+    /// タクソノミーの名前の一覧 (メッセージ用)
+    pub fn keys(&self) -> String {
+        self.taxonomies.join(", ")
+    }
+
+    /// タグを書き込むキーがタクソノミーでなければ警告する (Zola はそのキーの値から一覧のページを作らない)
+    pub fn check_key(&self, key: &str) {
+        if self.taxonomy(key).is_none_or(|k| k != key) {
+            warn!(
+                "⚠️  {}",
+                tr!(
+                    "'{}' is not a taxonomy of the Zola site {:?}, so Zola will not list these tags (taxonomies: {})",
+                    "'{}' は Zola のサイト {:?} のタクソノミーではないため、Zola はこのタグの一覧を作りません (タクソノミー: {})",
+                    key,
+                    self.root,
+                    self.keys()
+                )
+            );
+        }
+    }
+
+    /// content の下のページで key に使われている語
+    pub fn terms(&self, key: &str) -> HashSet<String> {
+        crate::hugo::content_terms(&self.root.join("content"), key)
+    }
+}